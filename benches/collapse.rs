@@ -0,0 +1,63 @@
+//! Compare the serial and the parallel scan with
+//! `cargo bench` and `cargo bench --features parallel`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use world_gen::{PlotGenerator, Tile};
+
+fn collapse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("collapse");
+    group.sample_size(10);
+
+    for size in [16, 32, 64] {
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            b.iter(|| {
+                let mut gen = PlotGenerator::new(size, size).with_seed(0);
+                gen.collapse().unwrap();
+                gen.into_plot()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn find_lowest_entropy(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find_lowest_entropy");
+
+    for size in [128, 512] {
+        let mut gen = PlotGenerator::new(size, size).with_seed(0);
+        gen.set_tile(size / 2, size / 2, Tile::River).unwrap();
+
+        group.bench_with_input(BenchmarkId::from_parameter(size), &gen, |b, gen| {
+            b.iter(|| gen.find_lowest_entropy())
+        });
+    }
+
+    group.finish();
+}
+
+fn propagate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("propagate");
+    group.sample_size(10);
+
+    for size in [128, 512] {
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            b.iter_batched(
+                || PlotGenerator::new(size, size).with_seed(0),
+                // A river through the middle of the grid, that every field next to it reacts to
+                |mut gen| {
+                    for y in 0..size {
+                        gen.set_tile(size / 2, y, Tile::River).unwrap();
+                    }
+                    gen
+                },
+                criterion::BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, collapse, find_lowest_entropy, propagate);
+criterion_main!(benches);
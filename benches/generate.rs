@@ -0,0 +1,75 @@
+//! Full generation across grid sizes and tile set sizes, to evaluate performance work like the
+//! domain bitsets, the entropy heap or the `parallel` feature. Record a baseline with
+//! `cargo bench --bench generate -- --save-baseline <version>`, the numbers of past releases are
+//! in `BENCHMARKS.md`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::sync::Arc;
+use world_gen::{PlotGenerator, TileDef, TileSet};
+
+/// `count` tiles in a chain, every tile may lie next to itself and the tiles right before and
+/// after it. Big enough to stress the domains, loose enough to never contradict.
+fn chain(count: usize) -> TileSet {
+    let defs = (0..count)
+        .map(|i| {
+            let neighbours = [Some(i), i.checked_sub(1)]
+                .into_iter()
+                .flatten()
+                .map(|n| format!("T{n}"))
+                .collect::<Vec<_>>();
+            TileDef::new(format!("T{i}"), format!("{:02}", i % 100)).with_neighbours(&neighbours)
+        })
+        .collect();
+
+    TileSet::new(defs).expect("The chain is a valid tile set")
+}
+
+fn generate(c: &mut Criterion) {
+    let tilesets = [
+        ("terrain", Arc::new(TileSet::terrain())),
+        ("world", Arc::new(TileSet::world())),
+        ("chain-64", Arc::new(chain(64))),
+    ];
+
+    for (name, tileset) in tilesets {
+        let mut group = c.benchmark_group(format!("generate/{name}"));
+        group.sample_size(10);
+
+        for size in [16, 64, 256] {
+            group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+                b.iter(|| {
+                    // Contradictions of the world count too, they are part of generating
+                    PlotGenerator::for_tileset(Arc::clone(&tileset), size, size)
+                        .with_seed(0)
+                        .generate()
+                })
+            });
+        }
+
+        group.finish();
+    }
+}
+
+/// The world across growing grids, with backtracking on, per field. The time per field stays
+/// about the same, as observations only remember the fields they change.
+fn scaling(c: &mut Criterion) {
+    let tileset = Arc::new(TileSet::world());
+    let mut group = c.benchmark_group("scaling/world");
+    group.sample_size(10);
+
+    for size in [64, 128, 256, 512] {
+        group.throughput(Throughput::Elements((size * size) as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            b.iter(|| {
+                PlotGenerator::for_tileset(Arc::clone(&tileset), size, size)
+                    .with_seed(0)
+                    .generate()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, generate, scaling);
+criterion_main!(benches);
@@ -0,0 +1,99 @@
+//! How much memory the wave takes, by counting the bytes the generator holds on the heap. Run
+//! with `cargo bench --bench memory`, the numbers of past releases are in `BENCHMARKS.md`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use world_gen::{PlotGenerator, TileDef, TileSet};
+
+/// Counts the bytes currently allocated.
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn allocated() -> usize {
+    ALLOCATED.load(Ordering::Relaxed)
+}
+
+/// `count` tiles in a chain, like in `benches/generate.rs`.
+fn chain(count: usize) -> TileSet {
+    let defs = (0..count)
+        .map(|i| {
+            let neighbours = [Some(i), i.checked_sub(1)]
+                .into_iter()
+                .flatten()
+                .map(|n| format!("T{n}"))
+                .collect::<Vec<_>>();
+            TileDef::new(format!("T{i}"), format!("{:02}", i % 100)).with_neighbours(&neighbours)
+        })
+        .collect();
+
+    TileSet::new(defs).expect("The chain is a valid tile set")
+}
+
+/// The bytes `gen` holds, by dropping it.
+fn held(gen: PlotGenerator) -> usize {
+    let before = allocated();
+    drop(gen);
+    before - allocated()
+}
+
+fn mib(bytes: usize) -> String {
+    format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+fn main() {
+    let tilesets = [
+        ("world", Arc::new(TileSet::world())),
+        ("chain-64", Arc::new(chain(64))),
+    ];
+
+    println!("| Tile set | Fields | Backtracking | Fresh | Half collapsed | Collapsed |");
+    println!("| -------- | ------ | ------------ | ----- | -------------- | --------- |");
+    for (name, tileset) in &tilesets {
+        for (size, budget) in [(128, None), (1024, None), (1024, Some(0))] {
+            let new = || {
+                let gen = PlotGenerator::for_tileset(Arc::clone(tileset), size, size).with_seed(0);
+                match budget {
+                    Some(budget) => gen.with_backtrack_budget(budget),
+                    None => gen,
+                }
+            };
+            let fresh = held(new());
+            let mut gen = new();
+            for _ in 0..size * size / 2 {
+                let _ = gen.step();
+            }
+            let half = held(gen);
+            let mut gen = new();
+            let _ = gen.collapse();
+            let collapsed = held(gen);
+
+            let backtracking = match budget {
+                Some(0) => "off",
+                _ => "on",
+            };
+            println!(
+                "| {name} | {size}² | {backtracking} | {} | {} | {} |",
+                mib(fresh),
+                mib(half),
+                mib(collapsed)
+            );
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! Rules compiled once and shared, against asking the tile set for its neighbours on every call,
+//! for batches of plots with the same rules. The numbers are in `BENCHMARKS.md`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::hint::black_box;
+use std::sync::Arc;
+use world_gen::{CompiledRuleset, Direction, DomainMask, PlotGenerator, TileDef, TileId, TileSet};
+
+/// `count` tiles in a chain, like in `benches/generate.rs`.
+fn chain(count: usize) -> TileSet {
+    let defs = (0..count)
+        .map(|i| {
+            let neighbours = [Some(i), i.checked_sub(1)]
+                .into_iter()
+                .flatten()
+                .map(|n| format!("T{n}"))
+                .collect::<Vec<_>>();
+            TileDef::new(format!("T{i}"), format!("{:02}", i % 100)).with_neighbours(&neighbours)
+        })
+        .collect();
+
+    TileSet::new(defs).expect("The chain is a valid tile set")
+}
+
+/// The rules of `tileset`, built from its neighbour lists on every call.
+fn recomputed(tileset: &TileSet, tile: TileId, direction: Direction) -> DomainMask {
+    DomainMask::from_tiles(
+        tileset.len(),
+        tileset.valid_neighbours(tile, direction).iter().copied(),
+    )
+}
+
+/// Every rule of the set once, the way propagating a superposition of every tile asks them.
+fn lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rules/lookup");
+    for count in [16, 256] {
+        let tileset = chain(count);
+        let compiled = CompiledRuleset::compile(&tileset, &tileset);
+        let mut allowed = DomainMask::empty(count);
+
+        group.bench_with_input(BenchmarkId::new("recomputed", count), &count, |b, _| {
+            b.iter(|| {
+                for tile in tileset.ids() {
+                    for direction in Direction::ALL {
+                        allowed.union_with(&recomputed(&tileset, tile, direction));
+                    }
+                }
+                black_box(&allowed);
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("compiled", count), &count, |b, _| {
+            b.iter(|| {
+                for tile in tileset.ids() {
+                    for direction in Direction::ALL {
+                        allowed.union_with(compiled.get(tile, direction));
+                    }
+                }
+                black_box(&allowed);
+            })
+        });
+    }
+    group.finish();
+}
+
+/// A batch of small plots, where compiling the rules for every generator adds up.
+fn batch(c: &mut Criterion) {
+    const PLOTS: u64 = 16;
+    let mut group = c.benchmark_group("rules/batch");
+
+    for count in [16, 256] {
+        let tileset = Arc::new(chain(count));
+        let compiled = Arc::new(CompiledRuleset::compile(&*tileset, &tileset));
+
+        group.bench_with_input(BenchmarkId::new("with_rules", count), &count, |b, _| {
+            b.iter(|| {
+                for seed in 0..PLOTS {
+                    let rules = Arc::clone(&tileset);
+                    PlotGenerator::for_tileset(Arc::clone(&tileset), 4, 4)
+                        .with_rules(move |tile, direction| recomputed(&rules, tile, direction))
+                        .with_seed(seed)
+                        .generate()
+                        .unwrap();
+                }
+            })
+        });
+        group.bench_with_input(
+            BenchmarkId::new("with_compiled_rules", count),
+            &count,
+            |b, _| {
+                b.iter(|| {
+                    for seed in 0..PLOTS {
+                        PlotGenerator::for_tileset(Arc::clone(&tileset), 4, 4)
+                            .with_compiled_rules(Arc::clone(&compiled))
+                            .with_seed(seed)
+                            .generate()
+                            .unwrap();
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, lookup, batch);
+criterion_main!(benches);
@@ -0,0 +1,153 @@
+//! Feeds random tile sets, grid sizes, seeds and pre-set fields into the generator. Fails on
+//! panics, on collapses that don't end within their steps, and on plots that break the rules or
+//! the pre-set fields. Run with `cargo fuzz run solver`, add `-- -timeout=10` to also catch
+//! single steps that never return.
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use world_gen::{
+    Direction, Neighbourhood, PlotGenerator, Step, TileDef, TileId, TileSet, WaveState,
+};
+
+/// Keeps the grids small, so an input runs in milliseconds.
+const MAX_SIZE: usize = 16;
+const MAX_TILES: usize = 8;
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    tiles: Vec<Tile>,
+    width: u8,
+    height: u8,
+    seed: u64,
+    backtrack_budget: u8,
+    diagonals: bool,
+    fields: Vec<Field>,
+}
+
+/// A tile, with its neighbours as indices into the tiles of the input.
+#[derive(Debug, Arbitrary)]
+struct Tile {
+    weight: u8,
+    neighbours: Vec<u8>,
+    sides: Vec<(u8, u8)>,
+}
+
+/// A field restricted to some of the tiles before the collapse.
+#[derive(Debug, Arbitrary)]
+struct Field {
+    x: u8,
+    y: u8,
+    tiles: Vec<u8>,
+}
+
+fuzz_target!(|input: Input| run(input));
+
+fn run(input: Input) {
+    let count = input.tiles.len().min(MAX_TILES);
+    if count == 0 {
+        return;
+    }
+    let name = |index: u8| format!("T{}", index as usize % count);
+    let defs = input.tiles[..count]
+        .iter()
+        .enumerate()
+        .map(|(i, tile)| {
+            let neighbours = tile.neighbours.iter().map(|&n| name(n)).collect::<Vec<_>>();
+            let mut def = TileDef::new(format!("T{i}"), char::from(b'a' + i as u8).to_string())
+                .with_weight(tile.weight as f64 / 16.0)
+                .with_neighbours(&neighbours);
+            for &(side, n) in &tile.sides {
+                def = def.with_side(Direction::ALL[side as usize % 8], &[name(n)]);
+            }
+            def
+        })
+        .collect();
+    // Tile sets that can't be built are fine, as long as building them doesn't panic
+    let Ok(tileset) = TileSet::new(defs) else {
+        return;
+    };
+
+    let width = input.width as usize % MAX_SIZE + 1;
+    let height = input.height as usize % MAX_SIZE + 1;
+    let neighbourhood = match input.diagonals {
+        true => Neighbourhood::Moore,
+        false => Neighbourhood::VonNeumann,
+    };
+    let budget = input.backtrack_budget as usize;
+    let mut gen = PlotGenerator::for_tileset(tileset.clone(), width, height)
+        .with_seed(input.seed)
+        .with_backtrack_budget(budget)
+        .with_neighbourhood(neighbourhood.clone());
+
+    // The tiles every field may still end up with, after the pre-set fields
+    let mut allowed =
+        vec![(0..count).map(|t| TileId(t as u16)).collect::<Vec<_>>(); width * height];
+    for field in &input.fields {
+        let (x, y) = (field.x as usize % width, field.y as usize % height);
+        let tiles = field
+            .tiles
+            .iter()
+            .map(|&t| TileId((t as usize % count) as u16))
+            .collect::<Vec<_>>();
+        // Contradictions leave the field as it was
+        if gen.constrain(x, y, &tiles).is_ok() {
+            allowed[y * width + x].retain(|tile| tiles.contains(tile));
+        }
+    }
+
+    // Every step collapses a field or undoes an observation, which uses up the budget
+    let max_steps = width * height + 2 * budget + 1;
+    let mut done = false;
+    for _ in 0..max_steps {
+        match gen.step() {
+            Ok(Step::Done) => {
+                done = true;
+                break;
+            }
+            Ok(_) => {}
+            Err(_) => return,
+        }
+    }
+    assert!(done, "The collapse didn't end within {max_steps} steps");
+    assert!(
+        gen.domains()
+            .all(|state| matches!(state, WaveState::Collapsed(_))),
+        "Done with fields in superposition"
+    );
+
+    let plot = gen.into_plot();
+    let directions = match neighbourhood {
+        Neighbourhood::Moore => &Direction::ALL[..],
+        _ => &[
+            Direction::North,
+            Direction::East,
+            Direction::South,
+            Direction::West,
+        ],
+    };
+    for (x, y) in plot.positions() {
+        let tile = plot.get(x, y);
+        assert!(
+            allowed[y * width + x].contains(&tile),
+            "({x}, {y}) became {}, which it was restricted from",
+            tileset.name(tile)
+        );
+        for &direction in directions {
+            let (dx, dy) = direction.offset();
+            let (Some(nx), Some(ny)) = (x.checked_add_signed(dx), y.checked_add_signed(dy)) else {
+                continue;
+            };
+            if nx >= width || ny >= height {
+                continue;
+            }
+            let neighbour = plot.get(nx, ny);
+            assert!(
+                tileset.neighbour_mask(tile, direction).contains(neighbour),
+                "{} at ({x}, {y}) has {} to its {direction:?}, which the rules forbid",
+                tileset.name(tile),
+                tileset.name(neighbour)
+            );
+        }
+    }
+}
@@ -0,0 +1,59 @@
+#ifndef WORLD_GEN_H
+#define WORLD_GEN_H
+
+/* Generated by cbindgen from src/ffi.rs, don't edit by hand */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// A generator along with the names of its tiles as C strings.
+typedef struct WorldGen WorldGen;
+
+// Creates a generator for a `width` x `height` plot of the built-in world.
+struct WorldGen *worldgen_create(size_t width, size_t height, uint64_t seed);
+
+// Creates a generator for a `width` x `height` plot of the tiles in the RON file at `path`.
+// Returns null if the file can't be loaded.
+//
+// # Safety
+//
+// `path` has to be a valid, nul-terminated C string.
+struct WorldGen *worldgen_create_with_tileset(const char *path,
+                                              size_t width,
+                                              size_t height,
+                                              uint64_t seed);
+
+// Collapses every field that is left. Returns 0 on success and -1 if the generator ran into a
+// contradiction, see [`PlotGenerator::collapse`].
+//
+// # Safety
+//
+// `gen` has to come from one of the `worldgen_create` functions and not be freed yet.
+int32_t worldgen_generate(struct WorldGen *gen);
+
+// The id of the tile at `(x, y)`, -1 if the field isn't collapsed yet or lies outside the plot.
+//
+// # Safety
+//
+// `gen` has to come from one of the `worldgen_create` functions and not be freed yet.
+int32_t worldgen_tile_at(const struct WorldGen *gen, size_t x, size_t y);
+
+// The name of the tile with the id `tile`, null if there is no such tile. The string lives as
+// long as the generator.
+//
+// # Safety
+//
+// `gen` has to come from one of the `worldgen_create` functions and not be freed yet.
+const char *worldgen_tile_name(const struct WorldGen *gen, int32_t tile);
+
+// Frees a generator. Does nothing if `gen` is null.
+//
+// # Safety
+//
+// `gen` has to come from one of the `worldgen_create` functions and must not be used afterwards.
+void worldgen_free(struct WorldGen *gen);
+
+#endif  /* WORLD_GEN_H */
@@ -0,0 +1,275 @@
+use crate::{Plot, PlotGenerator, TileId, TileSet, WfcError, World};
+use bevy::prelude::*;
+use bevy::tasks::futures_lite::future;
+use bevy::tasks::{block_on, AsyncComputeTaskPool, Task};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Generates tile worlds in a Bevy app: a [`WorldPlot`] at startup, and the chunks of an endless
+/// [`World`] in the background for every [`Chunk`] entity that gets spawned.
+///
+/// Every field becomes an entity with a [`TileSprite`] and a [`Transform`], `tile_size` units
+/// apart with the first row on top. With a [`TileAtlas`] resource the fields get a [`Sprite`]
+/// out of its texture atlas as well.
+///
+/// Bevy's dependencies need Rust 1.89 or newer, one version more than the rest of the crate.
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use world_gen::{Chunk, WorldGenPlugin};
+///
+/// App::new()
+///     .add_plugins(DefaultPlugins)
+///     .add_plugins(WorldGenPlugin::new(32, 32).with_seed(42).with_chunks(16))
+///     .add_systems(Startup, |mut commands: Commands| {
+///         commands.spawn(Chunk::new(2, 0));
+///     })
+///     .run();
+/// ```
+pub struct WorldGenPlugin {
+    tileset: Arc<TileSet>,
+    width: usize,
+    height: usize,
+    seed: u64,
+    chunk_size: Option<usize>,
+    tile_size: f32,
+}
+
+/// The plot generated by the [`WorldGenPlugin`] at startup.
+#[derive(Resource)]
+pub struct WorldPlot(pub Plot);
+
+/// Which sprite of a texture atlas shows which tile, see [`WorldGenPlugin`]. By default a tile
+/// is shown by the sprite at the index of its [`TileId`].
+#[derive(Resource, Clone)]
+pub struct TileAtlas {
+    image: Handle<Image>,
+    layout: Handle<TextureAtlasLayout>,
+    indices: HashMap<TileId, usize>,
+}
+
+/// The tile of a field spawned by the [`WorldGenPlugin`], at world position `(x, y)`.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileSprite {
+    pub x: i64,
+    pub y: i64,
+    pub tile: TileId,
+}
+
+/// Spawn an entity with a chunk to have the [`WorldGenPlugin`] generate the chunk of its
+/// [`World`] at chunk coordinates `(cx, cy)` in the background, see
+/// [`WorldGenPlugin::with_chunks`]. The fields get spawned as children of the entity, which
+/// gets moved to where the chunk lies.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+#[require(Transform)]
+pub struct Chunk {
+    pub cx: i64,
+    pub cy: i64,
+}
+
+/// The [`World`] the chunks come from, shared with the generating tasks.
+#[derive(Resource, Clone)]
+struct ChunkedWorld(Arc<Mutex<World>>);
+
+/// How far apart the fields are, see [`WorldGenPlugin::with_tile_size`].
+#[derive(Resource, Clone, Copy)]
+struct TileSize(f32);
+
+/// A chunk being generated, until its fields get spawned.
+#[derive(Component)]
+struct ChunkTask(Task<Result<Plot, WfcError>>);
+
+impl WorldGenPlugin {
+    /// Generates a `width` x `height` plot of the built-in [`TileSet::terrain`] at startup.
+    pub fn new(width: usize, height: usize) -> Self {
+        WorldGenPlugin::for_tileset(TileSet::terrain(), width, height)
+    }
+
+    /// Generates a `width` x `height` plot made of the tiles in `tileset` at startup. Nothing
+    /// gets generated if either is 0, e.g. for worlds of chunks only.
+    pub fn for_tileset(tileset: impl Into<Arc<TileSet>>, width: usize, height: usize) -> Self {
+        WorldGenPlugin {
+            tileset: tileset.into(),
+            width,
+            height,
+            seed: rand::random(),
+            chunk_size: None,
+            tile_size: 16.0,
+        }
+    }
+
+    /// Derives the plot and the chunks from `seed`.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Generates [`Chunk`]s of `chunk_size` x `chunk_size` fields, from a [`World`] of the same
+    /// tiles and seed as the plot.
+    pub fn with_chunks(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = Some(chunk_size);
+        self
+    }
+
+    /// Places the fields `size` units apart, 16 by default.
+    pub fn with_tile_size(mut self, size: f32) -> Self {
+        self.tile_size = size;
+        self
+    }
+}
+
+impl Plugin for WorldGenPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TileSize(self.tile_size));
+
+        if self.width > 0 && self.height > 0 {
+            let (tileset, width, height, seed) = (
+                Arc::clone(&self.tileset),
+                self.width,
+                self.height,
+                self.seed,
+            );
+            app.add_systems(Startup, move |mut commands: Commands| -> Result {
+                let plot = PlotGenerator::for_tileset(Arc::clone(&tileset), width, height)
+                    .with_seed(seed)
+                    .generate()?;
+                commands.insert_resource(WorldPlot(plot));
+                Ok(())
+            });
+            app.add_systems(Update, spawn_plot.run_if(resource_added::<WorldPlot>));
+        }
+
+        if let Some(chunk_size) = self.chunk_size {
+            let world =
+                World::for_tileset(Arc::clone(&self.tileset), chunk_size).with_seed(self.seed);
+            app.insert_resource(ChunkedWorld(Arc::new(Mutex::new(world))));
+            app.add_systems(Update, (start_chunks, finish_chunks));
+        }
+    }
+}
+
+impl TileAtlas {
+    /// Shows the tiles with the sprites of `image`, cut into the sprites of `layout`.
+    pub fn new(image: Handle<Image>, layout: Handle<TextureAtlasLayout>) -> Self {
+        TileAtlas {
+            image,
+            layout,
+            indices: HashMap::new(),
+        }
+    }
+
+    /// Shows `tile` with the sprite at `index`.
+    pub fn with_index(mut self, tile: impl Into<TileId>, index: usize) -> Self {
+        self.indices.insert(tile.into(), index);
+        self
+    }
+
+    /// The index of the sprite showing `tile`.
+    pub fn index(&self, tile: TileId) -> usize {
+        self.indices.get(&tile).copied().unwrap_or(tile.0 as usize)
+    }
+
+    fn sprite(&self, tile: TileId) -> Sprite {
+        let atlas = TextureAtlas {
+            layout: self.layout.clone(),
+            index: self.index(tile),
+        };
+        Sprite::from_atlas_image(self.image.clone(), atlas)
+    }
+}
+
+impl Chunk {
+    pub fn new(cx: i64, cy: i64) -> Self {
+        Chunk { cx, cy }
+    }
+}
+
+/// The tile of the field at `(x, y)` of `plot`, whose first field lies at the world position
+/// `origin`, and its place relative to the plot.
+fn field(
+    plot: &Plot,
+    (x, y): (usize, usize),
+    origin: (i64, i64),
+    size: TileSize,
+) -> (TileSprite, Transform) {
+    let tile = TileSprite {
+        x: origin.0 + x as i64,
+        y: origin.1 + y as i64,
+        tile: plot.get(x, y),
+    };
+    let transform = Transform::from_xyz(x as f32 * size.0, -(y as f32) * size.0, 0.0);
+    (tile, transform)
+}
+
+fn spawn_plot(
+    mut commands: Commands,
+    plot: Res<WorldPlot>,
+    size: Res<TileSize>,
+    atlas: Option<Res<TileAtlas>>,
+) {
+    for pos in plot.0.positions() {
+        let (tile, transform) = field(&plot.0, pos, (0, 0), *size);
+        let mut entity = commands.spawn((tile, transform));
+        if let Some(atlas) = &atlas {
+            entity.insert(atlas.sprite(tile.tile));
+        }
+    }
+}
+
+/// Starts generating the chunks spawned since the last frame.
+fn start_chunks(
+    mut commands: Commands,
+    world: Res<ChunkedWorld>,
+    size: Res<TileSize>,
+    chunks: Query<(Entity, &Chunk), Added<Chunk>>,
+) {
+    let pool = AsyncComputeTaskPool::get();
+    let chunk_size = world.0.lock().expect("No task panicked").chunk_size() as f32;
+
+    for (entity, &Chunk { cx, cy }) in &chunks {
+        let world = Arc::clone(&world.0);
+        let task = pool.spawn(async move {
+            let mut world = world.lock().expect("No task panicked");
+            world.chunk(cx, cy).cloned()
+        });
+
+        let origin = Transform::from_xyz(
+            cx as f32 * chunk_size * size.0,
+            -(cy as f32 * chunk_size * size.0),
+            0.0,
+        );
+        commands.entity(entity).insert((ChunkTask(task), origin));
+    }
+}
+
+/// Spawns the fields of the chunks generated since the last frame.
+fn finish_chunks(
+    mut commands: Commands,
+    size: Res<TileSize>,
+    atlas: Option<Res<TileAtlas>>,
+    mut tasks: Query<(Entity, &Chunk, &mut ChunkTask)>,
+) -> Result {
+    for (entity, chunk, mut task) in &mut tasks {
+        let Some(plot) = block_on(future::poll_once(&mut task.0)) else {
+            continue;
+        };
+        commands.entity(entity).remove::<ChunkTask>();
+        let plot = plot?;
+
+        let origin = (
+            chunk.cx * plot.width() as i64,
+            chunk.cy * plot.height() as i64,
+        );
+        commands.entity(entity).with_children(|parent| {
+            for pos in plot.positions() {
+                let (tile, transform) = field(&plot, pos, origin, *size);
+                let mut entity = parent.spawn((tile, transform));
+                if let Some(atlas) = &atlas {
+                    entity.insert(atlas.sprite(tile.tile));
+                }
+            }
+        });
+    }
+
+    Ok(())
+}
@@ -0,0 +1,128 @@
+use crate::prelude::*;
+use crate::{Direction, TileId};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Factors the weights of tiles get multiplied with, field by field, see
+/// [`PlotGenerator::with_bias`](crate::PlotGenerator::with_bias). They give a plot a layout on
+/// a large scale, like farmland around the center or rivers towards the west, where the weights
+/// alone only set how common a tile is.
+///
+/// Every factor starts at 1, and calls for the same tile multiply:
+///
+/// ```
+/// use world_gen::{BiasMap, Direction, PlotGenerator, Tile};
+///
+/// let bias = BiasMap::new(32, 32)
+///     .with_radial(Tile::Farmland, 4.0, 0.25)
+///     .with_gradient(Tile::River, Direction::West, 3.0, 0.5);
+/// let plot = PlotGenerator::new(32, 32).with_bias(bias).with_seed(5).generate()?;
+/// # Ok::<(), world_gen::GenError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BiasMap {
+    width: usize,
+    height: usize,
+    /// Row-major factors of the tiles with any.
+    factors: HashMap<TileId, Vec<f64>>,
+}
+
+impl BiasMap {
+    /// A map for a `width` x `height` grid that leaves every weight as it is.
+    pub fn new(width: usize, height: usize) -> Self {
+        BiasMap {
+            width,
+            height,
+            factors: HashMap::new(),
+        }
+    }
+
+    /// Multiplies the weight of `tile` with `factor(x, y)` on the field at `(x, y)`. Factors
+    /// have to be at least 0, 0 keeps the tile from being picked there.
+    pub fn with_factor(
+        mut self,
+        tile: impl Into<TileId>,
+        factor: impl Fn(usize, usize) -> f64,
+    ) -> Self {
+        let (width, height) = (self.width, self.height);
+        let factors = self
+            .factors
+            .entry(tile.into())
+            .or_insert_with(|| vec![1.0; width * height]);
+        for y in 0..height {
+            for x in 0..width {
+                let factor = factor(x, y);
+                assert!(factor >= 0.0, "Bias factors can't be negative");
+                factors[y * width + x] *= factor;
+            }
+        }
+        self
+    }
+
+    /// Multiplies the weight of `tile` with `center` in the center of the grid, going over to
+    /// `edge` towards the middle of the edges and staying there beyond.
+    pub fn with_radial(self, tile: impl Into<TileId>, center: f64, edge: f64) -> Self {
+        let (half_width, half_height) = (self.width as f64 / 2.0, self.height as f64 / 2.0);
+        self.with_factor(tile, |x, y| {
+            let dx = (x as f64 + 0.5 - half_width) / half_width;
+            let dy = (y as f64 + 0.5 - half_height) / half_height;
+            lerp(center, edge, dx.hypot(dy).min(1.0))
+        })
+    }
+
+    /// Multiplies the weight of `tile` with `near` on the edge of the grid in `direction`, going
+    /// over to `far` on the opposite edge, or corner for diagonal directions.
+    pub fn with_gradient(
+        self,
+        tile: impl Into<TileId>,
+        direction: Direction,
+        near: f64,
+        far: f64,
+    ) -> Self {
+        let (dx, dy) = direction.offset();
+        // How far a field lies in `direction`, from 0 on the far side to 1 on the near one
+        let span = ((self.width.max(1) - 1) * dx.unsigned_abs()
+            + (self.height.max(1) - 1) * dy.unsigned_abs())
+        .max(1) as f64;
+        let (width, height) = (self.width as isize, self.height as isize);
+        self.with_factor(tile, |x, y| {
+            let along_x = if dx < 0 {
+                width - 1 - x as isize
+            } else {
+                x as isize
+            };
+            let along_y = if dy < 0 {
+                height - 1 - y as isize
+            } else {
+                y as isize
+            };
+            let along = along_x * dx.abs() + along_y * dy.abs();
+            lerp(far, near, along as f64 / span)
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The factor of `tile` on the field at `(x, y)`.
+    pub fn factor(&self, tile: impl Into<TileId>, x: usize, y: usize) -> f64 {
+        self.factor_at(tile.into(), y * self.width + x)
+    }
+
+    /// The factor of `tile` on the field at row-major `index`.
+    pub(crate) fn factor_at(&self, tile: TileId, index: usize) -> f64 {
+        self.factors
+            .get(&tile)
+            .map_or(1.0, |factors| factors[index])
+    }
+}
+
+fn lerp(from: f64, to: f64, t: f64) -> f64 {
+    from + (to - from) * t
+}
@@ -0,0 +1,104 @@
+//! The `batch` subcommand, generating many plots and keeping the best one.
+use clap::ValueEnum;
+use world_gen::Plot;
+
+/// What makes a plot better than another, higher scores are better.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Score {
+    /// The most evenly mixed tiles, by the entropy of their distribution
+    TileBalance,
+    /// The fewest groups of connected fields, for big areas instead of speckles
+    Regions,
+    /// The most fields of the tile named River
+    RiverLength,
+    /// The most expensive cheapest path from the first to the last passable field, row by row,
+    /// for plots that take long to cross. Plots without such a path score 0
+    PathLength,
+}
+
+impl Score {
+    /// The function scoring a plot.
+    pub fn function(self) -> fn(&Plot) -> f64 {
+        match self {
+            Score::TileBalance => Plot::global_entropy,
+            Score::Regions => regions,
+            Score::RiverLength => river_length,
+            Score::PathLength => path_length,
+        }
+    }
+}
+
+/// Minus how many regions the tiles of `plot` form.
+fn regions(plot: &Plot) -> f64 {
+    let stats = plot.stats();
+    -(stats
+        .tiles
+        .iter()
+        .map(|tile| tile.regions.len())
+        .sum::<usize>() as f64)
+}
+
+fn river_length(plot: &Plot) -> f64 {
+    match plot.tileset().id("River") {
+        Some(river) => plot.count(river) as f64,
+        None => 0.0,
+    }
+}
+
+fn path_length(plot: &Plot) -> f64 {
+    let tileset = plot.tileset();
+    let mut passable = plot
+        .positions()
+        .filter(|&(x, y)| tileset.passable(plot.get(x, y)));
+    let (Some(start), Some(goal)) = (passable.next(), passable.last()) else {
+        return 0.0;
+    };
+    let cost = |tile| tileset.passable(tile).then(|| tileset.cost(tile));
+    match plot.find_path(start, goal, cost) {
+        Some(path) => path[1..]
+            .iter()
+            .map(|&(x, y)| tileset.cost(plot.get(x, y)))
+            .sum(),
+        None => 0.0,
+    }
+}
+
+/// Generates a plot for every seed with `generate`, which returns `None` if it fails, and ranks
+/// the seeds of the others by `score`, best first. Returns the ranking as `(seed, score)` and the
+/// best plot, only keeping that one around.
+pub fn rank(
+    seeds: impl Iterator<Item = u64>,
+    generate: impl Fn(u64) -> Option<Plot>,
+    score: impl Fn(&Plot) -> f64,
+) -> (Vec<(u64, f64)>, Option<Plot>) {
+    let mut ranking = vec![];
+    let mut best: Option<(f64, Plot)> = None;
+
+    for seed in seeds {
+        let Some(plot) = generate(seed) else {
+            continue;
+        };
+        let score = score(&plot);
+        ranking.push((seed, score));
+        // Ties keep the earlier seed
+        if best.as_ref().is_none_or(|(best, _)| score > *best) {
+            best = Some((score, plot));
+        }
+    }
+
+    // Stable, so ties stay in the order of the seeds
+    ranking.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    (ranking, best.map(|(_, plot)| plot))
+}
+
+/// Prints the `top` seeds of `ranking` to stderr, with how many of the `count` plots failed.
+pub fn print_ranking(ranking: &[(u64, f64)], top: usize, count: usize) {
+    eprintln!("{:>4}  {:>20}  {:>12}", "Rank", "Seed", "Score");
+    for (i, (seed, score)) in ranking.iter().take(top).enumerate() {
+        eprintln!("{:>4}  {seed:>20}  {score:>12.3}", i + 1);
+    }
+    let failed = count - ranking.len();
+    if failed > 0 {
+        eprintln!("{failed} of {count} plots failed to generate");
+    }
+}
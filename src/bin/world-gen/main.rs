@@ -0,0 +1,788 @@
+mod batch;
+mod repl;
+mod serve;
+mod tui;
+
+use batch::Score;
+use clap::builder::PossibleValuesParser;
+use clap::{Parser, Subcommand, ValueEnum};
+use crossterm::{cursor, execute, queue, terminal};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use world_gen::{
+    Annotated, Ansi, Blocks, Csv, Elevation, Golden, Hydrology, IslandGenerator, JsonGrid, Legend,
+    Mask, Plot, PlotGenerator, Prefab, Renderer, Ruleset, SelectionStrategy, Solver, Step,
+    SvgStyle, Theme, TileId, TileSet, TiledExport, Topology, WaveState, WfcError,
+};
+
+/// Generates a tile based world with the wave function collapse algorithm.
+#[derive(Parser)]
+#[command(version)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Width of the plot, in tiles
+    #[arg(long, global = true, default_value_t = 16)]
+    width: usize,
+
+    /// Height of the plot, in tiles
+    #[arg(long, global = true, default_value_t = 16)]
+    height: usize,
+
+    /// Seed for a reproducible plot, random if left out
+    #[arg(long, global = true)]
+    seed: Option<u64>,
+
+    /// RON file with the tile set, the built-in world if left out
+    #[arg(long, global = true)]
+    tileset: Option<PathBuf>,
+
+    /// Generate with a built-in tile set and the passes that go with it, instead of `--tileset`
+    #[arg(long, global = true, value_enum, conflicts_with = "tileset")]
+    preset: Option<Preset>,
+
+    /// File to write the plot to, stdout if left out. A `.png` or `.svg` file gets rendered as an
+    /// image, hexagons with `--hex`, `.tmx` and `.json` files become Tiled maps and a `.ron`
+    /// file keeps the whole plot along with its tile set
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// Print the plot as text in this format, or write it to `--output` in it whatever the
+    /// extension. Without it the plot gets printed in the glyphs of `--theme`
+    #[arg(long, value_enum)]
+    format: Option<Format>,
+
+    /// Put a header with the size, seed and options of the plot before it when writing it as
+    /// text, and a legend of the glyphs after it, so the map tells how to generate it again.
+    /// `diff` can't read annotated plots back
+    #[arg(long)]
+    annotate: bool,
+
+    /// Compare the plot, as text in `--format` or else in the glyphs of the tile set, byte for
+    /// byte with this golden file instead of printing it. Exits with 1 if they differ
+    #[arg(long, value_name = "FILE")]
+    golden: Option<PathBuf>,
+
+    /// Write the plot to the `--golden` file instead of comparing it, after an intended change.
+    /// Setting `WORLD_GEN_BLESS=1` does the same
+    #[arg(long, requires = "golden")]
+    bless: bool,
+
+    /// Size of a tile in pixels, when writing an image
+    #[arg(long, default_value_t = 8)]
+    scale: u32,
+
+    /// The Tiled tile set the tiles of a `.tmx` or `.json` map refer to, in the order of the tile
+    /// set of the plot
+    #[arg(long, default_value = "tileset.tsx")]
+    tiled_tileset: String,
+
+    /// Generate a grid of hexagons instead of squares
+    #[arg(long)]
+    hex: bool,
+
+    /// Shape the plot with a Perlin noise heightmap with hills this many tiles wide: water stays
+    /// low and mountains high. Only affects tiles named River, Lake and Mountain
+    #[arg(long)]
+    elevation: Option<f64>,
+
+    /// Let this many rivers flow downhill over the heightmap of `--elevation`, from springs in
+    /// the highlands to the edge. Uses the tile named River
+    #[arg(long, value_name = "COUNT", requires = "elevation")]
+    rivers: Option<usize>,
+
+    /// Surround the plot with these tiles, by name. Can be given several times to allow any of
+    /// them
+    #[arg(long)]
+    border: Vec<String>,
+
+    /// Only generate the fields inside this stencil, e.g. an island: a `.png` image where bright
+    /// pixels lie inside, or text where every character but spaces and `.` does. Needs
+    /// `--mask-fill`, and has to match `--width` and `--height`
+    #[arg(long, value_name = "FILE", requires = "mask_fill")]
+    mask: Option<PathBuf>,
+
+    /// The tile, by name, of the fields outside `--mask`
+    #[arg(long, value_name = "TILE", requires = "mask")]
+    mask_fill: Option<String>,
+
+    /// Stamp the structure of this prefab file before generating, a `.json` or `.ron` file with
+    /// its rows of characters, the tile names of the characters, and where to put it, see
+    /// `Prefab`. Can be given several times
+    #[arg(long, value_name = "FILE")]
+    prefab: Vec<PathBuf>,
+
+    /// Which field to collapse next
+    #[arg(long, value_enum, default_value_t = Selection::LowestEntropy)]
+    selection: Selection,
+
+    /// Solve with min-conflicts repair instead of wave function collapse, giving up after this
+    /// many repairs
+    #[arg(long, value_name = "STEPS")]
+    min_conflicts: Option<usize>,
+
+    /// Never fail: give fields that run out of possibilities the tile breaking the fewest rules,
+    /// and report the broken rules on stderr
+    #[arg(long, conflicts_with = "min_conflicts")]
+    best_effort: bool,
+
+    /// Redraw the plot after every collapse, showing the entropy of uncollapsed fields
+    #[arg(long)]
+    animate: bool,
+
+    /// If the generation fails, write how many possibilities every field had left to this file,
+    /// to see where the contradictions build up. A `.png` file gets rendered as an image
+    #[arg(long, value_name = "FILE")]
+    heatmap: Option<PathBuf>,
+
+    /// Print how the tiles are distributed after the plot: fields, regions, the largest regions
+    /// and the share of the edge of every tile
+    #[arg(long)]
+    stats: bool,
+
+    /// Pause between two frames of the animation, in milliseconds
+    #[arg(long, default_value_t = 20)]
+    delay: u64,
+
+    /// Color the tiles when printing to a terminal
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// The glyphs and colors of the tiles when printing to a terminal
+    #[arg(long, default_value = "classic", value_parser = PossibleValuesParser::new(Theme::BUILTIN))]
+    theme: String,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Explore plots interactively: move around, zoom, regenerate with other seeds and inspect
+    /// the tiles
+    Tui,
+    /// Compare two plots written with `--output`, as text or `.ron` files: prints the second one
+    /// with the changed fields highlighted and lists the changes. Exits with 1 if they differ
+    Diff { a: PathBuf, b: PathBuf },
+    /// Check the rules of the tile set for authoring errors, like rules that only go one way or
+    /// tiles without neighbours, and list them with fixes. Exits with 1 if there are any
+    Check,
+    /// Generate plots from `--count` seeds in a row, starting at `--seed`, and write the one
+    /// with the best `--score` like a single plot. A ranking of the seeds goes to stderr. The
+    /// options of the plots, like `--output` or `--elevation`, go before `batch`
+    Batch {
+        /// How many plots to generate
+        #[arg(long, default_value_t = 10)]
+        count: usize,
+        /// What makes a plot better than another
+        #[arg(long, value_enum, default_value_t = Score::TileBalance)]
+        score: Score,
+        /// How many of the best seeds the ranking lists
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+    },
+    /// Tweak the weights and rules of the tile set line by line, like `weight River 2` or
+    /// `forbid River Farmland`, and see the plot of the same seed before and after every change
+    /// side by side. `help` lists the commands. The options of the plots go before `repl`
+    Repl,
+    /// Generate plots over HTTP: `GET /map?w=64&h=64&seed=42&format=json` answers with a plot
+    /// as JSON, a `png` image or an `svg` drawing, `scale` sets the pixels per tile of images.
+    /// Leaving out `w`, `h` or `seed` takes `--width`, `--height` and a random seed, the seed
+    /// comes back in the `X-Seed` header
+    Serve {
+        /// The port to listen on
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+        /// The address to listen on, `0.0.0.0` for every interface
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Preset {
+    /// The built-in world, the same as leaving out `--tileset`
+    World,
+    /// Rooms, corridors and doors, with corridors carved through the walls until every room can
+    /// be reached
+    Dungeon,
+    /// An island: ocean along the border, land in the middle and a smoothed coast
+    Island,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Selection {
+    /// The most constrained field
+    LowestEntropy,
+    /// The first field, row by row
+    Scanline,
+    /// A random field
+    Random,
+    /// The field closest to the last one, growing the plot outwards
+    Nearest,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// The glyphs of the tile set, which `diff` reads back
+    Blocks,
+    /// A character per tile, followed by a legend of the characters
+    Legend,
+    /// The glyphs of `--theme`, always with ANSI colors
+    Ansi,
+    /// A JSON object with the names of the tiles and the rows of their ids
+    Json,
+    /// The names of the tiles, separated by commas
+    Csv,
+}
+
+impl Format {
+    fn renderer(self, theme: &Theme) -> Box<dyn Renderer> {
+        match self {
+            Format::Blocks => Box::new(Blocks),
+            Format::Legend => Box::new(Legend),
+            Format::Ansi => Box::new(Ansi::new(theme.clone().with_colors(true))),
+            Format::Json => Box::new(JsonGrid),
+            Format::Csv => Box::new(Csv),
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ColorChoice {
+    /// Only if stdout is a terminal and `NO_COLOR` isn't set
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Auto => {
+                std::io::stdout().is_terminal()
+                    && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+                    && std::env::var_os("TERM").is_none_or(|term| term != "dumb")
+            }
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let tileset = match (&args.tileset, args.preset) {
+        (Some(path), _) => TileSet::load(path).unwrap_or_else(|err| fail(err)),
+        (None, Some(Preset::Dungeon)) => TileSet::dungeon(),
+        (None, Some(Preset::Island)) => TileSet::island(),
+        (None, Some(Preset::World) | None) => TileSet::world(),
+    };
+
+    let theme = Theme::builtin(&args.theme)
+        .expect("clap only accepts built-in themes")
+        .with_colors(args.color.enabled());
+
+    match &args.command {
+        Some(Command::Tui) => {
+            let seed = args.seed.unwrap_or_else(rand::random);
+            tui::run(tileset, args.width, args.height, seed).unwrap_or_else(|err| fail(err));
+            return;
+        }
+        Some(Command::Diff { a, b }) => {
+            diff(tileset, a, b, args.color.enabled());
+            return;
+        }
+        Some(Command::Check) => {
+            let diagnostics = tileset.validate(&tileset);
+            for diagnostic in &diagnostics {
+                println!("{diagnostic}");
+            }
+            if !diagnostics.is_empty() {
+                std::process::exit(1);
+            }
+            println!("The tile set has no problems");
+            return;
+        }
+        Some(Command::Batch { count, score, top }) => {
+            let first = args.seed.unwrap_or_else(rand::random);
+            let tileset = Arc::new(tileset);
+            let seeds = (0..*count as u64).map(|i| first.wrapping_add(i));
+            let (ranking, best) = batch::rank(
+                seeds,
+                |seed| {
+                    let mut gen = generator(&args, Arc::clone(&tileset), seed);
+                    let mut plot = match args.best_effort {
+                        true => gen.generate_best_effort().0,
+                        false => gen.generate().ok()?,
+                    };
+                    finish(&args, &mut plot, seed);
+                    Some(plot)
+                },
+                score.function(),
+            );
+            batch::print_ranking(&ranking, *top, *count);
+            let Some(best) = best else {
+                fail("Every plot failed to generate");
+            };
+            output(&best, &args, &theme, ranking[0].0);
+            if args.stats {
+                print!("{}", best.stats());
+            }
+            return;
+        }
+        Some(Command::Repl) => {
+            let seed = args.seed.unwrap_or_else(rand::random);
+            repl::run(
+                tileset,
+                seed,
+                |tileset, seed| {
+                    let mut gen = generator(&args, tileset, seed);
+                    let mut plot = match args.best_effort {
+                        true => gen.generate_best_effort().0,
+                        false => gen.generate().map_err(|err| err.to_string())?,
+                    };
+                    finish(&args, &mut plot, seed);
+                    Ok(plot)
+                },
+                |plot| match args.format {
+                    Some(format) => plot.render(&*format.renderer(&theme)),
+                    None => plot.display_themed(&theme).to_string(),
+                },
+            )
+            .unwrap_or_else(|err| fail(err));
+            return;
+        }
+        Some(Command::Serve { port, host }) => {
+            let server = serve::Server {
+                tileset: Arc::new(tileset),
+                width: args.width,
+                height: args.height,
+                dungeon: args.preset == Some(Preset::Dungeon),
+            };
+            serve::run(server, &format!("{host}:{port}")).unwrap_or_else(|err| fail(err));
+            return;
+        }
+        None => {}
+    }
+
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let mut gen = generator(&args, Arc::new(tileset), seed);
+    if args.animate {
+        let collapsed = animate(&mut gen, Duration::from_millis(args.delay), &theme)
+            .unwrap_or_else(|err| fail(err));
+        if let Err(err) = collapsed {
+            fail(err);
+        }
+    }
+    let generated = if args.best_effort {
+        let (plot, violations) = gen.generate_best_effort();
+        for violation in &violations {
+            let ((x, y), (nx, ny)) = (violation.pos, violation.neighbour);
+            eprintln!("Broken rule between ({x}, {y}) and ({nx}, {ny})");
+        }
+        Ok(plot)
+    } else {
+        gen.generate()
+    };
+    let mut plot = match generated {
+        Ok(plot) => plot,
+        Err(err) => {
+            if let Some(path) = &args.heatmap {
+                write_heatmap(&gen, path, args.scale);
+            }
+            fail(err)
+        }
+    };
+    finish(&args, &mut plot, seed);
+
+    if let Some(path) = &args.golden {
+        let renderer = match args.format {
+            Some(format) => format.renderer(&theme),
+            None => Box::new(Blocks),
+        };
+        let text = plot.render(&*renderer);
+        let checked = if args.bless {
+            Golden::bless(path, &text)
+        } else {
+            Golden::verify(path, &text)
+        };
+        checked.unwrap_or_else(|err| fail(err));
+    } else {
+        output(&plot, &args, &theme, seed);
+    }
+    if args.stats {
+        print!("{}", plot.stats());
+    }
+}
+
+/// Sets up the generator of the plot for `seed` with the options of `args`.
+fn generator(args: &Args, tileset: Arc<TileSet>, seed: u64) -> PlotGenerator {
+    let elevation = args
+        .elevation
+        .map(|scale| elevation(&tileset, args.width, args.height, seed as u32, scale));
+
+    let selection = match args.selection {
+        Selection::LowestEntropy => SelectionStrategy::LowestEntropy,
+        Selection::Scanline => SelectionStrategy::Scanline,
+        Selection::Random => SelectionStrategy::Random,
+        Selection::Nearest => SelectionStrategy::Nearest,
+    };
+    let gen = match args.preset {
+        Some(Preset::Island) => island(args, seed).generator(),
+        _ => PlotGenerator::for_tileset(tileset, args.width, args.height),
+    };
+    let mut gen = gen.with_selection(selection).with_seed(seed);
+    if args.hex {
+        gen = gen.with_topology(Topology::Hex);
+    }
+    if let Some(max_steps) = args.min_conflicts {
+        gen = gen.with_solver(Solver::MinConflicts { max_steps });
+    }
+    if let Some(elevation) = elevation {
+        if let Some(count) = args.rivers {
+            rivers(&mut gen, &elevation, seed, count);
+        }
+        gen = gen.with_elevation(elevation);
+    }
+    if let (Some(path), Some(fill)) = (&args.mask, &args.mask_fill) {
+        let mask = read_mask(path);
+        if (mask.width(), mask.height()) != (args.width, args.height) {
+            fail(format!(
+                "The mask is {}x{}, but the plot {}x{}",
+                mask.width(),
+                mask.height(),
+                args.width,
+                args.height
+            ));
+        }
+        let fill = gen
+            .tileset()
+            .id(fill)
+            .unwrap_or_else(|| fail(format!("Unknown mask fill tile {fill}")));
+        gen = gen.with_mask(mask, fill);
+    }
+    for (i, path) in args.prefab.iter().enumerate() {
+        let prefab = Prefab::load(gen.tileset(), path)
+            .unwrap_or_else(|err| fail(format!("{}: {err}", path.display())));
+        if prefab
+            .with_seed(seed.wrapping_add(i as u64))
+            .apply(&mut gen)
+            .is_none()
+        {
+            fail(format!("{} fits nowhere on the plot", path.display()));
+        }
+    }
+    if !args.border.is_empty() {
+        let border = args
+            .border
+            .iter()
+            .map(|name| {
+                gen.tileset()
+                    .id(name)
+                    .unwrap_or_else(|| fail(format!("Unknown border tile {name}")))
+            })
+            .collect::<Vec<_>>();
+        gen.constrain_border(&border)
+            .unwrap_or_else(|err| fail(err));
+    }
+    gen
+}
+
+/// The island of `--preset island` for `seed`.
+fn island(args: &Args, seed: u64) -> IslandGenerator {
+    IslandGenerator::new(args.width, args.height).with_seed(seed)
+}
+
+/// Runs the passes of the preset over a generated `plot`.
+fn finish(args: &Args, plot: &mut Plot, seed: u64) {
+    match args.preset {
+        Some(Preset::Dungeon) => connect_rooms(plot),
+        Some(Preset::Island) => {
+            island(args, seed).smooth(plot);
+        }
+        _ => {}
+    }
+}
+
+/// Writes `plot` as text in `--format`, or else in the format `--output` picks.
+fn output(plot: &Plot, args: &Args, theme: &Theme, seed: u64) {
+    if let Some(format) = args.format {
+        let text = text(plot, &*format.renderer(theme), args, seed);
+        match &args.output {
+            Some(path) => std::fs::write(path, text).unwrap_or_else(|err| fail(err)),
+            None => print!("{text}"),
+        }
+    } else {
+        write_plot(plot, args, theme, seed);
+    }
+}
+
+/// `plot` as text in `renderer`, with the header and legend of `--annotate` if it's set.
+fn text(plot: &Plot, renderer: &dyn Renderer, args: &Args, seed: u64) -> String {
+    if !args.annotate {
+        return plot.render(renderer);
+    }
+
+    let shown = |path: &PathBuf| path.display().to_string();
+    let parameters = [
+        ("tileset", args.tileset.as_ref().map(shown)),
+        ("preset", args.preset.map(value_name)),
+        ("selection", Some(value_name(args.selection))),
+        ("hex", args.hex.then(|| "true".to_string())),
+        (
+            "min-conflicts",
+            args.min_conflicts.map(|steps| steps.to_string()),
+        ),
+        ("best-effort", args.best_effort.then(|| "true".to_string())),
+        ("elevation", args.elevation.map(|scale| scale.to_string())),
+        ("rivers", args.rivers.map(|count| count.to_string())),
+        (
+            "border",
+            (!args.border.is_empty()).then(|| args.border.join(", ")),
+        ),
+        ("mask", args.mask.as_ref().map(shown)),
+        ("mask-fill", args.mask_fill.clone()),
+        (
+            "prefab",
+            (!args.prefab.is_empty())
+                .then(|| args.prefab.iter().map(shown).collect::<Vec<_>>().join(", ")),
+        ),
+    ];
+    let renderer = parameters
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value?)))
+        .fold(
+            Annotated::new(renderer).with_seed(seed),
+            |renderer, (name, value)| renderer.with_parameter(name, value),
+        );
+    plot.render(&renderer.with_legend())
+}
+
+/// The name `value` has on the command line.
+fn value_name(value: impl ValueEnum) -> String {
+    let value = value.to_possible_value().expect("No value is skipped");
+    value.get_name().to_string()
+}
+
+/// Writes `plot` to `--output` in the format its extension picks, or prints it.
+fn write_plot(plot: &Plot, args: &Args, theme: &Theme, seed: u64) {
+    match &args.output {
+        Some(path) if path.extension().is_some_and(|ext| ext == "png") => plot
+            .to_image(args.scale)
+            .save(path)
+            .unwrap_or_else(|err| fail(err)),
+        Some(path) if path.extension().is_some_and(|ext| ext == "tmx") => {
+            let tmx = TiledExport::new(&args.tiled_tileset).to_tmx(plot);
+            std::fs::write(path, tmx).unwrap_or_else(|err| fail(err))
+        }
+        Some(path) if path.extension().is_some_and(|ext| ext == "json") => {
+            let json = TiledExport::new(&args.tiled_tileset).to_json(plot);
+            std::fs::write(path, json).unwrap_or_else(|err| fail(err))
+        }
+        Some(path) if path.extension().is_some_and(|ext| ext == "ron") => {
+            let ron = ron::ser::to_string_pretty(plot, Default::default())
+                .unwrap_or_else(|err| fail(err));
+            std::fs::write(path, ron).unwrap_or_else(|err| fail(err))
+        }
+        Some(path) if args.hex && path.extension().is_some_and(|ext| ext == "svg") => {
+            std::fs::write(path, plot.to_hex_svg(args.scale as f64)).unwrap_or_else(|err| fail(err))
+        }
+        Some(path) if path.extension().is_some_and(|ext| ext == "svg") => {
+            let svg = plot.to_svg(&SvgStyle::new(args.scale as f64));
+            std::fs::write(path, svg).unwrap_or_else(|err| fail(err))
+        }
+        Some(path) if args.hex => {
+            std::fs::write(path, plot.display_hex().to_string()).unwrap_or_else(|err| fail(err))
+        }
+        Some(path) => {
+            std::fs::write(path, text(plot, &Blocks, args, seed)).unwrap_or_else(|err| fail(err))
+        }
+        None if args.hex => println!("{}", plot.display_hex()),
+        // The last frame of the animation already shows the plot
+        None if args.animate => {}
+        None => println!("{}", text(plot, &Ansi::new(theme.clone()), args, seed)),
+    }
+}
+
+/// A Perlin heightmap keeping water in the lowlands and mountains in the highlands.
+fn elevation(tileset: &TileSet, width: usize, height: usize, seed: u32, scale: f64) -> Elevation {
+    let mut elevation = Elevation::perlin(width, height, seed, scale);
+    for name in ["River", "Lake"] {
+        if let Some(tile) = tileset.id(name) {
+            elevation = elevation.with_range(tile, ..0.5);
+        }
+    }
+    if let Some(tile) = tileset.id("Mountain") {
+        elevation = elevation.with_range(tile, 0.55..);
+    }
+    elevation
+}
+
+/// Stamps `count` rivers flowing over `elevation` into `gen`, before the elevation would keep
+/// them out of the highlands.
+fn rivers(gen: &mut PlotGenerator, elevation: &Elevation, seed: u64, count: usize) {
+    let river = gen
+        .tileset()
+        .id("River")
+        .unwrap_or_else(|| fail("Rivers need a tile named River"));
+    Hydrology::new(river)
+        .with_random_springs(count)
+        .with_seed(seed)
+        .apply(gen, elevation)
+        .unwrap_or_else(|err| fail(err));
+}
+
+/// Reads a stencil for `--mask`, from an image if it is a `.png` file.
+fn read_mask(path: &Path) -> Mask {
+    if path.extension().is_some_and(|ext| ext == "png") {
+        let image =
+            image::open(path).unwrap_or_else(|err| fail(format!("{}: {err}", path.display())));
+        Mask::from_image(&image)
+    } else {
+        let text = std::fs::read_to_string(path)
+            .unwrap_or_else(|err| fail(format!("{}: {err}", path.display())));
+        Mask::from_text(&text)
+    }
+}
+
+/// Carves corridors through the walls of a dungeon until every passable field can be reached.
+fn connect_rooms(plot: &mut Plot) {
+    let tileset = plot.tileset();
+    let walkable = tileset
+        .ids()
+        .filter(|&tile| tileset.passable(tile))
+        .collect::<Vec<_>>();
+    let corridor = tileset.id("Corridor").expect("The dungeon has corridors");
+    plot.connect_regions(&walkable, corridor);
+}
+
+/// Prints `b` with the fields that differ from `a` highlighted, in red if `colors` or else by
+/// leaving out the others, followed by the changes. Exits with 1 if there are any.
+fn diff(tileset: TileSet, a: &Path, b: &Path, colors: bool) {
+    let tileset = Arc::new(tileset);
+    let (a, b) = (read_plot(&tileset, a), read_plot(&tileset, b));
+    if (a.width(), a.height()) != (b.width(), b.height()) {
+        fail(format!(
+            "Can't compare a {}x{} plot with a {}x{} one",
+            a.width(),
+            a.height(),
+            b.width(),
+            b.height()
+        ));
+    }
+
+    let changes = a.diff(&b);
+    if changes.is_empty() {
+        println!("The plots are the same");
+        return;
+    }
+
+    let cell = b.tileset().glyph(TileId(0)).chars().count();
+    for y in 0..b.height() {
+        for x in 0..b.width() {
+            let glyph = b.tileset().glyph(b.get(x, y));
+            match (a.get(x, y) == b.get(x, y), colors) {
+                (true, true) => print!("{glyph}"),
+                (true, false) => print!("{:cell$}", ""),
+                (false, true) => print!("\x1b[41m{glyph}\x1b[0m"),
+                (false, false) => print!("{glyph}"),
+            }
+        }
+        println!();
+    }
+
+    for (x, y, from, to) in &changes {
+        let (from, to) = (a.tileset().name(*from), b.tileset().name(*to));
+        println!("({x}, {y}): {from} -> {to}");
+    }
+    println!(
+        "{} of {} fields differ",
+        changes.len(),
+        a.width() * a.height()
+    );
+    std::process::exit(1);
+}
+
+/// Reads a plot written with `--output`, from a `.ron` file or else from the glyphs of
+/// `tileset`.
+fn read_plot(tileset: &Arc<TileSet>, path: &Path) -> Plot {
+    let src = std::fs::read_to_string(path)
+        .unwrap_or_else(|err| fail(format!("{}: {err}", path.display())));
+    if path.extension().is_some_and(|ext| ext == "ron") {
+        ron::from_str(&src).unwrap_or_else(|err| fail(format!("{}: {err}", path.display())))
+    } else {
+        Plot::from_glyphs(tileset.clone(), &src)
+            .unwrap_or_else(|err| fail(format!("{}: {err}", path.display())))
+    }
+}
+
+/// Writes the heatmap of `gen` to `path`, as an image if it is a `.png` file.
+fn write_heatmap(gen: &PlotGenerator, path: &Path, scale: u32) {
+    if path.extension().is_some_and(|ext| ext == "png") {
+        gen.heatmap_image(scale)
+            .save(path)
+            .unwrap_or_else(|err| fail(err))
+    } else {
+        std::fs::write(path, gen.heatmap().to_string()).unwrap_or_else(|err| fail(err))
+    }
+}
+
+/// Collapses `gen` step by step, redrawing the terminal after every step. Collapsed fields show
+/// their glyph, the others how many tiles they can still become.
+fn animate(
+    gen: &mut PlotGenerator,
+    delay: Duration,
+    theme: &Theme,
+) -> std::io::Result<Result<(), WfcError>> {
+    let mut stdout = std::io::stdout();
+    let cell = gen.tileset().glyph(TileId(0)).chars().count();
+
+    execute!(
+        stdout,
+        terminal::Clear(terminal::ClearType::All),
+        cursor::Hide
+    )?;
+    let collapsed = loop {
+        queue!(stdout, cursor::MoveTo(0, 0))?;
+        for y in 0..gen.height() {
+            for x in 0..gen.width() {
+                match gen.get(x, y) {
+                    WaveState::Collapsed(tile) => {
+                        let glyph = theme.glyph(gen.tileset(), *tile);
+                        match theme.colors(gen.tileset(), *tile) {
+                            (None, None) => write!(stdout, "{glyph}")?,
+                            (fg, bg) => {
+                                if let Some([r, g, b]) = fg {
+                                    write!(stdout, "\x1b[38;2;{r};{g};{b}m")?;
+                                }
+                                if let Some([r, g, b]) = bg {
+                                    write!(stdout, "\x1b[48;2;{r};{g};{b}m")?;
+                                }
+                                write!(stdout, "{glyph}\x1b[0m")?
+                            }
+                        }
+                    }
+                    WaveState::Superposition(states) => write!(stdout, "{:>cell$}", states.len())?,
+                }
+            }
+            writeln!(stdout)?;
+        }
+        stdout.flush()?;
+
+        match gen.step() {
+            Ok(Step::Done) => break Ok(()),
+            Ok(_) => std::thread::sleep(delay),
+            Err(err) => break Err(err),
+        }
+    };
+    execute!(stdout, cursor::Show)?;
+
+    Ok(collapsed)
+}
+
+fn fail(err: impl std::fmt::Display) -> ! {
+    eprintln!("{err}");
+    std::process::exit(1);
+}
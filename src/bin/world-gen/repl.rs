@@ -0,0 +1,275 @@
+//! The `repl` subcommand, tweaking the rules of a tile set and seeing the plot change.
+use std::io::{BufRead, Write};
+use std::sync::Arc;
+use world_gen::{Direction, Plot, Ruleset, TileDef, TileSet};
+
+/// The commands, printed by `help`.
+const HELP: &str = "\
+weight TILE WEIGHT       set how likely TILE is picked
+allow TILE OTHER [SIDE]  let OTHER lie next to TILE, on every side or only on SIDE of it
+forbid TILE OTHER        keep OTHER from lying next to TILE, as far as their lists allow it
+seed [SEED]              generate with SEED, or with the next seed
+undo                     go back to the rules before the last change
+check                    list authoring errors in the rules
+load FILE                replace the tile set with a RON file
+save FILE                write the tile set to a RON file
+help                     show this list
+quit                     leave, also on end of input";
+
+/// The state of the session.
+struct Session {
+    defs: Vec<TileDef>,
+    tileset: Arc<TileSet>,
+    seed: u64,
+    /// The rules before every change, latest last.
+    undo: Vec<Vec<TileDef>>,
+    /// The last plot that generated, to compare the next one with.
+    plot: Option<Plot>,
+}
+
+/// Reads commands from stdin until `quit`, regenerating with `generate` after every change and
+/// printing the plot in the text of `render` next to the one before. `generate` gets the tile
+/// set and the seed, and fails with a message.
+pub fn run(
+    tileset: TileSet,
+    seed: u64,
+    generate: impl Fn(Arc<TileSet>, u64) -> Result<Plot, String>,
+    render: impl Fn(&Plot) -> String,
+) -> std::io::Result<()> {
+    let defs = tileset
+        .ids()
+        .map(|tile| tileset.def(tile).clone())
+        .collect();
+    let mut session = Session {
+        defs,
+        tileset: Arc::new(tileset),
+        seed,
+        undo: vec![],
+        plot: None,
+    };
+    println!("Type `help` for the commands");
+    session.regenerate(&generate, &render);
+
+    let mut stdin = std::io::stdin().lock();
+    let mut line = String::new();
+    loop {
+        print!("> ");
+        std::io::stdout().flush()?;
+        line.clear();
+        if stdin.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let words = line.split_whitespace().collect::<Vec<_>>();
+
+        let changed = match words[..] {
+            [] => Ok(false),
+            ["quit" | "exit"] => return Ok(()),
+            ["help"] => {
+                println!("{HELP}");
+                Ok(false)
+            }
+            ["weight", tile, weight] => match weight.parse::<f64>() {
+                Ok(weight) => session.change(|defs| {
+                    def_mut(defs, tile)?.weight = weight;
+                    Ok(())
+                }),
+                Err(_) => Err(format!("{weight} isn't a number")),
+            },
+            ["allow", tile, other] => session.change(|defs| {
+                known(defs, other)?;
+                def_mut(defs, tile)?.neighbours.push(other.to_string());
+                Ok(())
+            }),
+            ["allow", tile, other, side] => match direction(side) {
+                Some(direction) => session.change(|defs| {
+                    known(defs, other)?;
+                    let sides = &mut def_mut(defs, tile)?.sides;
+                    sides.entry(direction).or_default().push(other.to_string());
+                    Ok(())
+                }),
+                None => Err(format!("{side} isn't a side, like north or south-east")),
+            },
+            ["forbid", tile, other] => session.change(|defs| {
+                known(defs, tile)?;
+                known(defs, other)?;
+                // Rules go both ways, so either tile may list the other
+                for (a, b) in [(tile, other), (other, tile)] {
+                    let def = def_mut(defs, a)?;
+                    def.neighbours.retain(|name| name != b);
+                    for names in def.sides.values_mut() {
+                        names.retain(|name| name != b);
+                    }
+                }
+                Ok(())
+            }),
+            ["seed"] => {
+                session.seed = session.seed.wrapping_add(1);
+                Ok(true)
+            }
+            ["seed", seed] => match seed.parse() {
+                Ok(seed) => {
+                    session.seed = seed;
+                    Ok(true)
+                }
+                Err(_) => Err(format!("{seed} isn't a seed")),
+            },
+            ["undo"] => match session.undo.pop() {
+                Some(defs) => session.rebuild(defs).map(|()| true),
+                None => Err("Nothing to undo".to_string()),
+            },
+            ["check"] => {
+                let diagnostics = session.tileset.validate(&session.tileset);
+                for diagnostic in &diagnostics {
+                    println!("{diagnostic}");
+                }
+                if diagnostics.is_empty() {
+                    println!("The tile set has no problems");
+                }
+                Ok(false)
+            }
+            ["load", path] => match TileSet::load(path) {
+                Ok(tileset) => {
+                    let defs = tileset
+                        .ids()
+                        .map(|tile| tileset.def(tile).clone())
+                        .collect();
+                    session.change(|old| {
+                        *old = defs;
+                        Ok(())
+                    })
+                }
+                Err(err) => Err(format!("{path}: {err}")),
+            },
+            ["save", path] => {
+                let ron = ron::ser::to_string_pretty(&*session.tileset, Default::default())
+                    .map_err(|err| err.to_string());
+                ron.and_then(|ron| std::fs::write(path, ron).map_err(|err| err.to_string()))
+                    .map(|()| false)
+            }
+            _ => Err(format!("Unknown command `{}`, see `help`", line.trim())),
+        };
+
+        match changed {
+            Ok(true) => session.regenerate(&generate, &render),
+            Ok(false) => {}
+            Err(err) => println!("{err}"),
+        }
+    }
+}
+
+impl Session {
+    /// Applies `change` to a copy of the rules and switches to them if they make a tile set.
+    /// Returns whether the plot needs to be generated again.
+    fn change(
+        &mut self,
+        change: impl FnOnce(&mut Vec<TileDef>) -> Result<(), String>,
+    ) -> Result<bool, String> {
+        let mut defs = self.defs.clone();
+        change(&mut defs)?;
+        let old = self.defs.clone();
+        self.rebuild(defs)?;
+        self.undo.push(old);
+        Ok(true)
+    }
+
+    /// Switches to the tile set made of `defs`.
+    fn rebuild(&mut self, defs: Vec<TileDef>) -> Result<(), String> {
+        self.tileset = Arc::new(TileSet::new(defs.clone()).map_err(|err| err.to_string())?);
+        self.defs = defs;
+        Ok(())
+    }
+
+    /// Generates the plot of the current rules and seed, and prints it next to the one before.
+    fn regenerate(
+        &mut self,
+        generate: impl Fn(Arc<TileSet>, u64) -> Result<Plot, String>,
+        render: impl Fn(&Plot) -> String,
+    ) {
+        let plot = match generate(Arc::clone(&self.tileset), self.seed) {
+            Ok(plot) => plot,
+            Err(err) => {
+                println!("Seed {}: {err}", self.seed);
+                return;
+            }
+        };
+
+        match &self.plot {
+            Some(before) if (before.width(), before.height()) == (plot.width(), plot.height()) => {
+                println!("Seed {}, the plot before on the left:", self.seed);
+                let (before_text, after_text) = (render(before), render(&plot));
+                let (left, right) = (
+                    before_text.lines().collect::<Vec<_>>(),
+                    after_text.lines().collect::<Vec<_>>(),
+                );
+                let width = left
+                    .iter()
+                    .map(|line| visible_width(line))
+                    .max()
+                    .unwrap_or(0);
+                for i in 0..left.len().max(right.len()) {
+                    let (a, b) = (left.get(i).unwrap_or(&""), right.get(i).unwrap_or(&""));
+                    let padding = width - visible_width(a);
+                    println!("{a}{:padding$}   {b}", "");
+                }
+                // By name, since the plots have different tile sets
+                let changed = plot
+                    .positions()
+                    .filter(|&(x, y)| {
+                        before.tileset().name(before.get(x, y))
+                            != plot.tileset().name(plot.get(x, y))
+                    })
+                    .count();
+                println!(
+                    "{changed} of {} fields changed",
+                    plot.width() * plot.height()
+                );
+            }
+            _ => {
+                println!("Seed {}:", self.seed);
+                print!("{}", render(&plot));
+            }
+        }
+        self.plot = Some(plot);
+    }
+}
+
+/// The definition of the tile named `name`.
+fn def_mut<'a>(defs: &'a mut [TileDef], name: &str) -> Result<&'a mut TileDef, String> {
+    defs.iter_mut()
+        .find(|def| def.name == name)
+        .ok_or_else(|| format!("Unknown tile {name}"))
+}
+
+/// Fails if there is no tile named `name`.
+fn known(defs: &[TileDef], name: &str) -> Result<(), String> {
+    match defs.iter().any(|def| def.name == name) {
+        true => Ok(()),
+        false => Err(format!("Unknown tile {name}")),
+    }
+}
+
+/// The direction named like `north` or `south-east`.
+fn direction(name: &str) -> Option<Direction> {
+    let name = name.to_lowercase().replace(['-', '_'], "");
+    Direction::ALL
+        .into_iter()
+        .find(|direction| format!("{direction:?}").to_lowercase() == name)
+}
+
+/// How many characters of `line` show up in a terminal, leaving out ANSI color codes.
+fn visible_width(line: &str) -> usize {
+    let mut escaped = false;
+    line.chars()
+        .filter(|&c| match (escaped, c) {
+            (false, '\x1b') => {
+                escaped = true;
+                false
+            }
+            (true, 'm') => {
+                escaped = false;
+                false
+            }
+            (escaped, _) => !escaped,
+        })
+        .count()
+}
@@ -0,0 +1,223 @@
+//! The `serve` subcommand, generating plots over HTTP.
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::time::Duration;
+use world_gen::{Plot, PlotGenerator, SvgStyle, TileSet};
+
+/// The most fields a requested plot may have, so one request can't keep the server busy for
+/// long.
+const MAX_FIELDS: usize = 512 * 512;
+
+/// The most bytes of a request line or header, longer ones get the connection closed.
+const MAX_LINE: usize = 8192;
+
+/// How long a client may take to send its request.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What every request shares.
+pub struct Server {
+    pub tileset: Arc<TileSet>,
+    /// The size of plots requested without `w` or `h`.
+    pub width: usize,
+    pub height: usize,
+    /// Whether the plots are dungeons whose rooms get connected, see `--preset`.
+    pub dungeon: bool,
+}
+
+/// A response, before it gets written.
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+}
+
+/// Answers requests on `address` until the process gets killed, each one on its own thread.
+pub fn run(server: Server, address: &str) -> std::io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    eprintln!("Listening on http://{}", listener.local_addr()?);
+
+    let server = Arc::new(server);
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let server = Arc::clone(&server);
+        std::thread::spawn(move || {
+            // The client went away, nothing left to do
+            let _ = server.handle(stream);
+        });
+    }
+    Ok(())
+}
+
+impl Server {
+    fn handle(&self, stream: TcpStream) -> std::io::Result<()> {
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        let mut reader = BufReader::new(&stream);
+        let request = read_line(&mut reader)?;
+        // The headers don't matter, but have to be read before answering
+        while !read_line(&mut reader)?.is_empty() {}
+
+        let response = match request.split(' ').collect::<Vec<_>>().as_slice() {
+            ["GET", target, _] => match target.split_once('?').unwrap_or((target, "")) {
+                ("/map", query) => self.map(&parse_query(query)),
+                _ => Response::error("404 Not Found", "Only /map is served"),
+            },
+            [_, _, _] => Response::error("405 Method Not Allowed", "Only GET is supported"),
+            _ => Response::error("400 Bad Request", "Malformed request line"),
+        };
+        response.write(&mut &stream)
+    }
+
+    /// `GET /map?w=64&h=64&seed=…&format=json|png|svg&scale=…`, everything optional.
+    fn map(&self, query: &HashMap<&str, &str>) -> Response {
+        let number = |key: &str, default: u64| match query.get(key) {
+            Some(value) => value.parse::<u64>().map_err(|_| {
+                Response::error("400 Bad Request", &format!("{key} has to be a number"))
+            }),
+            None => Ok(default),
+        };
+        let params = (|| {
+            let width = number("w", self.width as u64)? as usize;
+            let height = number("h", self.height as u64)? as usize;
+            let seed = number("seed", rand::random())?;
+            let scale = number("scale", 8)?;
+            Ok((width, height, seed, scale))
+        })();
+        let (width, height, seed, scale) = match params {
+            Ok(params) => params,
+            Err(response) => return response,
+        };
+
+        if width == 0 || height == 0 || width.saturating_mul(height) > MAX_FIELDS {
+            let message = format!("Plots need between 1 and {MAX_FIELDS} fields");
+            return Response::error("400 Bad Request", &message);
+        }
+        if !(1..=64).contains(&scale) {
+            return Response::error("400 Bad Request", "scale has to be between 1 and 64");
+        }
+
+        let format = query.get("format").copied().unwrap_or("json");
+        if !["json", "png", "svg"].contains(&format) {
+            return Response::error("400 Bad Request", "format has to be json, png or svg");
+        }
+
+        let mut plot = match PlotGenerator::for_tileset(Arc::clone(&self.tileset), width, height)
+            .with_seed(seed)
+            .generate()
+        {
+            Ok(plot) => plot,
+            Err(err) => return Response::error("500 Internal Server Error", &err.to_string()),
+        };
+        if self.dungeon {
+            super::connect_rooms(&mut plot);
+        }
+
+        let (content_type, body) = match format {
+            "png" => {
+                let mut png = std::io::Cursor::new(vec![]);
+                if let Err(err) = plot
+                    .to_image(scale as u32)
+                    .write_to(&mut png, image::ImageFormat::Png)
+                {
+                    return Response::error("500 Internal Server Error", &err.to_string());
+                }
+                ("image/png", png.into_inner())
+            }
+            "svg" => {
+                let svg = plot.to_svg(&SvgStyle::new(scale as f64));
+                ("image/svg+xml", svg.into_bytes())
+            }
+            _ => ("application/json", to_json(&plot, seed).into_bytes()),
+        };
+        Response {
+            status: "200 OK",
+            content_type,
+            headers: vec![("X-Seed", seed.to_string())],
+            body,
+        }
+    }
+}
+
+impl Response {
+    fn error(status: &'static str, message: &str) -> Self {
+        Response {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            headers: vec![],
+            body: format!("{message}\n").into_bytes(),
+        }
+    }
+
+    fn write(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        write!(stream, "HTTP/1.1 {}\r\n", self.status)?;
+        write!(stream, "Content-Type: {}\r\n", self.content_type)?;
+        write!(stream, "Content-Length: {}\r\n", self.body.len())?;
+        // Lets web frontends on other origins fetch maps
+        write!(stream, "Access-Control-Allow-Origin: *\r\n")?;
+        for (name, value) in &self.headers {
+            write!(stream, "{name}: {value}\r\n")?;
+        }
+        write!(stream, "Connection: close\r\n\r\n")?;
+        stream.write_all(&self.body)?;
+        stream.flush()
+    }
+}
+
+/// A line of the request without the line break, failing on lines longer than [`MAX_LINE`].
+fn read_line(reader: &mut impl BufRead) -> std::io::Result<String> {
+    let mut line = String::new();
+    reader.by_ref().take(MAX_LINE as u64).read_line(&mut line)?;
+    if !line.ends_with('\n') {
+        return Err(std::io::ErrorKind::InvalidData.into());
+    }
+    Ok(line.trim_end().to_string())
+}
+
+/// The `key=value` pairs of a query string. Values aren't percent-decoded, none of the valid ones
+/// need it.
+fn parse_query(query: &str) -> HashMap<&str, &str> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| pair.split_once('=').unwrap_or((pair, "")))
+        .collect()
+}
+
+/// The plot as JSON: its size and seed, the names of the tiles, and the row-major indexes of the
+/// tiles of the fields.
+fn to_json(plot: &Plot, seed: u64) -> String {
+    let tileset = plot.tileset();
+    let tiles = tileset
+        .ids()
+        .map(|tile| format!("\"{}\"", escape_json(tileset.name(tile))))
+        .collect::<Vec<_>>()
+        .join(",");
+    let data = plot
+        .positions()
+        .map(|(x, y)| plot.get(x, y).0.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    format!(
+        "{{\"width\":{},\"height\":{},\"seed\":{seed},\"tiles\":[{tiles}],\"data\":[{data}]}}\n",
+        plot.width(),
+        plot.height()
+    )
+}
+
+fn escape_json(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
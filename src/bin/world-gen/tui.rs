@@ -0,0 +1,346 @@
+//! The `tui` subcommand, an interactive explorer for generated plots.
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Color, Print, SetAttribute, SetBackgroundColor};
+use crossterm::{cursor, execute, queue, terminal};
+use std::collections::HashSet;
+use std::io::Write;
+use std::sync::Arc;
+use world_gen::{
+    Connectivity, GenError, Label, LayeredGenerator, MetaValue, NameGenerator, Plot, PlotGenerator,
+    RoadNetwork, TileDef, TileId, TileSet,
+};
+
+/// The keys, shown in the status line.
+const HELP: &str = "arrows move, +/- zoom, r new seed, [/] previous/next seed, g glyphs, \
+                    c colors, 1/2/3 features/roads/names, q quit";
+
+/// How many random points the roads of the road overlay connect.
+const ROAD_POINTS: usize = 6;
+
+/// How many fields a region needs to get a name in the name overlay.
+const NAMED_REGION_SIZE: usize = 16;
+
+/// What gets drawn over the plot, each one toggled by a key.
+#[derive(Default)]
+struct Overlays {
+    /// A feature layer of trees on the passable tiles, generated with a [`LayeredGenerator`].
+    features: Option<Plot>,
+    /// The fields of roads between random points.
+    roads: HashSet<(usize, usize)>,
+    /// Names of the bigger regions of every tile.
+    legend: Vec<Label>,
+}
+
+/// What the explorer shows.
+struct Explorer {
+    tileset: Arc<TileSet>,
+    width: usize,
+    height: usize,
+    seed: u64,
+    plot: Result<Plot, GenError>,
+    /// The tile under the cursor.
+    cursor: (usize, usize),
+    /// The tile in the top left corner of the screen.
+    view: (usize, usize),
+    /// How many tiles along each axis share a screen cell, 1 shows every tile.
+    zoom: usize,
+    /// Whether the tiles show their glyphs.
+    glyphs: bool,
+    /// Whether the tiles show their colors as background.
+    colors: bool,
+    /// The overlays of the plot, empty if it failed.
+    overlays: Overlays,
+    /// Whether the feature layer hides the terrain under it.
+    show_features: bool,
+    /// Whether roads get drawn.
+    show_roads: bool,
+    /// Whether the names of the legend get written over the plot.
+    show_legend: bool,
+}
+
+/// Generates a `width` x `height` plot with `seed`, and lets the user explore it until they
+/// quit.
+pub fn run(tileset: TileSet, width: usize, height: usize, seed: u64) -> std::io::Result<()> {
+    let tileset = Arc::new(tileset);
+    let plot = generate(&tileset, width, height, seed);
+    let overlays = match &plot {
+        Ok(plot) => overlays(&tileset, plot, seed),
+        Err(_) => Overlays::default(),
+    };
+    let mut explorer = Explorer {
+        plot,
+        tileset,
+        width,
+        height,
+        seed,
+        cursor: (0, 0),
+        view: (0, 0),
+        zoom: 1,
+        glyphs: true,
+        colors: true,
+        overlays,
+        show_features: false,
+        show_roads: false,
+        show_legend: false,
+    };
+
+    let mut stdout = std::io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+
+    let explored = explorer.explore(&mut stdout);
+
+    // Give the terminal back, even if exploring failed
+    execute!(stdout, terminal::LeaveAlternateScreen, cursor::Show)?;
+    terminal::disable_raw_mode()?;
+
+    explored
+}
+
+fn generate(
+    tileset: &Arc<TileSet>,
+    width: usize,
+    height: usize,
+    seed: u64,
+) -> Result<Plot, GenError> {
+    PlotGenerator::for_tileset(Arc::clone(tileset), width, height)
+        .with_seed(seed)
+        .generate()
+}
+
+/// The overlays of `terrain`, generated with `seed`.
+fn overlays(tileset: &Arc<TileSet>, terrain: &Plot, seed: u64) -> Overlays {
+    let features = TileSet::new(vec![
+        TileDef::new("Empty", "  ")
+            .with_neighbours(&["Empty", "Trees"])
+            .with_metadata("transparent", MetaValue::Bool(true)),
+        TileDef::new("Trees", "♣♣")
+            .with_color([34, 110, 40])
+            .with_neighbours(&["Empty", "Trees"]),
+    ])
+    .expect("The feature tiles are valid");
+    let trees = features.id("Trees").expect("Trees are a feature");
+    let passable = tileset
+        .ids()
+        .filter(|&tile| tileset.passable(tile))
+        .collect::<Vec<_>>();
+    // The first layer gets generated like the terrain, so the features fit it
+    let features = LayeredGenerator::new(terrain.width(), terrain.height())
+        .with_seed(seed)
+        .with_layer(Arc::clone(tileset))
+        .with_layer(features)
+        .with_rule(1, trees, 0, &passable)
+        .generate()
+        .ok()
+        .and_then(|mut layers| layers.pop());
+
+    let roads = RoadNetwork::new(TileId(0))
+        .with_random_points(ROAD_POINTS)
+        .with_seed(seed)
+        .route(terrain)
+        .into_iter()
+        .flatten()
+        .collect();
+
+    let mut names = NameGenerator::new().with_seed(seed);
+    let mut named = terrain.clone();
+    for tile in tileset.ids() {
+        names.label_regions(&mut named, tile, Connectivity::Four, NAMED_REGION_SIZE);
+    }
+
+    Overlays {
+        features,
+        roads,
+        legend: named.legend().to_vec(),
+    }
+}
+
+/// `glyph` cut or padded to `cell` characters.
+fn fit(glyph: &str, cell: usize) -> String {
+    format!("{:cell$.cell$}", glyph)
+}
+
+impl Explorer {
+    /// Draws the plot and handles keys, until the user quits.
+    fn explore(&mut self, stdout: &mut impl Write) -> std::io::Result<()> {
+        loop {
+            self.draw(stdout)?;
+
+            let Event::Key(key) = event::read()? else {
+                // Resizes and the like only need a redraw
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            let step = self.zoom;
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(())
+                }
+                KeyCode::Left => self.cursor.0 = self.cursor.0.saturating_sub(step),
+                KeyCode::Right => self.cursor.0 = (self.cursor.0 + step).min(self.width.max(1) - 1),
+                KeyCode::Up => self.cursor.1 = self.cursor.1.saturating_sub(step),
+                KeyCode::Down => self.cursor.1 = (self.cursor.1 + step).min(self.height.max(1) - 1),
+                KeyCode::Char('+') => self.zoom = (self.zoom / 2).max(1),
+                KeyCode::Char('-') => self.zoom = (self.zoom * 2).min(64),
+                KeyCode::Char('r') => self.regenerate(rand::random()),
+                KeyCode::Char('[') => self.regenerate(self.seed.wrapping_sub(1)),
+                KeyCode::Char(']') => self.regenerate(self.seed.wrapping_add(1)),
+                KeyCode::Char('g') => self.glyphs = !self.glyphs,
+                KeyCode::Char('c') => self.colors = !self.colors,
+                KeyCode::Char('1') => self.show_features = !self.show_features,
+                KeyCode::Char('2') => self.show_roads = !self.show_roads,
+                KeyCode::Char('3') => self.show_legend = !self.show_legend,
+                _ => {}
+            }
+        }
+    }
+
+    fn regenerate(&mut self, seed: u64) {
+        self.seed = seed;
+        self.plot = generate(&self.tileset, self.width, self.height, seed);
+        self.overlays = match &self.plot {
+            Ok(plot) => overlays(&self.tileset, plot, seed),
+            Err(_) => Overlays::default(),
+        };
+    }
+
+    /// Redraws the whole screen: the part of the plot around the cursor, and a status line.
+    fn draw(&mut self, stdout: &mut impl Write) -> std::io::Result<()> {
+        let (columns, rows) = terminal::size()?;
+        let cell = self.tileset.glyph(TileId(0)).chars().count().max(1);
+        let (cells_x, cells_y) = (columns as usize / cell, (rows as usize).saturating_sub(1));
+
+        // Scroll the view just far enough to keep the cursor on the screen
+        let (span_x, span_y) = (cells_x.max(1) * self.zoom, cells_y.max(1) * self.zoom);
+        self.view.0 = self
+            .view
+            .0
+            .clamp((self.cursor.0 + 1).saturating_sub(span_x), self.cursor.0);
+        self.view.1 = self
+            .view
+            .1
+            .clamp((self.cursor.1 + 1).saturating_sub(span_y), self.cursor.1);
+
+        queue!(stdout, terminal::Clear(terminal::ClearType::All))?;
+        if let Ok(plot) = &self.plot {
+            for sy in 0..cells_y {
+                queue!(stdout, cursor::MoveTo(0, sy as u16))?;
+                for sx in 0..cells_x {
+                    let (x, y) = (self.view.0 + sx * self.zoom, self.view.1 + sy * self.zoom);
+                    if x >= plot.width() || y >= plot.height() {
+                        break;
+                    }
+                    self.draw_cell(stdout, plot, (x, y), cell)?;
+                }
+            }
+            if self.show_legend {
+                self.draw_legend(stdout, (cells_x, cells_y), cell)?;
+            }
+        }
+
+        let status = match &self.plot {
+            Ok(plot) if plot.width() == 0 || plot.height() == 0 => {
+                format!("seed {}  empty plot  {HELP}", self.seed)
+            }
+            Ok(plot) => {
+                let (x, y) = self.cursor;
+                let tile = plot.tileset().name(plot.get(x, y));
+                let features = match &self.overlays.features {
+                    Some(features) if self.show_features => {
+                        format!(" {}", features.tileset().name(features.get(x, y)))
+                    }
+                    None if self.show_features => " no features".to_string(),
+                    _ => String::new(),
+                };
+                format!(
+                    "seed {}  ({x}, {y}) {tile}{features}  zoom 1:{}  {HELP}",
+                    self.seed, self.zoom
+                )
+            }
+            Err(err) => format!("seed {}  {err}  {HELP}", self.seed),
+        };
+        let status = status.chars().take(columns as usize).collect::<String>();
+        queue!(
+            stdout,
+            cursor::MoveTo(0, rows.saturating_sub(1)),
+            Print(status)
+        )?;
+
+        stdout.flush()
+    }
+
+    /// Draws the screen cell showing the tile at `pos`, highlighted if it holds the cursor.
+    fn draw_cell(
+        &self,
+        stdout: &mut impl Write,
+        plot: &Plot,
+        (x, y): (usize, usize),
+        cell: usize,
+    ) -> std::io::Result<()> {
+        let tile = plot.get(x, y);
+        let (cx, cy) = self.cursor;
+        let has_cursor = (x..x + self.zoom).contains(&cx) && (y..y + self.zoom).contains(&cy);
+
+        if self.colors {
+            let [r, g, b] = plot.tileset().color(tile);
+            queue!(stdout, SetBackgroundColor(Color::Rgb { r, g, b }))?;
+        }
+        if has_cursor {
+            queue!(stdout, SetAttribute(Attribute::Reverse))?;
+        }
+        let feature = match &self.overlays.features {
+            Some(features) if self.show_features => {
+                let feature = features.get(x, y);
+                (!features.tileset().transparent(feature))
+                    .then(|| features.tileset().glyph(feature))
+            }
+            _ => None,
+        };
+        let glyph = if self.show_roads && self.overlays.roads.contains(&(x, y)) {
+            "=".repeat(cell)
+        } else if let Some(feature) = feature {
+            fit(feature, cell)
+        } else if self.glyphs {
+            fit(plot.tileset().glyph(tile), cell)
+        } else {
+            " ".repeat(cell)
+        };
+        queue!(stdout, Print(glyph), SetAttribute(Attribute::Reset))
+    }
+
+    /// Writes the names of the legend over the plot, starting at the field they are anchored to,
+    /// for those on the screen of `cells` screen cells.
+    fn draw_legend(
+        &self,
+        stdout: &mut impl Write,
+        (cells_x, cells_y): (usize, usize),
+        cell: usize,
+    ) -> std::io::Result<()> {
+        for label in &self.overlays.legend {
+            let (x, y) = label.anchor();
+            let (Some(dx), Some(dy)) = (x.checked_sub(self.view.0), y.checked_sub(self.view.1))
+            else {
+                continue;
+            };
+            let (sx, sy) = (dx / self.zoom, dy / self.zoom);
+            if sx >= cells_x || sy >= cells_y {
+                continue;
+            }
+
+            let room = (cells_x - sx) * cell;
+            let name = label.name.chars().take(room).collect::<String>();
+            queue!(
+                stdout,
+                cursor::MoveTo((sx * cell) as u16, sy as u16),
+                SetAttribute(Attribute::Bold),
+                Print(name),
+                SetAttribute(Attribute::Reset)
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+use crate::prelude::*;
+use crate::{GenError, Plot, PlotGenerator, TileId, TileSet, WfcError};
+use alloc::sync::Arc;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+/// Generates plots in two phases: first the grid gets split into biome regions, then the tiles
+/// get collapsed with every field restricted to the tiles of its biome.
+///
+/// The regions are the Voronoi cells of randomly placed sites, every site picks a biome by
+/// weight. A biome is a subset of the tile set, like water and sand for a coast or forest and
+/// mountain for highlands, so the plot gets structure on a larger scale than the adjacency rules
+/// alone can give it. Fields near the border of two biomes may also hold the transition tiles of
+/// that pair, see [`BiomeGenerator::with_transition`].
+///
+/// All biomes share one tile set, the adjacency rules have to allow the tiles of two bordering
+/// biomes to meet, directly or through their transition tiles.
+pub struct BiomeGenerator {
+    width: usize,
+    height: usize,
+    tileset: Arc<TileSet>,
+    seed: u64,
+    sites: usize,
+    border_width: usize,
+    biomes: Vec<Biome>,
+    transitions: Vec<Transition>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Biome {
+    tiles: Vec<TileId>,
+    weight: f64,
+}
+
+/// `tiles` are allowed near the borders between the biomes `a` and `b`.
+#[derive(Debug, Clone, PartialEq)]
+struct Transition {
+    a: usize,
+    b: usize,
+    tiles: Vec<TileId>,
+}
+
+impl BiomeGenerator {
+    /// Creates a generator for `width` x `height` plots of `tileset`, without any biome yet. The
+    /// grid gets split into one region per 16 x 16 fields.
+    pub fn new(tileset: impl Into<Arc<TileSet>>, width: usize, height: usize) -> Self {
+        BiomeGenerator {
+            width,
+            height,
+            tileset: tileset.into(),
+            seed: random_seed(),
+            sites: (width * height / 256).max(1),
+            border_width: 1,
+            biomes: vec![],
+            transitions: vec![],
+        }
+    }
+
+    /// Derives the regions and the seed of the tile generator from `seed`, see
+    /// [`PlotGenerator::with_seed`].
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Splits the grid into `sites` regions. Neighbouring regions may get the same biome.
+    pub fn with_sites(mut self, sites: usize) -> Self {
+        assert!(sites > 0, "The grid needs at least one region");
+        self.sites = sites;
+        self
+    }
+
+    /// Allows transition tiles on fields with a field of another biome within `width` steps,
+    /// in the "#" shape. Defaults to 1.
+    pub fn with_border_width(mut self, width: usize) -> Self {
+        self.border_width = width;
+        self
+    }
+
+    /// Adds a biome made of `tiles`, picked by regions with a chance proportional to `weight`.
+    /// Biomes are numbered in the order they were added, starting at 0.
+    pub fn with_biome<T: Copy + Into<TileId>>(mut self, tiles: &[T], weight: f64) -> Self {
+        assert!(weight > 0.0, "Biomes need a positive weight");
+        self.biomes.push(Biome {
+            tiles: tiles.iter().map(|t| (*t).into()).collect(),
+            weight,
+        });
+        self
+    }
+
+    /// Also allows `tiles` on the fields near the borders between the biomes `a` and `b`, on
+    /// both sides, e.g. beaches between sea and land.
+    ///
+    /// Several transitions for the same pair add up.
+    pub fn with_transition<T: Copy + Into<TileId>>(
+        mut self,
+        a: usize,
+        b: usize,
+        tiles: &[T],
+    ) -> Self {
+        assert!(
+            a < self.biomes.len() && b < self.biomes.len(),
+            "Unknown biome {}",
+            a.max(b)
+        );
+
+        self.transitions.push(Transition {
+            a,
+            b,
+            tiles: tiles.iter().map(|t| (*t).into()).collect(),
+        });
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn biome_count(&self) -> usize {
+        self.biomes.len()
+    }
+
+    /// The biome of every field as `regions[x][y]`, the first phase of
+    /// [`BiomeGenerator::generate`]. The same seed gives the same regions.
+    pub fn regions(&self) -> Vec<Vec<usize>> {
+        assert!(!self.biomes.is_empty(), "Regions need at least one biome");
+
+        let mut rng = ChaCha12Rng::seed_from_u64(self.seed);
+        let biomes = WeightedIndex::new(self.biomes.iter().map(|biome| biome.weight))
+            .expect("Biome weights are positive");
+        let sites = (0..self.sites)
+            .map(|_| {
+                let x = rng.gen::<f64>() * self.width as f64;
+                let y = rng.gen::<f64>() * self.height as f64;
+                ((x, y), biomes.sample(&mut rng))
+            })
+            .collect::<Vec<_>>();
+
+        (0..self.width)
+            .map(|x| {
+                (0..self.height)
+                    .map(|y| {
+                        // The centre of the field, so sites on a grid line don't favour a side
+                        let (fx, fy) = (x as f64 + 0.5, y as f64 + 0.5);
+                        let distance = |(sx, sy): (f64, f64)| (sx - fx).powi(2) + (sy - fy).powi(2);
+                        let (_, biome) = sites
+                            .iter()
+                            .min_by(|(a, _), (b, _)| distance(*a).total_cmp(&distance(*b)))
+                            .expect("The grid has at least one site");
+                        *biome
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Splits the grid into regions and collapses the tiles inside of them.
+    ///
+    /// Fails with a [`GenError::Contradiction`] if a field has no tile left, e.g. because the
+    /// tiles of two bordering biomes can't meet.
+    pub fn generate(&self) -> Result<Plot, GenError> {
+        let regions = self.regions();
+        let mut gen =
+            PlotGenerator::for_tileset(Arc::clone(&self.tileset), self.width, self.height)
+                .with_seed(self.seed.wrapping_add(1));
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let allowed = self.allowed(&regions, x, y);
+                if let Err(WfcError::Contradiction(pos)) = gen.constrain(x, y, &allowed) {
+                    return Err(GenError::Contradiction(pos));
+                }
+            }
+        }
+
+        gen.generate()
+    }
+
+    /// The tiles of the biome at `(x, y)`, and the transition tiles of the biomes it borders.
+    fn allowed(&self, regions: &[Vec<usize>], x: usize, y: usize) -> Vec<TileId> {
+        let biome = regions[x][y];
+        let mut allowed = self.biomes[biome].tiles.clone();
+
+        let width = self.border_width as isize;
+        for dx in -width..=width {
+            for dy in -width..=width {
+                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                if !(0..self.width as isize).contains(&nx)
+                    || !(0..self.height as isize).contains(&ny)
+                {
+                    continue;
+                }
+
+                let other = regions[nx as usize][ny as usize];
+                if other == biome {
+                    continue;
+                }
+                for transition in &self.transitions {
+                    if (transition.a, transition.b) == (biome, other)
+                        || (transition.a, transition.b) == (other, biome)
+                    {
+                        allowed.extend(&transition.tiles);
+                    }
+                }
+            }
+        }
+
+        allowed.sort();
+        allowed.dedup();
+        allowed
+    }
+}
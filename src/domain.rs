@@ -0,0 +1,140 @@
+use crate::prelude::*;
+use crate::TileId;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A set of tiles stored as a bitmask, one bit per [`TileId`] of a tile set.
+///
+/// All masks of a tile set have the same number of words, so intersecting and merging them
+/// happens in place, without allocating.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DomainMask {
+    words: Box<[u64]>,
+}
+
+impl DomainMask {
+    /// A mask for `tile_count` tiles without any tile.
+    pub fn empty(tile_count: usize) -> Self {
+        DomainMask {
+            words: vec![0; tile_count.div_ceil(64)].into_boxed_slice(),
+        }
+    }
+
+    /// A mask for `tile_count` tiles holding all of them.
+    pub fn full(tile_count: usize) -> Self {
+        let mut mask = DomainMask::empty(tile_count);
+        for i in 0..tile_count {
+            mask.insert(TileId(i as u16));
+        }
+        mask
+    }
+
+    /// A mask for `tile_count` tiles holding `tiles`.
+    pub fn from_tiles(tile_count: usize, tiles: impl IntoIterator<Item = TileId>) -> Self {
+        let mut mask = DomainMask::empty(tile_count);
+        for tile in tiles {
+            mask.insert(tile);
+        }
+        mask
+    }
+
+    pub fn contains(&self, tile: TileId) -> bool {
+        let (word, bit) = Self::bit(tile);
+        self.words[word] & bit != 0
+    }
+
+    pub fn insert(&mut self, tile: TileId) {
+        let (word, bit) = Self::bit(tile);
+        self.words[word] |= bit;
+    }
+
+    pub fn remove(&mut self, tile: TileId) {
+        let (word, bit) = Self::bit(tile);
+        self.words[word] &= !bit;
+    }
+
+    /// Removes every tile.
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    /// How many tiles the mask holds.
+    pub fn len(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|word| *word == 0)
+    }
+
+    /// Whether the mask belongs to a tile set of `tile_count` tiles: it has as many words as
+    /// their masks and no tile beyond them.
+    #[cfg(feature = "serde")]
+    pub(crate) fn fits(&self, tile_count: usize) -> bool {
+        self.words.len() == tile_count.div_ceil(64)
+            && self.iter().all(|tile| (tile.0 as usize) < tile_count)
+    }
+
+    /// Adds every tile of `other`.
+    pub fn union_with(&mut self, other: &DomainMask) {
+        for (word, other) in self.words.iter_mut().zip(other.words.iter()) {
+            *word |= other;
+        }
+    }
+
+    /// Whether every tile of the mask is in `other` too.
+    pub fn is_subset(&self, other: &DomainMask) -> bool {
+        self.words
+            .iter()
+            .zip(other.words.iter())
+            .all(|(word, other)| word & !other == 0)
+    }
+
+    /// Removes every tile missing in `other`, returns whether anything got removed.
+    pub fn intersect_with(&mut self, other: &DomainMask) -> bool {
+        let mut changed = false;
+        for (word, other) in self.words.iter_mut().zip(other.words.iter()) {
+            changed |= *word & !other != 0;
+            *word &= other;
+        }
+        changed
+    }
+
+    /// The tiles of the mask, in order.
+    pub fn iter(&self) -> impl Iterator<Item = TileId> + '_ {
+        self.words.iter().enumerate().flat_map(|(i, &word)| {
+            // Every step clears the lowest set bit
+            core::iter::successors(Some(word), |word| Some(word & word.wrapping_sub(1)))
+                .take_while(|word| *word != 0)
+                .map(move |word| TileId((i * 64 + word.trailing_zeros() as usize) as u16))
+        })
+    }
+
+    /// The mask as 32 bit words, lowest tiles first, for the GPU.
+    #[cfg(feature = "gpu")]
+    pub(crate) fn to_u32s(&self) -> impl Iterator<Item = u32> + '_ {
+        self.words
+            .iter()
+            .flat_map(|&word| [word as u32, (word >> 32) as u32])
+    }
+
+    /// The mask of [`DomainMask::to_u32s`].
+    #[cfg(feature = "gpu")]
+    pub(crate) fn from_u32s(words: &[u32]) -> Self {
+        DomainMask {
+            words: words
+                .chunks_exact(2)
+                .map(|pair| pair[0] as u64 | (pair[1] as u64) << 32)
+                .collect(),
+        }
+    }
+
+    fn bit(tile: TileId) -> (usize, u64) {
+        let i = tile.0 as usize;
+        (i / 64, 1 << (i % 64))
+    }
+}
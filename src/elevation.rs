@@ -0,0 +1,87 @@
+use crate::prelude::*;
+use crate::{DomainMask, TileId, TileSet};
+use core::ops::{Bound, RangeBounds};
+
+/// A height for every field of a grid, and the heights each tile may appear at, see
+/// [`PlotGenerator::with_elevation`](crate::PlotGenerator::with_elevation).
+///
+/// Tiles without a range may appear at any height.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Elevation {
+    width: usize,
+    height: usize,
+    heights: Vec<f64>,
+    ranges: HashMap<TileId, (Bound<f64>, Bound<f64>)>,
+}
+
+impl Elevation {
+    /// Uses row-major `heights` for a `width` x `height` grid.
+    pub fn from_heights(width: usize, height: usize, heights: Vec<f64>) -> Self {
+        assert_eq!(heights.len(), width * height, "Every field needs a height");
+        Elevation {
+            width,
+            height,
+            heights,
+            ranges: HashMap::new(),
+        }
+    }
+
+    /// Heights between 0 and 1 from fractal Perlin noise seeded with `seed`. `scale` is the size
+    /// of the hills in fields, the bigger the smoother.
+    #[cfg(feature = "noise")]
+    pub fn perlin(width: usize, height: usize, seed: u32, scale: f64) -> Self {
+        use noise::{Fbm, NoiseFn, Perlin};
+
+        let noise = Fbm::<Perlin>::new(seed);
+        let heights = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| noise.get([x as f64 / scale, y as f64 / scale]))
+            // Fractal noise lies roughly between -1 and 1
+            .map(|h| ((h + 1.0) / 2.0).clamp(0.0, 1.0))
+            .collect();
+
+        Elevation::from_heights(width, height, heights)
+    }
+
+    /// Only allows `tile` on fields with a height in `range`, like `..0.3` for a river or
+    /// `0.7..` for a mountain.
+    pub fn with_range(mut self, tile: impl Into<TileId>, range: impl RangeBounds<f64>) -> Self {
+        let range = (range.start_bound().cloned(), range.end_bound().cloned());
+        self.ranges.insert(tile.into(), range);
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The height of the field at `(x, y)`.
+    pub fn get(&self, x: usize, y: usize) -> f64 {
+        self.heights[y * self.width + x]
+    }
+
+    /// Every height, row by row.
+    pub fn heights(&self) -> &[f64] {
+        &self.heights
+    }
+
+    /// The tiles of `tileset` allowed at the height of the field at `(x, y)`.
+    pub fn allowed(&self, tileset: &TileSet, x: usize, y: usize) -> DomainMask {
+        let height = self.get(x, y);
+        let allowed = tileset.ids().filter(|tile| {
+            self.ranges
+                .get(tile)
+                .is_none_or(|range| range.contains(&height))
+        });
+
+        DomainMask::from_tiles(tileset.len(), allowed)
+    }
+
+    pub(crate) fn into_heights(self) -> Vec<f64> {
+        self.heights
+    }
+}
@@ -0,0 +1,2951 @@
+#[cfg(feature = "gpu")]
+use crate::gpu::{self, GpuPropagator};
+use crate::grid::Grid;
+use crate::min_conflicts;
+use crate::neighbourhood::Offsets;
+use crate::prelude::*;
+use crate::wave::Wave;
+use crate::{
+    BiasMap, BoundaryMode, CompiledRuleset, Direction, DomainMask, Elevation, GenerationLog,
+    LogEvent, Mask, Neighbourhood, Plot, ReplayError, Ruleset, TileId, TileSet, TileSetError,
+    Topology, Weights,
+};
+use alloc::collections::{BinaryHeap, VecDeque};
+use alloc::sync::Arc;
+use core::cmp::Reverse;
+use core::fmt::Display;
+use core::iter::FusedIterator;
+use core::ops::{Bound, Range, RangeBounds};
+#[cfg(feature = "std")]
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::{AtomicBool, Ordering};
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
+use std::thread::JoinHandle;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+/// How many observations [`PlotGenerator::collapse_cancellable`] makes between checking its
+/// cancel flag.
+pub const CANCEL_CHECK_INTERVAL: usize = 16;
+
+/// How many fields in superposition [`PlotGenerator::all_orders_succeed`] goes through at most.
+pub const ALL_ORDERS_MAX_FIELDS: usize = 12;
+
+/// How many contradictions a [`PlotGenerator`] backtracks out of before giving up, unless
+/// configured with [`PlotGenerator::with_backtrack_budget`].
+pub const DEFAULT_BACKTRACK_BUDGET: usize = 1000;
+
+/// The scale of the random noise added to entropies, so that ties are broken at random.
+pub(crate) const ENTROPY_NOISE: f64 = 1e-6;
+
+/// How many observations can be undone by backtracking. Older observations get dropped, so
+/// memory stays bounded on big grids.
+pub(crate) const BACKTRACK_DEPTH: usize = 64;
+
+/// The state of a single field while generating.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum WaveState {
+    Collapsed(TileId),
+    Superposition(DomainMask),
+}
+
+/// Collapses a `width` x `height` grid of fields into a [`Plot`].
+///
+/// With the `serde` feature the whole state, including the random generator and the backtracking
+/// history, can be serialized, so a partially collapsed generator can be saved and resumed later.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PlotGenerator {
+    width: usize,
+    height: usize,
+    tileset: Arc<TileSet>,
+    tiles: Wave,
+    symmetry: Option<Symmetry>,
+    boundary: BoundaryMode,
+    topology: Topology,
+    #[cfg_attr(feature = "serde", serde(default))]
+    neighbourhood: Neighbourhood,
+    weights: Weights,
+    rng: ChaCha12Rng,
+    history: VecDeque<Observation>,
+    /// Uncollapsed fields as `(entropy with noise, possibilities, index)`, lowest entropy first.
+    /// Entries go stale when their field changes and get skipped when popped. The counts are
+    /// 32 bits wide, to keep the heap small on huge grids.
+    entropies: BinaryHeap<Reverse<(Entropy, u32, u32)>>,
+    backtrack_budget: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    observer: Option<Box<dyn Observer + Send>>,
+    /// Replaces the rules of the tile set, see [`PlotGenerator::with_rules`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    rules: Option<Arc<CompiledRuleset>>,
+    /// Multiplies the weights field by field, see [`PlotGenerator::with_bias`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    bias: Option<BiasMap>,
+    /// Row-major weights of every field, see [`PlotGenerator::set_weight_field`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    weight_field: Option<Vec<HashMap<TileId, f64>>>,
+    /// The heights handed on to the plot, see [`PlotGenerator::with_elevation`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    elevation: Option<Vec<f64>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    solver: Solver,
+    #[cfg_attr(feature = "serde", serde(default))]
+    propagation: Propagation,
+    #[cfg_attr(feature = "serde", serde(default))]
+    selection: SelectionStrategy,
+    /// The field observed last, see [`SelectionStrategy::Nearest`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    last: Option<(usize, usize)>,
+    /// The states to go back to with [`PlotGenerator::undo`], oldest first.
+    #[cfg_attr(feature = "serde", serde(default))]
+    undo: VecDeque<Snapshot>,
+    /// The states [`PlotGenerator::undo`] went back from, latest undo last.
+    #[cfg_attr(feature = "serde", serde(default))]
+    redo: Vec<Snapshot>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    history_limit: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    quotas: Vec<Quota>,
+    /// The steps taken so far, if recorded, see [`PlotGenerator::with_log`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    log: Option<GenerationLog>,
+    /// The fields that belong to the plot, see [`PlotGenerator::with_mask`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    mask: Option<Mask>,
+    /// Propagates on the GPU, see [`PlotGenerator::with_gpu`].
+    #[cfg(feature = "gpu")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    gpu: Option<Arc<GpuPropagator>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    stats: GenerationStats,
+}
+
+/// How many fields of the plot `tile` may take, see [`PlotGenerator::with_quota`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Quota {
+    tile: TileId,
+    min: usize,
+    max: usize,
+}
+
+/// A field that got observed, with what changed since, used for backtracking.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Observation {
+    /// The fields that changed since, with their states before, oldest change first.
+    changes: Vec<(u32, WaveState)>,
+    pos: (usize, usize),
+    tile: TileId,
+}
+
+/// The state of a [`PlotGenerator`] at some point, to go back to with
+/// [`PlotGenerator::restore`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Snapshot {
+    tiles: Wave,
+    rng: ChaCha12Rng,
+    entropies: BinaryHeap<Reverse<(Entropy, u32, u32)>>,
+    backtrack_budget: usize,
+}
+
+/// What a single [`PlotGenerator::step`] did.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    /// The field at `pos` got observed and collapsed into `tile`. Propagation removed
+    /// possibilities from the `changed` fields.
+    Collapsed {
+        pos: (usize, usize),
+        tile: TileId,
+        changed: Vec<(usize, usize)>,
+    },
+    /// Observing the field at `contradiction` failed, so the observation at `undone` got undone.
+    Backtracked {
+        contradiction: (usize, usize),
+        undone: (usize, usize),
+    },
+    /// Every field is collapsed, there is nothing left to do.
+    Done,
+}
+
+/// A field getting collapsed, see [`PlotGenerator::iter_collapses`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollapseEvent {
+    pub pos: (usize, usize),
+    pub tile: TileId,
+    /// The fields propagation removed possibilities from, with how many possibilities they have
+    /// left.
+    pub propagated: Vec<((usize, usize), usize)>,
+}
+
+/// Collapses a [`PlotGenerator`] one field at a time, see [`PlotGenerator::iter_collapses`].
+pub struct Collapses<'a> {
+    gen: &'a mut PlotGenerator,
+    done: bool,
+}
+
+/// A [`PlotGenerator`] collapsing on another thread, see
+/// [`PlotGenerator::collapse_in_background`].
+#[cfg(feature = "std")]
+pub struct BackgroundCollapse {
+    cancel: Arc<AtomicBool>,
+    /// The bits of the latest share of collapsed fields.
+    progress: Arc<AtomicU64>,
+    thread: JoinHandle<(Result<Plot, WfcError>, PlotGenerator)>,
+}
+
+/// Displays how many possibilities the fields of a [`PlotGenerator`] have left, see
+/// [`PlotGenerator::heatmap`].
+pub struct Heatmap<'a>(&'a PlotGenerator);
+
+/// Watches a [`PlotGenerator`] while it collapses, see [`PlotGenerator::with_observer`].
+///
+/// Every method does nothing by default, so only the interesting events have to be implemented.
+pub trait Observer {
+    /// The field at `pos` got collapsed into `tile`.
+    fn on_collapse(&mut self, _pos: (usize, usize), _tile: TileId) {}
+
+    /// Propagation removed possibilities from the field at `pos`, `states` are the ones left.
+    fn on_propagate(&mut self, _pos: (usize, usize), _states: &DomainMask) {}
+
+    /// The field at `pos` ran out of possibilities.
+    fn on_contradiction(&mut self, _pos: (usize, usize)) {}
+}
+
+/// An entropy that can be ordered in the entropy heap.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) struct Entropy(pub(crate) f64);
+
+/// How close a [`PlotGenerator`] is to a contradiction, see [`PlotGenerator::risk_summary`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RiskSummary {
+    /// Uncollapsed fields with a single possibility left.
+    pub forced: usize,
+    /// Uncollapsed fields without any possibility left.
+    pub contradicted: usize,
+    /// The fewest possibilities any uncollapsed field has left, 0 if every field is collapsed.
+    pub min_entropy: usize,
+}
+
+/// A symmetry that [`PlotGenerator::collapse`] keeps intact by collapsing mirrored fields into
+/// the same tile, e.g. for fair arenas.
+///
+/// Tiles keep their orientation in the mirrored fields, so symmetries suit tile sets whose rules
+/// look the same from every side.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Symmetry {
+    /// Every row reads the same from left to right as from right to left.
+    #[deprecated(note = "Use `Symmetry::MirrorX`")]
+    PalindromeX,
+    /// Every row reads the same from left to right as from right to left.
+    MirrorX,
+    /// Every column reads the same from top to bottom as from bottom to top.
+    MirrorY,
+    /// The plot looks the same after half a turn.
+    Rotational2,
+    /// The plot looks the same after a quarter turn, only for square plots.
+    Rotational4,
+}
+
+/// How [`PlotGenerator::generate`] finds a plot, see [`PlotGenerator::with_solver`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Solver {
+    /// Wave function collapse: observe the field with the lowest entropy, propagate, and
+    /// backtrack out of contradictions.
+    #[default]
+    Wfc,
+    /// Min-conflicts repair: assign every field a random tile of its possibilities, then
+    /// reassign fields that break a rule to the tile breaking the fewest, up to `max_steps`
+    /// times. Doesn't run into contradictions, so it can beat WFC on big grids with tight rules,
+    /// but it can run out of steps with rules left broken.
+    MinConflicts { max_steps: usize },
+}
+
+/// How much work [`PlotGenerator::run_for`] does at a time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Budget {
+    /// Steps for up to this long. A step that starts in time runs to its end, so the budget can
+    /// be overrun by one step. Needs a clock, which `wasm32-unknown-unknown` doesn't have, and
+    /// the `std` feature.
+    #[cfg(feature = "std")]
+    Time(Duration),
+    /// Up to this many steps.
+    Steps(usize),
+}
+
+#[cfg(feature = "std")]
+impl From<Duration> for Budget {
+    fn from(duration: Duration) -> Self {
+        Budget::Time(duration)
+    }
+}
+
+/// Which field [`PlotGenerator::step`] observes next, see [`PlotGenerator::with_selection`].
+/// Each one gives plots a texture of its own, and runs into contradictions more or less often.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SelectionStrategy {
+    /// The field with the lowest entropy, the one most constrained by what collapsed so far.
+    /// Least likely to run into contradictions.
+    #[default]
+    LowestEntropy,
+    /// The first uncollapsed field, row by row, like a typewriter.
+    Scanline,
+    /// Any uncollapsed field, picked at random.
+    Random,
+    /// The uncollapsed field closest to the last observed one, in steps of the "#" shape, the
+    /// one with the lowest entropy among those equally close. Grows the plot outwards from the
+    /// first field.
+    Nearest,
+}
+
+/// How far a change spreads through the wave, see [`PlotGenerator::with_propagation`].
+///
+/// Propagation goes in waves: the neighbours of a changed field lose the tiles the rules don't
+/// allow next to it, then the neighbours of those that changed, and so on. Stopping early saves
+/// time, but fields may keep tiles that can't work out anymore, so more observations end in a
+/// contradiction and get backtracked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Propagation {
+    /// Only the direct neighbours of a changed field get updated.
+    OneStep,
+    /// Changes spread for up to this many waves, `Radius(1)` is [`Propagation::OneStep`].
+    Radius(usize),
+    /// Changes spread until no field changes anymore, making every pair of neighbours
+    /// arc-consistent, like AC-3.
+    #[default]
+    Full,
+}
+
+/// One of the four edges of the grid, see [`PlotGenerator::constrain_edge`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Edge {
+    /// The first row.
+    Top,
+    /// The last column.
+    Right,
+    /// The last row.
+    Bottom,
+    /// The first column.
+    Left,
+}
+
+/// Errors that can happen while collapsing a [`PlotGenerator`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum WfcError {
+    /// The field at this position has no possible state left.
+    Contradiction((usize, usize)),
+    /// The generation got cancelled before every field was collapsed.
+    Cancelled,
+    /// The field at this `(x, y, z)` position of a [`VolumeGenerator`](crate::VolumeGenerator)
+    /// has no possible state left.
+    VolumeContradiction((usize, usize, usize)),
+    /// The field at this world position of a [`World`](crate::World) has no possible state
+    /// left.
+    WorldContradiction((i64, i64)),
+    /// Values of a grid were given for a `(width, height)` plot, but `len` of them instead of
+    /// one per field.
+    GridSize {
+        expected: (usize, usize),
+        len: usize,
+    },
+    /// No field can become this tile anymore.
+    NoField(TileId),
+}
+
+/// What [`PlotGenerator::generate_with_retries`] went through.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryStats {
+    /// How many attempts were made, including the last one.
+    pub attempts: usize,
+    /// Where every failed attempt ran into its contradiction, in order.
+    pub failures: Vec<(usize, usize)>,
+    /// The seed of the last attempt. [`PlotGenerator::with_seed`] with this seed reproduces its
+    /// result, given the same settings and pre-set fields.
+    pub seed: u64,
+}
+
+/// What the steps of a [`PlotGenerator`] went through, see [`PlotGenerator::into_parts`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GenerationStats {
+    /// How many fields got observed, including the observations undone later.
+    pub observations: usize,
+    /// How many contradictions got backtracked out of.
+    pub backtracks: usize,
+}
+
+/// The settings a [`PlotGenerator`] generated with, see [`PlotGenerator::into_parts`].
+///
+/// [`GenerationSpec::generator`] builds a generator with them that generates the same plot, given
+/// the seed got set after the other settings and no fields got set or restricted. Rules, biases,
+/// weight fields, masks, elevations and quotas aren't part of the spec.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GenerationSpec {
+    pub width: usize,
+    pub height: usize,
+    pub tileset: Arc<TileSet>,
+    /// The seed of the random generator, see [`GenerationLog::seed`].
+    pub seed: [u8; 32],
+    pub weights: Weights,
+    pub symmetry: Option<Symmetry>,
+    pub boundary: BoundaryMode,
+    pub topology: Topology,
+    pub neighbourhood: Neighbourhood,
+    pub selection: SelectionStrategy,
+    pub solver: Solver,
+    pub propagation: Propagation,
+    pub backtrack_budget: usize,
+}
+
+/// Neighbouring fields whose tiles break the rules, see
+/// [`PlotGenerator::generate_best_effort`]: the tile at `neighbour` may not lie in `direction`
+/// of the tile at `pos`, or the other way around.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Violation {
+    pub pos: (usize, usize),
+    pub direction: Direction,
+    pub neighbour: (usize, usize),
+}
+
+/// Errors of [`PlotGenerator::generate`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum GenError {
+    /// The field at this position ran out of possible tiles, and backtracking didn't resolve
+    /// it. Another seed may work.
+    Contradiction((usize, usize)),
+    /// The field at this position is still in superposition, e.g. because the generation got
+    /// cancelled.
+    Incomplete((usize, usize)),
+    /// The rules make every plot of this size impossible, so retrying with another seed won't
+    /// help. Also covers tile sets that can't be built or loaded.
+    InvalidRules(String),
+}
+
+impl Iterator for Collapses<'_> {
+    type Item = Result<CollapseEvent, WfcError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            match self.gen.step() {
+                Ok(Step::Collapsed { pos, tile, changed }) => {
+                    let mut changed = changed;
+                    changed.sort_unstable();
+                    changed.dedup();
+                    let propagated = changed
+                        .into_iter()
+                        .map(|pos| (pos, self.gen.state(pos).count()))
+                        .collect();
+
+                    return Some(Ok(CollapseEvent {
+                        pos,
+                        tile,
+                        propagated,
+                    }));
+                }
+                Ok(Step::Backtracked { .. }) => continue,
+                Ok(Step::Done) => self.done = true,
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl FusedIterator for Collapses<'_> {}
+
+#[cfg(feature = "std")]
+impl BackgroundCollapse {
+    /// The share of collapsed fields as last reported, from 0 to 1.
+    pub fn progress(&self) -> f64 {
+        f64::from_bits(self.progress.load(Ordering::Relaxed))
+    }
+
+    /// Asks the collapse to stop, it ends with [`WfcError::Cancelled`] soon after.
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the collapse is over, so [`BackgroundCollapse::join`] returns right away.
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Waits for the collapse to end. Returns its result along with the generator, which keeps
+    /// the partially collapsed state after a contradiction or cancellation.
+    pub fn join(self) -> (Result<Plot, WfcError>, PlotGenerator) {
+        match self.thread.join() {
+            Ok(result) => result,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+}
+
+impl Edge {
+    /// Every edge, clockwise from the top.
+    pub const ALL: [Edge; 4] = [Edge::Top, Edge::Right, Edge::Bottom, Edge::Left];
+
+    /// The fields on this edge of a `width` x `height` grid, left to right or top to bottom.
+    pub fn fields(&self, width: usize, height: usize) -> Vec<(usize, usize)> {
+        if width == 0 || height == 0 {
+            return vec![];
+        }
+
+        match self {
+            Edge::Top => (0..width).map(|x| (x, 0)).collect(),
+            Edge::Right => (0..height).map(|y| (width - 1, y)).collect(),
+            Edge::Bottom => (0..width).map(|x| (x, height - 1)).collect(),
+            Edge::Left => (0..height).map(|y| (0, y)).collect(),
+        }
+    }
+}
+
+impl GenerationSpec {
+    /// A fresh generator with these settings, seeded with [`GenerationSpec::seed`].
+    pub fn generator(&self) -> PlotGenerator {
+        let mut gen =
+            PlotGenerator::for_tileset(Arc::clone(&self.tileset), self.width, self.height)
+                .with_weights(self.weights.clone())
+                .with_boundary(self.boundary)
+                .with_topology(self.topology)
+                .with_neighbourhood(self.neighbourhood.clone())
+                .with_selection(self.selection)
+                .with_solver(self.solver)
+                .with_propagation(self.propagation)
+                .with_backtrack_budget(self.backtrack_budget);
+        if let Some(symmetry) = self.symmetry {
+            gen = gen.with_symmetry(symmetry);
+        }
+        gen.rng = ChaCha12Rng::from_seed(self.seed);
+        gen.rebuild_entropies();
+        gen
+    }
+}
+
+impl WaveState {
+    /// The tiles this field can still become.
+    pub fn states(&self) -> impl Iterator<Item = TileId> + '_ {
+        let (tile, states) = match self {
+            WaveState::Collapsed(tile) => (Some(*tile), None),
+            WaveState::Superposition(states) => (None, Some(states)),
+        };
+
+        tile.into_iter()
+            .chain(states.into_iter().flat_map(DomainMask::iter))
+    }
+
+    /// Whether this field can still become `tile`.
+    pub fn contains(&self, tile: TileId) -> bool {
+        match self {
+            WaveState::Collapsed(collapsed) => *collapsed == tile,
+            WaveState::Superposition(states) => states.contains(tile),
+        }
+    }
+
+    /// How many tiles this field can still become.
+    pub fn count(&self) -> usize {
+        match self {
+            WaveState::Collapsed(_) => 1,
+            WaveState::Superposition(states) => states.len(),
+        }
+    }
+}
+
+impl Symmetry {
+    /// The other fields that have to hold the same tile as `(x, y)` in a `width` x `height`
+    /// grid. Empty for fields that are their own mirror image, like the centre.
+    pub fn counterparts(
+        &self,
+        (x, y): (usize, usize),
+        width: usize,
+        height: usize,
+    ) -> Vec<(usize, usize)> {
+        let (right, bottom) = (width - 1, height - 1);
+        #[allow(deprecated)]
+        let mut fields = match self {
+            Symmetry::PalindromeX | Symmetry::MirrorX => vec![(right - x, y)],
+            Symmetry::MirrorY => vec![(x, bottom - y)],
+            Symmetry::Rotational2 => vec![(right - x, bottom - y)],
+            Symmetry::Rotational4 => vec![(bottom - y, x), (right - x, bottom - y), (y, right - x)],
+        };
+        fields.sort_unstable();
+        fields.dedup();
+        fields.retain(|&field| field != (x, y));
+        fields
+    }
+}
+
+impl PlotGenerator {
+    /// Creates a generator for a `width` x `height` plot of the built-in
+    /// [`TileSet::terrain`], with every field in superposition.
+    pub fn new(width: usize, height: usize) -> Self {
+        PlotGenerator::for_tileset(TileSet::terrain(), width, height)
+    }
+
+    /// Creates a generator for a `width` x `height` plot made of the tiles in `tileset`, with
+    /// every field in superposition.
+    pub fn for_tileset(tileset: impl Into<Arc<TileSet>>, width: usize, height: usize) -> Self {
+        let tileset = tileset.into();
+        let tile_count = tileset.len();
+        assert!(
+            width * height <= u32::MAX as usize,
+            "The grid can't have more than 2³² - 1 fields"
+        );
+        let mut gen = PlotGenerator {
+            width,
+            height,
+            tileset,
+            tiles: Wave::new(width * height, tile_count),
+            symmetry: None,
+            boundary: BoundaryMode::Clamp,
+            topology: Topology::Square,
+            neighbourhood: Neighbourhood::Moore,
+            weights: Weights::default(),
+            rng: random_rng(),
+            history: VecDeque::new(),
+            entropies: BinaryHeap::new(),
+            backtrack_budget: DEFAULT_BACKTRACK_BUDGET,
+            observer: None,
+            rules: None,
+            bias: None,
+            weight_field: None,
+            elevation: None,
+            solver: Solver::Wfc,
+            propagation: Propagation::Full,
+            selection: SelectionStrategy::LowestEntropy,
+            last: None,
+            undo: VecDeque::new(),
+            redo: vec![],
+            history_limit: 0,
+            quotas: vec![],
+            log: None,
+            mask: None,
+            #[cfg(feature = "gpu")]
+            gpu: None,
+            stats: GenerationStats::default(),
+        };
+        gen.rebuild_entropies();
+
+        gen
+    }
+
+    /// Draws every random decision from a generator seeded with `seed`, so the same seed and
+    /// settings always produce the same plot.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = ChaCha12Rng::seed_from_u64(seed);
+        // The tie breakers have to come from the seeded generator as well
+        self.rebuild_entropies();
+        self
+    }
+
+    /// Draws every random decision from a generator seeded by `rng`, e.g. the random source of a
+    /// device without the `std` feature, see "Without std" in the crate docs. `rng` gets asked
+    /// for a seed once, so two sources giving the same bytes produce the same plot.
+    pub fn with_rng(mut self, rng: &mut impl RngCore) -> Self {
+        let mut seed = <ChaCha12Rng as SeedableRng>::Seed::default();
+        rng.fill_bytes(&mut seed);
+        self.rng = ChaCha12Rng::from_seed(seed);
+        self.rebuild_entropies();
+        self
+    }
+
+    /// Approximate memory footprint, in bytes, of the fields of a `width` x `height` generator
+    /// with `tile_count` tiles, at worst. Saturates at `usize::MAX` instead of overflowing.
+    ///
+    /// A fresh grid takes a byte per field, four with more than 254 tiles, and so do collapsed
+    /// fields. Only fields in superposition that lost a tile, the frontier of the collapse, own a
+    /// [`DomainMask`] with one bit per tile, up to half of the grid before every field gets a
+    /// [`WaveState`] of its own. On top come up to four queued entropies per field, and the
+    /// states backtracking remembers, at worst one for every tile a field loses, see
+    /// [`PlotGenerator::with_backtrack_budget`]. Most plots take a small part of this.
+    ///
+    /// A history of [`PlotGenerator::with_history`] keeps up to `limit` more copies of the grid
+    /// and its queued entropies.
+    pub fn estimated_bytes(width: usize, height: usize, tile_count: usize) -> usize {
+        use core::mem::size_of;
+
+        let fields = width.saturating_mul(height);
+        let mask = tile_count.div_ceil(64).saturating_mul(size_of::<u64>());
+        // A code and a frontier entry with its mask, the frontier map reserves up to twice the
+        // half of the grid it holds
+        let wave = size_of::<u32>() + size_of::<(usize, WaveState)>() + 1 + mask;
+        // The heap starts over beyond four entries per field, and can have reserved twice that
+        let entropies = 8 * size_of::<Reverse<(Entropy, u32, u32)>>();
+        // One remembered state per tile, in vectors that can have reserved twice as many
+        let trail = tile_count.saturating_mul(2 * (size_of::<(u32, WaveState)>() + mask));
+
+        fields.saturating_mul(wave.saturating_add(entropies).saturating_add(trail))
+    }
+
+    /// Picks tiles proportionally to `weights` when a field collapses, instead of the weights of
+    /// the tile set.
+    pub fn with_weights(mut self, weights: Weights) -> Self {
+        self.weights = weights;
+        self.rebuild_entropies();
+        self
+    }
+
+    /// The weights the tiles get picked by, see [`PlotGenerator::with_weights`].
+    pub fn weights(&self) -> &Weights {
+        &self.weights
+    }
+
+    /// Shifts the weights of the tiles towards how often they occur in `liked`, e.g. a plot a
+    /// user rated well, so the plots generated from then on trend towards their taste.
+    ///
+    /// Every weight moves by `rate` of the way to the share of its tile in `liked`, scaled to the
+    /// sum of the weights, so the sum stays the same: 0 keeps the weights, 1 takes the shares of
+    /// `liked` over and never picks tiles missing from it again.
+    ///
+    /// Panics if `rate` lies outside of `0..=1`, or if `liked` is of another tile set.
+    pub fn reinforce(&mut self, liked: &Plot, rate: f64) {
+        assert!(
+            (0.0..=1.0).contains(&rate),
+            "The rate has to be between 0 and 1"
+        );
+        assert_eq!(
+            liked.tileset().fingerprint(),
+            self.tileset.fingerprint(),
+            "The liked plot has to be of the same tile set"
+        );
+
+        let fields = liked.positions().count().max(1) as f64;
+        let total = self.tileset.ids().map(|t| self.weight(t)).sum::<f64>();
+        let mut weights = core::mem::take(&mut self.weights);
+        for tile in self.tileset.ids() {
+            let target = liked.count(tile) as f64 / fields * total;
+            let weight = weights
+                .get(tile)
+                .unwrap_or_else(|| self.tileset.weight(tile));
+            weights = weights.with(tile, weight + (target - weight) * rate);
+        }
+        self.weights = weights;
+        self.rebuild_entropies();
+    }
+
+    /// Multiplies the weights of the tiles with the factors of `bias` on every field, so tiles
+    /// get likelier in some parts of the plot and rarer in others.
+    ///
+    /// Panics if `bias` is for a grid of another size.
+    pub fn with_bias(mut self, bias: BiasMap) -> Self {
+        assert!(
+            bias.width() == self.width && bias.height() == self.height,
+            "The bias has to cover the grid"
+        );
+        self.bias = Some(bias);
+        self.rebuild_entropies();
+        self
+    }
+
+    /// Picks tiles with the weights of `field` instead of the tile weights, one distribution per
+    /// field, indexed as `field[y][x]`. Tiles missing from the distribution of a field are never
+    /// picked there. The bias of [`PlotGenerator::with_bias`] still applies on top.
+    ///
+    /// Fails without changing anything with a [`WfcError::GridSize`] if `field` doesn't have a
+    /// distribution for every field. Panics if a weight is negative.
+    pub fn set_weight_field(
+        &mut self,
+        field: Vec<Vec<HashMap<TileId, f64>>>,
+    ) -> Result<(), WfcError> {
+        if field.len() != self.height || field.iter().any(|row| row.len() != self.width) {
+            return Err(WfcError::GridSize {
+                expected: (self.width, self.height),
+                len: field.iter().map(Vec::len).sum(),
+            });
+        }
+        assert!(
+            field
+                .iter()
+                .flatten()
+                .flat_map(HashMap::values)
+                .all(|&w| w >= 0.0),
+            "Weights can't be negative"
+        );
+
+        self.weight_field = Some(field.into_iter().flatten().collect());
+        self.rebuild_entropies();
+        Ok(())
+    }
+
+    /// The bias of [`PlotGenerator::with_bias`], if there is one.
+    pub(crate) fn bias(&self) -> Option<&BiasMap> {
+        self.bias.as_ref()
+    }
+
+    /// Gives up with a [`WfcError::Contradiction`] after backtracking out of `budget`
+    /// contradictions.
+    ///
+    /// Every observation remembers the states the fields it changed had before, to backtrack to,
+    /// the latest 64 of them are kept. A budget of 0 remembers nothing, which saves memory on
+    /// huge grids.
+    pub fn with_backtrack_budget(mut self, budget: usize) -> Self {
+        self.backtrack_budget = budget;
+        self
+    }
+
+    /// Keeps the states before the last `limit` changes, to go back to with
+    /// [`PlotGenerator::undo`]. Every [`PlotGenerator::step`], [`PlotGenerator::set_tile`] and
+    /// [`PlotGenerator::constrain`] is a change, methods made of several of them, like
+    /// [`PlotGenerator::collapse`], make several changes. Defaults to 0, keeping nothing.
+    ///
+    /// Every state is a copy of the wave, so a long history of a big grid takes a lot of memory.
+    pub fn with_history(mut self, limit: usize) -> Self {
+        self.history_limit = limit;
+        while self.undo.len() > limit {
+            self.undo.pop_front();
+        }
+        self
+    }
+
+    /// Records every [`PlotGenerator::step`] from now on into a [`GenerationLog`], to replay it
+    /// with [`PlotGenerator::replay`].
+    pub fn with_log(mut self) -> Self {
+        self.log = Some(GenerationLog::default());
+        self
+    }
+
+    /// Reports every collapse, propagation and contradiction to `observer`.
+    pub fn with_observer(mut self, observer: impl Observer + Send + 'static) -> Self {
+        self.observer = Some(Box::new(observer));
+        self
+    }
+
+    /// Decides which tiles may lie next to each other with `rules` instead of the rules of the
+    /// tile set, which still provides the tiles, their weights and their looks. Call this before
+    /// setting or restricting any field, those already got propagated with the old rules.
+    ///
+    /// The rules get compiled into a [`CompiledRuleset`] right away, so they only get asked once
+    /// per tile and direction, and get saved with the generator. Generators of a batch sharing
+    /// the same rules can skip compiling them with [`PlotGenerator::with_compiled_rules`].
+    pub fn with_rules(mut self, rules: impl Ruleset + Send + Sync + 'static) -> Self {
+        self.rules = Some(Arc::new(CompiledRuleset::compile(&rules, &self.tileset)));
+        self
+    }
+
+    /// Like [`PlotGenerator::with_rules`], but with rules compiled before, so a batch of
+    /// generators shares them instead of every generator asking the rules again.
+    ///
+    /// Panics if the rules got compiled for another tile set, see [`CompiledRuleset::fits`].
+    pub fn with_compiled_rules(mut self, rules: Arc<CompiledRuleset>) -> Self {
+        assert!(
+            rules.fits(&self.tileset),
+            "The rules were compiled for another tile set"
+        );
+        self.rules = Some(rules);
+        self
+    }
+
+    /// Treats the edges of the grid according to `boundary`, see [`BoundaryMode`].
+    pub fn with_boundary(mut self, boundary: BoundaryMode) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    /// Only treats the fields of `neighbourhood` as neighbours on a square grid,
+    /// [`Neighbourhood::Moore`] by default. Hex grids always use their 6 neighbours.
+    ///
+    /// Panics if a custom neighbourhood lacks the opposite of one of its directions, the fields
+    /// on that side would ignore the rules.
+    pub fn with_neighbourhood(mut self, neighbourhood: Neighbourhood) -> Self {
+        if let Neighbourhood::Custom(directions) = &neighbourhood {
+            assert!(
+                directions
+                    .iter()
+                    .all(|direction| directions.contains(&direction.opposite())),
+                "Every direction of the neighbourhood needs its opposite"
+            );
+        }
+        self.neighbourhood = neighbourhood;
+        self
+    }
+
+    /// Lays the fields out according to `topology`, see [`Topology`].
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    /// Removes every tile from every field that doesn't fit the height of the field, and
+    /// propagates that. The heights end up in the plot, see [`Plot::elevation`].
+    ///
+    /// Panics if `elevation` is for a grid of another size.
+    pub fn with_elevation(mut self, elevation: Elevation) -> Self {
+        assert!(
+            elevation.width() == self.width && elevation.height() == self.height,
+            "The elevation has to cover the grid"
+        );
+
+        let mut changed = vec![];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let allowed = elevation.allowed(&self.tileset, x, y);
+                if self.intersect_state(y * self.width + x, &allowed) {
+                    changed.push((x, y));
+                }
+            }
+        }
+        for pos in changed {
+            self.update_neighbours(pos);
+        }
+        self.rebuild_entropies();
+
+        self.elevation = Some(elevation.into_heights());
+        self
+    }
+
+    /// Uses `solver` in [`PlotGenerator::generate`], [`Solver::Wfc`] by default. The
+    /// step-by-step methods like [`PlotGenerator::collapse`] are always WFC.
+    pub fn with_solver(mut self, solver: Solver) -> Self {
+        self.solver = solver;
+        self
+    }
+
+    /// Picks the field to observe next with `selection`, [`SelectionStrategy::LowestEntropy`]
+    /// by default.
+    pub fn with_selection(mut self, selection: SelectionStrategy) -> Self {
+        self.selection = selection;
+        self.rebuild_entropies();
+        self
+    }
+
+    /// Spreads changes through the wave as far as `propagation` says, [`Propagation::Full`] by
+    /// default.
+    pub fn with_propagation(mut self, propagation: Propagation) -> Self {
+        assert!(
+            propagation != Propagation::Radius(0),
+            "Changes have to reach at least the neighbours"
+        );
+        self.propagation = propagation;
+        self
+    }
+
+    /// Propagates changes on the GPU with a compute shader, while observing stays on the CPU.
+    /// Every propagation sweeps over the whole grid, so it only pays off for very big grids.
+    ///
+    /// Without a GPU, and for propagations other than [`Propagation::Full`], propagation stays
+    /// on the CPU, and so does any propagation the GPU fails at. Fields lose their tiles in
+    /// another order than on the CPU, so the same seed can give another plot, see
+    /// [`PlotGenerator::uses_gpu`].
+    ///
+    /// The `gpu` feature needs Rust 1.90 or newer, for the dependencies of `wgpu`.
+    #[cfg(feature = "gpu")]
+    pub fn with_gpu(mut self) -> Self {
+        self.gpu = GpuPropagator::shared();
+        #[cfg(feature = "tracing")]
+        if self.gpu.is_none() {
+            tracing::debug!("no GPU found, propagating on the CPU");
+        }
+        self
+    }
+
+    /// Whether propagation runs on the GPU, see [`PlotGenerator::with_gpu`].
+    #[cfg(feature = "gpu")]
+    pub fn uses_gpu(&self) -> bool {
+        self.gpu.is_some() && self.propagation == Propagation::Full
+    }
+
+    /// Keeps the number of `tile`s in the plot within `count`, e.g. `..=20` for at most 20 rivers
+    /// or `30..=60` for a balanced share of farmland. Replaces an earlier quota of the tile.
+    ///
+    /// Once a tile reaches its maximum, it is removed from every field still in superposition.
+    /// Once only as many fields can still become a tile as its minimum needs, they can't become
+    /// anything else. A collapse that makes a quota impossible counts as a contradiction and gets
+    /// backtracked. [`Solver::MinConflicts`] ignores the quotas.
+    pub fn with_quota(mut self, tile: impl Into<TileId>, count: impl RangeBounds<usize>) -> Self {
+        let tile = tile.into();
+        let min = match count.start_bound() {
+            Bound::Included(&min) => min,
+            Bound::Excluded(&min) => min + 1,
+            Bound::Unbounded => 0,
+        };
+        let max = match count.end_bound() {
+            Bound::Included(&max) => max,
+            Bound::Excluded(&max) => max.checked_sub(1).expect("The quota can't be empty"),
+            Bound::Unbounded => usize::MAX,
+        };
+        assert!(min <= max, "The quota can't be empty");
+
+        self.quotas.retain(|quota| quota.tile != tile);
+        self.quotas.push(Quota { tile, min, max });
+        self
+    }
+
+    /// Only generates the fields inside `mask`, e.g. the outline of an island. The fields outside
+    /// get `fill` and count as lying beyond the edge of the grid: they don't restrict their
+    /// neighbours, whatever the rules say about `fill`.
+    pub fn with_mask(mut self, mask: Mask, fill: impl Into<TileId>) -> Self {
+        assert!(
+            mask.width() == self.width && mask.height() == self.height,
+            "The mask has to cover the grid"
+        );
+
+        let fill = fill.into();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if !mask.contains(x, y) {
+                    self.set_state((x, y), WaveState::Collapsed(fill));
+                }
+            }
+        }
+        self.mask = Some(mask);
+        self.rebuild_entropies();
+        self
+    }
+
+    /// Only generates the fields of [`Mask::from_alpha`], so the outline can be drawn in any
+    /// image editor. Images of another size than the grid get scaled to it, taking the nearest
+    /// pixel for every field.
+    #[cfg(feature = "image")]
+    pub fn with_mask_image(
+        self,
+        image: &image::RgbaImage,
+        threshold: u8,
+        fill: impl Into<TileId>,
+    ) -> Self {
+        let (width, height) = (self.width as u32, self.height as u32);
+        let mask = match image.dimensions() == (width, height) {
+            true => Mask::from_alpha(image, threshold),
+            false => {
+                let filter = image::imageops::FilterType::Nearest;
+                Mask::from_alpha(
+                    &image::imageops::resize(image, width, height, filter),
+                    threshold,
+                )
+            }
+        };
+        self.with_mask(mask, fill)
+    }
+
+    /// Keeps `symmetry` intact while collapsing.
+    pub fn with_symmetry(mut self, symmetry: Symmetry) -> Self {
+        assert!(
+            symmetry != Symmetry::Rotational4 || self.width == self.height,
+            "Only square plots can look the same after a quarter turn"
+        );
+        self.symmetry = Some(symmetry);
+        self
+    }
+
+    /// Fixes the four borders to the given tile sequences, so that plots with the same
+    /// fingerprint interlock with each other.
+    ///
+    /// `top` and `bottom` are read from left to right and need `width` tiles, `left` and `right`
+    /// are read from top to bottom and need `height` tiles. Edges that share a corner have to
+    /// agree on it.
+    pub fn with_edge_fingerprint<T: Copy + Into<TileId>>(
+        mut self,
+        top: &[T],
+        right: &[T],
+        bottom: &[T],
+        left: &[T],
+    ) -> Self {
+        let (width, height) = (self.width, self.height);
+        let edge = |tiles: &[T]| tiles.iter().map(|t| (*t).into()).collect::<Vec<TileId>>();
+        let (top, right, bottom, left) = (edge(top), edge(right), edge(bottom), edge(left));
+
+        assert!(width > 0 && height > 0, "Edge fingerprints can't be empty");
+        assert!(
+            top.len() == width && bottom.len() == width,
+            "Top and bottom edge fingerprints need `width` tiles"
+        );
+        assert!(
+            left.len() == height && right.len() == height,
+            "Left and right edge fingerprints need `height` tiles"
+        );
+        assert!(
+            top[0] == left[0]
+                && top[width - 1] == right[0]
+                && bottom[0] == left[height - 1]
+                && bottom[width - 1] == right[height - 1],
+            "Edge fingerprints disagree on a corner"
+        );
+
+        let mut border = vec![];
+        for x in 0..width {
+            border.push(((x, 0), top[x]));
+            border.push(((x, height - 1), bottom[x]));
+        }
+        for y in 0..height {
+            border.push(((0, y), left[y]));
+            border.push(((width - 1, y), right[y]));
+        }
+
+        for (pos, tile) in border {
+            self.set_state(pos, WaveState::Collapsed(tile));
+            self.update_neighbours(pos);
+        }
+
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn tileset(&self) -> &TileSet {
+        &self.tileset
+    }
+
+    /// The state of the field at `(x, y)`.
+    pub fn get(&self, x: usize, y: usize) -> &WaveState {
+        self.state((x, y))
+    }
+
+    /// The states of every field, row by row, so the field at `(x, y)` comes
+    /// `y * width + x`th. For tools that inspect the whole wave at once, like visualizers, see
+    /// [`PlotGenerator::entropy`] for the entropy of a field.
+    pub fn domains(&self) -> impl Iterator<Item = &WaveState> + '_ {
+        self.tiles.iter()
+    }
+
+    /// Every tile that is still possible anywhere: all remaining possibilities of the
+    /// superpositions plus all collapsed tiles.
+    ///
+    /// If a tile drops out of this set it can no longer appear in the plot.
+    pub fn possible_tiles(&self) -> HashSet<TileId> {
+        self.tiles.iter().flat_map(WaveState::states).collect()
+    }
+
+    /// Whether every order of observations collapses the generator without a contradiction,
+    /// whatever field gets observed next and whichever of its tiles it becomes. Backtracking
+    /// and quotas are ignored, only the rules, symmetry and mask count. Rules like these are
+    /// easy: generating never has to backtrack.
+    ///
+    /// There are up to `fields! * tiles^fields` orders. States that different orders reach are
+    /// only checked once, but that still leaves up to `(tiles + 1)^fields` states, so only
+    /// generators with up to [`ALL_ORDERS_MAX_FIELDS`] fields in superposition can be checked. A
+    /// 3 x 3 grid of the built-in terrain takes under a second, a 4 x 3 one half a minute, and
+    /// every tile more makes it a lot slower.
+    ///
+    /// Panics if more fields are in superposition.
+    pub fn all_orders_succeed(&self) -> bool {
+        let open = self
+            .tiles
+            .iter()
+            .filter(|state| matches!(state, WaveState::Superposition(_)))
+            .count();
+        assert!(
+            open <= ALL_ORDERS_MAX_FIELDS,
+            "Checking every order of {open} fields takes too long"
+        );
+
+        let mut gen =
+            PlotGenerator::for_tileset(Arc::clone(&self.tileset), self.width, self.height);
+        gen.tiles = self.tiles.clone();
+        gen.symmetry = self.symmetry;
+        gen.boundary = self.boundary;
+        gen.topology = self.topology;
+        gen.neighbourhood = self.neighbourhood.clone();
+        gen.weights = self.weights.clone();
+        gen.rules = self.rules.clone();
+        gen.bias = self.bias.clone();
+        gen.weight_field = self.weight_field.clone();
+        gen.mask = self.mask.clone();
+        gen.propagation = self.propagation;
+        gen.backtrack_budget = 0;
+        gen.orders_succeed(&mut HashSet::new())
+    }
+
+    /// Goes through every observation from the current state, see
+    /// [`PlotGenerator::all_orders_succeed`]. `succeeded` holds the states every order from
+    /// which succeeds.
+    fn orders_succeed(&mut self, succeeded: &mut HashSet<Vec<WaveState>>) -> bool {
+        let key = self.tiles.iter().cloned().collect::<Vec<_>>();
+        if succeeded.contains(&key) {
+            return true;
+        }
+
+        let before = self.tiles.clone();
+        for index in 0..self.tiles.len() {
+            let pos = (index % self.width, index / self.width);
+            let WaveState::Superposition(states) = self.tiles.get(index) else {
+                continue;
+            };
+            let mirrors = match self.symmetry {
+                Some(symmetry) => symmetry.counterparts(pos, self.width, self.height),
+                None => vec![],
+            };
+            let tiles = states
+                .iter()
+                .filter(|&tile| {
+                    mirrors
+                        .iter()
+                        .all(|&mirror| self.state(mirror).contains(tile))
+                })
+                .collect::<Vec<_>>();
+            if tiles.is_empty() {
+                return false;
+            }
+
+            for tile in tiles {
+                let collapsed = self.observe(pos, Some(tile)).is_ok()
+                    && !self.tiles.iter().any(
+                        |state| matches!(state, WaveState::Superposition(states) if states.is_empty()),
+                    )
+                    && self.orders_succeed(succeeded);
+                self.tiles = before.clone();
+                if !collapsed {
+                    return false;
+                }
+            }
+        }
+
+        succeeded.insert(key);
+        true
+    }
+
+    /// Counts the fields that are about to be forced or already contradict, to warn before a
+    /// contradiction happens.
+    pub fn risk_summary(&self) -> RiskSummary {
+        let entropies = self.tiles.iter().filter_map(|state| match state {
+            WaveState::Collapsed(_) => None,
+            WaveState::Superposition(states) => Some(states.len()),
+        });
+
+        let mut summary = RiskSummary {
+            forced: 0,
+            contradicted: 0,
+            min_entropy: usize::MAX,
+        };
+        for entropy in entropies {
+            match entropy {
+                0 => summary.contradicted += 1,
+                1 => summary.forced += 1,
+                _ => {}
+            }
+            summary.min_entropy = summary.min_entropy.min(entropy);
+        }
+
+        if summary.min_entropy == usize::MAX {
+            summary.min_entropy = 0;
+        }
+
+        summary
+    }
+
+    /// Displays how many possibilities every field has left, one character per field, to see
+    /// where contradictions build up. Collapsed fields show as `.`, contradictions as `!`, fields
+    /// with more than 9 possibilities left as `+`.
+    pub fn heatmap(&self) -> Heatmap<'_> {
+        Heatmap(self)
+    }
+
+    /// Renders how many possibilities every field has left as a `scale` x `scale` square, from
+    /// dark blue for fields that can still become any tile to yellow for fields with a single
+    /// one left. Contradictions are red and collapsed fields black.
+    #[cfg(feature = "image")]
+    pub fn heatmap_image(&self, scale: u32) -> image::RgbImage {
+        let most = self.tileset.len().saturating_sub(1).max(1) as f64;
+        let (width, height) = (self.width as u32 * scale, self.height as u32 * scale);
+        image::RgbImage::from_fn(width, height, |x, y| {
+            let states = match self.get((x / scale) as usize, (y / scale) as usize) {
+                WaveState::Collapsed(_) => return image::Rgb([0, 0, 0]),
+                WaveState::Superposition(states) if states.is_empty() => {
+                    return image::Rgb([255, 0, 0])
+                }
+                WaveState::Superposition(states) => states.len(),
+            };
+
+            let share = (states - 1) as f64 / most;
+            let mix = |few: f64, many: f64| (few + (many - few) * share).round() as u8;
+            image::Rgb([mix(255.0, 20.0), mix(220.0, 30.0), mix(0.0, 120.0)])
+        })
+    }
+
+    /// Iterates over every field.
+    /// * If found_entropy < entropy: reset the found fields (add current), and entropy = found_entropy
+    /// * If found_entropy > entropy: ignore
+    /// * if found_entropy = entropy: add to field array
+    /// * if the field is collapsed : ignore
+    ///
+    /// The entropy is the Shannon entropy of the weighted possibilities, see
+    /// [`PlotGenerator::entropy`]. With the `parallel` feature the grid is scanned with rayon
+    /// instead.
+    #[cfg(not(feature = "parallel"))]
+    pub fn find_lowest_entropy(&self) -> Vec<(usize, usize)> {
+        let mut lowest = vec![];
+        let mut entropy = f64::INFINITY;
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let WaveState::Superposition(states) = self.state((x, y)) else {
+                    continue;
+                };
+
+                let found = shannon_entropy(states, |t| self.weight_at(t, y * self.width + x));
+                if found < entropy {
+                    // replace new lowest
+                    entropy = found;
+                    lowest = vec![(x, y)];
+                } else if found == entropy {
+                    // Has the same entropy as the current lowest
+                    lowest.push((x, y));
+                }
+            }
+        }
+
+        lowest
+    }
+
+    /// Finds every uncollapsed field with the lowest entropy, in row-major order.
+    #[cfg(feature = "parallel")]
+    pub fn find_lowest_entropy(&self) -> Vec<(usize, usize)> {
+        let (tileset, weights, bias) = (&self.tileset, &self.weights, self.bias.as_ref());
+        let field = self.weight_field.as_deref();
+        let tiles = &self.tiles;
+        let entropies = (0..tiles.len())
+            .into_par_iter()
+            .map(|i| match tiles.get(i) {
+                WaveState::Collapsed(_) => f64::INFINITY,
+                WaveState::Superposition(states) => shannon_entropy(states, |t| {
+                    let weight = match field {
+                        Some(field) => field[i].get(&t).copied().unwrap_or(0.0),
+                        None => weights.get(t).unwrap_or_else(|| tileset.weight(t)),
+                    };
+                    weight * bias.map_or(1.0, |bias| bias.factor_at(t, i))
+                }),
+            });
+
+        let entropy = entropies.clone().reduce(|| f64::INFINITY, f64::min);
+        if entropy == f64::INFINITY {
+            return vec![];
+        }
+
+        let width = self.width;
+        entropies
+            .enumerate()
+            .filter(|(_, e)| *e == entropy)
+            .map(|(i, _)| (i % width, i / width))
+            .collect()
+    }
+
+    /// The Shannon entropy `log Σw - Σ(w log w) / Σw` of the weighted tiles the field at `(x, y)`
+    /// can still become. 0 for collapsed fields and fields with one possibility left, negative
+    /// infinity for fields without any.
+    pub fn entropy(&self, x: usize, y: usize) -> f64 {
+        match self.state((x, y)) {
+            WaveState::Collapsed(_) => 0.0,
+            WaveState::Superposition(states) => {
+                shannon_entropy(states, |t| self.weight_at(t, y * self.width + x))
+            }
+        }
+    }
+
+    // After the current field got updated, update other fields accordingly (remove impossible
+    // states). Every field that loses a state updates its own neighbours in turn, until no
+    // field changes anymore or the `Propagation` stops it.
+    //
+    // Updates neighbours in a "#" shape, or the 6 around a hex (see `Topology`), each one with
+    // the rules for the side it lies on.
+    // Returns the fields that lost a possibility.
+    //
+    // The changed fields are handled in batches. With the `parallel` feature the allowed tiles
+    // of all neighbours in a batch are computed in parallel first, and applied afterwards.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "propagate", level = "trace", skip(self))
+    )]
+    pub fn update_neighbours(&mut self, pos: (usize, usize)) -> Vec<(usize, usize)> {
+        if self.is_masked(pos) {
+            return vec![];
+        }
+        #[cfg(feature = "gpu")]
+        if self.uses_gpu() {
+            if let Some(changed) = self.propagate_on_gpu(pos) {
+                return changed;
+            }
+        }
+        let mut changed = vec![];
+        let mut batch = vec![pos];
+        #[cfg(not(feature = "parallel"))]
+        let (mut allowed, offsets) = (DomainMask::empty(self.tileset.len()), self.offsets());
+        let mut waves = match self.propagation {
+            Propagation::OneStep => 1,
+            Propagation::Radius(radius) => radius,
+            Propagation::Full => usize::MAX,
+        };
+
+        while !batch.is_empty() && waves > 0 {
+            waves -= 1;
+            let mut next = vec![];
+
+            #[cfg(feature = "parallel")]
+            for (neighbour, allowed) in self.neighbour_constraints(&batch) {
+                self.restrict(neighbour, &allowed, &mut changed, &mut next);
+            }
+
+            #[cfg(not(feature = "parallel"))]
+            for &(x, y) in &batch {
+                // skip overflows, or wrap around
+                for (direction, neighbour) in
+                    offsets.neighbours((x, y), self.boundary, self.width, self.height)
+                {
+                    allowed.clear();
+                    for t in self.state((x, y)).states() {
+                        allowed.union_with(self.rule(t, direction));
+                    }
+                    self.restrict(neighbour, &allowed, &mut changed, &mut next);
+                }
+            }
+
+            next.sort_unstable();
+            next.dedup();
+            batch = next;
+        }
+
+        changed
+    }
+
+    /// Runs [`PlotGenerator::update_neighbours`] on the GPU, `None` if the GPU failed.
+    #[cfg(feature = "gpu")]
+    fn propagate_on_gpu(&mut self, pos: (usize, usize)) -> Option<Vec<(usize, usize)>> {
+        let propagator = self.gpu.clone()?;
+        let (tile_count, offsets) = (self.tileset.len(), self.offsets());
+        let words = gpu::words(tile_count);
+
+        let mut rules = Vec::with_capacity(offsets.len() * words * 32 * words);
+        let empty = DomainMask::empty(tile_count);
+        for direction in offsets.directions() {
+            for t in 0..words * 32 {
+                let allowed = match t < tile_count {
+                    true => self.rule(TileId(t as u16), direction),
+                    false => &empty,
+                };
+                rules.extend(allowed.to_u32s());
+            }
+        }
+
+        let mut fields = Vec::with_capacity(self.tiles.len() * (words + 1));
+        for (index, state) in self.tiles.iter().enumerate() {
+            let start = (index == pos.1 * self.width + pos.0) as u32 * gpu::ACTIVE;
+            match state {
+                WaveState::Collapsed(t) => {
+                    fields.extend(DomainMask::from_tiles(tile_count, [*t]).to_u32s());
+                    fields.push(gpu::COLLAPSED | start);
+                }
+                WaveState::Superposition(states) => {
+                    fields.extend(states.to_u32s());
+                    fields.push(start);
+                }
+            }
+        }
+
+        let wave = propagator.propagate(&gpu::Wave {
+            width: self.width,
+            height: self.height,
+            wrap: self.boundary == BoundaryMode::Wrap,
+            offsets: offsets.iter().map(|(_, offset)| offset).collect(),
+            words,
+            rules,
+            fields,
+        })?;
+
+        let (mut changed, mut next) = (vec![], vec![]);
+        for (index, field) in wave.chunks_exact(words + 1).enumerate() {
+            if field[words] & gpu::ACTIVE != 0 {
+                let pos = (index % self.width, index / self.width);
+                self.restrict(
+                    pos,
+                    &DomainMask::from_u32s(&field[..words]),
+                    &mut changed,
+                    &mut next,
+                );
+            }
+        }
+        Some(changed)
+    }
+
+    /// Removes every tile but the `allowed` ones from the field at `(x, y)`. If that changed the
+    /// field, it gets recorded in `changed` and queued in `next`.
+    fn restrict(
+        &mut self,
+        (x, y): (usize, usize),
+        allowed: &DomainMask,
+        changed: &mut Vec<(usize, usize)>,
+        next: &mut Vec<(usize, usize)>,
+    ) {
+        let index = y * self.width + x;
+        if self.intersect_state(index, allowed) {
+            let WaveState::Superposition(states) = self.tiles.get(index) else {
+                unreachable!("Only superpositions lose tiles")
+            };
+            #[cfg(feature = "tracing")]
+            tracing::trace!(x, y, remaining = states.len(), "propagated");
+            if let Some(observer) = &mut self.observer {
+                observer.on_propagate((x, y), states);
+            }
+            changed.push((x, y));
+            next.push((x, y));
+            self.push_entropy(index);
+        }
+    }
+
+    /// The tiles every neighbour of the `batch` fields may still become, as far as that field is
+    /// concerned.
+    #[cfg(feature = "parallel")]
+    fn neighbour_constraints(&self, batch: &[(usize, usize)]) -> Vec<((usize, usize), DomainMask)> {
+        let (tileset, tiles, boundary) = (&self.tileset, &self.tiles, self.boundary);
+        let rules = self.rules.as_ref();
+        let (offsets, width, height) = (self.offsets(), self.width, self.height);
+
+        batch
+            .par_iter()
+            .flat_map_iter(|&(x, y)| {
+                // skip overflows, or wrap around
+                let neighbours = offsets.neighbours((x, y), boundary, width, height);
+                neighbours.map(move |(direction, neighbour)| {
+                    let mut allowed = DomainMask::empty(tileset.len());
+                    for t in tiles.get(y * width + x).states() {
+                        match rules {
+                            Some(rules) => allowed.union_with(rules.get(t, direction)),
+                            None => allowed.union_with(tileset.neighbour_mask(t, direction)),
+                        }
+                    }
+
+                    (neighbour, allowed)
+                })
+            })
+            .collect()
+    }
+
+    /// Forces a band of `transition` tiles along the borders between regions.
+    ///
+    /// `regions` assigns a region id to every field, row by row. Every field that has a field of
+    /// another region within `width` steps (in the "#" shape) is collapsed into `transition`, and
+    /// its neighbours are updated. Call this before [`PlotGenerator::collapse`], so the interiors
+    /// collapse around the band.
+    ///
+    /// Fails without changing anything with a [`WfcError::GridSize`] if `regions` doesn't hold
+    /// one id per field, and with a [`WfcError::Contradiction`] at a field of the band that can't
+    /// become `transition`, or that `transition` can't lie next to in the band.
+    pub fn constrain_region_borders(
+        &mut self,
+        regions: &[usize],
+        transition: impl Into<TileId>,
+        width: usize,
+    ) -> Result<(), WfcError> {
+        if regions.len() != self.tiles.len() {
+            return Err(WfcError::GridSize {
+                expected: (self.width, self.height),
+                len: regions.len(),
+            });
+        }
+
+        let transition = transition.into();
+        let region = |(x, y): (usize, usize)| regions[y * self.width + x];
+        let reach = width as isize;
+        let band = Grid::from_fn(self.width, self.height, |x, y| {
+            (-reach..=reach).any(|dx| {
+                (-reach..=reach).any(|dy| {
+                    let (nx, ny) = (x as isize + dx, y as isize + dy);
+                    (0..self.width as isize).contains(&nx)
+                        && (0..self.height as isize).contains(&ny)
+                        && region((nx as usize, ny as usize)) != region((x, y))
+                })
+            })
+        });
+
+        let offsets = self.offsets();
+        for (pos, _) in band.iter().filter(|(_, in_band)| **in_band) {
+            if !self.state(pos).contains(transition) {
+                return Err(WfcError::Contradiction(pos));
+            }
+            for (direction, neighbour) in
+                offsets.neighbours(pos, self.boundary, self.width, self.height)
+            {
+                if band[neighbour] && !self.rule(transition, direction).contains(transition) {
+                    return Err(WfcError::Contradiction(neighbour));
+                }
+            }
+        }
+
+        self.record();
+        for (pos, _) in band.iter().filter(|(_, in_band)| **in_band) {
+            self.set_state(pos, WaveState::Collapsed(transition));
+            self.update_neighbours(pos);
+        }
+        Ok(())
+    }
+
+    /// Collapses the field at `(x, y)` into `tile` and propagates it right away, e.g. to fix parts
+    /// of the plot before [`PlotGenerator::collapse`].
+    ///
+    /// Fails with a [`WfcError::Contradiction`], without changing anything, if the field can't
+    /// become `tile` anymore.
+    pub fn set_tile(
+        &mut self,
+        x: usize,
+        y: usize,
+        tile: impl Into<TileId>,
+    ) -> Result<(), WfcError> {
+        let tile = tile.into();
+        if !self.state((x, y)).contains(tile) {
+            return Err(WfcError::Contradiction((x, y)));
+        }
+
+        self.record();
+        self.set_state((x, y), WaveState::Collapsed(tile));
+        self.update_neighbours((x, y));
+
+        Ok(())
+    }
+
+    /// Restricts the field at `(x, y)` to `tiles` and propagates it right away. The field stays in
+    /// superposition, unless a single tile is left.
+    ///
+    /// Fails with a [`WfcError::Contradiction`], without changing anything, if the field can't
+    /// become any of `tiles` anymore.
+    pub fn constrain<T: Copy + Into<TileId>>(
+        &mut self,
+        x: usize,
+        y: usize,
+        tiles: &[T],
+    ) -> Result<(), WfcError> {
+        let tiles = tiles.iter().map(|t| (*t).into()).collect::<Vec<TileId>>();
+        let states = self
+            .state((x, y))
+            .states()
+            .filter(|t| tiles.contains(t))
+            .collect::<Vec<_>>();
+
+        if states.is_empty() {
+            return Err(WfcError::Contradiction((x, y)));
+        }
+
+        self.record();
+        match states.as_slice() {
+            [tile] => self.set_state((x, y), WaveState::Collapsed(*tile)),
+            _ => {
+                let states = DomainMask::from_tiles(self.tileset.len(), states);
+                self.set_state((x, y), WaveState::Superposition(states));
+                self.push_entropy(y * self.width + x);
+            }
+        }
+        self.update_neighbours((x, y));
+
+        Ok(())
+    }
+
+    /// Restricts every field on the outer ring of the grid to `tiles` and propagates it inwards,
+    /// e.g. to surround the plot with water or walls.
+    ///
+    /// Fails like [`PlotGenerator::constrain`] if a field can't become any of `tiles` anymore,
+    /// the fields restricted before stay restricted.
+    pub fn constrain_border<T: Copy + Into<TileId>>(
+        &mut self,
+        tiles: &[T],
+    ) -> Result<(), WfcError> {
+        for edge in Edge::ALL {
+            self.constrain_edge(edge, tiles)?;
+        }
+        Ok(())
+    }
+
+    /// Restricts every field on `edge` to `tiles` and propagates it inwards. Corners belong to
+    /// both of their edges, so constraining two edges meeting there allows only the tiles both
+    /// allow.
+    ///
+    /// Fails like [`PlotGenerator::constrain`], the fields restricted before stay restricted.
+    pub fn constrain_edge<T: Copy + Into<TileId>>(
+        &mut self,
+        edge: Edge,
+        tiles: &[T],
+    ) -> Result<(), WfcError> {
+        for (x, y) in edge.fields(self.width, self.height) {
+            self.constrain(x, y, tiles)?;
+        }
+        Ok(())
+    }
+
+    /// Pre-seeds a meandering channel of `tile` from the left to the right edge, so the plot gets
+    /// at least one connected run of `tile` across it, e.g. a river. The channel moves one field
+    /// at a time, so it's connected in the "+" shape as well.
+    ///
+    /// Returns the fields of the channel. Fails like [`PlotGenerator::set_tile`] if a field on
+    /// the way can't become `tile`, the fields set before stay set.
+    pub fn carve_channel(
+        &mut self,
+        tile: impl Into<TileId>,
+    ) -> Result<Vec<(usize, usize)>, WfcError> {
+        let tile = tile.into();
+        let mut channel = vec![];
+
+        let mut y = self.rng.gen_range(0..self.height);
+        for x in 0..self.width {
+            // Meander up or down a field before moving on to the right
+            let drift = self.rng.gen_range(-1..=1);
+            let target = (y as isize + drift).clamp(0, self.height as isize - 1) as usize;
+
+            channel.push((x, y));
+            if target != y {
+                y = target;
+                channel.push((x, y));
+            }
+        }
+
+        for &(x, y) in &channel {
+            self.set_tile(x, y, tile)?;
+        }
+
+        Ok(channel)
+    }
+
+    /// The current state of the fields, the random generator and the backtrack budget, e.g. to
+    /// try something out and go back with [`PlotGenerator::restore`].
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            tiles: self.tiles.clone(),
+            rng: self.rng.clone(),
+            entropies: self.entropies.clone(),
+            backtrack_budget: self.backtrack_budget,
+        }
+    }
+
+    /// Goes back to the state of `snapshot`, as a change that [`PlotGenerator::undo`] can undo.
+    ///
+    /// Observations from before can't be backtracked into anymore.
+    ///
+    /// Panics if `snapshot` was taken from a generator of another size.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        assert_eq!(
+            snapshot.tiles.len(),
+            self.tiles.len(),
+            "The snapshot has to be of a grid of the same size"
+        );
+
+        self.record();
+        self.load(snapshot.clone());
+    }
+
+    /// Goes back to the state before the latest change, see [`PlotGenerator::with_history`].
+    /// Returns false if there is nothing left to undo.
+    pub fn undo(&mut self) -> bool {
+        let Some(snapshot) = self.undo.pop_back() else {
+            return false;
+        };
+
+        self.redo.push(self.snapshot());
+        self.load(snapshot);
+        true
+    }
+
+    /// Goes forward to the state before the latest [`PlotGenerator::undo`]. Returns false if
+    /// there is nothing to redo, any change since the undo drops what there was.
+    pub fn redo(&mut self) -> bool {
+        let Some(snapshot) = self.redo.pop() else {
+            return false;
+        };
+
+        self.undo.push_back(self.snapshot());
+        self.load(snapshot);
+        true
+    }
+
+    /// Rerolls the fields in `xs` x `ys`, e.g. to edit a part of a collapsed plot without
+    /// regenerating the rest.
+    ///
+    /// The fields inside go back into superposition, restricted by the fields around them, and
+    /// get collapsed again. Everything outside stays as it is. Restrictions of the fields inside,
+    /// like [`PlotGenerator::constrain`] or an [`Elevation`], are lost.
+    ///
+    /// Fails like [`PlotGenerator::collapse`], e.g. if the fields around leave no tile for a
+    /// field inside.
+    pub fn regenerate_region(
+        &mut self,
+        xs: Range<usize>,
+        ys: Range<usize>,
+    ) -> Result<(), WfcError> {
+        assert!(
+            xs.end <= self.width && ys.end <= self.height,
+            "The region has to lie inside the grid"
+        );
+
+        let inside = |(x, y): (usize, usize)| xs.contains(&x) && ys.contains(&y);
+        let (full, offsets) = (DomainMask::full(self.tileset.len()), self.offsets());
+        let mut around = vec![];
+        for y in ys.clone() {
+            for x in xs.clone() {
+                if self.is_masked((x, y)) {
+                    continue;
+                }
+                self.set_state((x, y), WaveState::Superposition(full.clone()));
+
+                let neighbours = offsets.neighbours((x, y), self.boundary, self.width, self.height);
+                around.extend(neighbours.map(|(_, pos)| pos).filter(|&pos| !inside(pos)));
+            }
+        }
+        around.sort_unstable();
+        around.dedup();
+
+        // The snapshots are from before, restoring one would undo the reset
+        self.history.clear();
+        for pos in around {
+            self.update_neighbours(pos);
+        }
+        self.rebuild_entropies();
+
+        self.collapse()
+    }
+
+    /// Places exactly one `tile` at a random field that can still become it.
+    ///
+    /// The chosen field is collapsed, every other field loses `tile` as a possibility, and all
+    /// of that gets propagated. Returns the position of the anchor.
+    ///
+    /// Fails without changing anything with a [`WfcError::NoField`] if no field can become `tile`,
+    /// or with a [`WfcError::Contradiction`] if several fields can't become anything else.
+    pub fn place_anchor(
+        &mut self,
+        tile: impl Into<TileId>,
+        rng: &mut impl Rng,
+    ) -> Result<(usize, usize), WfcError> {
+        let tile = tile.into();
+        let mut candidates = vec![];
+        let mut forced = vec![];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if let WaveState::Superposition(states) = self.state((x, y)) {
+                    if states.contains(tile) {
+                        candidates.push((x, y));
+                        if states.len() == 1 {
+                            forced.push((x, y));
+                        }
+                    }
+                }
+            }
+        }
+
+        let pos = match forced[..] {
+            [] => *candidates.choose(rng).ok_or(WfcError::NoField(tile))?,
+            [pos] => pos,
+            [_, other, ..] => return Err(WfcError::Contradiction(other)),
+        };
+
+        self.record();
+        for &(x, y) in &candidates {
+            self.remove_state(y * self.width + x, tile);
+            self.push_entropy(y * self.width + x);
+        }
+        self.set_state(pos, WaveState::Collapsed(tile));
+        for candidate in candidates {
+            self.update_neighbours(candidate);
+        }
+
+        Ok(pos)
+    }
+
+    /// SEE: [This Video](https://www.youtube.com/watch?v=2SuvO4Gi7uY)
+    ///
+    /// * Find the fields with the lowest entropy,
+    ///   -> if there is just one field, take it
+    ///   -> If there is more than one field, take random
+    ///
+    /// * Collapse field into a field with just one possibility.
+    /// * Update the neighbours, and remove possibilities that got "destoryed", in the previous
+    ///   step
+    ///
+    /// If a field runs out of possibilities, the generator backtracks (see
+    /// [`PlotGenerator::with_backtrack_budget`]) and only fails if that doesn't resolve it.
+    pub fn collapse(&mut self) -> Result<(), WfcError> {
+        while self.step()? != Step::Done {}
+        Ok(())
+    }
+
+    /// Makes as many [`PlotGenerator::step`]s as fit into `budget`, so a game can spread the
+    /// collapse over its frames without a thread of its own. Returns whether the collapse is
+    /// done, which the step after the last field collapsed finds out.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use world_gen::PlotGenerator;
+    ///
+    /// let mut gen = PlotGenerator::new(32, 32).with_seed(1);
+    /// // A few milliseconds of every frame
+    /// while !gen.run_for(Duration::from_millis(2))? {}
+    /// let plot = gen.into_plot();
+    /// # Ok::<(), world_gen::WfcError>(())
+    /// ```
+    pub fn run_for(&mut self, budget: impl Into<Budget>) -> Result<bool, WfcError> {
+        let budget = budget.into();
+        #[cfg(feature = "std")]
+        let start = Instant::now();
+        let within = |steps: usize| match budget {
+            #[cfg(feature = "std")]
+            Budget::Time(duration) => start.elapsed() < duration,
+            Budget::Steps(max) => steps < max,
+        };
+
+        let mut steps = 0;
+        while within(steps) {
+            if self.step()? == Step::Done {
+                return Ok(true);
+            }
+            steps += 1;
+        }
+        Ok(false)
+    }
+
+    /// Like [`PlotGenerator::collapse`], but stops as soon as `count` fields are collapsed into
+    /// `tile`. All other fields stay in superposition, so the generator can be finished later.
+    ///
+    /// Returns early, without an error, if every field got collapsed before reaching `count`.
+    pub fn collapse_until_count(
+        &mut self,
+        tile: impl Into<TileId>,
+        count: usize,
+    ) -> Result<(), WfcError> {
+        let tile = tile.into();
+        // Backtracking can undo earlier observations, so the fields are counted again every time
+        while self
+            .tiles
+            .iter()
+            .filter(|state| matches!(state, WaveState::Collapsed(t) if *t == tile))
+            .count()
+            < count
+        {
+            if self.step()? == Step::Done {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Like [`PlotGenerator::collapse`], but gives up with [`WfcError::Cancelled`] once `cancel`
+    /// is set. The flag is checked every [`CANCEL_CHECK_INTERVAL`] observations, the partially
+    /// collapsed state stays in the generator.
+    pub fn collapse_cancellable(&mut self, cancel: &AtomicBool) -> Result<Plot, WfcError> {
+        self.collapse_with_progress(cancel, |_| {})
+    }
+
+    /// Like [`PlotGenerator::collapse_cancellable`], but also calls `progress` with the share of
+    /// collapsed fields, from 0 to 1, about every percent of the fields and once more when done,
+    /// e.g. for a progress bar. Backtracking can make the share go down again.
+    pub fn collapse_with_progress(
+        &mut self,
+        cancel: &AtomicBool,
+        mut progress: impl FnMut(f64),
+    ) -> Result<Plot, WfcError> {
+        let report_interval = (self.tiles.len() / 100).max(1);
+        let mut observations = 0;
+
+        loop {
+            if observations % CANCEL_CHECK_INTERVAL == 0 && cancel.load(Ordering::Relaxed) {
+                return Err(WfcError::Cancelled);
+            }
+            if observations % report_interval == 0 {
+                progress(self.collapsed_share());
+            }
+
+            if self.step()? == Step::Done {
+                break;
+            }
+            observations += 1;
+        }
+
+        progress(1.0);
+        Ok(self.to_plot())
+    }
+
+    /// Collapses the generator on a new thread with [`PlotGenerator::collapse_with_progress`],
+    /// so e.g. a GUI stays responsive on huge grids. The returned handle tells the progress, can
+    /// cancel the collapse and gives the generator back once it's over.
+    #[cfg(feature = "std")]
+    pub fn collapse_in_background(mut self) -> BackgroundCollapse {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let progress = Arc::new(AtomicU64::new(0.0_f64.to_bits()));
+
+        let thread = {
+            let (cancel, progress) = (Arc::clone(&cancel), Arc::clone(&progress));
+            std::thread::spawn(move || {
+                let plot = self.collapse_with_progress(&cancel, |share| {
+                    progress.store(share.to_bits(), Ordering::Relaxed)
+                });
+                (plot, self)
+            })
+        };
+
+        BackgroundCollapse {
+            cancel,
+            progress,
+            thread,
+        }
+    }
+
+    /// The share of fields that are collapsed, from 0 to 1.
+    fn collapsed_share(&self) -> f64 {
+        if self.tiles.is_empty() {
+            return 1.0;
+        }
+        let collapsed = self
+            .tiles
+            .iter()
+            .filter(|state| matches!(state, WaveState::Collapsed(_)))
+            .count();
+        collapsed as f64 / self.tiles.len() as f64
+    }
+
+    /// Observes the field with the lowest entropy, or the one the [`SelectionStrategy`] picks,
+    /// and propagates the result, a single step of [`PlotGenerator::collapse`]. Returns
+    /// [`Step::Done`] once every field is collapsed.
+    ///
+    /// Every observation remembers the states of the fields it changes. When a field runs out of
+    /// possibilities, the latest observation is undone and the tile that got picked there is
+    /// ruled out. Running out of observations or of the backtrack budget fails with the
+    /// contradiction.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "step", level = "trace", skip_all)
+    )]
+    pub fn step(&mut self) -> Result<Step, WfcError> {
+        self.logged_step(None)
+    }
+
+    /// Goes through the first `steps` steps of `log` again, collapsing the same fields into the
+    /// same tiles with the random generator where it was, see [`GenerationLog`]. Steps taken
+    /// before continue from there, e.g. with another seed.
+    ///
+    /// Fails at the first step that goes differently, e.g. because the fields got restricted
+    /// differently than in the generator that recorded the log. The steps before stay replayed.
+    pub fn replay(&mut self, log: &GenerationLog, steps: usize) -> Result<(), ReplayError> {
+        self.rng = ChaCha12Rng::from_seed(log.seed());
+        for (i, event) in log.events().iter().take(steps).enumerate() {
+            let forced = match *event {
+                LogEvent::Collapsed { pos, tile, .. } => (pos, Some(tile)),
+                LogEvent::Backtracked { contradiction, .. } => (contradiction, None),
+            };
+            if matches!(self.state(forced.0), WaveState::Collapsed(_)) {
+                return Err(ReplayError::Diverged(i));
+            }
+
+            self.rng.set_word_pos(event.draws().into());
+            let step = self
+                .logged_step(Some(forced))
+                .map_err(|err| ReplayError::Failed(i, err))?;
+            if !event.matches(&step) {
+                return Err(ReplayError::Diverged(i));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The steps recorded since [`PlotGenerator::with_log`], `None` if they aren't recorded.
+    pub fn log(&self) -> Option<&GenerationLog> {
+        self.log.as_ref()
+    }
+
+    /// Takes a step and records it, see [`PlotGenerator::advance`].
+    fn logged_step(
+        &mut self,
+        forced: Option<((usize, usize), Option<TileId>)>,
+    ) -> Result<Step, WfcError> {
+        let (seed, draws) = (self.rng.get_seed(), self.rng.get_word_pos() as u64);
+        let step = self.advance(forced)?;
+        match step {
+            Step::Collapsed { .. } => self.stats.observations += 1,
+            Step::Backtracked { .. } => self.stats.backtracks += 1,
+            Step::Done => {}
+        }
+        if let Some(log) = &mut self.log {
+            log.record(seed, draws, &step);
+        }
+        Ok(step)
+    }
+
+    /// A single [`PlotGenerator::step`]. With `forced`, observes that field instead of choosing
+    /// one, and collapses it into that tile if there is one, when replaying a [`GenerationLog`].
+    fn advance(
+        &mut self,
+        forced: Option<((usize, usize), Option<TileId>)>,
+    ) -> Result<Step, WfcError> {
+        // Choosing the field already changes the entropies, so the state is taken before
+        let before = (self.history_limit > 0).then(|| self.snapshot());
+        let Some(pos) = forced.map(|(pos, _)| pos).or_else(|| self.choose_field()) else {
+            return Ok(Step::Done);
+        };
+        self.last = Some(pos);
+
+        if let Some(before) = before {
+            self.remember(before);
+        }
+        // Without a budget there is nothing to backtrack to
+        let tracked = self.backtrack_budget > 0;
+        if tracked {
+            self.history.push_back(Observation {
+                changes: vec![],
+                pos,
+                // Filled in once observed
+                tile: TileId(0),
+            });
+        }
+        match self.observe(pos, forced.and_then(|(_, tile)| tile)) {
+            Ok((tile, mut changed)) => {
+                if tracked {
+                    if self.history.len() > BACKTRACK_DEPTH {
+                        self.history.pop_front();
+                    }
+                    if let Some(observation) = self.history.back_mut() {
+                        observation.tile = tile;
+                    }
+                }
+
+                match self.enforce_quotas() {
+                    Some(restricted) => {
+                        changed.extend(restricted);
+                        Ok(Step::Collapsed { pos, tile, changed })
+                    }
+                    // Undoes this observation
+                    None => self.backtrack(pos, WfcError::Contradiction(pos)),
+                }
+            }
+            Err(err) => {
+                // What the failed observation changed gets undone along with the one before
+                if let Some(failed) = tracked.then(|| self.history.pop_back()).flatten() {
+                    if let Some(previous) = self.history.back_mut() {
+                        previous.changes.extend(failed.changes);
+                    }
+                }
+                self.backtrack(pos, err)
+            }
+        }
+    }
+
+    /// Handles the contradiction at `pos`: goes back to the latest observation and rules out the
+    /// tile picked there, or fails with `err` once out of observations or budget.
+    fn backtrack(&mut self, pos: (usize, usize), err: WfcError) -> Result<Step, WfcError> {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(x = pos.0, y = pos.1, "contradiction");
+        if let Some(observer) = &mut self.observer {
+            observer.on_contradiction(pos);
+        }
+
+        if self.backtrack_budget == 0 {
+            return Err(err);
+        }
+        let Some(observation) = self.history.pop_back() else {
+            return Err(err);
+        };
+
+        self.backtrack_budget -= 1;
+        for (index, state) in observation.changes.into_iter().rev() {
+            self.tiles.set(index as usize, state);
+            // The queued entry of the field, if any, went stale
+            self.push_entropy(index as usize);
+        }
+        let (x, y) = observation.pos;
+        self.remove_state(y * self.width + x, observation.tile);
+        self.push_entropy(y * self.width + x);
+        self.update_neighbours(observation.pos);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            x = observation.pos.0,
+            y = observation.pos.1,
+            budget = self.backtrack_budget,
+            "backtracked"
+        );
+
+        Ok(Step::Backtracked {
+            contradiction: pos,
+            undone: observation.pos,
+        })
+    }
+
+    /// Removes the tiles that reached their maximum from the fields in superposition, and keeps
+    /// the last fields that can still become a tile short of its minimum from becoming anything
+    /// else. Returns the fields that lost a possibility, `None` if a quota can't be met anymore.
+    fn enforce_quotas(&mut self) -> Option<Vec<(usize, usize)>> {
+        let mut changed = vec![];
+        for i in 0..self.quotas.len() {
+            let Quota { tile, min, max } = self.quotas[i];
+            let collapsed = self
+                .tiles
+                .iter()
+                .filter(|state| matches!(state, WaveState::Collapsed(t) if *t == tile))
+                .count();
+            let open = (0..self.tiles.len())
+                .filter(|&index| match self.tiles.get(index) {
+                    WaveState::Collapsed(_) => false,
+                    WaveState::Superposition(states) => states.contains(tile),
+                })
+                .map(|index| (index % self.width, index / self.width))
+                .collect::<Vec<_>>();
+            if collapsed > max || collapsed + open.len() < min {
+                return None;
+            }
+
+            let allowed = if collapsed == max {
+                let mut allowed = DomainMask::full(self.tileset.len());
+                allowed.remove(tile);
+                allowed
+            } else if collapsed + open.len() == min {
+                DomainMask::from_tiles(self.tileset.len(), [tile])
+            } else {
+                continue;
+            };
+            for pos in open {
+                let mut restricted = vec![];
+                self.restrict(pos, &allowed, &mut changed, &mut restricted);
+                if !restricted.is_empty() {
+                    changed.extend(self.update_neighbours(pos));
+                }
+            }
+        }
+
+        Some(changed)
+    }
+
+    /// Collapses the generator lazily, one field per item, e.g. to render between collapses in a
+    /// game loop or to stop early by dropping the iterator. The fields stay as far as they got.
+    ///
+    /// Backtracking happens in between items, so a later item may replace fields an earlier one
+    /// collapsed. The iterator ends after every field is collapsed, or after yielding the error
+    /// [`PlotGenerator::step`] failed with.
+    pub fn iter_collapses(&mut self) -> Collapses<'_> {
+        Collapses {
+            gen: self,
+            done: false,
+        }
+    }
+
+    /// Picks the field to observe, `None` once every field is collapsed.
+    fn choose_field(&mut self) -> Option<(usize, usize)> {
+        match (self.selection, self.last) {
+            (SelectionStrategy::Nearest, Some(last)) => self
+                .choose_nearest(last)
+                .or_else(|| self.choose_lowest_entropy()),
+            _ => self.choose_lowest_entropy(),
+        }
+    }
+
+    /// The uncollapsed field closest to `last` with the lowest entropy, searching ring after
+    /// ring around it.
+    fn choose_nearest(&self, last: (usize, usize)) -> Option<(usize, usize)> {
+        let (x, y) = last;
+        let rings = self.width.max(self.height);
+        (1..rings).find_map(|r| {
+            let xs = x.saturating_sub(r)..(x + r + 1).min(self.width);
+            let ys = y.saturating_sub(r)..(y + r + 1).min(self.height);
+            ys.flat_map(|fy| xs.clone().map(move |fx| (fx, fy)))
+                .filter(|&(fx, fy)| fx.abs_diff(x).max(fy.abs_diff(y)) == r)
+                .filter_map(|(fx, fy)| match self.state((fx, fy)) {
+                    WaveState::Superposition(states) => {
+                        let index = fy * self.width + fx;
+                        let entropy = shannon_entropy(states, |t| self.weight_at(t, index));
+                        Some((entropy, (fx, fy)))
+                    }
+                    WaveState::Collapsed(_) => None,
+                })
+                .min_by(|(a, _), (b, _)| a.total_cmp(b))
+                .map(|(_, pos)| pos)
+        })
+    }
+
+    /// Picks one of the fields with the lowest entropy, ties are broken by a little noise, or the
+    /// field first in the order of a [`SelectionStrategy`] other than lowest entropy. `None` once
+    /// every field is collapsed.
+    ///
+    /// Pops the entropy heap until an entry still matches its field, so this doesn't have to scan
+    /// the whole grid like [`PlotGenerator::find_lowest_entropy`].
+    fn choose_lowest_entropy(&mut self) -> Option<(usize, usize)> {
+        while let Some(Reverse((_, count, index))) = self.entropies.pop() {
+            let index = index as usize;
+            if matches!(self.tiles.get(index), WaveState::Superposition(states) if states.len() == count as usize)
+            {
+                return Some((index % self.width, index / self.width));
+            }
+        }
+
+        // Gives back the memory of the stale entries
+        self.entropies.shrink_to_fit();
+        None
+    }
+
+    /// Queues the field at `index` with its current entropy, if it's uncollapsed.
+    fn push_entropy(&mut self, index: usize) {
+        // Stale entries pile up during propagation, so start over once they outnumber the fields
+        if self.entropies.len() > 4 * self.tiles.len() {
+            return self.rebuild_entropies();
+        }
+
+        if let WaveState::Superposition(states) = self.tiles.get(index) {
+            let noise = self.rng.gen::<f64>();
+            // The heap takes the lowest first, whatever the strategy orders the fields by
+            let order = match self.selection {
+                SelectionStrategy::LowestEntropy | SelectionStrategy::Nearest => {
+                    shannon_entropy(states, |t| self.weight_at(t, index)) + noise * ENTROPY_NOISE
+                }
+                SelectionStrategy::Scanline => index as f64,
+                SelectionStrategy::Random => noise,
+            };
+            let entry = (Entropy(order), states.len() as u32, index as u32);
+            self.entropies.push(Reverse(entry));
+        }
+    }
+
+    /// Remembers the current state for [`PlotGenerator::undo`], before a change.
+    fn record(&mut self) {
+        if self.history_limit > 0 {
+            self.remember(self.snapshot());
+        }
+    }
+
+    /// Adds `snapshot` to the states to undo to, dropping the states to redo to.
+    fn remember(&mut self, snapshot: Snapshot) {
+        if self.undo.len() == self.history_limit {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(snapshot);
+        self.redo.clear();
+    }
+
+    /// Replaces the current state with `snapshot`.
+    fn load(&mut self, snapshot: Snapshot) {
+        self.tiles = snapshot.tiles;
+        self.rng = snapshot.rng;
+        self.entropies = snapshot.entropies;
+        self.backtrack_budget = snapshot.backtrack_budget;
+        // The observations lead up to the replaced state
+        self.history.clear();
+    }
+
+    /// The neighbours a field has, given the topology and the neighbourhood.
+    fn offsets(&self) -> Offsets {
+        Offsets::new(self.topology, &self.neighbourhood)
+    }
+
+    /// Queues every uncollapsed field again, after the wave got replaced.
+    fn rebuild_entropies(&mut self) {
+        self.entropies.clear();
+        // Gives back the memory of the stale entries, beyond one entry per field
+        self.entropies.shrink_to(self.tiles.len());
+        for index in 0..self.tiles.len() {
+            self.push_entropy(index);
+        }
+    }
+
+    /// Collapses a single field into one of its possible states, picked by their weights, and
+    /// updates its neighbours. Returns the tile and the fields that lost a possibility.
+    ///
+    /// A `forced` tile gets picked instead, the random generator still draws as if picking one,
+    /// so it stays where a [`GenerationLog`] expects it.
+    ///
+    /// With a [`Symmetry`], the mirrored fields get collapsed into the same tile, so only tiles
+    /// possible in all of them are considered.
+    fn observe(
+        &mut self,
+        pos: (usize, usize),
+        forced: Option<TileId>,
+    ) -> Result<(TileId, Vec<(usize, usize)>), WfcError> {
+        let mirrors = match self.symmetry {
+            Some(symmetry) => symmetry.counterparts(pos, self.width, self.height),
+            None => vec![],
+        };
+
+        let neighbours = self.collapsed_neighbours(pos);
+        let mut states = self
+            .state(pos)
+            .states()
+            .map(|t| {
+                let directional = neighbours
+                    .iter()
+                    .map(|&(direction, other)| self.weights.directional(other, direction, t))
+                    .product::<f64>();
+                (
+                    t,
+                    self.weight_at(t, pos.1 * self.width + pos.0) * directional,
+                )
+            })
+            .collect::<Vec<_>>();
+        for &mirror in &mirrors {
+            let mirrored = self.state(mirror);
+            states.retain(|(t, _)| mirrored.contains(*t));
+        }
+
+        let (chosen, _) = *states
+            .choose_weighted(&mut self.rng, |(_, weight)| *weight)
+            .map_err(|_| WfcError::Contradiction(pos))?;
+        let tile = match forced {
+            Some(tile) if states.iter().any(|(t, _)| *t == tile) => tile,
+            Some(_) => return Err(WfcError::Contradiction(pos)),
+            None => chosen,
+        };
+
+        let mut changed = vec![];
+        for pos in core::iter::once(pos).chain(mirrors) {
+            self.set_state(pos, WaveState::Collapsed(tile));
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                x = pos.0,
+                y = pos.1,
+                tile = self.tileset.name(tile),
+                "collapsed"
+            );
+            if let Some(observer) = &mut self.observer {
+                observer.on_collapse(pos, tile);
+            }
+            changed.extend(self.update_neighbours(pos));
+        }
+
+        Ok((tile, changed))
+    }
+
+    /// Checks the rules, collapses every field and returns the plot, the entry point for callers
+    /// that want to handle failures, e.g. by retrying with another seed.
+    ///
+    /// Unlike [`PlotGenerator::collapse`] followed by [`PlotGenerator::into_plot`] this never
+    /// panics, and it fails right away with [`GenError::InvalidRules`] if no plot is possible.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "generate",
+            skip_all,
+            fields(width = self.width, height = self.height),
+            err(level = "debug")
+        )
+    )]
+    pub fn generate(&mut self) -> Result<Plot, GenError> {
+        self.check_rules()?;
+        if let Solver::MinConflicts { max_steps } = self.solver {
+            return self.repair(max_steps);
+        }
+        // Anything else than a contradiction leaves fields in superposition, reported below
+        if let Err(WfcError::Contradiction(pos)) = self.collapse() {
+            return Err(GenError::Contradiction(pos));
+        }
+        self.try_to_plot()
+    }
+
+    /// Like [`PlotGenerator::generate`], but gives up once `cancel` is set, checked like
+    /// [`PlotGenerator::collapse_cancellable`] does. A cancelled generation fails with
+    /// [`GenError::Incomplete`], the partially collapsed state stays in the generator, see
+    /// [`PlotGenerator::get`]. [`Solver::MinConflicts`] is bounded by its steps instead, the flag
+    /// is only checked before it starts.
+    pub fn generate_cancellable(&mut self, cancel: &AtomicBool) -> Result<Plot, GenError> {
+        self.check_rules()?;
+        if let Solver::MinConflicts { max_steps } = self.solver {
+            if !cancel.load(Ordering::Relaxed) {
+                return self.repair(max_steps);
+            }
+        }
+        // Cancelling leaves fields in superposition, reported below
+        if let Err(WfcError::Contradiction(pos)) = self.collapse_cancellable(cancel) {
+            return Err(GenError::Contradiction(pos));
+        }
+        self.try_to_plot()
+    }
+
+    /// Like [`PlotGenerator::generate`], but never fails: a field that runs out of possibilities
+    /// after backtracking gets the tile that breaks the rules with the fewest collapsed
+    /// neighbours, the heavier one on a tie, and the collapse goes on. Returns the plot along with
+    /// every pair of neighbours that breaks the rules, none if the generation went through.
+    ///
+    /// Best for when a plot is needed quickly and a few broken rules are fine, e.g. with
+    /// [`PlotGenerator::with_backtrack_budget`] at 0. Always collapses with [`Solver::Wfc`], and
+    /// the [`PlotGenerator::with_quota`] quotas may end up broken as well.
+    pub fn generate_best_effort(&mut self) -> (Plot, Vec<Violation>) {
+        loop {
+            match self.step() {
+                Ok(Step::Done) => break,
+                Ok(_) => {}
+                // A broken quota leaves the field collapsed
+                Err(WfcError::Contradiction(pos))
+                    if matches!(self.state(pos), WaveState::Superposition(_)) =>
+                {
+                    let tile = self.least_violating(pos);
+                    self.set_state(pos, WaveState::Collapsed(tile));
+                    if let Some(observer) = &mut self.observer {
+                        observer.on_collapse(pos, tile);
+                    }
+                    self.update_neighbours(pos);
+                }
+                Err(_) => {}
+            }
+        }
+
+        let plot = self.to_plot();
+        let violations = self.violations(&plot);
+        (plot, violations)
+    }
+
+    /// The tile for the field at `pos` that breaks the rules with the fewest collapsed
+    /// neighbours, the heaviest one of those.
+    fn least_violating(&self, pos: (usize, usize)) -> TileId {
+        let index = pos.1 * self.width + pos.0;
+        let neighbours = self
+            .offsets()
+            .neighbours(pos, self.boundary, self.width, self.height)
+            .filter_map(|(direction, neighbour)| match self.state(neighbour) {
+                WaveState::Collapsed(tile) => Some((direction, *tile)),
+                WaveState::Superposition(_) => None,
+            })
+            .collect::<Vec<_>>();
+        let violations = |tile| {
+            neighbours
+                .iter()
+                .filter(|&&(direction, other)| {
+                    !self.allows(tile, direction, other)
+                        || !self.allows(other, direction.opposite(), tile)
+                })
+                .count()
+        };
+
+        self.tileset
+            .ids()
+            .min_by(|&a, &b| {
+                violations(a).cmp(&violations(b)).then(
+                    self.weight_at(b, index)
+                        .total_cmp(&self.weight_at(a, index)),
+                )
+            })
+            .expect("Tile sets have tiles")
+    }
+
+    /// Every pair of neighbours on `plot` that breaks the rules, each pair once.
+    fn violations(&self, plot: &Plot) -> Vec<Violation> {
+        let (offsets, width, height) = (self.offsets(), self.width, self.height);
+        let mut seen = HashSet::new();
+        let mut violations = vec![];
+        for pos in plot.positions() {
+            for (direction, neighbour) in offsets.neighbours(pos, self.boundary, width, height) {
+                let (tile, other) = (plot.get(pos.0, pos.1), plot.get(neighbour.0, neighbour.1));
+                let pair = (pos.min(neighbour), pos.max(neighbour));
+                if !self.allows(tile, direction, other) && seen.insert(pair) {
+                    violations.push(Violation {
+                        pos,
+                        direction,
+                        neighbour,
+                    });
+                }
+            }
+        }
+        violations
+    }
+
+    /// Whether the rules allow `b` in `direction` of `a`.
+    fn allows(&self, a: TileId, direction: Direction, b: TileId) -> bool {
+        self.rule(a, direction).contains(b)
+    }
+
+    /// The tiles the rules allow in `direction` of `a`.
+    fn rule(&self, a: TileId, direction: Direction) -> &DomainMask {
+        match &self.rules {
+            Some(rules) => rules.get(a, direction),
+            None => self.tileset.neighbour_mask(a, direction),
+        }
+    }
+
+    /// Solves the grid with [`Solver::MinConflicts`], starting from the possibilities every field
+    /// has now. Ignores the [`Symmetry`] and doesn't notify the [`Observer`].
+    fn repair(&mut self, max_steps: usize) -> Result<Plot, GenError> {
+        let domains = self
+            .tiles
+            .iter()
+            .map(|state| state.states().collect())
+            .collect::<Vec<_>>();
+        let offsets = self.offsets();
+        let neighbours = (0..self.tiles.len())
+            .map(|index| {
+                let pos = (index % self.width, index / self.width);
+                if self.is_masked(pos) {
+                    return vec![];
+                }
+                offsets
+                    .neighbours(pos, self.boundary, self.width, self.height)
+                    .filter(|&(_, neighbour)| !self.is_masked(neighbour))
+                    .map(|(direction, (x, y))| (y * self.width + x, direction))
+                    .collect()
+            })
+            .collect::<Vec<_>>();
+
+        let (tileset, weights, bias) = (&self.tileset, &self.weights, self.bias.as_ref());
+        let field = self.weight_field.as_deref();
+        let weight = |index: usize, tile| {
+            let weight = match field {
+                Some(field) => field[index].get(&tile).copied().unwrap_or(0.0),
+                None => weights.get(tile).unwrap_or_else(|| tileset.weight(tile)),
+            };
+            weight * bias.map_or(1.0, |bias| bias.factor_at(tile, index))
+        };
+        let rules = self.rules.as_ref();
+        let allows = |a, direction, b| match rules {
+            Some(rules) => rules.get(a, direction).contains(b),
+            None => tileset.neighbour_mask(a, direction).contains(b),
+        };
+        let tiles = min_conflicts::solve(
+            allows,
+            &domains,
+            &neighbours,
+            weight,
+            &mut self.rng,
+            max_steps,
+        )
+        .map_err(|index| GenError::Contradiction((index % self.width, index / self.width)))?;
+
+        for (index, tile) in tiles.into_iter().enumerate() {
+            self.tiles.set(index, WaveState::Collapsed(tile));
+        }
+        self.history.clear();
+        self.rebuild_entropies();
+        self.try_to_plot()
+    }
+
+    /// Like [`PlotGenerator::generate`], but starts over with a new seed after a contradiction, up
+    /// to `max_attempts` times. The seeds are drawn from the generator itself, so a seeded
+    /// generator retries the same way every time.
+    ///
+    /// Every attempt starts from the fields as they are now, so fields set before are kept.
+    /// Restarting is a simpler alternative to backtracking, combine it with
+    /// [`PlotGenerator::with_backtrack_budget`] to limit or disable that.
+    pub fn generate_with_retries(
+        &mut self,
+        max_attempts: usize,
+    ) -> (Result<Plot, GenError>, RetryStats) {
+        assert!(max_attempts > 0, "Generating needs at least one attempt");
+
+        let start = self.snapshot();
+        let mut stats = RetryStats {
+            attempts: 0,
+            failures: vec![],
+            seed: 0,
+        };
+
+        loop {
+            stats.attempts += 1;
+            stats.seed = self.rng.gen();
+
+            self.restore(&start);
+            self.rng = ChaCha12Rng::seed_from_u64(stats.seed);
+            self.rebuild_entropies();
+
+            match self.generate() {
+                Err(GenError::Contradiction(pos)) if stats.attempts < max_attempts => {
+                    stats.failures.push(pos)
+                }
+                Err(GenError::Contradiction(pos)) => {
+                    stats.failures.push(pos);
+                    return (Err(GenError::Contradiction(pos)), stats);
+                }
+                result => return (result, stats),
+            }
+        }
+    }
+
+    /// Fails if no plot of this size can satisfy the rules, whatever gets picked.
+    fn check_rules(&self) -> Result<(), GenError> {
+        if self.tiles.is_empty() {
+            return Ok(());
+        }
+        if self.tileset.ids().all(|tile| self.weight(tile) <= 0.0) {
+            return Err(GenError::InvalidRules(
+                "No tile has a positive weight".to_string(),
+            ));
+        }
+
+        for (direction, offset) in self.offsets().iter() {
+            let has_neighbours = (0..self.height).any(|y| {
+                (0..self.width).any(|x| {
+                    let neighbour =
+                        self.boundary
+                            .neighbour((x, y), offset, self.width, self.height);
+                    neighbour.is_some()
+                })
+            });
+            let allowed = self
+                .tileset
+                .ids()
+                .any(|tile| !self.rule(tile, direction).is_empty());
+
+            if has_neighbours && !allowed {
+                return Err(GenError::InvalidRules(format!(
+                    "No tile may lie {direction:?} of another"
+                )));
+            }
+        }
+
+        let fields = self.tiles.len();
+        if self.quotas.iter().map(|quota| quota.min).sum::<usize>() > fields {
+            return Err(GenError::InvalidRules(
+                "The quotas need more fields than the plot has".to_string(),
+            ));
+        }
+        let max = |tile| match self.quotas.iter().find(|quota| quota.tile == tile) {
+            Some(quota) => quota.max,
+            None => fields,
+        };
+        let room = self.tileset.ids().map(max).fold(0, usize::saturating_add);
+        if room < fields {
+            return Err(GenError::InvalidRules(
+                "The quotas leave fields without a tile".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Panics if a field is still in superposition, see [`PlotGenerator::generate`] for a
+    /// version that doesn't.
+    pub fn into_plot(self) -> Plot {
+        self.to_plot()
+    }
+
+    /// The plot like [`PlotGenerator::into_plot`], with what the steps went through and the
+    /// settings to generate it again, e.g. to log or cache it.
+    ///
+    /// Panics if any field is not collapsed.
+    pub fn into_parts(self) -> (Plot, GenerationStats, GenerationSpec) {
+        let plot = self.to_plot();
+        let spec = GenerationSpec {
+            width: self.width,
+            height: self.height,
+            seed: self.rng.get_seed(),
+            tileset: self.tileset,
+            weights: self.weights,
+            symmetry: self.symmetry,
+            boundary: self.boundary,
+            topology: self.topology,
+            neighbourhood: self.neighbourhood,
+            selection: self.selection,
+            solver: self.solver,
+            propagation: self.propagation,
+            backtrack_budget: self.backtrack_budget,
+        };
+        (plot, self.stats, spec)
+    }
+
+    fn to_plot(&self) -> Plot {
+        self.try_to_plot().expect("Found not collapsed tile")
+    }
+
+    fn try_to_plot(&self) -> Result<Plot, GenError> {
+        let tiles = self
+            .tiles
+            .iter()
+            .enumerate()
+            .map(|(i, state)| match state {
+                WaveState::Collapsed(x) => Ok(*x),
+                WaveState::Superposition(_) => {
+                    Err(GenError::Incomplete((i % self.width, i / self.width)))
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        let plot = Plot::from_tiles(Arc::clone(&self.tileset), self.width, self.height, tiles);
+        Ok(match &self.elevation {
+            Some(heights) => plot.with_elevation(heights.clone()),
+            None => plot,
+        })
+    }
+
+    /// The collapsed neighbours of `pos` with the direction `pos` lies in from them, if any
+    /// directional weights need them, see [`Weights::with_directional`].
+    fn collapsed_neighbours(&self, pos: (usize, usize)) -> Vec<(Direction, TileId)> {
+        if !self.weights.has_directional() {
+            return vec![];
+        }
+        self.offsets()
+            .neighbours(pos, self.boundary, self.width, self.height)
+            .filter_map(|(direction, neighbour)| match self.state(neighbour) {
+                WaveState::Collapsed(tile) => Some((direction.opposite(), *tile)),
+                WaveState::Superposition(_) => None,
+            })
+            .collect()
+    }
+
+    /// The weight of `tile`, from the configured weights or else the tile set.
+    fn weight(&self, tile: TileId) -> f64 {
+        self.weights
+            .get(tile)
+            .unwrap_or_else(|| self.tileset.weight(tile))
+    }
+
+    /// The weight of `tile` on the field at row-major `index`, from the weight field if there is
+    /// one, with the bias applied.
+    fn weight_at(&self, tile: TileId, index: usize) -> f64 {
+        let factor = self
+            .bias
+            .as_ref()
+            .map_or(1.0, |bias| bias.factor_at(tile, index));
+        let weight = match &self.weight_field {
+            Some(field) => field[index].get(&tile).copied().unwrap_or(0.0),
+            None => self.weight(tile),
+        };
+        weight * factor
+    }
+
+    /// Whether the field at `(x, y)` lies outside the mask, see [`PlotGenerator::with_mask`].
+    fn is_masked(&self, (x, y): (usize, usize)) -> bool {
+        self.mask.as_ref().is_some_and(|mask| !mask.contains(x, y))
+    }
+
+    fn state(&self, (x, y): (usize, usize)) -> &WaveState {
+        self.tiles.get(y * self.width + x)
+    }
+
+    fn set_state(&mut self, (x, y): (usize, usize), state: WaveState) {
+        let index = y * self.width + x;
+        self.trail(index);
+        self.tiles.set(index, state);
+    }
+
+    /// Removes every tile missing in `allowed` from the field at `index`, see
+    /// [`Wave::intersect`]. Returns whether anything got removed.
+    fn intersect_state(&mut self, index: usize, allowed: &DomainMask) -> bool {
+        match self.tiles.get(index) {
+            WaveState::Superposition(states) if !states.is_subset(allowed) => {
+                self.trail(index);
+                self.tiles.intersect(index, allowed)
+            }
+            _ => false,
+        }
+    }
+
+    /// Removes `tile` from the field at `index`, if it is in superposition.
+    fn remove_state(&mut self, index: usize, tile: TileId) {
+        if let WaveState::Superposition(states) = self.tiles.get(index) {
+            if states.contains(tile) {
+                self.trail(index);
+                self.tiles.remove(index, tile);
+            }
+        }
+    }
+
+    /// Remembers the state of the field at `index` before it changes, so backtracking into the
+    /// latest observation can put it back.
+    fn trail(&mut self, index: usize) {
+        if self.backtrack_budget == 0 {
+            return;
+        }
+        if let Some(observation) = self.history.back_mut() {
+            let state = self.tiles.get(index).clone();
+            observation.changes.push((index as u32, state));
+        }
+    }
+}
+
+/// The Shannon entropy of `states`, see [`PlotGenerator::entropy`].
+pub(crate) fn shannon_entropy(states: &DomainMask, weight: impl Fn(TileId) -> f64) -> f64 {
+    let (sum, sum_log) = states
+        .iter()
+        .map(weight)
+        .filter(|w| *w > 0.0)
+        .fold((0.0, 0.0), |(sum, sum_log), w| {
+            (sum + w, sum_log + w * w.ln())
+        });
+
+    // Tiles of weight 0 can't be picked, so nothing left to pick is a contradiction
+    if sum == 0.0 {
+        return f64::NEG_INFINITY;
+    }
+
+    sum.ln() - sum_log / sum
+}
+
+impl PartialEq for Entropy {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == core::cmp::Ordering::Equal
+    }
+}
+
+impl Eq for Entropy {}
+
+impl PartialOrd for Entropy {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entropy {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl Default for PlotGenerator {
+    fn default() -> Self {
+        PlotGenerator::new(16, 16)
+    }
+}
+
+impl Display for Heatmap<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for y in 0..self.0.height {
+            for x in 0..self.0.width {
+                match self.0.get(x, y) {
+                    WaveState::Collapsed(_) => write!(f, ".")?,
+                    WaveState::Superposition(states) => match states.len() {
+                        0 => write!(f, "!")?,
+                        count @ 1..=9 => write!(f, "{count}")?,
+                        _ => write!(f, "+")?,
+                    },
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for WfcError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            WfcError::Contradiction((x, y)) => write!(f, "No valid state possible at ({x}, {y})"),
+            WfcError::Cancelled => write!(f, "Generation got cancelled"),
+            WfcError::VolumeContradiction((x, y, z)) => {
+                write!(f, "No valid state possible at ({x}, {y}, {z})")
+            }
+            WfcError::WorldContradiction((x, y)) => {
+                write!(f, "No valid state possible at ({x}, {y})")
+            }
+            WfcError::GridSize {
+                expected: (width, height),
+                len,
+            } => write!(
+                f,
+                "Expected {width} x {height} values, one per field, got {len}"
+            ),
+            WfcError::NoField(tile) => write!(f, "No field can become tile {}", tile.0),
+        }
+    }
+}
+
+impl core::error::Error for WfcError {}
+
+impl Display for GenError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            GenError::Contradiction((x, y)) => write!(f, "No valid state possible at ({x}, {y})"),
+            GenError::Incomplete((x, y)) => write!(f, "The field at ({x}, {y}) isn't collapsed"),
+            GenError::InvalidRules(reason) => write!(f, "The rules allow no plot: {reason}"),
+        }
+    }
+}
+
+impl core::error::Error for GenError {}
+
+impl From<TileSetError> for GenError {
+    fn from(err: TileSetError) -> Self {
+        GenError::InvalidRules(err.to_string())
+    }
+}
@@ -0,0 +1,139 @@
+//! Godot bindings, a GDExtension built with `cargo build --release --features godot`. Godot
+//! finds the library through a `world_gen.gdextension` file in the project:
+//!
+//! ```ini
+//! [configuration]
+//! entry_symbol = "gdext_rust_init"
+//! compatibility_minimum = 4.1
+//!
+//! [libraries]
+//! linux.release.x86_64 = "res://bin/libworld_gen.so"
+//! windows.release.x86_64 = "res://bin/world_gen.dll"
+//! macos.release = "res://bin/libworld_gen.dylib"
+//! ```
+//!
+//! Plots are handed over as tile ids, row by row, with the names of the tiles in
+//! `WorldGen.tile_names`. They map straight onto the cells of a `TileMapLayer` whose atlas has
+//! one tile per id:
+//!
+//! ```gdscript
+//! var gen := WorldGen.new()
+//! var tiles := gen.generate(64, 64, 42)
+//! for i in tiles.size():
+//!     $TileMapLayer.set_cell(Vector2i(i % 64, i / 64), 0, Vector2i(tiles[i], 0))
+//! ```
+use crate::{PlotGenerator, TileDef, TileSet};
+use godot::classes::{FileAccess, IRefCounted, RefCounted};
+use godot::prelude::*;
+
+struct WorldGenExtension;
+
+#[gdextension]
+unsafe impl ExtensionLibrary for WorldGenExtension {}
+
+/// Generates plots of the built-in world, or of the tiles registered with it. Failures are
+/// reported with `push_error`.
+#[derive(GodotClass)]
+#[class(base = RefCounted)]
+pub struct WorldGen {
+    /// `None` after `clear_tiles` until the first tile gets registered.
+    tileset: Option<TileSet>,
+}
+
+#[godot_api]
+impl IRefCounted for WorldGen {
+    fn init(_base: Base<RefCounted>) -> Self {
+        WorldGen {
+            tileset: Some(TileSet::world()),
+        }
+    }
+}
+
+#[godot_api]
+impl WorldGen {
+    /// Collapses a `width` x `height` plot and returns its tile ids, empty if it fails.
+    #[func]
+    fn generate(&self, width: i64, height: i64, seed: i64) -> PackedInt32Array {
+        let Some(tileset) = &self.tileset else {
+            godot_error!("WorldGen has no tiles, register some first");
+            return PackedInt32Array::new();
+        };
+        let (Ok(width @ 1..), Ok(height @ 1..)) = (usize::try_from(width), usize::try_from(height))
+        else {
+            godot_error!("WorldGen can't generate a {width} x {height} plot");
+            return PackedInt32Array::new();
+        };
+
+        let mut gen =
+            PlotGenerator::for_tileset(tileset.clone(), width, height).with_seed(seed as u64);
+        match gen.generate() {
+            Ok(plot) => plot
+                .positions()
+                .map(|(x, y)| plot.get(x, y).0 as i32)
+                .collect(),
+            Err(err) => {
+                godot_error!("WorldGen failed to generate seed {seed}: {err}");
+                PackedInt32Array::new()
+            }
+        }
+    }
+
+    /// Adds a tile that may lie next to the tiles named in `neighbours`, see
+    /// [`TileSet::register`]. Returns its id, -1 if the tile set rejects it.
+    #[func]
+    fn register_tile(&mut self, name: GString, neighbours: PackedStringArray, weight: f64) -> i64 {
+        let neighbours = neighbours.as_slice().iter().map(GString::to_string);
+        let def = TileDef::new(name.to_string(), name.to_string())
+            .with_weight(weight)
+            .with_neighbours(&neighbours.collect::<Vec<_>>());
+        let registered = match &mut self.tileset {
+            Some(tileset) => tileset.register(def).map(|tile| tile.0 as i64),
+            None => TileSet::new(vec![def]).map(|tileset| {
+                self.tileset = Some(tileset);
+                0
+            }),
+        };
+        registered.unwrap_or_else(|err| {
+            godot_error!("WorldGen can't register {name}: {err}");
+            -1
+        })
+    }
+
+    /// Removes every tile, to register others instead of the built-in world.
+    #[func]
+    fn clear_tiles(&mut self) {
+        self.tileset = None;
+    }
+
+    /// Replaces the tiles with those of the RON file at `path`, which may be a `res://` path.
+    /// Returns whether it loaded.
+    #[func]
+    fn load_tileset(&mut self, path: GString) -> bool {
+        if !FileAccess::file_exists(&path) {
+            godot_error!("WorldGen can't find {path}");
+            return false;
+        }
+        match TileSet::from_ron(&FileAccess::get_file_as_string(&path).to_string()) {
+            Ok(tileset) => {
+                self.tileset = Some(tileset);
+                true
+            }
+            Err(err) => {
+                godot_error!("WorldGen can't load {path}: {err}");
+                false
+            }
+        }
+    }
+
+    /// The names of the tiles, indexed by their ids.
+    #[func]
+    fn tile_names(&self) -> PackedStringArray {
+        let Some(tileset) = &self.tileset else {
+            return PackedStringArray::new();
+        };
+        tileset
+            .ids()
+            .map(|tile| GString::from(tileset.name(tile)))
+            .collect()
+    }
+}
@@ -0,0 +1,196 @@
+use crate::prelude::*;
+use crate::{Blocks, GenError, PlotGenerator, Renderer};
+use core::fmt::Display;
+use std::path::{Path, PathBuf};
+
+/// Golden files: plots generated from fixed seeds, checked in, and compared byte for byte with
+/// what the same seeds generate now. They catch changes to the rules or the solver that silently
+/// change existing seeds.
+///
+/// If the change is intended, run the tests again with `WORLD_GEN_BLESS=1` to write the new
+/// output to the golden files, and check them in along with it.
+///
+/// ```no_run
+/// use world_gen::{Golden, PlotGenerator, TileSet};
+///
+/// Golden::new("tests/golden")
+///     .with_case("seed_42", || {
+///         PlotGenerator::for_tileset(TileSet::world(), 16, 16).with_seed(42)
+///     })
+///     .check()
+///     .unwrap_or_else(|failures| panic!("{failures:?}"));
+/// ```
+pub struct Golden {
+    dir: PathBuf,
+    cases: Vec<Case>,
+}
+
+/// A plot checked by [`Golden`].
+struct Case {
+    name: String,
+    generator: Box<dyn Fn() -> PlotGenerator>,
+    renderer: Box<dyn Renderer>,
+}
+
+/// Why a golden file didn't match, see [`Golden`].
+#[derive(Debug)]
+pub enum GoldenError {
+    Io(PathBuf, std::io::Error),
+    /// The golden file doesn't exist yet.
+    Missing(PathBuf),
+    /// The output differs from the golden file, first at this line, counted from 1.
+    Mismatch {
+        path: PathBuf,
+        line: usize,
+        expected: String,
+        actual: String,
+    },
+    /// The case with this name failed to generate.
+    Generate(String, GenError),
+}
+
+impl Golden {
+    /// The environment variable that makes [`Golden::verify`] write the golden files instead of
+    /// comparing them, if set to anything but an empty string or `0`.
+    pub const BLESS_VAR: &'static str = "WORLD_GEN_BLESS";
+
+    /// Cases with golden files in `dir`, named `<case>.txt`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Golden {
+            dir: dir.into(),
+            cases: vec![],
+        }
+    }
+
+    /// Checks the plot `generator` generates, with the glyphs of its tile set like
+    /// [`Blocks`]. The generator gets built anew for every check.
+    pub fn with_case(
+        self,
+        name: impl Into<String>,
+        generator: impl Fn() -> PlotGenerator + 'static,
+    ) -> Self {
+        self.with_rendered_case(name, generator, Blocks)
+    }
+
+    /// Checks the plot `generator` generates, written by `renderer`.
+    pub fn with_rendered_case(
+        mut self,
+        name: impl Into<String>,
+        generator: impl Fn() -> PlotGenerator + 'static,
+        renderer: impl Renderer + 'static,
+    ) -> Self {
+        self.cases.push(Case {
+            name: name.into(),
+            generator: Box::new(generator),
+            renderer: Box::new(renderer),
+        });
+        self
+    }
+
+    /// The path of the golden file of the case called `name`.
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{name}.txt"))
+    }
+
+    /// Generates every case and compares it with its golden file, see [`Golden::verify`].
+    /// Returns every case that failed.
+    pub fn check(&self) -> Result<(), Vec<GoldenError>> {
+        let failures = self
+            .cases
+            .iter()
+            .filter_map(|case| {
+                let plot = match (case.generator)().generate() {
+                    Ok(plot) => plot,
+                    Err(err) => return Some(GoldenError::Generate(case.name.clone(), err)),
+                };
+                Golden::verify(self.path(&case.name), &plot.render(&*case.renderer)).err()
+            })
+            .collect::<Vec<_>>();
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(failures)
+        }
+    }
+
+    /// Compares `actual` byte for byte with the golden file at `path`. If
+    /// [`Golden::BLESS_VAR`] is set, writes `actual` to the file instead, creating its
+    /// directory if needed.
+    pub fn verify(path: impl AsRef<Path>, actual: &str) -> Result<(), GoldenError> {
+        let path = path.as_ref();
+        let bless =
+            std::env::var(Golden::BLESS_VAR).is_ok_and(|value| !["", "0"].contains(&&*value));
+        if bless {
+            return Golden::bless(path, actual);
+        }
+
+        let expected = match std::fs::read_to_string(path) {
+            Ok(expected) => expected,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Err(GoldenError::Missing(path.to_path_buf()))
+            }
+            Err(err) => return Err(GoldenError::Io(path.to_path_buf(), err)),
+        };
+        if expected == actual {
+            return Ok(());
+        }
+
+        // Lines that are missing on one side compare as empty, so a missing line break still
+        // points at a line
+        let (mut expected, mut actual) = (expected.split('\n'), actual.split('\n'));
+        let mut line = 1;
+        loop {
+            match (expected.next(), actual.next()) {
+                (Some(e), Some(a)) if e == a => line += 1,
+                (e, a) => {
+                    return Err(GoldenError::Mismatch {
+                        path: path.to_path_buf(),
+                        line,
+                        expected: e.unwrap_or_default().to_string(),
+                        actual: a.unwrap_or_default().to_string(),
+                    })
+                }
+            }
+        }
+    }
+
+    /// Writes `actual` to the golden file at `path`, creating its directory if needed.
+    pub fn bless(path: impl AsRef<Path>, actual: &str) -> Result<(), GoldenError> {
+        let path = path.as_ref();
+        let io = |err| GoldenError::Io(path.to_path_buf(), err);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(io)?;
+        }
+        std::fs::write(path, actual).map_err(io)
+    }
+}
+
+impl Display for GoldenError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            GoldenError::Io(path, err) => write!(f, "{}: {err}", path.display()),
+            GoldenError::Missing(path) => write!(
+                f,
+                "{} doesn't exist, run with {}=1 to write it",
+                path.display(),
+                Golden::BLESS_VAR
+            ),
+            GoldenError::Mismatch {
+                path,
+                line,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{} differs at line {line}, expected\n  {expected}\nbut got\n  {actual}\nrun with \
+                 {}=1 if the change is intended",
+                path.display(),
+                Golden::BLESS_VAR
+            ),
+            GoldenError::Generate(name, err) => write!(f, "{name} failed to generate: {err}"),
+        }
+    }
+}
+
+impl core::error::Error for GoldenError {}
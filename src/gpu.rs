@@ -0,0 +1,250 @@
+use std::sync::{mpsc, Arc, OnceLock};
+use wgpu::util::DeviceExt;
+
+/// How many sweeps run between checking whether the wave changed.
+const SWEEPS: usize = 8;
+
+/// Fields one invocation group of the shader updates, see `src/propagate.wgsl`.
+const GROUP_SIZE: u32 = 64;
+
+/// The flag of a field next to a field that changed, or where propagation starts.
+pub(crate) const ACTIVE: u32 = 1;
+/// The flag of a collapsed field, which keeps its tile.
+pub(crate) const COLLAPSED: u32 = 2;
+
+/// Runs full propagation on the GPU, see
+/// [`PlotGenerator::with_gpu`](crate::PlotGenerator::with_gpu).
+///
+/// The shader sweeps over every field at once, restricting each one to the tiles its active
+/// neighbours allow, until a sweep changes nothing anymore. Fields turn active once they change,
+/// so the wave ends up like after propagating on the CPU.
+pub(crate) struct GpuPropagator {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+}
+
+/// The wave handed to [`GpuPropagator::propagate`].
+pub(crate) struct Wave {
+    pub width: usize,
+    pub height: usize,
+    pub wrap: bool,
+    /// The step from a field to its neighbour, per direction.
+    pub offsets: Vec<(isize, isize)>,
+    /// 32 bit words per mask, see [`words`].
+    pub words: usize,
+    /// The tiles allowed next to a tile, `words` per direction and tile, for `words * 32` tiles
+    /// per direction.
+    pub rules: Vec<u32>,
+    /// Row-major, the mask of every field followed by its flags.
+    pub fields: Vec<u32>,
+}
+
+/// The 32 bit words of a mask of `tile_count` tiles.
+pub(crate) fn words(tile_count: usize) -> usize {
+    tile_count.div_ceil(64) * 2
+}
+
+impl GpuPropagator {
+    /// The propagator shared by every generator, `None` if there is no GPU.
+    pub(crate) fn shared() -> Option<Arc<GpuPropagator>> {
+        static SHARED: OnceLock<Option<Arc<GpuPropagator>>> = OnceLock::new();
+        SHARED
+            .get_or_init(|| pollster::block_on(GpuPropagator::new()).map(Arc::new))
+            .clone()
+    }
+
+    async fn new() -> Option<GpuPropagator> {
+        let instance =
+            wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle_from_env());
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                ..Default::default()
+            })
+            .await
+            .ok()?;
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                label: Some("world-gen"),
+                // Big grids need big buffers
+                required_limits: adapter.limits(),
+                ..Default::default()
+            })
+            .await
+            .ok()?;
+
+        let scope = device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("propagate"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("propagate.wgsl").into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("propagate"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        if scope.pop().await.is_some() {
+            return None;
+        }
+
+        Some(GpuPropagator {
+            device,
+            queue,
+            pipeline,
+        })
+    }
+
+    /// Propagates until no field changes anymore, and returns the fields of the new wave.
+    /// `None` if the GPU failed, e.g. because the wave doesn't fit into its memory.
+    pub(crate) fn propagate(&self, wave: &Wave) -> Option<Vec<u32>> {
+        let device = &self.device;
+        let fields = (wave.width * wave.height) as u64;
+        let size = (wave.fields.len() * 4) as u64;
+        if size > device.limits().max_storage_buffer_binding_size {
+            return None;
+        }
+        let scope = device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let params = [
+            wave.width as u32,
+            wave.height as u32,
+            wave.words as u32,
+            wave.offsets.len() as u32,
+            wave.wrap as u32,
+        ];
+        let offsets = wave
+            .offsets
+            .iter()
+            .flat_map(|&(dx, dy)| [dx as i32 as u32, dy as i32 as u32])
+            .collect::<Vec<_>>();
+        let storage = wgpu::BufferUsages::STORAGE;
+        let params = self.buffer("params", &params, wgpu::BufferUsages::UNIFORM);
+        let offsets = self.buffer("offsets", &offsets, storage);
+        let rules = self.buffer("rules", &wave.rules, storage);
+        // The sweeps go back and forth between two waves
+        let waves = [
+            self.buffer("wave", &wave.fields, storage | wgpu::BufferUsages::COPY_SRC),
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("wave"),
+                size,
+                usage: storage,
+                mapped_at_creation: false,
+            }),
+        ];
+        let changed = self.buffer(
+            "changed",
+            &[0],
+            storage | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+        );
+
+        let layout = self.pipeline.get_bind_group_layout(0);
+        let bind_groups = [(0, 1), (1, 0)].map(|(from, to)| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("propagate"),
+                layout: &layout,
+                entries: &[
+                    params.as_entire_binding(),
+                    offsets.as_entire_binding(),
+                    rules.as_entire_binding(),
+                    waves[from].as_entire_binding(),
+                    waves[to].as_entire_binding(),
+                    changed.as_entire_binding(),
+                ]
+                .into_iter()
+                .enumerate()
+                .map(|(binding, resource)| wgpu::BindGroupEntry {
+                    binding: binding as u32,
+                    resource,
+                })
+                .collect::<Vec<_>>(),
+            })
+        });
+
+        // Dispatches can't have more than `max_compute_workgroups_per_dimension` groups in a row
+        let groups = fields.div_ceil(GROUP_SIZE as u64) as u32;
+        let max_groups = device.limits().max_compute_workgroups_per_dimension;
+        let (groups_x, groups_y) = (groups.min(max_groups), groups.div_ceil(max_groups));
+
+        loop {
+            let mut encoder = device.create_command_encoder(&Default::default());
+            for sweep in 0..SWEEPS {
+                // Only the last sweep tells whether the wave is done
+                if sweep == SWEEPS - 1 {
+                    encoder.clear_buffer(&changed, 0, None);
+                }
+                let mut pass = encoder.begin_compute_pass(&Default::default());
+                pass.set_pipeline(&self.pipeline);
+                pass.set_bind_group(0, &bind_groups[sweep % 2], &[]);
+                pass.dispatch_workgroups(groups_x, groups_y, 1);
+            }
+            if self.read(encoder, &changed, 4)?[0] == 0 {
+                break;
+            }
+        }
+
+        if pollster::block_on(scope.pop()).is_some() {
+            return None;
+        }
+        // An even number of sweeps ends in the first wave
+        let encoder = device.create_command_encoder(&Default::default());
+        self.read(encoder, &waves[0], size)
+    }
+
+    fn buffer(&self, label: &str, contents: &[u32], usage: wgpu::BufferUsages) -> wgpu::Buffer {
+        let contents = contents
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect::<Vec<_>>();
+        self.device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents: &contents,
+                usage,
+            })
+    }
+
+    /// Finishes `encoder` with copying the first `size` bytes of `buffer` back, and waits for
+    /// them.
+    fn read(
+        &self,
+        mut encoder: wgpu::CommandEncoder,
+        buffer: &wgpu::Buffer,
+        size: u64,
+    ) -> Option<Vec<u32>> {
+        let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("staging"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        encoder.copy_buffer_to_buffer(buffer, 0, &staging, 0, size);
+        self.queue.submit([encoder.finish()]);
+
+        let (sender, receiver) = mpsc::channel();
+        staging
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+        self.device
+            .poll(wgpu::PollType::Wait {
+                submission_index: None,
+                timeout: None,
+            })
+            .ok()?;
+        receiver.recv().ok()?.ok()?;
+
+        let words = staging
+            .slice(..)
+            .get_mapped_range()
+            .ok()?
+            .chunks_exact(4)
+            .map(|word| u32::from_le_bytes(word.try_into().expect("Words have 4 bytes")))
+            .collect();
+        Some(words)
+    }
+}
@@ -0,0 +1,99 @@
+use crate::prelude::*;
+use crate::Connectivity;
+use core::ops::{Index, IndexMut};
+
+/// A `width` x `height` grid of values, stored row by row and indexed by `(x, y)`, so the
+/// bookkeeping of flood fills and searches doesn't repeat the index math.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    /// A grid with `value` in every field.
+    pub(crate) fn new(width: usize, height: usize, value: T) -> Self {
+        Grid {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    /// A grid with `value(x, y)` in every field.
+    pub(crate) fn from_fn(width: usize, height: usize, value: impl Fn(usize, usize) -> T) -> Self {
+        Grid {
+            width,
+            height,
+            cells: (0..height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .map(|(x, y)| value(x, y))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn contains(&self, (x, y): (usize, usize)) -> bool {
+        x < self.width && y < self.height
+    }
+
+    /// The value at `pos`, `None` outside of the grid.
+    pub(crate) fn get(&self, pos: (usize, usize)) -> Option<&T> {
+        self.contains(pos)
+            .then(|| &self.cells[pos.1 * self.width + pos.0])
+    }
+
+    /// The value at `pos`, `None` outside of the grid.
+    pub(crate) fn get_mut(&mut self, pos: (usize, usize)) -> Option<&mut T> {
+        match self.contains(pos) {
+            true => Some(&mut self.cells[pos.1 * self.width + pos.0]),
+            false => None,
+        }
+    }
+
+    /// The field `(dx, dy)` away from `pos`, `None` if it lies outside of the grid.
+    pub(crate) fn offset(
+        &self,
+        (x, y): (usize, usize),
+        (dx, dy): (isize, isize),
+    ) -> Option<(usize, usize)> {
+        let pos = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+        self.contains(pos).then_some(pos)
+    }
+
+    /// The neighbours of `pos` inside the grid, see [`Connectivity::neighbours`].
+    pub(crate) fn neighbours(
+        &self,
+        pos: (usize, usize),
+        connectivity: Connectivity,
+    ) -> impl Iterator<Item = (usize, usize)> {
+        connectivity.neighbours(pos, self.width, self.height)
+    }
+
+    /// The fields with their values, row by row.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(index, value)| ((index % width, index / width), value))
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        self.get((x, y))
+            .unwrap_or_else(|| panic!("({x}, {y}) lies outside of the grid"))
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        self.get_mut((x, y))
+            .unwrap_or_else(|| panic!("({x}, {y}) lies outside of the grid"))
+    }
+}
@@ -0,0 +1,151 @@
+use crate::prelude::*;
+use crate::{GenError, Plot, PlotGenerator, TileId, TileSet, WfcError};
+use alloc::sync::Arc;
+
+/// Generates big plots in two resolutions: first a coarse plot of macro tiles, like the shapes of
+/// continents, then the fine plot, with every macro cell upscaled into a block of fields
+/// restricted to the tiles of its macro tile.
+///
+/// Flat wave function collapse only keeps neighbouring fields consistent, so big plots lack
+/// structure on a larger scale. Here the macro plot gives that structure, and fields near the
+/// border of a neighbouring macro cell may also hold the tiles of that cell, so blocks blend into
+/// each other, see [`HierarchicalGenerator::with_blend`]. The adjacency rules of the fine tile
+/// set have to allow the tiles of two neighbouring macro tiles to meet.
+pub struct HierarchicalGenerator {
+    width: usize,
+    height: usize,
+    macro_tileset: Arc<TileSet>,
+    tileset: Arc<TileSet>,
+    block_size: usize,
+    blend: usize,
+    seed: u64,
+    /// The fine tiles every macro tile allows, all of them for macro tiles missing here.
+    macro_tiles: HashMap<TileId, Vec<TileId>>,
+}
+
+impl HierarchicalGenerator {
+    /// Creates a generator for plots of `width` x `height` macro cells of `macro_tileset`, every
+    /// one upscaled into 16 x 16 fields of `tileset`. Every macro tile allows every fine tile
+    /// until restricted with [`HierarchicalGenerator::with_macro_tile`].
+    pub fn new(
+        macro_tileset: impl Into<Arc<TileSet>>,
+        tileset: impl Into<Arc<TileSet>>,
+        width: usize,
+        height: usize,
+    ) -> Self {
+        HierarchicalGenerator {
+            width,
+            height,
+            macro_tileset: macro_tileset.into(),
+            tileset: tileset.into(),
+            block_size: 16,
+            blend: 2,
+            seed: random_seed(),
+            macro_tiles: HashMap::new(),
+        }
+    }
+
+    /// Derives the seeds of both plots from `seed`, see [`PlotGenerator::with_seed`].
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Upscales every macro cell into `size` x `size` fields.
+    pub fn with_block_size(mut self, size: usize) -> Self {
+        assert!(size > 0, "Blocks need at least one field");
+        self.block_size = size;
+        self
+    }
+
+    /// Also allows the tiles of a neighbouring macro cell on fields within `width` steps of it,
+    /// in the "#" shape. Defaults to 2, 0 keeps every block to its own tiles.
+    pub fn with_blend(mut self, width: usize) -> Self {
+        self.blend = width;
+        self
+    }
+
+    /// Restricts the fields of the cells of `macro_tile` to `tiles`, e.g. water for an ocean.
+    pub fn with_macro_tile<T: Copy + Into<TileId>>(
+        mut self,
+        macro_tile: impl Into<TileId>,
+        tiles: &[T],
+    ) -> Self {
+        self.macro_tiles.insert(
+            macro_tile.into(),
+            tiles.iter().map(|t| (*t).into()).collect(),
+        );
+        self
+    }
+
+    /// Collapses the macro plot, the first phase of [`HierarchicalGenerator::generate`]. The
+    /// same seed gives the same macro plot.
+    pub fn generate_macro(&self) -> Result<Plot, GenError> {
+        PlotGenerator::for_tileset(Arc::clone(&self.macro_tileset), self.width, self.height)
+            .with_seed(self.seed)
+            .generate()
+    }
+
+    /// Generates the macro plot and upscales it, see [`HierarchicalGenerator::upscale`].
+    pub fn generate(&self) -> Result<Plot, GenError> {
+        self.upscale(&self.generate_macro()?)
+    }
+
+    /// Collapses the fine plot of `macro_plot`, e.g. one drawn by hand, with every field
+    /// restricted to the tiles of its macro cell and the cells it blends with.
+    ///
+    /// Fails with a [`GenError::Contradiction`] if a field has no tile left, e.g. because the
+    /// tiles of two neighbouring macro tiles can't meet.
+    pub fn upscale(&self, macro_plot: &Plot) -> Result<Plot, GenError> {
+        let (width, height) = (
+            macro_plot.width() * self.block_size,
+            macro_plot.height() * self.block_size,
+        );
+        let mut gen = PlotGenerator::for_tileset(Arc::clone(&self.tileset), width, height)
+            .with_seed(self.seed.wrapping_add(1));
+
+        for y in 0..height {
+            for x in 0..width {
+                let allowed = self.allowed(macro_plot, x, y);
+                if let Err(WfcError::Contradiction(pos)) = gen.constrain(x, y, &allowed) {
+                    return Err(GenError::Contradiction(pos));
+                }
+            }
+        }
+
+        gen.generate()
+    }
+
+    /// The tiles of the macro cell of the field at `(x, y)`, and of the cells it blends with.
+    fn allowed(&self, macro_plot: &Plot, x: usize, y: usize) -> Vec<TileId> {
+        let (width, height) = (
+            macro_plot.width() * self.block_size,
+            macro_plot.height() * self.block_size,
+        );
+        let mut macro_tiles = vec![];
+        let blend = self.blend as isize;
+        for dx in -blend..=blend {
+            for dy in -blend..=blend {
+                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                if !(0..width as isize).contains(&nx) || !(0..height as isize).contains(&ny) {
+                    continue;
+                }
+                let (cx, cy) = (nx as usize / self.block_size, ny as usize / self.block_size);
+                macro_tiles.push(macro_plot.get(cx, cy));
+            }
+        }
+        macro_tiles.sort();
+        macro_tiles.dedup();
+
+        let mut allowed = macro_tiles
+            .into_iter()
+            .flat_map(|macro_tile| match self.macro_tiles.get(&macro_tile) {
+                Some(tiles) => tiles.clone(),
+                None => self.tileset.ids().collect(),
+            })
+            .collect::<Vec<_>>();
+        allowed.sort();
+        allowed.dedup();
+        allowed
+    }
+}
@@ -0,0 +1,198 @@
+use crate::grid::Grid;
+use crate::prelude::*;
+use crate::{Connectivity, Elevation, PlotGenerator, TileId, WfcError};
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+
+/// Lets rivers flow downhill over an [`Elevation`] and stamps them into a generator before it
+/// collapses, so the plot drains like a landscape instead of where the adjacency rules happen to
+/// put water.
+///
+/// Every river starts at a spring and moves to its lowest neighbour in the "+" shape, until it
+/// reaches the edge of the grid or joins an earlier river. A river stuck in a pit overflows into
+/// the lowest neighbour it hasn't been on yet, leaving a lake in the pit if there is a lake tile,
+/// see [`Hydrology::with_lake`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hydrology {
+    river: TileId,
+    lake: Option<TileId>,
+    springs: Vec<(usize, usize)>,
+    random_springs: usize,
+    seed: u64,
+}
+
+/// The fields of a river from its spring to its mouth, and the pits it fills on the way.
+#[derive(Debug, Clone, PartialEq)]
+struct River {
+    fields: Vec<(usize, usize)>,
+    lakes: Vec<(usize, usize)>,
+}
+
+impl Hydrology {
+    /// Lets rivers of `river` tiles flow, without any spring yet.
+    pub fn new(river: impl Into<TileId>) -> Self {
+        Hydrology {
+            river: river.into(),
+            lake: None,
+            springs: vec![],
+            random_springs: 0,
+            seed: random_seed(),
+        }
+    }
+
+    /// Starts a river at the field at `(x, y)`.
+    pub fn with_spring(mut self, x: usize, y: usize) -> Self {
+        self.springs.push((x, y));
+        self
+    }
+
+    /// Also starts `count` rivers at random fields among the highest quarter of the grid, away
+    /// from the edge, after the explicit springs.
+    pub fn with_random_springs(mut self, count: usize) -> Self {
+        self.random_springs = count;
+        self
+    }
+
+    /// Fills the pits rivers get stuck in with a `lake` tile, before they overflow.
+    pub fn with_lake(mut self, lake: impl Into<TileId>) -> Self {
+        self.lake = Some(lake.into());
+        self
+    }
+
+    /// Derives the random springs from `seed`.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Finds the fields of the rivers flowing over `elevation`, from their spring to their
+    /// mouth. The lakes of a river are fields of it as well.
+    pub fn trace(&self, elevation: &Elevation) -> Vec<Vec<(usize, usize)>> {
+        self.rivers(elevation)
+            .into_iter()
+            .map(|river| river.fields)
+            .collect()
+    }
+
+    /// Sets the fields of the rivers flowing over `elevation` in `gen`, the rest collapses
+    /// around them. Returns the rivers, like [`Hydrology::trace`].
+    ///
+    /// Call this before [`PlotGenerator::with_elevation`] if the elevation keeps the river tile
+    /// in the lowlands, the springs lie higher up. Fails like [`PlotGenerator::set_tile`] if a
+    /// field on the way can't become a river or lake, the fields set before stay set.
+    pub fn apply(
+        &self,
+        gen: &mut PlotGenerator,
+        elevation: &Elevation,
+    ) -> Result<Vec<Vec<(usize, usize)>>, WfcError> {
+        assert!(
+            elevation.width() == gen.width() && elevation.height() == gen.height(),
+            "The elevation has to cover the grid"
+        );
+
+        let rivers = self.rivers(elevation);
+        for river in &rivers {
+            for &(x, y) in &river.fields {
+                let tile = match self.lake {
+                    Some(lake) if river.lakes.contains(&(x, y)) => lake,
+                    _ => self.river,
+                };
+                gen.set_tile(x, y, tile)?;
+            }
+        }
+
+        Ok(rivers.into_iter().map(|river| river.fields).collect())
+    }
+
+    /// The rivers flowing over `elevation`.
+    fn rivers(&self, elevation: &Elevation) -> Vec<River> {
+        let (width, height) = (elevation.width(), elevation.height());
+        let mut wet = Grid::new(width, height, false);
+        let mut rivers = vec![];
+
+        for spring in self.springs(elevation) {
+            if wet[spring] {
+                // Already on an earlier river
+                continue;
+            }
+
+            let river = self.flow(elevation, spring, &wet);
+            for &pos in &river.fields {
+                wet[pos] = true;
+            }
+            rivers.push(river);
+        }
+
+        rivers
+    }
+
+    /// The explicit springs, followed by the random ones.
+    fn springs(&self, elevation: &Elevation) -> Vec<(usize, usize)> {
+        let mut rng = ChaCha12Rng::seed_from_u64(self.seed);
+        let (width, height) = (elevation.width(), elevation.height());
+        let mut fields = (1..height.saturating_sub(1))
+            .flat_map(|y| (1..width.saturating_sub(1)).map(move |x| (x, y)))
+            .collect::<Vec<_>>();
+        fields.sort_by(|&(ax, ay), &(bx, by)| {
+            elevation.get(bx, by).total_cmp(&elevation.get(ax, ay))
+        });
+        fields.truncate(fields.len().div_ceil(4));
+
+        let mut springs = self.springs.clone();
+        springs.extend(
+            fields
+                .choose_multiple(&mut rng, self.random_springs)
+                .copied(),
+        );
+        springs
+    }
+
+    /// The river from `spring` down to the edge or a field of an earlier river in `wet`.
+    fn flow(&self, elevation: &Elevation, spring: (usize, usize), wet: &Grid<bool>) -> River {
+        let (width, height) = (elevation.width(), elevation.height());
+        let mut river = River {
+            fields: vec![spring],
+            lakes: vec![],
+        };
+        let mut visited = Grid::new(width, height, false);
+        visited[spring] = true;
+
+        let mut current = spring;
+        loop {
+            let (x, y) = current;
+            if x == 0 || y == 0 || x + 1 == width || y + 1 == height {
+                return river;
+            }
+            if self.lake.is_some() && Self::is_pit(elevation, current) {
+                river.lakes.push(current);
+            }
+
+            let Some(next) = visited
+                .neighbours(current, Connectivity::Four)
+                .filter(|&neighbour| !visited[neighbour])
+                .min_by(|&(ax, ay), &(bx, by)| {
+                    elevation.get(ax, ay).total_cmp(&elevation.get(bx, by))
+                })
+            else {
+                // Walled in by its own course
+                return river;
+            };
+
+            river.fields.push(next);
+            if wet[next] {
+                return river;
+            }
+            visited[next] = true;
+            current = next;
+        }
+    }
+
+    /// Whether no neighbour of `pos` in the "+" shape lies lower.
+    fn is_pit(elevation: &Elevation, pos: (usize, usize)) -> bool {
+        let here = elevation.get(pos.0, pos.1);
+        Connectivity::Four
+            .neighbours(pos, elevation.width(), elevation.height())
+            .all(|(x, y)| elevation.get(x, y) >= here)
+    }
+}
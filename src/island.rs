@@ -0,0 +1,109 @@
+use crate::prelude::*;
+use crate::{BiasMap, GenError, Plot, PlotGenerator, TileSet};
+
+/// Generates islands out of the built-in [`TileSet::island`]: ocean all along the border, a
+/// landmass in the middle, and a coast without single field peninsulas and bays.
+///
+/// The ocean gets fixed on the border, a [`BiasMap`] makes it rare inside the island and common
+/// beyond, and [`Plot::smooth_coastline`] cleans up the coast afterwards.
+///
+/// ```
+/// use world_gen::IslandGenerator;
+///
+/// let plot = IslandGenerator::new(48, 32).with_seed(3).generate()?;
+/// let ocean = plot.tileset().id("Ocean").unwrap();
+/// assert_eq!(plot.get(0, 0), ocean);
+/// # Ok::<(), world_gen::GenError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct IslandGenerator {
+    width: usize,
+    height: usize,
+    seed: u64,
+    size: f64,
+    smoothing: usize,
+}
+
+impl IslandGenerator {
+    /// Creates a generator for `width` x `height` islands, the land reaching 70% of the way
+    /// from the center to the edges, with two passes of smoothing.
+    pub fn new(width: usize, height: usize) -> Self {
+        IslandGenerator {
+            width,
+            height,
+            seed: random_seed(),
+            size: 0.7,
+            smoothing: 2,
+        }
+    }
+
+    /// Derives every random decision from `seed`, see [`PlotGenerator::with_seed`].
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// How far the land reaches from the center towards the middle of the edges, from 0 to 1.
+    pub fn with_size(mut self, size: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&size),
+            "The size has to be between 0 and 1"
+        );
+        self.size = size;
+        self
+    }
+
+    /// Smoothes the coast up to `passes` times, see [`Plot::smooth_coastline`]. 0 leaves it
+    /// as generated.
+    pub fn with_smoothing(mut self, passes: usize) -> Self {
+        self.smoothing = passes;
+        self
+    }
+
+    /// The generator of the island before it gets smoothed, with the ocean on the border and the
+    /// bias towards land in the middle, e.g. to set further options on it. Finish the plot it
+    /// generates with [`IslandGenerator::smooth`].
+    pub fn generator(&self) -> PlotGenerator {
+        let tileset = TileSet::island();
+        let ocean = tileset.id("Ocean").expect("The island has an ocean");
+        let land = tileset
+            .ids()
+            .filter(|&tile| tile != ocean)
+            .collect::<Vec<_>>();
+
+        // How far a field lies inside the land, 1 in the center, 0 on the coast, below outside
+        let (half_width, half_height) = (self.width as f64 / 2.0, self.height as f64 / 2.0);
+        let size = self.size.max(f64::EPSILON);
+        let inland = |x: usize, y: usize| {
+            let dx = (x as f64 + 0.5 - half_width) / half_width;
+            let dy = (y as f64 + 0.5 - half_height) / half_height;
+            1.0 - dx.hypot(dy) / size
+        };
+        let mut bias = BiasMap::new(self.width, self.height)
+            .with_factor(ocean, |x, y| (-8.0 * inland(x, y)).exp());
+        for tile in land {
+            bias = bias.with_factor(tile, |x, y| (4.0 * inland(x, y)).exp());
+        }
+
+        let mut gen = PlotGenerator::for_tileset(tileset, self.width, self.height)
+            .with_bias(bias)
+            .with_seed(self.seed);
+        gen.constrain_border(&[ocean])
+            .expect("A fresh grid allows the ocean everywhere");
+        gen
+    }
+
+    /// Smoothes the coast of an island `plot` as configured, see [`Plot::smooth_coastline`].
+    /// Returns how many fields got replaced.
+    pub fn smooth(&self, plot: &mut Plot) -> usize {
+        let ocean = plot.tileset().id("Ocean").expect("The island has an ocean");
+        plot.smooth_coastline(&[ocean], self.smoothing)
+    }
+
+    /// Generates and smoothes an island.
+    pub fn generate(&self) -> Result<Plot, GenError> {
+        let mut plot = self.generator().generate()?;
+        self.smooth(&mut plot);
+        Ok(plot)
+    }
+}
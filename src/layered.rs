@@ -0,0 +1,117 @@
+use crate::prelude::*;
+use crate::{Plot, PlotGenerator, TileId, TileSet, WfcError};
+use alloc::sync::Arc;
+
+/// Generates several layers over the same grid, e.g. a terrain layer and a feature layer with
+/// trees, villages and roads on top of it.
+///
+/// Layers get collapsed in the order they were added. Rules tie tiles of a layer to the tiles
+/// below them: a rule for a village only on farmland removes the village from every field whose
+/// terrain isn't farmland, before the feature layer collapses.
+pub struct LayeredGenerator {
+    width: usize,
+    height: usize,
+    seed: Option<u64>,
+    layers: Vec<Arc<TileSet>>,
+    rules: Vec<LayerRule>,
+}
+
+/// `tile` of `layer` may only lie on one of the `allowed` tiles of the lower layer `on`.
+#[derive(Debug, Clone, PartialEq)]
+struct LayerRule {
+    layer: usize,
+    tile: TileId,
+    on: usize,
+    allowed: Vec<TileId>,
+}
+
+impl LayeredGenerator {
+    /// Creates a generator for `width` x `height` plots, without any layer yet.
+    pub fn new(width: usize, height: usize) -> Self {
+        LayeredGenerator {
+            width,
+            height,
+            seed: None,
+            layers: vec![],
+            rules: vec![],
+        }
+    }
+
+    /// Derives the seed of every layer from `seed`, see [`PlotGenerator::with_seed`].
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Adds a layer made of the tiles in `tileset` on top of the existing ones.
+    pub fn with_layer(mut self, tileset: impl Into<Arc<TileSet>>) -> Self {
+        self.layers.push(tileset.into());
+        self
+    }
+
+    /// Only allows `tile` of `layer` on fields where the lower layer `on` holds one of `allowed`.
+    /// Layers are numbered in the order they were added, starting at 0.
+    ///
+    /// Several rules for the same tile have to hold all at once.
+    pub fn with_rule<T: Copy + Into<TileId>>(
+        mut self,
+        layer: usize,
+        tile: impl Into<TileId>,
+        on: usize,
+        allowed: &[T],
+    ) -> Self {
+        assert!(layer < self.layers.len(), "Unknown layer {layer}");
+        assert!(on < layer, "Rules can only refer to lower layers");
+
+        self.rules.push(LayerRule {
+            layer,
+            tile: tile.into(),
+            on,
+            allowed: allowed.iter().map(|t| (*t).into()).collect(),
+        });
+        self
+    }
+
+    pub fn layer_count(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Collapses every layer, bottom to top, and returns one plot per layer.
+    ///
+    /// Fails with a [`WfcError::Contradiction`] if a field of some layer can't hold any tile that
+    /// fits the layers below it, or if collapsing a layer fails.
+    pub fn generate(&self) -> Result<Vec<Plot>, WfcError> {
+        let mut plots: Vec<Plot> = vec![];
+
+        for (i, tileset) in self.layers.iter().enumerate() {
+            let mut gen = PlotGenerator::for_tileset(Arc::clone(tileset), self.width, self.height);
+            if let Some(seed) = self.seed {
+                gen = gen.with_seed(seed.wrapping_add(i as u64));
+            }
+
+            let rules = self.rules.iter().filter(|rule| rule.layer == i);
+            if rules.clone().next().is_some() {
+                for y in 0..self.height {
+                    for x in 0..self.width {
+                        let allowed = tileset
+                            .ids()
+                            .filter(|tile| {
+                                rules
+                                    .clone()
+                                    .filter(|rule| rule.tile == *tile)
+                                    .all(|rule| rule.allowed.contains(&plots[rule.on].get(x, y)))
+                            })
+                            .collect::<Vec<_>>();
+
+                        gen.constrain(x, y, &allowed)?;
+                    }
+                }
+            }
+
+            gen.collapse()?;
+            plots.push(gen.into_plot());
+        }
+
+        Ok(plots)
+    }
+}
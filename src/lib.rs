@@ -0,0 +1,161 @@
+//! Generates tile based worlds with the wave function collapse algorithm.
+//!
+//! ```no_run
+//! use world_gen::PlotGenerator;
+//!
+//! let mut gen = PlotGenerator::default();
+//! gen.collapse()?;
+//!
+//! println!("{}", gen.into_plot());
+//! # Ok::<(), world_gen::WfcError>(())
+//! ```
+//!
+//! # Tracing
+//!
+//! With the `tracing` feature, the generator reports what it does to
+//! [`tracing`](https://docs.rs/tracing) subscribers:
+//!
+//! * a `generate` span around every [`PlotGenerator::generate`], also every attempt of
+//!   [`PlotGenerator::generate_with_retries`], with a `debug` event if it fails,
+//! * a `step` span around every [`PlotGenerator::step`], and a `propagate` span around every
+//!   propagation, both at the `trace` level,
+//! * `trace` events for every collapsed field and every field propagation removed possibilities
+//!   from,
+//! * `debug` events for every contradiction and every observation backtracking undid.
+//!
+//! # Minimum supported Rust version
+//!
+//! The crate builds on stable Rust, 1.88 or newer, with its default features and every other
+//! feature but these, whose dependencies need a newer version:
+//!
+//! * `bevy` needs 1.89, for `uuid`,
+//! * `gpu` needs 1.90, for `ordered-float`,
+//!
+//! so every feature together needs 1.90. CI checks all three versions on the versions of the
+//! dependencies a fresh `cargo update` picks.
+//!
+//! Raising the minimum version is not a breaking change, but it only happens in minor releases,
+//! and only when a dependency or a new language feature requires it.
+//!
+//! # Without std
+//!
+//! Without the default `std` feature the crate is `no_std` and only needs `alloc`, so the solver
+//! runs on devices without an operating system. It keeps the generators, plots, tile sets built
+//! in code and everything else that only computes, but loses what needs the system:
+//!
+//! * builders without a seed start from seed 0 instead of a random one, seed them with
+//!   [`PlotGenerator::with_seed`] or from a random source of the device with
+//!   [`PlotGenerator::with_rng`],
+//! * collapsing in the background, and the time limits of [`Budget`],
+//! * loading and saving files, and every feature but `std` itself, as they all need it.
+//!
+//! The maps of the API, like [`TileDef::metadata`], are `hashbrown`'s then instead of the ones of
+//! `std::collections`. The `cdylib` of the bindings needs std, so build for a target without an
+//! operating system, like `thumbv7em-none-eabihf`, where Cargo leaves it out:
+//!
+//! ```text
+//! cargo build --no-default-features --target thumbv7em-none-eabihf
+//! ```
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "bevy")]
+mod bevy_plugin;
+mod bias;
+mod biome;
+mod domain;
+mod elevation;
+#[cfg(feature = "ffi")]
+mod ffi;
+mod generator;
+#[cfg(feature = "godot")]
+mod godot;
+#[cfg(feature = "std")]
+mod golden;
+#[cfg(feature = "gpu")]
+mod gpu;
+mod grid;
+mod hierarchical;
+mod hydrology;
+#[cfg(feature = "serde")]
+mod island;
+mod layered;
+mod log;
+mod mask;
+mod min_conflicts;
+mod morph;
+mod names;
+mod neighbourhood;
+mod overlapping;
+mod pathfinding;
+mod plot;
+mod prefab;
+mod prelude;
+#[cfg(feature = "python")]
+mod python;
+mod render;
+mod roads;
+mod ruleset;
+mod scatter;
+mod settlements;
+mod stats;
+mod stitch;
+mod svg;
+mod theme;
+mod tile;
+mod tiled;
+mod tileset;
+mod volume;
+#[cfg(feature = "wasm")]
+mod wasm;
+mod waterways;
+mod wave;
+mod world;
+
+#[cfg(feature = "bevy")]
+pub use bevy_plugin::{Chunk, TileAtlas, TileSprite, WorldGenPlugin, WorldPlot};
+pub use bias::BiasMap;
+pub use biome::BiomeGenerator;
+pub use domain::DomainMask;
+pub use elevation::Elevation;
+#[cfg(feature = "std")]
+pub use generator::BackgroundCollapse;
+pub use generator::{
+    Budget, CollapseEvent, Collapses, Edge, GenError, GenerationSpec, GenerationStats, Heatmap,
+    Observer, PlotGenerator, Propagation, RetryStats, RiskSummary, SelectionStrategy, Snapshot,
+    Solver, Step, Symmetry, Violation, WaveState, WfcError, ALL_ORDERS_MAX_FIELDS,
+    CANCEL_CHECK_INTERVAL, DEFAULT_BACKTRACK_BUDGET,
+};
+#[cfg(feature = "std")]
+pub use golden::{Golden, GoldenError};
+pub use hierarchical::HierarchicalGenerator;
+pub use hydrology::Hydrology;
+#[cfg(feature = "serde")]
+pub use island::IslandGenerator;
+pub use layered::LayeredGenerator;
+pub use log::{GenerationLog, LogEvent, ReplayError};
+pub use mask::Mask;
+pub use morph::Morph;
+pub use names::{Feature, Label, NameGenerator};
+pub use neighbourhood::{BoundaryMode, Connectivity, Neighbourhood, Topology};
+pub use overlapping::OverlappingModel;
+pub use plot::{
+    ColoredPlot, EditError, EditMode, HexPlot, ParsePlotError, PlacedTile, Plot, Region, ThemedPlot,
+};
+pub use prefab::{Prefab, PrefabError};
+pub use render::{Annotated, Ansi, Blocks, Csv, JsonGrid, Legend, Renderer};
+pub use roads::RoadNetwork;
+pub use ruleset::{CompiledRuleset, Diagnostic, Issue, RuleTable, Ruleset};
+pub use scatter::{Resource, Scatter};
+pub use settlements::{Settlement, SettlementKind, Settlements};
+pub use stats::{PlotStats, TileStats};
+pub use svg::SvgStyle;
+pub use theme::{Style, Theme, TileColors};
+pub use tile::{Direction, Tile, Weights};
+pub use tiled::TiledExport;
+pub use tileset::{MetaValue, SymmetryClass, TileDef, TileId, TileSet, TileSetError};
+pub use volume::{Volume, VolumeGenerator};
+pub use waterways::Waterways;
+pub use world::{World, WorldFileError};
@@ -0,0 +1,158 @@
+use crate::prelude::*;
+use crate::{Step, TileId, WfcError};
+use core::fmt::Display;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Every step of a [`PlotGenerator`](crate::PlotGenerator), recorded with
+/// [`PlotGenerator::with_log`](crate::PlotGenerator::with_log): which field got collapsed into
+/// which tile, where it backtracked, and how far the random generator was before each step.
+///
+/// Replaying the log with [`PlotGenerator::replay`](crate::PlotGenerator::replay) on a generator
+/// set up the same way reconstructs the exact same wave, without rolling any dice. Replaying only
+/// the first steps and reseeding explores variations of a plot that share its beginning:
+///
+/// ```
+/// use world_gen::PlotGenerator;
+///
+/// let mut gen = PlotGenerator::new(16, 16).with_seed(1).with_log();
+/// let plot = gen.generate()?;
+/// let log = gen.log().unwrap().clone();
+///
+/// let mut again = PlotGenerator::new(16, 16);
+/// again.replay(&log, log.len()).unwrap();
+/// assert_eq!(again.into_plot().to_string(), plot.to_string());
+///
+/// let mut variation = PlotGenerator::new(16, 16);
+/// variation.replay(&log, 100).unwrap();
+/// let variation = variation.with_seed(2).generate()?;
+/// # Ok::<(), world_gen::GenError>(())
+/// ```
+///
+/// Only steps get recorded, changes like
+/// [`PlotGenerator::set_tile`](crate::PlotGenerator::set_tile) or
+/// [`PlotGenerator::undo`](crate::PlotGenerator::undo) have to be made the same way before the
+/// replay.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GenerationLog {
+    /// The seed of the random generator, taken at the first step.
+    seed: [u8; 32],
+    events: Vec<LogEvent>,
+}
+
+/// A step recorded in a [`GenerationLog`], see [`Step`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LogEvent {
+    /// The field at `pos` got collapsed into `tile`.
+    Collapsed {
+        pos: (usize, usize),
+        tile: TileId,
+        /// How many 32 bit words the random generator had drawn before the step.
+        draws: u64,
+    },
+    /// Observing the field at `contradiction` failed, so the observation at `undone` got undone.
+    Backtracked {
+        contradiction: (usize, usize),
+        undone: (usize, usize),
+        /// How many 32 bit words the random generator had drawn before the step.
+        draws: u64,
+    },
+}
+
+/// Why a [`GenerationLog`] couldn't be replayed, see
+/// [`PlotGenerator::replay`](crate::PlotGenerator::replay).
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayError {
+    /// The step at this index of the log went differently, e.g. because the generator got set up
+    /// differently than the one that recorded the log.
+    Diverged(usize),
+    /// The step at this index of the log failed.
+    Failed(usize, WfcError),
+}
+
+impl GenerationLog {
+    pub fn seed(&self) -> [u8; 32] {
+        self.seed
+    }
+
+    /// The recorded steps, oldest first.
+    pub fn events(&self) -> &[LogEvent] {
+        &self.events
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Records the step `step`, taken with the random generator at `draws` words of a
+    /// generator seeded with `seed`.
+    pub(crate) fn record(&mut self, seed: [u8; 32], draws: u64, step: &Step) {
+        if self.events.is_empty() {
+            self.seed = seed;
+        }
+        match *step {
+            Step::Collapsed { pos, tile, .. } => {
+                self.events.push(LogEvent::Collapsed { pos, tile, draws })
+            }
+            Step::Backtracked {
+                contradiction,
+                undone,
+            } => self.events.push(LogEvent::Backtracked {
+                contradiction,
+                undone,
+                draws,
+            }),
+            Step::Done => {}
+        }
+    }
+}
+
+impl LogEvent {
+    /// How many 32 bit words the random generator had drawn before the step.
+    pub fn draws(&self) -> u64 {
+        match *self {
+            LogEvent::Collapsed { draws, .. } | LogEvent::Backtracked { draws, .. } => draws,
+        }
+    }
+
+    /// Whether `step` is the step this event recorded.
+    pub(crate) fn matches(&self, step: &Step) -> bool {
+        match (*self, step) {
+            (
+                LogEvent::Collapsed { pos, tile, .. },
+                Step::Collapsed {
+                    pos: p, tile: t, ..
+                },
+            ) => pos == *p && tile == *t,
+            (
+                LogEvent::Backtracked {
+                    contradiction,
+                    undone,
+                    ..
+                },
+                Step::Backtracked {
+                    contradiction: c,
+                    undone: u,
+                },
+            ) => contradiction == *c && undone == *u,
+            _ => false,
+        }
+    }
+}
+
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ReplayError::Diverged(step) => write!(f, "Step {step} of the log went differently"),
+            ReplayError::Failed(step, err) => write!(f, "Step {step} of the log failed: {err}"),
+        }
+    }
+}
+
+impl core::error::Error for ReplayError {}
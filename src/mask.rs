@@ -0,0 +1,80 @@
+use crate::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Which fields of a grid belong to the plot, for plots that aren't rectangles like an island
+/// or a round arena, see [`PlotGenerator::with_mask`](crate::PlotGenerator::with_mask).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Mask {
+    width: usize,
+    height: usize,
+    /// Row-major, true for the fields inside.
+    inside: Vec<bool>,
+}
+
+impl Mask {
+    /// A `width` x `height` mask with the fields inside for which `inside(x, y)` holds, e.g. a
+    /// circle.
+    pub fn new(width: usize, height: usize, inside: impl Fn(usize, usize) -> bool) -> Self {
+        Mask {
+            width,
+            height,
+            inside: (0..height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .map(|(x, y)| inside(x, y))
+                .collect(),
+        }
+    }
+
+    /// Reads a stencil drawn as text, one character per field and one row per line. Spaces and
+    /// `.` lie outside, every other character inside. Shorter rows are filled up with fields
+    /// outside.
+    pub fn from_text(text: &str) -> Self {
+        let rows = text
+            .lines()
+            .map(|line| {
+                line.chars()
+                    .map(|c| c != ' ' && c != '.')
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        Mask::new(width, rows.len(), |x, y| {
+            rows[y].get(x).copied().unwrap_or(false)
+        })
+    }
+
+    /// Reads a stencil from an image, one pixel per field. Bright, opaque pixels lie inside,
+    /// dark or transparent ones outside.
+    #[cfg(feature = "image")]
+    pub fn from_image(image: &image::DynamicImage) -> Self {
+        let image = image.to_luma_alpha8();
+        Mask::new(image.width() as usize, image.height() as usize, |x, y| {
+            let image::LumaA([luma, alpha]) = *image.get_pixel(x as u32, y as u32);
+            luma >= 128 && alpha >= 128
+        })
+    }
+
+    /// Reads a stencil from the alpha channel of an image, one pixel per field. Pixels at least
+    /// as opaque as `threshold` lie inside, more transparent ones outside.
+    #[cfg(feature = "image")]
+    pub fn from_alpha(image: &image::RgbaImage, threshold: u8) -> Self {
+        Mask::new(image.width() as usize, image.height() as usize, |x, y| {
+            image.get_pixel(x as u32, y as u32).0[3] >= threshold
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Whether the field at `(x, y)` belongs to the plot.
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        self.inside[y * self.width + x]
+    }
+}
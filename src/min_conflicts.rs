@@ -0,0 +1,115 @@
+//! The repair based solver behind [`Solver::MinConflicts`](crate::Solver::MinConflicts).
+use crate::prelude::*;
+use crate::{Direction, TileId};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// How often a repair picks a random tile instead of the one with the fewest conflicts, so the
+/// search can leave local minima.
+const NOISE: f64 = 0.05;
+
+/// Assigns every field one of its `domains` such that every pair of neighbours follows the rules,
+/// `allows(a, direction, b)` tells whether `b` may lie next to `a` in `direction`. `neighbours[i]`
+/// lists the neighbours of field `i` with the direction they lie in.
+///
+/// Starts from a random assignment, weighted by `weight(field, tile)`, and repeatedly reassigns a
+/// random conflicted field to the tile with the fewest conflicts. Fails with a field that still
+/// has a conflict after `max_steps` repairs, or with a field without any tile in its domain.
+pub(crate) fn solve(
+    allows: impl Fn(TileId, Direction, TileId) -> bool,
+    domains: &[Vec<TileId>],
+    neighbours: &[Vec<(usize, Direction)>],
+    weight: impl Fn(usize, TileId) -> f64,
+    rng: &mut impl Rng,
+    max_steps: usize,
+) -> Result<Vec<TileId>, usize> {
+    if let Some(empty) = domains.iter().position(Vec::is_empty) {
+        return Err(empty);
+    }
+
+    let mut tiles = domains
+        .iter()
+        .enumerate()
+        .map(|(index, domain)| pick(domain, &|tile| weight(index, tile), rng))
+        .collect::<Vec<_>>();
+    let conflicts = |tiles: &[TileId], index: usize, tile: TileId| {
+        neighbours[index]
+            .iter()
+            .filter(|(neighbour, direction)| !allows(tile, *direction, tiles[*neighbour]))
+            .count()
+    };
+
+    // The conflicted fields, and where each field sits in that list
+    let mut conflicted = vec![];
+    let mut slots = vec![None; tiles.len()];
+    for index in 0..tiles.len() {
+        let is_conflicted = conflicts(&tiles, index, tiles[index]) > 0;
+        mark(&mut conflicted, &mut slots, index, is_conflicted);
+    }
+
+    for _ in 0..max_steps {
+        let Some(&index) = conflicted.choose(rng) else {
+            return Ok(tiles);
+        };
+
+        let domain = &domains[index];
+        let field_weight = |tile| weight(index, tile);
+        tiles[index] = if rng.gen_bool(NOISE) {
+            pick(domain, &field_weight, rng)
+        } else {
+            let least = domain
+                .iter()
+                .map(|&tile| conflicts(&tiles, index, tile))
+                .min()
+                .expect("Domains aren't empty");
+            let best = domain
+                .iter()
+                .copied()
+                .filter(|&tile| conflicts(&tiles, index, tile) == least)
+                .collect::<Vec<_>>();
+            pick(&best, &field_weight, rng)
+        };
+
+        // Rules go both ways, so only the field and its neighbours can change their status
+        for &field in core::iter::once(&index).chain(neighbours[index].iter().map(|(n, _)| n)) {
+            let is_conflicted = conflicts(&tiles, field, tiles[field]) > 0;
+            mark(&mut conflicted, &mut slots, field, is_conflicted);
+        }
+    }
+
+    match conflicted.first() {
+        Some(&index) => Err(index),
+        None => Ok(tiles),
+    }
+}
+
+/// Adds `index` to the `conflicted` fields or removes it, keeping `slots` up to date.
+fn mark(
+    conflicted: &mut Vec<usize>,
+    slots: &mut [Option<usize>],
+    index: usize,
+    is_conflicted: bool,
+) {
+    match (slots[index], is_conflicted) {
+        (None, true) => {
+            slots[index] = Some(conflicted.len());
+            conflicted.push(index);
+        }
+        (Some(slot), false) => {
+            conflicted.swap_remove(slot);
+            if let Some(&moved) = conflicted.get(slot) {
+                slots[moved] = Some(slot);
+            }
+            slots[index] = None;
+        }
+        _ => {}
+    }
+}
+
+/// One of `tiles`, picked by weight, or uniformly if none has a positive weight.
+fn pick(tiles: &[TileId], weight: &impl Fn(TileId) -> f64, rng: &mut impl Rng) -> TileId {
+    match tiles.choose_weighted(rng, |&tile| weight(tile)) {
+        Ok(&tile) => tile,
+        Err(_) => *tiles.choose(rng).expect("Domains aren't empty"),
+    }
+}
@@ -0,0 +1,115 @@
+use crate::prelude::*;
+use crate::{BiasMap, GenError, Plot, PlotGenerator};
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+
+/// In-between plots of two seeds, to morph from the layout of one into the other and find
+/// designs between them. Experimental, it may change in any release.
+///
+/// [`Morph::at`] pins a share of the fields to their tiles on the plot of seed `a` and collapses
+/// the rest again with seed `b`, pulled towards the tiles of the plot of `b`, see
+/// [`Morph::with_pull`]. The fields get pinned in a fixed random order, so every frame keeps the
+/// fields of `a` of the frames before it.
+///
+/// ```
+/// use world_gen::{Morph, PlotGenerator};
+///
+/// let morph = Morph::new(|| PlotGenerator::new(16, 16), 1, 2)?;
+/// let frames = morph.frames(5).collect::<Result<Vec<_>, _>>()?;
+/// assert_eq!(frames[0].to_string(), morph.b().to_string());
+/// assert_eq!(frames[4].to_string(), morph.a().to_string());
+/// # Ok::<(), world_gen::GenError>(())
+/// ```
+pub struct Morph<F> {
+    new: F,
+    /// The seed of `b`, which the frames get collapsed with.
+    seed: u64,
+    a: Plot,
+    b: Plot,
+    /// The fields in the order they get pinned to their tiles on `a`.
+    order: Vec<(usize, usize)>,
+    pull: f64,
+}
+
+impl<F: Fn() -> PlotGenerator> Morph<F> {
+    /// Generates the plots of seeds `a` and `b` with the generators of `new`, which are
+    /// seeded by the morph. Fails if either of them fails.
+    pub fn new(new: F, a: u64, b: u64) -> Result<Self, GenError> {
+        let plot_a = new().with_seed(a).generate()?;
+        let plot_b = new().with_seed(b).generate()?;
+        assert!(
+            (plot_a.width(), plot_a.height()) == (plot_b.width(), plot_b.height()),
+            "The generators of `new` have to have the same size"
+        );
+
+        let mut order = plot_a.positions().collect::<Vec<_>>();
+        order.shuffle(&mut ChaCha12Rng::seed_from_u64(a ^ b.rotate_left(32)));
+        Ok(Morph {
+            new,
+            seed: b,
+            a: plot_a,
+            b: plot_b,
+            order,
+            pull: 64.0,
+        })
+    }
+
+    /// Multiplies the weight of the tile of `b` on every field that isn't pinned with `factor`,
+    /// so the frames resemble `b` where they aren't `a`. Defaults to 64, 1 fills the fields
+    /// without regard for `b`.
+    pub fn with_pull(mut self, factor: f64) -> Self {
+        assert!(factor >= 1.0, "The pull has to be at least 1");
+        self.pull = factor;
+        self
+    }
+
+    /// The plot of seed `a`, the frame at 1.
+    pub fn a(&self) -> &Plot {
+        &self.a
+    }
+
+    /// The plot of seed `b`, the frame at 0.
+    pub fn b(&self) -> &Plot {
+        &self.b
+    }
+
+    /// The frame with the share `t` of the fields pinned to `a`, from 0 for `b` to 1 for `a`.
+    /// Fails if collapsing the other fields runs into a contradiction.
+    pub fn at(&self, t: f64) -> Result<Plot, GenError> {
+        let pinned = (t.clamp(0.0, 1.0) * self.order.len() as f64).round() as usize;
+        if pinned == 0 {
+            return Ok(self.b.clone());
+        }
+
+        let gen = (self.new)().with_seed(self.seed);
+        let (width, height) = (gen.width(), gen.height());
+        let mut bias = gen
+            .bias()
+            .cloned()
+            .unwrap_or_else(|| BiasMap::new(width, height));
+        for tile in gen.tileset().ids() {
+            let b = &self.b;
+            bias = bias.with_factor(tile, |x, y| match b.get(x, y) == tile {
+                true => self.pull,
+                false => 1.0,
+            });
+        }
+
+        let mut gen = gen.with_bias(bias);
+        for &(x, y) in &self.order[..pinned] {
+            gen.set_tile(x, y, self.a.get(x, y))
+                .map_err(|_| GenError::Contradiction((x, y)))?;
+        }
+        gen.generate()
+    }
+
+    /// `count` frames from `b` to `a`, evenly spaced.
+    pub fn frames(&self, count: usize) -> impl Iterator<Item = Result<Plot, GenError>> + '_ {
+        assert!(
+            count >= 2,
+            "A morph needs at least the frames of both seeds"
+        );
+        (0..count).map(move |i| self.at(i as f64 / (count - 1) as f64))
+    }
+}
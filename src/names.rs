@@ -0,0 +1,195 @@
+use crate::prelude::*;
+use crate::{Connectivity, Plot, Settlement, SettlementKind, TileId};
+use core::ops::RangeInclusive;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Makes up names for the features of a plot from random syllables, and adds them to the legend
+/// of the plot, see [`Plot::legend`].
+///
+/// A name is a few syllables of an onset and a vowel, followed by a coda, each picked from a
+/// list. Names don't repeat within one generator, and the same seed gives the same names in the
+/// same order.
+#[derive(Debug, Clone)]
+pub struct NameGenerator {
+    rng: ChaCha12Rng,
+    onsets: Vec<String>,
+    vowels: Vec<String>,
+    codas: Vec<String>,
+    syllables: RangeInclusive<usize>,
+    used: HashSet<String>,
+}
+
+/// A named feature of a plot, see [`Plot::legend`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub name: String,
+    pub feature: Feature,
+    /// The fields the feature covers.
+    pub fields: Vec<(usize, usize)>,
+}
+
+/// What a [`Label`] names.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feature {
+    /// A group of connected fields of a tile, like a forest, a lake or a river.
+    Region(TileId),
+    Settlement(SettlementKind),
+}
+
+impl NameGenerator {
+    /// Names of two or three syllables resembling no language in particular.
+    pub fn new() -> Self {
+        let strings = |parts: &[&str]| parts.iter().map(|part| part.to_string()).collect();
+        NameGenerator {
+            rng: ChaCha12Rng::seed_from_u64(random_seed()),
+            onsets: strings(&[
+                "", "b", "br", "d", "dr", "f", "g", "gr", "h", "k", "kr", "l", "m", "n", "p", "r",
+                "s", "st", "t", "th", "v", "w", "z",
+            ]),
+            vowels: strings(&["a", "e", "i", "o", "u", "ae", "ai", "ei", "ou"]),
+            codas: strings(&["", "", "", "l", "m", "n", "nd", "r", "rk", "s", "th"]),
+            syllables: 2..=3,
+            used: HashSet::new(),
+        }
+    }
+
+    /// Derives the names from `seed`.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = ChaCha12Rng::seed_from_u64(seed);
+        self
+    }
+
+    /// Builds the names from these parts, e.g. to make them sound like a certain region. Empty
+    /// strings let a name go without that part.
+    pub fn with_syllables(mut self, onsets: &[&str], vowels: &[&str], codas: &[&str]) -> Self {
+        assert!(
+            !onsets.is_empty() && !vowels.is_empty() && !codas.is_empty(),
+            "Names need at least one of each part"
+        );
+
+        let strings = |parts: &[&str]| parts.iter().map(|part| part.to_string()).collect();
+        self.onsets = strings(onsets);
+        self.vowels = strings(vowels);
+        self.codas = strings(codas);
+        self
+    }
+
+    /// Builds names out of a number of syllables in `count`.
+    pub fn with_syllable_count(mut self, count: RangeInclusive<usize>) -> Self {
+        assert!(
+            !count.is_empty() && *count.start() > 0,
+            "Names need at least one syllable"
+        );
+        self.syllables = count;
+        self
+    }
+
+    /// A new name, capitalized. Once the syllables run out of new names, the names get numbered.
+    pub fn name(&mut self) -> String {
+        // A few tries before giving up on finding a name that wasn't used yet
+        for _ in 0..32 {
+            let name = self.syllables();
+            if self.used.insert(name.clone()) {
+                return name;
+            }
+        }
+
+        let base = self.syllables();
+        let name = (2..)
+            .map(|number| format!("{base} {number}"))
+            .find(|name| !self.used.contains(name))
+            .expect("Some number is unused");
+        self.used.insert(name.clone());
+        name
+    }
+
+    /// Names every group of at least `min_size` connected `tile`s of `plot`, like
+    /// "Tharn Forest". Returns the new labels, which are also added to the legend.
+    pub fn label_regions(
+        &mut self,
+        plot: &mut Plot,
+        tile: impl Into<TileId>,
+        connectivity: Connectivity,
+        min_size: usize,
+    ) -> Vec<Label> {
+        let tile = tile.into();
+        let kind = plot.tileset().name(tile).to_string();
+        let labels = plot
+            .regions(tile, connectivity)
+            .into_iter()
+            .filter(|region| region.len() >= min_size)
+            .map(|region| Label {
+                name: format!("{} {kind}", self.name()),
+                feature: Feature::Region(tile),
+                fields: region.fields().to_vec(),
+            })
+            .collect::<Vec<_>>();
+
+        for label in &labels {
+            plot.add_label(label.clone());
+        }
+        labels
+    }
+
+    /// Gives each of `settlements` a name, and adds them to the legend of `plot`.
+    pub fn label_settlements(&mut self, plot: &mut Plot, settlements: &mut [Settlement]) {
+        for settlement in settlements {
+            settlement.name = self.name();
+            plot.add_label(Label {
+                name: settlement.name.clone(),
+                feature: Feature::Settlement(settlement.kind),
+                fields: vec![(settlement.x, settlement.y)],
+            });
+        }
+    }
+
+    /// A random name, possibly used before.
+    fn syllables(&mut self) -> String {
+        let count = self.rng.gen_range(self.syllables.clone());
+        let mut name = String::new();
+        for _ in 0..count {
+            for parts in [&self.onsets, &self.vowels] {
+                name.push_str(parts.choose(&mut self.rng).expect("Parts aren't empty"));
+            }
+        }
+        name.push_str(
+            self.codas
+                .choose(&mut self.rng)
+                .expect("Parts aren't empty"),
+        );
+
+        let mut chars = name.chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => name,
+        }
+    }
+}
+
+impl Default for NameGenerator {
+    fn default() -> Self {
+        NameGenerator::new()
+    }
+}
+
+impl Label {
+    /// The field of the feature closest to its centre, where a map would put the name.
+    pub fn anchor(&self) -> (usize, usize) {
+        let count = self.fields.len().max(1) as f64;
+        let cx = self.fields.iter().map(|&(x, _)| x as f64).sum::<f64>() / count;
+        let cy = self.fields.iter().map(|&(_, y)| y as f64).sum::<f64>() / count;
+
+        let distance = |&(x, y): &(usize, usize)| (x as f64 - cx).powi(2) + (y as f64 - cy).powi(2);
+        self.fields
+            .iter()
+            .copied()
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+            .unwrap_or((0, 0))
+    }
+}
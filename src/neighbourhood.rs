@@ -0,0 +1,234 @@
+use crate::prelude::*;
+use crate::Direction;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The directions of the 4 fields in a "+" shape, in the order propagation visits them.
+const VON_NEUMANN: [Direction; 4] = [
+    Direction::North,
+    Direction::East,
+    Direction::South,
+    Direction::West,
+];
+
+/// The directions of the 6 neighbours of a hexagon, in the order propagation visits them.
+const HEX: [Direction; 6] = [
+    Direction::East,
+    Direction::NorthEast,
+    Direction::NorthWest,
+    Direction::West,
+    Direction::SouthWest,
+    Direction::SouthEast,
+];
+
+/// The steps to the neighbours of a hexagon in axial coordinates, indexed by direction. North
+/// and South aren't neighbours and keep their square steps. The row above is shifted half a
+/// field to the west, so north-west is straight up and south-east straight down.
+const HEX_OFFSETS: [(isize, isize); 8] = [
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (0, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (0, -1),
+];
+
+const FOUR: [(isize, isize); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+const EIGHT: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// How propagation treats the edges of the grid, see
+/// [`PlotGenerator::with_boundary`](crate::PlotGenerator::with_boundary).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BoundaryMode {
+    /// Fields at an edge have no neighbours beyond it.
+    #[default]
+    Clamp,
+    /// The grid is a torus: the left edge neighbours the right one and the top edge the bottom
+    /// one, so the plot can be repeated seamlessly.
+    Wrap,
+}
+
+/// The shape of the fields of a [`PlotGenerator`](crate::PlotGenerator) and which fields are
+/// their neighbours, see [`PlotGenerator::with_topology`](crate::PlotGenerator::with_topology).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Topology {
+    /// Square fields with 8 neighbours, one in every [`Direction`].
+    #[default]
+    Square,
+    /// Pointy-top hexagons with 6 neighbours, stored in axial coordinates: `x` runs east along a
+    /// row, and every row is shifted half a field to the east of the one above, so the grid forms
+    /// a rhombus. See [`Plot::display_hex`](crate::Plot::display_hex).
+    ///
+    /// The neighbours lie in the directions of [`Topology::directions`], so the tile set rules for
+    /// North and South are never used.
+    Hex,
+}
+
+/// Which fields of a square grid are neighbours for the rules, see
+/// [`PlotGenerator::with_neighbourhood`](crate::PlotGenerator::with_neighbourhood).
+///
+/// Fields that aren't neighbours don't restrict each other, so the rules for their direction
+/// are never checked. Without the diagonals, tiles the rules keep apart may still touch at a
+/// corner.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Neighbourhood {
+    /// The 4 fields in a "+" shape.
+    VonNeumann,
+    /// The 8 fields in a "#" shape.
+    #[default]
+    Moore,
+    /// The fields in these directions only, e.g. only east and west for rows that ignore each
+    /// other. Has to contain the opposite of every direction in it.
+    Custom(Vec<Direction>),
+}
+
+/// Which fields count as neighbours of a field.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Connectivity {
+    /// The 4 fields in a "+" shape.
+    Four,
+    /// The 8 fields in a "#" shape.
+    Eight,
+}
+
+/// The directions of the neighbours of a field with the steps to them, looked up once for a
+/// topology and neighbourhood. It is `Copy`, so it can be held while the generator changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Offsets {
+    len: usize,
+    table: [(Direction, (isize, isize)); 8],
+}
+
+impl BoundaryMode {
+    /// The field `(dx, dy)` away from `(x, y)` in a `width` x `height` grid, `None` if the step
+    /// leaves the grid.
+    pub fn neighbour(
+        &self,
+        (x, y): (usize, usize),
+        (dx, dy): (isize, isize),
+        width: usize,
+        height: usize,
+    ) -> Option<(usize, usize)> {
+        let (nx, ny) = (x as isize + dx, y as isize + dy);
+        match self {
+            BoundaryMode::Clamp => ((0..width as isize).contains(&nx)
+                && (0..height as isize).contains(&ny))
+            .then_some((nx as usize, ny as usize)),
+            BoundaryMode::Wrap => Some((
+                nx.rem_euclid(width as isize) as usize,
+                ny.rem_euclid(height as isize) as usize,
+            )),
+        }
+    }
+}
+
+impl Topology {
+    /// The directions a field has neighbours in.
+    pub fn directions(&self) -> &'static [Direction] {
+        match self {
+            Topology::Square => &Direction::ALL,
+            Topology::Hex => &HEX,
+        }
+    }
+
+    /// The `(dx, dy)` step from a field to its neighbour in `direction`, one of
+    /// [`Topology::directions`].
+    pub fn offset(&self, direction: Direction) -> (isize, isize) {
+        match self {
+            Topology::Square => direction.offset(),
+            Topology::Hex => HEX_OFFSETS[direction as usize],
+        }
+    }
+}
+
+impl Neighbourhood {
+    /// The directions a field of `topology` has neighbours in. Hex grids always use all of
+    /// theirs.
+    pub fn directions(&self, topology: Topology) -> &[Direction] {
+        match (topology, self) {
+            (Topology::Hex, _) | (Topology::Square, Neighbourhood::Moore) => topology.directions(),
+            (Topology::Square, Neighbourhood::VonNeumann) => &VON_NEUMANN,
+            (Topology::Square, Neighbourhood::Custom(directions)) => directions,
+        }
+    }
+}
+
+impl Connectivity {
+    pub fn offsets(&self) -> &'static [(isize, isize)] {
+        match self {
+            Connectivity::Four => &FOUR,
+            Connectivity::Eight => &EIGHT,
+        }
+    }
+
+    /// The neighbours of `(x, y)` that lie inside a `width` x `height` grid.
+    pub fn neighbours(
+        &self,
+        (x, y): (usize, usize),
+        width: usize,
+        height: usize,
+    ) -> impl Iterator<Item = (usize, usize)> {
+        self.offsets()
+            .iter()
+            .filter_map(move |&offset| BoundaryMode::Clamp.neighbour((x, y), offset, width, height))
+    }
+}
+
+impl Offsets {
+    /// The neighbours of `neighbourhood` on a grid of `topology`, every direction once.
+    pub(crate) fn new(topology: Topology, neighbourhood: &Neighbourhood) -> Self {
+        let mut offsets = Offsets {
+            len: 0,
+            table: [(Direction::North, (0, 0)); 8],
+        };
+        for &direction in neighbourhood.directions(topology) {
+            if !offsets.directions().any(|d| d == direction) {
+                offsets.table[offsets.len] = (direction, topology.offset(direction));
+                offsets.len += 1;
+            }
+        }
+        offsets
+    }
+
+    #[cfg(feature = "gpu")]
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The directions with their steps.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (Direction, (isize, isize))> + '_ {
+        self.table[..self.len].iter().copied()
+    }
+
+    pub(crate) fn directions(&self) -> impl Iterator<Item = Direction> + '_ {
+        self.iter().map(|(direction, _)| direction)
+    }
+
+    /// The neighbours of `pos` in a `width` x `height` grid with the direction they lie in.
+    pub(crate) fn neighbours(
+        &self,
+        pos: (usize, usize),
+        boundary: BoundaryMode,
+        width: usize,
+        height: usize,
+    ) -> impl Iterator<Item = (Direction, (usize, usize))> + '_ {
+        self.iter().filter_map(move |(direction, offset)| {
+            Some((direction, boundary.neighbour(pos, offset, width, height)?))
+        })
+    }
+}
@@ -0,0 +1,150 @@
+use crate::prelude::*;
+use crate::{Direction, Plot, PlotGenerator, TileDef, TileId, TileSet};
+use alloc::sync::Arc;
+
+/// The overlapping model: learns the rules from a sample plot instead of a hand-written
+/// [`TileSet`].
+///
+/// Every `n` x `n` window of the sample becomes a pattern, weighted by how often it occurs. The
+/// sample wraps around at its edges, so every field starts a window. Two
+/// patterns may lie next to each other when they agree on all fields they overlap in. The
+/// patterns are collapsed like any other tile set, and every field of the result takes the
+/// top-left tile of its pattern.
+///
+/// ```no_run
+/// use world_gen::{OverlappingModel, Plot, TileSet};
+///
+/// let sample = Plot::from_glyphs(TileSet::terrain(), "░░▓▓██\n░░▓▓██\n▓▓▓▓██\n").unwrap();
+/// let model = OverlappingModel::new(&sample, 2);
+///
+/// let mut gen = model.generator(32, 16);
+/// gen.collapse()?;
+///
+/// println!("{}", model.resolve(&gen.into_plot()));
+/// # Ok::<(), world_gen::WfcError>(())
+/// ```
+pub struct OverlappingModel {
+    n: usize,
+    /// The tile set of the sample.
+    tiles: Arc<TileSet>,
+    /// Every distinct window of the sample, row-major.
+    patterns: Vec<Vec<TileId>>,
+    /// A tile set with one tile per pattern.
+    tileset: Arc<TileSet>,
+}
+
+impl OverlappingModel {
+    /// Extracts the `n` x `n` patterns of `sample`. `n` has to be at least 2, as single fields
+    /// don't overlap their neighbours, and can't be bigger than the sample.
+    pub fn new(sample: &Plot, n: usize) -> Self {
+        assert!(n >= 2, "Patterns need at least 2 x 2 fields to overlap");
+        assert!(
+            n <= sample.width() && n <= sample.height(),
+            "Patterns can't be bigger than the sample"
+        );
+
+        let mut patterns: Vec<Vec<TileId>> = vec![];
+        let mut counts: HashMap<Vec<TileId>, usize> = HashMap::new();
+        let (width, height) = (sample.width(), sample.height());
+        for (x, y) in sample.positions() {
+            let pattern = (0..n)
+                .flat_map(|dy| (0..n).map(move |dx| ((x + dx) % width, (y + dy) % height)))
+                .map(|(x, y)| sample.get(x, y))
+                .collect::<Vec<_>>();
+
+            let count = counts.entry(pattern.clone()).or_insert(0);
+            if *count == 0 {
+                patterns.push(pattern);
+            }
+            *count += 1;
+        }
+
+        let name = |i: usize| format!("Pattern {i}");
+        let defs = patterns
+            .iter()
+            .enumerate()
+            .map(|(i, pattern)| {
+                let sides = Direction::ALL
+                    .into_iter()
+                    .map(|direction| {
+                        let compatible = (0..patterns.len())
+                            .filter(|j| overlaps(n, pattern, &patterns[*j], direction.offset()))
+                            .map(name)
+                            .collect();
+                        (direction, compatible)
+                    })
+                    .collect();
+
+                TileDef {
+                    name: name(i),
+                    glyph: sample.tileset().glyph(pattern[0]).to_string(),
+                    weight: counts[pattern] as f64,
+                    color: sample.tileset().color(pattern[0]),
+                    neighbours: vec![],
+                    sides,
+                    symmetry: None,
+                    edges: None,
+                    between: vec![],
+                    metadata: HashMap::new(),
+                }
+            })
+            .collect();
+
+        OverlappingModel {
+            n,
+            tiles: Arc::new(sample.tileset().clone()),
+            patterns,
+            tileset: Arc::new(TileSet::new(defs).expect("Patterns form a valid tile set")),
+        }
+    }
+
+    /// The size of the patterns.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// How many distinct patterns the sample contains.
+    pub fn pattern_count(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// The tile set with one tile per pattern.
+    pub fn tileset(&self) -> &TileSet {
+        &self.tileset
+    }
+
+    /// Creates a generator for a `width` x `height` plot of patterns, turn the result into a
+    /// plot of sample tiles with [`OverlappingModel::resolve`].
+    pub fn generator(&self, width: usize, height: usize) -> PlotGenerator {
+        PlotGenerator::for_tileset(Arc::clone(&self.tileset), width, height)
+    }
+
+    /// Replaces every pattern of a plot from [`OverlappingModel::generator`] by its top-left
+    /// tile.
+    pub fn resolve(&self, patterns: &Plot) -> Plot {
+        let tiles = patterns
+            .positions()
+            .map(|(x, y)| self.patterns[patterns.get(x, y).0 as usize][0])
+            .collect();
+
+        Plot::from_tiles(
+            Arc::clone(&self.tiles),
+            patterns.width(),
+            patterns.height(),
+            tiles,
+        )
+    }
+}
+
+/// Whether pattern `b`, placed `(dx, dy)` away from pattern `a`, agrees with `a` on every field
+/// they share.
+fn overlaps(n: usize, a: &[TileId], b: &[TileId], (dx, dy): (isize, isize)) -> bool {
+    let n = n as isize;
+    (0..n).all(|y| {
+        (0..n).all(|x| {
+            let (bx, by) = (x - dx, y - dy);
+            !((0..n).contains(&bx) && (0..n).contains(&by))
+                || a[(y * n + x) as usize] == b[(by * n + bx) as usize]
+        })
+    })
+}
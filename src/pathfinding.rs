@@ -0,0 +1,139 @@
+use crate::grid::Grid;
+use crate::prelude::*;
+use crate::{Connectivity, Plot, TileId};
+use alloc::collections::BinaryHeap;
+use core::cmp::Reverse;
+
+/// A path cost, ordered with `total_cmp` so it can go into the A* queue.
+#[derive(Debug, Clone, Copy)]
+struct Cost(f64);
+
+impl Plot {
+    /// The cheapest path from `start` to `goal`, both included, found with A*. Paths move in the
+    /// "+" shape, and every step costs what `cost` returns for the tile stepped onto, `None` for
+    /// tiles that can't be crossed. Costs can't be negative. Returns `None` if `goal` can't be
+    /// reached.
+    ///
+    /// The movement costs of a tile set, see [`TileSet::cost`](crate::TileSet::cost), are one
+    /// choice:
+    ///
+    /// ```
+    /// use world_gen::{PlotGenerator, TileSet};
+    ///
+    /// let plot = PlotGenerator::for_tileset(TileSet::world(), 24, 24).with_seed(4).generate()?;
+    /// let tileset = plot.tileset();
+    /// let path = plot.find_path((0, 0), (23, 23), |tile| {
+    ///     tileset.passable(tile).then(|| tileset.cost(tile))
+    /// });
+    /// if let Some(path) = path {
+    ///     assert_eq!((path[0], path[path.len() - 1]), ((0, 0), (23, 23)));
+    /// }
+    /// # Ok::<(), world_gen::GenError>(())
+    /// ```
+    pub fn find_path(
+        &self,
+        start: (usize, usize),
+        goal: (usize, usize),
+        cost: impl Fn(TileId) -> Option<f64>,
+    ) -> Option<Vec<(usize, usize)>> {
+        shortest_path(self.width(), self.height(), start, goal, |(x, y)| {
+            cost(self.get(x, y))
+        })
+    }
+}
+
+/// The cheapest path from `from` to `to` on a `width` x `height` grid, both included. `cost`
+/// gives the cost of stepping onto a field, `None` if it can't be stepped onto.
+pub(crate) fn shortest_path(
+    width: usize,
+    height: usize,
+    from: (usize, usize),
+    to: (usize, usize),
+    cost: impl Fn((usize, usize)) -> Option<f64>,
+) -> Option<Vec<(usize, usize)>> {
+    if from == to {
+        return Some(vec![from]);
+    }
+
+    let costs_of = Grid::from_fn(width, height, |x, y| cost((x, y)));
+    assert!(
+        costs_of
+            .iter()
+            .flat_map(|(_, step)| step)
+            .all(|&step| step >= 0.0),
+        "Path costs can't be negative"
+    );
+
+    // Every step costs at least this much, which keeps the heuristic admissible
+    let cheapest = costs_of
+        .iter()
+        .flat_map(|(_, step)| step)
+        .copied()
+        .fold(f64::INFINITY, f64::min);
+    if cheapest.is_infinite() {
+        return None;
+    }
+    let estimate = |(x, y): (usize, usize)| (x.abs_diff(to.0) + y.abs_diff(to.1)) as f64 * cheapest;
+
+    let mut costs = Grid::new(width, height, f64::INFINITY);
+    let mut previous = Grid::new(width, height, None);
+    let mut queue = BinaryHeap::new();
+    costs[from] = 0.0;
+    // Ordered by the estimated total cost, then by the cost so far
+    queue.push(Reverse((Cost(estimate(from)), Cost(0.0), from)));
+
+    while let Some(Reverse((_, Cost(cost), pos))) = queue.pop() {
+        if pos == to {
+            let mut path = vec![pos];
+            let mut current = pos;
+            while let Some(before) = previous[current] {
+                path.push(before);
+                current = before;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        if cost > costs[pos] {
+            // Already reached more cheaply
+            continue;
+        }
+
+        for neighbour in costs_of.neighbours(pos, Connectivity::Four) {
+            let Some(step) = costs_of[neighbour] else {
+                continue;
+            };
+            let cost = cost + step;
+            if cost < costs[neighbour] {
+                costs[neighbour] = cost;
+                previous[neighbour] = Some(pos);
+                queue.push(Reverse((
+                    Cost(cost + estimate(neighbour)),
+                    Cost(cost),
+                    neighbour,
+                )));
+            }
+        }
+    }
+
+    None
+}
+
+impl PartialEq for Cost {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == core::cmp::Ordering::Equal
+    }
+}
+
+impl Eq for Cost {}
+
+impl PartialOrd for Cost {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Cost {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
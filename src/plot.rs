@@ -0,0 +1,1183 @@
+use crate::grid::Grid;
+use crate::prelude::*;
+use crate::render::write_themed;
+use crate::svg::escape_xml;
+use crate::{
+    Blocks, Connectivity, Direction, Label, MetaValue, Neighbourhood, PlotGenerator, PlotStats,
+    Renderer, SvgStyle, Theme, Tile, TileDef, TileId, TileSet,
+};
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use core::cmp::Reverse;
+use core::fmt::Display;
+use core::str::FromStr;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A fully collapsed `width` x `height` grid of tiles.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Plot {
+    width: usize,
+    height: usize,
+    tileset: Arc<TileSet>,
+    tiles: Vec<TileId>,
+    /// The height of every field, if it got generated with one.
+    #[cfg_attr(feature = "serde", serde(default))]
+    elevation: Option<Vec<f64>>,
+    /// The paths of the roads placed on the plot, see [`RoadNetwork`](crate::RoadNetwork).
+    #[cfg_attr(feature = "serde", serde(default))]
+    roads: Vec<Vec<(usize, usize)>>,
+    /// The names of the features of the plot, see [`NameGenerator`](crate::NameGenerator).
+    #[cfg_attr(feature = "serde", serde(default))]
+    legend: Vec<Label>,
+}
+
+impl Plot {
+    /// Creates a `width` x `height` plot of the built-in [`TileSet::terrain`], filled with the
+    /// default tile.
+    pub fn new(width: usize, height: usize) -> Self {
+        let tiles = vec![Tile::default().into(); width * height];
+        Plot::from_tiles(Arc::new(TileSet::terrain()), width, height, tiles)
+    }
+
+    /// Wraps row-major `tiles` of `tileset` into a plot.
+    pub(crate) fn from_tiles(
+        tileset: Arc<TileSet>,
+        width: usize,
+        height: usize,
+        tiles: Vec<TileId>,
+    ) -> Self {
+        assert_eq!(tiles.len(), width * height);
+        Plot {
+            width,
+            height,
+            tileset,
+            tiles,
+            elevation: None,
+            roads: vec![],
+            legend: vec![],
+        }
+    }
+
+    /// Attaches row-major heights to the plot, see [`Plot::elevation`].
+    pub(crate) fn with_elevation(mut self, elevation: Vec<f64>) -> Self {
+        assert_eq!(elevation.len(), self.tiles.len());
+        self.elevation = Some(elevation);
+        self
+    }
+
+    /// Parses a plot from the text [`Display`] prints, one row per line, with the glyphs of
+    /// `tileset`. Plots of the [`TileSet::terrain`] can be parsed with [`str::parse`] as well.
+    ///
+    /// Fails if the text contains something that isn't a glyph of the tile set, or if the rows
+    /// have different lengths.
+    pub fn from_glyphs(
+        tileset: impl Into<Arc<TileSet>>,
+        text: &str,
+    ) -> Result<Self, ParsePlotError> {
+        Plot::parse_glyphs(tileset.into(), text)
+    }
+
+    /// Parses a plot drawn with one character per field, one row per line, e.g. `~` for rivers
+    /// and `.` for wasteland in hand-written test fixtures. `legend` gives the tile of
+    /// `tileset` every character stands for.
+    pub fn from_ascii<T: Copy + Into<TileId>>(
+        tileset: impl Into<Arc<TileSet>>,
+        text: &str,
+        legend: &HashMap<char, T>,
+    ) -> Result<Self, ParsePlotError> {
+        Plot::parse(tileset.into(), text, |rest| {
+            let glyph = rest.chars().next()?;
+            let tile = legend.get(&glyph)?;
+            Some(((*tile).into(), glyph.len_utf8()))
+        })
+    }
+
+    fn parse_glyphs(tileset: Arc<TileSet>, text: &str) -> Result<Self, ParsePlotError> {
+        let glyphs = Arc::clone(&tileset);
+        Plot::parse(tileset, text, |rest| {
+            let tile = glyphs
+                .ids()
+                .find(|tile| rest.starts_with(glyphs.glyph(*tile)))?;
+            Some((tile, glyphs.glyph(tile).len()))
+        })
+    }
+
+    /// Parses the rows of `text`, skipping empty lines. `glyph` splits the tile off the start of
+    /// a row, along with the number of bytes it takes.
+    fn parse(
+        tileset: Arc<TileSet>,
+        text: &str,
+        glyph: impl Fn(&str) -> Option<(TileId, usize)>,
+    ) -> Result<Self, ParsePlotError> {
+        let mut rows: Vec<Vec<TileId>> = vec![];
+
+        for (i, line) in text.lines().enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut row = vec![];
+            let mut rest = line;
+            while !rest.is_empty() {
+                let Some((tile, len)) = glyph(rest) else {
+                    let column = line[..line.len() - rest.len()].chars().count() + 1;
+                    return Err(ParsePlotError::UnknownGlyph {
+                        line: i + 1,
+                        column,
+                    });
+                };
+                rest = &rest[len..];
+                row.push(tile);
+            }
+
+            if rows.first().is_some_and(|first| first.len() != row.len()) {
+                return Err(ParsePlotError::RaggedRow(i + 1));
+            }
+            rows.push(row);
+        }
+
+        let (width, height) = (rows.first().ok_or(ParsePlotError::Empty)?.len(), rows.len());
+        Ok(Plot::from_tiles(tileset, width, height, rows.concat()))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn tileset(&self) -> &TileSet {
+        &self.tileset
+    }
+
+    /// The tile at `(x, y)`.
+    pub fn get(&self, x: usize, y: usize) -> TileId {
+        self.tiles[y * self.width + x]
+    }
+
+    /// The tile at `(x, y)` together with its definition and attributes, so they don't have to be
+    /// looked up in the tile set.
+    ///
+    /// ```
+    /// use world_gen::{MetaValue, PlotGenerator, TileDef, TileSet};
+    ///
+    /// let tileset = TileSet::new(vec![
+    ///     TileDef::new("Ore", "**")
+    ///         .with_neighbours(&["Ore"])
+    ///         .with_metadata("material", MetaValue::Int(7)),
+    /// ])
+    /// .unwrap();
+    /// let plot = PlotGenerator::for_tileset(tileset, 2, 2).generate().unwrap();
+    /// assert_eq!(plot.tile(1, 0).meta("material"), Some(&MetaValue::Int(7)));
+    /// ```
+    pub fn tile(&self, x: usize, y: usize) -> PlacedTile<'_> {
+        let id = self.get(x, y);
+        PlacedTile {
+            id,
+            def: self.tileset.def(id),
+        }
+    }
+
+    /// The attributes of the tile at `(x, y)`, see [`TileDef::metadata`](crate::TileDef::metadata).
+    pub fn metadata(&self, x: usize, y: usize) -> &HashMap<String, MetaValue> {
+        self.tileset.metadata(self.get(x, y))
+    }
+
+    /// The paths of the roads placed on the plot, field by field from start to end.
+    pub fn roads(&self) -> &[Vec<(usize, usize)>] {
+        &self.roads
+    }
+
+    /// The tile set, shared with the plot.
+    pub(crate) fn shared_tileset(&self) -> Arc<TileSet> {
+        Arc::clone(&self.tileset)
+    }
+
+    pub(crate) fn add_road(&mut self, path: Vec<(usize, usize)>) {
+        self.roads.push(path);
+    }
+
+    /// The named features of the plot, in the order they got named, e.g. for labels on a map.
+    pub fn legend(&self) -> &[Label] {
+        &self.legend
+    }
+
+    /// Adds a feature to the legend, next to the ones named by a
+    /// [`NameGenerator`](crate::NameGenerator).
+    pub fn add_label(&mut self, label: Label) {
+        self.legend.push(label);
+    }
+
+    /// The height of the field at `(x, y)`, if the plot got generated with an
+    /// [`Elevation`](crate::Elevation).
+    pub fn elevation(&self, x: usize, y: usize) -> Option<f64> {
+        let elevation = self.elevation.as_ref()?;
+        Some(elevation[y * self.width + x])
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, tile: impl Into<TileId>) {
+        self.tiles[y * self.width + x] = tile.into();
+    }
+
+    /// Renders every tile as a `scale` x `scale` square in the color of its tile set. Tiles that
+    /// are [`TileSet::transparent`] get fully transparent pixels, so the images of layers can be
+    /// put on top of each other.
+    #[cfg(feature = "image")]
+    pub fn to_image(&self, scale: u32) -> image::RgbaImage {
+        let (width, height) = (self.width as u32 * scale, self.height as u32 * scale);
+        image::RgbaImage::from_fn(width, height, |x, y| {
+            let tile = self.get((x / scale) as usize, (y / scale) as usize);
+            let [r, g, b] = self.tileset.color(tile);
+            let alpha = match self.tileset.transparent(tile) {
+                true => 0,
+                false => u8::MAX,
+            };
+            image::Rgba([r, g, b, alpha])
+        })
+    }
+
+    /// Displays the plot like [`Display`], but with every field in the color of its tile as the
+    /// terminal background, using 24-bit ANSI escape codes.
+    ///
+    /// Whether the terminal understands them is up to the caller, see `--color` of the
+    /// `world-gen` binary.
+    pub fn display_colored(&self) -> ColoredPlot<'_> {
+        ColoredPlot(self)
+    }
+
+    /// Displays the plot with the glyphs and colors of `theme`, using 24-bit ANSI escape codes for
+    /// the colors.
+    pub fn display_themed<'a>(&'a self, theme: &'a Theme) -> ThemedPlot<'a> {
+        ThemedPlot(self, theme)
+    }
+
+    /// Writes the plot in the text format of `renderer`, see [`Renderer`].
+    pub fn render(&self, renderer: &(impl Renderer + ?Sized)) -> String {
+        let mut text = String::new();
+        renderer
+            .render(self, &mut text)
+            .expect("Writing to a string doesn't fail");
+        text
+    }
+
+    /// Renders the plot as an SVG document with one shape per tile, drawn according to `style`.
+    /// Unlike [`Plot::to_image`] it stays sharp at any size.
+    pub fn to_svg(&self, style: &SvgStyle) -> String {
+        let size = style.cell_size();
+        let (width, height) = (self.width as f64 * size, self.height as f64 * size);
+        let stroke = match style.stroke() {
+            Some((color, width)) => format!(
+                " stroke=\"{}\" stroke-width=\"{width}\" vector-effect=\"non-scaling-stroke\"",
+                escape_xml(color)
+            ),
+            None => String::new(),
+        };
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">\n"
+        );
+        for (x, y) in self.positions() {
+            let tile = self.get(x, y);
+            let fill = match style.fill(tile) {
+                Some(fill) => escape_xml(fill),
+                None => {
+                    let [r, g, b] = self.tileset.color(tile);
+                    format!("rgb({r},{g},{b})")
+                }
+            };
+            let (left, top) = (x as f64 * size, y as f64 * size);
+
+            svg += &match style.path(tile) {
+                Some(path) => format!(
+                    "  <path d=\"{}\" transform=\"translate({left} {top}) scale({size})\" fill=\"{fill}\"{stroke}/>\n",
+                    escape_xml(path)
+                ),
+                None => format!(
+                    "  <rect x=\"{left}\" y=\"{top}\" width=\"{size}\" height=\"{size}\" fill=\"{fill}\"{stroke}/>\n"
+                ),
+            };
+        }
+
+        svg + "</svg>\n"
+    }
+
+    /// Displays the plot as a [`Topology::Hex`](crate::Topology::Hex) grid: every row is indented
+    /// by half a glyph more than the one above, so every field sits between its neighbours.
+    pub fn display_hex(&self) -> HexPlot<'_> {
+        HexPlot(self)
+    }
+
+    /// Renders the plot as a [`Topology::Hex`](crate::Topology::Hex) grid of pointy-top hexagons
+    /// in the colors of its tile set, as an SVG document. `size` is the distance from the center
+    /// of a hexagon to its corners.
+    pub fn to_hex_svg(&self, size: f64) -> String {
+        let (w, h) = (3f64.sqrt() * size, 1.5 * size);
+        let center =
+            |x: usize, y: usize| (w * (x as f64 + y as f64 / 2.0 + 0.5), h * y as f64 + size);
+
+        let (width, height) = (
+            w * (self.width as f64 + self.height.saturating_sub(1) as f64 / 2.0),
+            h * self.height.saturating_sub(1) as f64 + 2.0 * size,
+        );
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width:.1}\" height=\"{height:.1}\">\n"
+        );
+
+        for (x, y) in self.positions() {
+            let (cx, cy) = center(x, y);
+            let points = (0..6)
+                .map(|corner| {
+                    let angle = (60.0 * corner as f64 - 30.0).to_radians();
+                    format!(
+                        "{:.1},{:.1}",
+                        cx + size * angle.cos(),
+                        cy + size * angle.sin()
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            let [r, g, b] = self.tileset.color(self.get(x, y));
+            svg += &format!("  <polygon points=\"{points}\" fill=\"rgb({r},{g},{b})\"/>\n");
+        }
+
+        svg + "</svg>\n"
+    }
+
+    /// Every position of the plot, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    /// How many fields hold `tile`.
+    pub fn count(&self, tile: impl Into<TileId>) -> usize {
+        let tile = tile.into();
+        self.tiles.iter().filter(|t| **t == tile).count()
+    }
+
+    /// The fields holding another tile in `other`, row by row, as `(x, y, tile here, tile in
+    /// other)`, e.g. to see what a change to the rules or the seed did. Tiles are compared by
+    /// their [`TileId`], so both plots should share a tile set.
+    pub fn diff(&self, other: &Plot) -> Vec<(usize, usize, TileId, TileId)> {
+        assert!(
+            self.width == other.width && self.height == other.height,
+            "Only plots of the same size can be compared"
+        );
+
+        self.positions()
+            .map(|(x, y)| (x, y, self.get(x, y), other.get(x, y)))
+            .filter(|(_, _, a, b)| a != b)
+            .collect()
+    }
+
+    /// A plot `factor` times smaller, e.g. for a minimap, or as the macro plot of a
+    /// [`HierarchicalGenerator`](crate::HierarchicalGenerator). Every field holds the most
+    /// common tile of its `factor` x `factor` block, the lower id on a tie. Blocks on the right
+    /// and bottom edge may be cut off. Heights are averaged per block, roads and the legend are
+    /// dropped.
+    pub fn downsample(&self, factor: usize) -> Plot {
+        self.downsample_by(factor, |_| None)
+    }
+
+    /// Like [`Plot::downsample`], but a block holding any of `priority` becomes the first of them
+    /// it holds, however rare, e.g. so thin rivers stay on the minimap. Blocks without any of
+    /// them become their most common tile.
+    pub fn downsample_with_priority<T: Copy + Into<TileId>>(
+        &self,
+        factor: usize,
+        priority: &[T],
+    ) -> Plot {
+        let priority = priority.iter().map(|t| (*t).into()).collect::<Vec<_>>();
+        self.downsample_by(factor, |counts| {
+            priority
+                .iter()
+                .copied()
+                .find(|tile| counts[tile.0 as usize] > 0)
+        })
+    }
+
+    /// Downsamples with the tile `pick` chooses from the tile counts of a block, or the most
+    /// common one if it chooses none.
+    fn downsample_by(&self, factor: usize, pick: impl Fn(&[usize]) -> Option<TileId>) -> Plot {
+        assert!(factor > 0, "The factor has to be at least 1");
+        let width = self.width.div_ceil(factor);
+        let height = self.height.div_ceil(factor);
+        let block = |bx: usize, by: usize| {
+            let xs = bx * factor..((bx + 1) * factor).min(self.width);
+            let ys = by * factor..((by + 1) * factor).min(self.height);
+            ys.flat_map(move |y| xs.clone().map(move |x| y * self.width + x))
+        };
+
+        let mut tiles = Vec::with_capacity(width * height);
+        let mut elevation = Vec::with_capacity(width * height);
+        for by in 0..height {
+            for bx in 0..width {
+                let mut counts = vec![0; self.tileset.len()];
+                for index in block(bx, by) {
+                    counts[self.tiles[index].0 as usize] += 1;
+                }
+                let majority = || {
+                    // `max_by_key` keeps the last maximum, so go from the highest id down
+                    let most = counts.iter().enumerate().rev().max_by_key(|(_, n)| **n);
+                    TileId(most.map_or(0, |(id, _)| id) as u16)
+                };
+                tiles.push(pick(&counts).unwrap_or_else(majority));
+
+                if let Some(heights) = &self.elevation {
+                    let (sum, n) =
+                        block(bx, by).fold((0.0, 0), |(sum, n), i| (sum + heights[i], n + 1));
+                    elevation.push(sum / n as f64);
+                }
+            }
+        }
+
+        let plot = Plot::from_tiles(self.tileset.clone(), width, height, tiles);
+        match self.elevation {
+            Some(_) => plot.with_elevation(elevation),
+            None => plot,
+        }
+    }
+
+    /// The groups of connected `tile`s, connected over `connectivity` neighbours, like the
+    /// separate lakes of a map. Regions are ordered by their first field, row by row.
+    pub fn regions(&self, tile: impl Into<TileId>, connectivity: Connectivity) -> Vec<Region> {
+        let tile = tile.into();
+        let mut visited = Grid::new(self.width, self.height, false);
+        let mut regions = vec![];
+
+        for (x, y) in self.positions() {
+            if visited[(x, y)] || self.get(x, y) != tile {
+                continue;
+            }
+
+            // Flood fill the region
+            let mut fields = vec![(x, y)];
+            let mut queue = VecDeque::from([(x, y)]);
+            visited[(x, y)] = true;
+            while let Some(pos) = queue.pop_front() {
+                for (nx, ny) in visited.neighbours(pos, connectivity) {
+                    if !visited[(nx, ny)] && self.get(nx, ny) == tile {
+                        visited[(nx, ny)] = true;
+                        fields.push((nx, ny));
+                        queue.push_back((nx, ny));
+                    }
+                }
+            }
+
+            regions.push(Region { tile, fields });
+        }
+
+        regions
+    }
+
+    /// The `tile` closest to `(x, y)`, walking over `connectivity` neighbours, or `None` if the
+    /// plot has no `tile`. Ties go to the field found first.
+    pub fn find_nearest(
+        &self,
+        x: usize,
+        y: usize,
+        tile: impl Into<TileId>,
+        connectivity: Connectivity,
+    ) -> Option<(usize, usize)> {
+        let tile = tile.into();
+        let mut visited = Grid::new(self.width, self.height, false);
+        let mut queue = VecDeque::from([(x, y)]);
+        visited[(x, y)] = true;
+
+        while let Some(pos) = queue.pop_front() {
+            if self.get(pos.0, pos.1) == tile {
+                return Some(pos);
+            }
+            for neighbour in visited.neighbours(pos, connectivity) {
+                if !visited[neighbour] {
+                    visited[neighbour] = true;
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// The fields with a `connectivity` neighbour holding another tile, like the shores of a
+    /// lake, row by row.
+    pub fn border_tiles(&self, connectivity: Connectivity) -> Vec<(usize, usize)> {
+        self.positions()
+            .filter(|&(x, y)| {
+                connectivity
+                    .neighbours((x, y), self.width, self.height)
+                    .any(|(nx, ny)| self.get(nx, ny) != self.get(x, y))
+            })
+            .collect()
+    }
+
+    /// Distance from every field to the nearest `tile`, walking over `connectivity` neighbours.
+    /// The result holds the rows of the plot, so it is indexed as `[y][x]`.
+    ///
+    /// Fields holding `tile` have distance 0. If the plot contains no `tile` at all, every field
+    /// has distance `u32::MAX`.
+    pub fn distance_field(
+        &self,
+        tile: impl Into<TileId>,
+        connectivity: Connectivity,
+    ) -> Vec<Vec<u32>> {
+        let tile = tile.into();
+        let mut distances = vec![vec![u32::MAX; self.width]; self.height];
+        let mut queue = VecDeque::new();
+
+        for (x, y) in self.positions() {
+            if self.get(x, y) == tile {
+                distances[y][x] = 0;
+                queue.push_back((x, y));
+            }
+        }
+
+        // Multi-source BFS, every field is visited once from its closest source
+        while let Some((x, y)) = queue.pop_front() {
+            for (nx, ny) in connectivity.neighbours((x, y), self.width, self.height) {
+                if distances[ny][nx] == u32::MAX {
+                    distances[ny][nx] = distances[y][x] + 1;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+
+        distances
+    }
+
+    /// Replaces every group of connected `tile`s that doesn't reach from the left to the right
+    /// edge, or from the top to the bottom edge, by `replacement`. The `tile`s left over form
+    /// channels across the plot, see
+    /// [`PlotGenerator::carve_channel`](crate::PlotGenerator::carve_channel).
+    ///
+    /// `replacement` has to be allowed next to everything `tile` lies next to, like the
+    /// Wasteland of the built-in terrain. Returns how many fields got replaced.
+    pub fn retain_channels(
+        &mut self,
+        tile: impl Into<TileId>,
+        replacement: impl Into<TileId>,
+        connectivity: Connectivity,
+    ) -> usize {
+        let (tile, replacement) = (tile.into(), replacement.into());
+        let mut replaced = 0;
+
+        for region in self.regions(tile, connectivity) {
+            let (right, bottom) = (self.width - 1, self.height - 1);
+            let ((left, top), (max_x, max_y)) = region.bounds();
+            let crosses = (left == 0 && max_x == right) || (top == 0 && max_y == bottom);
+
+            if !crosses {
+                for &(x, y) in region.fields() {
+                    self.set(x, y, replacement);
+                }
+                replaced += region.len();
+            }
+        }
+
+        replaced
+    }
+
+    /// Connects every group of `walkable` fields to the others, by carving the shortest way
+    /// through the fields in between out of `passage` tiles, like corridors through the walls
+    /// of a dungeon. Walking goes in the "+" shape. The passages get written over the plot
+    /// without looking at the adjacency rules.
+    ///
+    /// Returns the carved passages, the fields that weren't walkable before.
+    pub fn connect_regions<T: Copy + Into<TileId>>(
+        &mut self,
+        walkable: &[T],
+        passage: impl Into<TileId>,
+    ) -> Vec<Vec<(usize, usize)>> {
+        let mut walkable = walkable.iter().map(|t| (*t).into()).collect::<Vec<_>>();
+        let passage = passage.into();
+        walkable.push(passage);
+        let is_walkable = |plot: &Plot, (x, y): (usize, usize)| walkable.contains(&plot.get(x, y));
+        let mut passages = vec![];
+
+        let Some(start) = self.positions().find(|&pos| is_walkable(self, pos)) else {
+            return passages;
+        };
+        loop {
+            // Everything reachable from the start so far
+            let mut connected = Grid::new(self.width, self.height, false);
+            let mut queue = VecDeque::from([start]);
+            connected[start] = true;
+            while let Some(pos) = queue.pop_front() {
+                for neighbour in connected.neighbours(pos, Connectivity::Four) {
+                    if !connected[neighbour] && is_walkable(self, neighbour) {
+                        connected[neighbour] = true;
+                        queue.push_back(neighbour);
+                    }
+                }
+            }
+
+            // The closest walkable field that isn't connected yet, over any field
+            let mut previous = Grid::new(self.width, self.height, None);
+            let mut visited = connected.clone();
+            let mut queue = connected
+                .iter()
+                .filter(|(_, &connected)| connected)
+                .map(|(pos, _)| pos)
+                .collect::<VecDeque<_>>();
+            let mut found = None;
+            'search: while let Some(pos) = queue.pop_front() {
+                for neighbour in visited.neighbours(pos, Connectivity::Four) {
+                    if visited[neighbour] {
+                        continue;
+                    }
+                    visited[neighbour] = true;
+                    previous[neighbour] = Some(pos);
+                    if is_walkable(self, neighbour) {
+                        found = Some(pos);
+                        break 'search;
+                    }
+                    queue.push_back(neighbour);
+                }
+            }
+            let Some(mut current) = found else {
+                return passages;
+            };
+
+            let mut path = vec![];
+            while !connected[current] {
+                path.push(current);
+                current = previous[current].expect("Found from the start");
+            }
+            path.reverse();
+            for &(x, y) in &path {
+                self.set(x, y, passage);
+            }
+            passages.push(path);
+        }
+    }
+
+    /// Merges every group of connected `tile`s smaller than `min_size` into the tiles around it,
+    /// so single fields don't speckle the plot, like lone farmland in the wasteland. Call it once
+    /// per tile with the size that tile needs.
+    ///
+    /// A speck becomes the tile most of its neighbours hold, among the ones the rules of the tile
+    /// set allow in every field of the speck. Specks no surrounding tile fits into stay. Returns
+    /// how many fields got replaced.
+    pub fn remove_specks(
+        &mut self,
+        tile: impl Into<TileId>,
+        min_size: usize,
+        connectivity: Connectivity,
+    ) -> usize {
+        let tile = tile.into();
+        let directions = Direction::ALL
+            .into_iter()
+            .filter(|&direction| {
+                connectivity == Connectivity::Eight || (direction as usize).is_multiple_of(2)
+            })
+            .collect::<Vec<_>>();
+        let mut replaced = 0;
+
+        for region in self.regions(tile, connectivity) {
+            if region.len() >= min_size {
+                continue;
+            }
+
+            let mut around: HashMap<TileId, usize> = HashMap::new();
+            for &pos in region.fields() {
+                for (nx, ny) in connectivity.neighbours(pos, self.width, self.height) {
+                    if !region.contains(nx, ny) {
+                        *around.entry(self.get(nx, ny)).or_insert(0) += 1;
+                    }
+                }
+            }
+
+            let fits = |candidate: TileId| {
+                region.fields().iter().all(|&(x, y)| {
+                    directions.iter().all(|&direction| {
+                        let (dx, dy) = direction.offset();
+                        let (nx, ny) = (x as isize + dx, y as isize + dy);
+                        if !(0..self.width as isize).contains(&nx)
+                            || !(0..self.height as isize).contains(&ny)
+                        {
+                            return true;
+                        }
+                        let (nx, ny) = (nx as usize, ny as usize);
+                        let neighbour = match region.contains(nx, ny) {
+                            true => candidate,
+                            false => self.get(nx, ny),
+                        };
+                        self.tileset
+                            .neighbour_mask(candidate, direction)
+                            .contains(neighbour)
+                    })
+                })
+            };
+            // Ties go to the lower tile id, so the result doesn't depend on the hash order
+            let best = around
+                .into_iter()
+                .filter(|&(candidate, _)| fits(candidate))
+                .max_by_key(|&(candidate, count)| (count, Reverse(candidate)));
+
+            if let Some((replacement, _)) = best {
+                for &(x, y) in region.fields() {
+                    self.set(x, y, replacement);
+                }
+                replaced += region.len();
+            }
+        }
+
+        replaced
+    }
+
+    /// Smoothes the coast between `water` and the other tiles like a cellular automaton, up to
+    /// `passes` times: a land field with water on at least three sides of the "+" shape, the tip
+    /// of a one field peninsula, becomes water, and a water field with land on at least three
+    /// sides, a one field bay, becomes land.
+    ///
+    /// A field becomes the tile most of those sides hold, the lower id on a tie, among the ones
+    /// the rules of the tile set allow next to all eight neighbours, or stays if there is none.
+    /// Stops early once a pass changes nothing. Returns how many fields got replaced.
+    pub fn smooth_coastline<T: Copy + Into<TileId>>(
+        &mut self,
+        water: &[T],
+        passes: usize,
+    ) -> usize {
+        let water = water.iter().map(|t| (*t).into()).collect::<Vec<_>>();
+        let mut replaced = 0;
+
+        for _ in 0..passes {
+            let mut flips = vec![];
+            for (x, y) in self.positions() {
+                let is_water = water.contains(&self.get(x, y));
+                let mut sides: HashMap<TileId, usize> = HashMap::new();
+                for (nx, ny) in Connectivity::Four.neighbours((x, y), self.width, self.height) {
+                    let tile = self.get(nx, ny);
+                    if water.contains(&tile) != is_water {
+                        *sides.entry(tile).or_insert(0) += 1;
+                    }
+                }
+                if sides.values().sum::<usize>() >= 3 {
+                    let mut candidates = sides.into_iter().collect::<Vec<_>>();
+                    candidates.sort_by_key(|&(tile, count)| (Reverse(count), tile));
+                    flips.push(((x, y), candidates));
+                }
+            }
+
+            // Earlier flips change the neighbours of later ones, so the rules are checked as it
+            // goes
+            let mut changed = 0;
+            for ((x, y), candidates) in flips {
+                let fitting = candidates
+                    .into_iter()
+                    .find(|&(tile, _)| self.conflicts(x, y, tile, Connectivity::Eight).is_empty());
+                if let Some((tile, _)) = fitting {
+                    self.set(x, y, tile);
+                    changed += 1;
+                }
+            }
+
+            replaced += changed;
+            if changed == 0 {
+                break;
+            }
+        }
+
+        replaced
+    }
+
+    /// Sets the field at `(x, y)` to `tile`, if it fits the rules of the tile set with its
+    /// `connectivity` neighbours, e.g. for a level editor. Returns the fields that changed, row
+    /// by row.
+    ///
+    /// With [`EditMode::Strict`] an edit the neighbours don't allow fails with
+    /// [`EditError::Conflicts`]. With [`EditMode::Repair`] the fields around get collapsed again
+    /// with the edited field pinned to `tile`, so they fit it, like
+    /// [`PlotGenerator::regenerate_region`]. Nothing changes if the edit fails.
+    pub fn edit(
+        &mut self,
+        x: usize,
+        y: usize,
+        tile: impl Into<TileId>,
+        connectivity: Connectivity,
+        mode: EditMode,
+    ) -> Result<Vec<(usize, usize)>, EditError> {
+        let tile = tile.into();
+        let conflicts = self.conflicts(x, y, tile, connectivity);
+        let changed = match (conflicts.is_empty(), mode) {
+            (true, _) if self.get(x, y) == tile => vec![],
+            (true, _) => vec![(x, y, tile)],
+            (false, EditMode::Strict) => return Err(EditError::Conflicts(conflicts)),
+            (false, EditMode::Repair { radius }) => {
+                self.repair(x, y, tile, connectivity, radius)?
+            }
+        };
+
+        for &(cx, cy, new) in &changed {
+            self.set(cx, cy, new);
+        }
+        Ok(changed.into_iter().map(|(x, y, _)| (x, y)).collect())
+    }
+
+    /// The `connectivity` neighbours of the field at `(x, y)` that `tile` breaks a rule with.
+    pub(crate) fn conflicts(
+        &self,
+        x: usize,
+        y: usize,
+        tile: TileId,
+        connectivity: Connectivity,
+    ) -> Vec<(usize, usize)> {
+        Direction::ALL
+            .into_iter()
+            .filter(|&direction| {
+                connectivity == Connectivity::Eight || (direction as usize).is_multiple_of(2)
+            })
+            .filter_map(|direction| {
+                let (dx, dy) = direction.offset();
+                let (nx, ny) = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+                if nx >= self.width || ny >= self.height {
+                    return None;
+                }
+                let allowed = self.tileset.neighbour_mask(tile, direction);
+                (!allowed.contains(self.get(nx, ny))).then_some((nx, ny))
+            })
+            .collect()
+    }
+
+    /// Collapses the fields within `radius` steps of `(x, y)` again, with `(x, y)` pinned to
+    /// `tile` and the fields around them kept. Returns the new tiles of the fields that changed.
+    fn repair(
+        &self,
+        x: usize,
+        y: usize,
+        tile: TileId,
+        connectivity: Connectivity,
+        radius: usize,
+    ) -> Result<Vec<(usize, usize, TileId)>, EditError> {
+        let xs = x.saturating_sub(radius)..(x + radius + 1).min(self.width);
+        let ys = y.saturating_sub(radius)..(y + radius + 1).min(self.height);
+        let free = ys
+            .flat_map(|fy| xs.clone().map(move |fx| (fx, fy)))
+            .collect::<Vec<_>>();
+        self.resolve(&free, &[(x, y, tile)], connectivity)
+    }
+
+    /// Collapses the `free` fields again, with the `pinned` ones set to their tile and the fields
+    /// around them kept, fitting their `connectivity` neighbours. Returns the new tiles of the
+    /// fields that changed.
+    pub(crate) fn resolve(
+        &self,
+        free: &[(usize, usize)],
+        pinned: &[(usize, usize, TileId)],
+        connectivity: Connectivity,
+    ) -> Result<Vec<(usize, usize, TileId)>, EditError> {
+        let fields = free
+            .iter()
+            .copied()
+            .chain(pinned.iter().map(|&(x, y, _)| (x, y)));
+        let Some(((left, top), (right, bottom))) = fields.fold(None, |bounds, (x, y)| {
+            let ((left, top), (right, bottom)) = bounds.unwrap_or(((x, y), (x, y)));
+            Some(((left.min(x), top.min(y)), (right.max(x), bottom.max(y))))
+        }) else {
+            return Ok(vec![]);
+        };
+
+        // The fields to collapse, and one more ring of fields they have to fit
+        let xs = left.saturating_sub(1)..(right + 2).min(self.width);
+        let ys = top.saturating_sub(1)..(bottom + 2).min(self.height);
+        let (left, top) = (xs.start, ys.start);
+        let mut is_free = Grid::new(xs.len(), ys.len(), false);
+        for &(x, y) in free {
+            is_free[(x - left, y - top)] = true;
+        }
+
+        let neighbourhood = match connectivity {
+            Connectivity::Four => Neighbourhood::VonNeumann,
+            Connectivity::Eight => Neighbourhood::Moore,
+        };
+        let mut gen = PlotGenerator::for_tileset(Arc::clone(&self.tileset), xs.len(), ys.len())
+            .with_neighbourhood(neighbourhood);
+        let unrepairable = |_| EditError::Unrepairable;
+        for &(x, y, tile) in pinned {
+            gen.set_tile(x - left, y - top, tile)
+                .map_err(unrepairable)?;
+        }
+        let window = ys
+            .flat_map(|fy| xs.clone().map(move |fx| (fx, fy)))
+            .collect::<Vec<_>>();
+        for &(fx, fy) in &window {
+            let pin = pinned.iter().any(|&(x, y, _)| (x, y) == (fx, fy));
+            if !is_free[(fx - left, fy - top)] && !pin {
+                gen.set_tile(fx - left, fy - top, self.get(fx, fy))
+                    .map_err(unrepairable)?;
+            }
+        }
+        gen.collapse().map_err(unrepairable)?;
+
+        let repaired = gen.into_plot();
+        Ok(window
+            .into_iter()
+            .map(|(fx, fy)| (fx, fy, repaired.get(fx - left, fy - top)))
+            .filter(|&(fx, fy, repair)| self.get(fx, fy) != repair)
+            .collect())
+    }
+
+    /// Shannon entropy `-Σ p log p` (in nats) of the tile distribution.
+    ///
+    /// A plot made of a single tile has entropy 0, the more evenly the tiles are mixed, the higher
+    /// the entropy.
+    pub fn global_entropy(&self) -> f64 {
+        let mut counts: HashMap<TileId, usize> = HashMap::new();
+        for tile in &self.tiles {
+            *counts.entry(*tile).or_insert(0) += 1;
+        }
+
+        let total = self.tiles.len() as f64;
+        counts
+            .values()
+            .map(|&count| count as f64 / total)
+            .map(|p| -p * p.ln())
+            .sum()
+    }
+
+    /// Counts how often each pair of tiles lies next to each other.
+    ///
+    /// Pairs are ordered `(field, neighbour)`, so every adjacency is counted once from each side:
+    /// a River next to a Farmland adds one to both `(River, Farmland)` and `(Farmland, River)`.
+    pub fn adjacency_histogram(
+        &self,
+        connectivity: Connectivity,
+    ) -> HashMap<(TileId, TileId), usize> {
+        let mut histogram = HashMap::new();
+
+        for (x, y) in self.positions() {
+            for (nx, ny) in connectivity.neighbours((x, y), self.width, self.height) {
+                let pair = (self.get(x, y), self.get(nx, ny));
+                *histogram.entry(pair).or_insert(0) += 1;
+            }
+        }
+
+        histogram
+    }
+
+    /// How the tiles are distributed: how many fields and regions every tile has, how big the
+    /// regions are and how much of the edge every tile takes, e.g. to tune weights and rules.
+    /// Regions are connected in the "+" shape.
+    pub fn stats(&self) -> PlotStats {
+        PlotStats::new(self)
+    }
+}
+
+/// A group of connected fields holding the same tile, see [`Plot::regions`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    tile: TileId,
+    fields: Vec<(usize, usize)>,
+}
+
+impl Region {
+    pub fn tile(&self) -> TileId {
+        self.tile
+    }
+
+    /// The positions of the fields, starting with the first one row by row.
+    pub fn fields(&self) -> &[(usize, usize)] {
+        &self.fields
+    }
+
+    /// How many fields the region covers, never 0.
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        self.fields.contains(&(x, y))
+    }
+
+    /// The smallest rectangle around the region, as `((min_x, min_y), (max_x, max_y))`.
+    pub fn bounds(&self) -> ((usize, usize), (usize, usize)) {
+        let xs = self.fields.iter().map(|&(x, _)| x);
+        let ys = self.fields.iter().map(|&(_, y)| y);
+        (
+            (xs.clone().min().unwrap_or(0), ys.clone().min().unwrap_or(0)),
+            (xs.max().unwrap_or(0), ys.max().unwrap_or(0)),
+        )
+    }
+}
+
+/// Why a [`Plot`] couldn't be parsed from text, see [`Plot::from_glyphs`], [`Plot::from_ascii`]
+/// and the [`FromStr`] implementation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsePlotError {
+    /// The text contains no rows.
+    Empty,
+    /// The text at this line and column, both counted from 1, isn't a tile.
+    UnknownGlyph { line: usize, column: usize },
+    /// The row on this line, counted from 1, is longer or shorter than the first one.
+    RaggedRow(usize),
+}
+
+/// How [`Plot::edit`] deals with neighbours that don't allow the new tile, and
+/// [`Plot::paste`] and [`Plot::stitch`] with seams that break the rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditMode {
+    /// The edit fails.
+    Strict,
+    /// The fields up to `radius` steps away from the edited one, or from the fields breaking
+    /// the rules, in the "#" shape, get collapsed again, fitting the new tiles and the fields
+    /// around them.
+    Repair { radius: usize },
+}
+
+/// Why [`Plot::edit`], [`Plot::paste`] or [`Plot::stitch`] failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditError {
+    /// The neighbours at these positions don't allow the new tile, or break the rules across a
+    /// seam.
+    Conflicts(Vec<(usize, usize)>),
+    /// No tiles within the radius fit both the new tiles and the fields around them.
+    Unrepairable,
+}
+
+/// A tile of a [`Plot`] with its definition, see [`Plot::tile`].
+#[derive(Debug, Clone, Copy)]
+pub struct PlacedTile<'a> {
+    id: TileId,
+    def: &'a TileDef,
+}
+
+impl<'a> PlacedTile<'a> {
+    pub fn id(&self) -> TileId {
+        self.id
+    }
+
+    pub fn def(&self) -> &'a TileDef {
+        self.def
+    }
+
+    pub fn name(&self) -> &'a str {
+        &self.def.name
+    }
+
+    /// The attributes of the tile, see [`TileDef::metadata`].
+    pub fn metadata(&self) -> &'a HashMap<String, MetaValue> {
+        &self.def.metadata
+    }
+
+    /// The attribute `key` of the tile, if it has one.
+    pub fn meta(&self, key: &str) -> Option<&'a MetaValue> {
+        self.def.metadata.get(key)
+    }
+}
+
+/// Displays a [`Plot`] with ANSI colors, see [`Plot::display_colored`].
+pub struct ColoredPlot<'a>(&'a Plot);
+
+/// Displays a [`Plot`] with a [`Theme`], see [`Plot::display_themed`].
+pub struct ThemedPlot<'a>(&'a Plot, &'a Theme);
+
+/// Displays a [`Plot`] as a hex grid, see [`Plot::display_hex`].
+pub struct HexPlot<'a>(&'a Plot);
+
+impl Default for Plot {
+    fn default() -> Self {
+        Plot::new(16, 16)
+    }
+}
+
+/// Parses the glyphs of the [`TileSet::terrain`], like a terrain plot gets displayed.
+impl FromStr for Plot {
+    type Err = ParsePlotError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Plot::parse_glyphs(Arc::new(TileSet::terrain()), s)
+    }
+}
+
+impl Display for ParsePlotError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ParsePlotError::Empty => write!(f, "The plot has no rows"),
+            ParsePlotError::UnknownGlyph { line, column } => {
+                write!(f, "Unknown tile at line {line}, column {column}")
+            }
+            ParsePlotError::RaggedRow(line) => {
+                write!(
+                    f,
+                    "The row at line {line} differs in length from the first one"
+                )
+            }
+        }
+    }
+}
+
+impl core::error::Error for ParsePlotError {}
+
+impl Display for EditError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            EditError::Conflicts(positions) => {
+                let positions = positions
+                    .iter()
+                    .map(|(x, y)| format!("({x}, {y})"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "The tiles don't fit the neighbours at {positions}")
+            }
+            EditError::Unrepairable => {
+                write!(
+                    f,
+                    "No tiles around the change fit both it and the rest of the plot"
+                )
+            }
+        }
+    }
+}
+
+impl core::error::Error for EditError {}
+
+impl Display for Plot {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        Blocks.render(self, f)
+    }
+}
+
+impl Display for ColoredPlot<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let plot = self.0;
+        for y in 0..plot.height {
+            for x in 0..plot.width {
+                let tile = plot.get(x, y);
+                let [r, g, b] = plot.tileset.color(tile);
+                write!(f, "\x1b[48;2;{r};{g};{b}m{}", plot.tileset.glyph(tile))?;
+            }
+            // Reset before the line break, so the color doesn't bleed into the rest of the line
+            writeln!(f, "\x1b[0m")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for ThemedPlot<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write_themed(self.0, self.1, f)
+    }
+}
+
+impl Display for HexPlot<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let plot = self.0;
+        let half = plot.tileset.glyph(TileId(0)).chars().count() / 2;
+        for y in 0..plot.height {
+            write!(f, "{:indent$}", "", indent = y * half)?;
+            for x in 0..plot.width {
+                write!(f, "{}", plot.tileset.glyph(plot.get(x, y)))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,260 @@
+use crate::prelude::*;
+#[cfg(feature = "serde")]
+use crate::TileSet;
+use crate::{ParsePlotError, PlotGenerator, TileId, WaveState};
+use core::fmt::Display;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A predefined structure, like the footprint of a castle, the shape of a lake or a clearing to
+/// spawn in, stamped into a generator as fixed tiles before it collapses. The rest of the plot
+/// collapses around it.
+///
+/// With the `serde` feature, prefabs can be loaded from RON or JSON files, drawn as rows of
+/// characters with a legend of tile names. Spaces are left to the generator:
+///
+/// ```ron
+/// (
+///     rows: [
+///         " ... ",
+///         ".....",
+///         " ... ",
+///     ],
+///     legend: {'.': "Wasteland"},
+///     // At least 2 fields away from the edge, at a random position
+///     margin: 2,
+/// )
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prefab {
+    width: usize,
+    height: usize,
+    /// Row-major, `None` for the fields left to the generator.
+    tiles: Vec<Option<TileId>>,
+    margin: usize,
+    position: Option<(usize, usize)>,
+    seed: u64,
+}
+
+/// The layout of a prefab file, see [`Prefab`].
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct PrefabFile {
+    rows: Vec<String>,
+    legend: HashMap<char, String>,
+    #[serde(default)]
+    margin: usize,
+    #[serde(default)]
+    position: Option<(usize, usize)>,
+}
+
+/// Errors that can happen while loading a [`Prefab`].
+#[derive(Debug)]
+pub enum PrefabError {
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    #[cfg(feature = "serde")]
+    Parse(ron::error::SpannedError),
+    #[cfg(feature = "serde")]
+    ParseJson(serde_json::Error),
+    /// The legend maps a character to this tile, which isn't part of the tile set.
+    UnknownTile(String),
+    /// The rows are no prefab, e.g. because they contain a character missing in the legend.
+    Rows(ParsePlotError),
+}
+
+impl Prefab {
+    /// Reads a prefab drawn as text, one character per field and one row per line, with the tile
+    /// of every character in `legend`. Spaces are left to the generator, shorter rows get filled
+    /// up with spaces.
+    pub fn from_ascii<T: Copy + Into<TileId>>(
+        text: &str,
+        legend: &HashMap<char, T>,
+    ) -> Result<Self, ParsePlotError> {
+        let mut rows = vec![];
+        for (i, line) in text.lines().enumerate() {
+            let row = line
+                .chars()
+                .enumerate()
+                .map(|(column, c)| match (c, legend.get(&c)) {
+                    (' ', _) => Ok(None),
+                    (_, Some(tile)) => Ok(Some((*tile).into())),
+                    (_, None) => Err(ParsePlotError::UnknownGlyph {
+                        line: i + 1,
+                        column: column + 1,
+                    }),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            rows.push(row);
+        }
+
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if width == 0 {
+            return Err(ParsePlotError::Empty);
+        }
+        Ok(Prefab {
+            width,
+            height: rows.len(),
+            tiles: rows
+                .into_iter()
+                .flat_map(|mut row| {
+                    row.resize(width, None);
+                    row
+                })
+                .collect(),
+            margin: 0,
+            position: None,
+            seed: random_seed(),
+        })
+    }
+
+    /// Parses a prefab of the tiles of `tileset` from the RON format shown in the [`Prefab`]
+    /// docs.
+    #[cfg(feature = "serde")]
+    pub fn from_ron(tileset: &TileSet, src: &str) -> Result<Self, PrefabError> {
+        Prefab::from_file(tileset, ron::from_str(src).map_err(PrefabError::Parse)?)
+    }
+
+    /// Parses a prefab like [`Prefab::from_ron`], from JSON with the same fields.
+    #[cfg(feature = "serde")]
+    pub fn from_json(tileset: &TileSet, src: &str) -> Result<Self, PrefabError> {
+        Prefab::from_file(
+            tileset,
+            serde_json::from_str(src).map_err(PrefabError::ParseJson)?,
+        )
+    }
+
+    /// Loads a prefab from a `.json` file, or else from a RON file.
+    #[cfg(feature = "serde")]
+    pub fn load(tileset: &TileSet, path: impl AsRef<std::path::Path>) -> Result<Self, PrefabError> {
+        let path = path.as_ref();
+        let src = std::fs::read_to_string(path).map_err(PrefabError::Io)?;
+        if path.extension().is_some_and(|ext| ext == "json") {
+            Prefab::from_json(tileset, &src)
+        } else {
+            Prefab::from_ron(tileset, &src)
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn from_file(tileset: &TileSet, file: PrefabFile) -> Result<Self, PrefabError> {
+        let legend = file
+            .legend
+            .into_iter()
+            .map(|(c, name)| match tileset.id(&name) {
+                Some(tile) => Ok((c, tile)),
+                None => Err(PrefabError::UnknownTile(name)),
+            })
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        let mut prefab =
+            Prefab::from_ascii(&file.rows.join("\n"), &legend).map_err(PrefabError::Rows)?;
+        prefab.margin = file.margin;
+        prefab.position = file.position;
+        Ok(prefab)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The tile the prefab sets `(x, y)` fields from its top left corner, `None` if it leaves
+    /// the field to the generator.
+    pub fn get(&self, x: usize, y: usize) -> Option<TileId> {
+        self.tiles[y * self.width + x]
+    }
+
+    /// Keeps the prefab at least `margin` fields away from the edge of the grid, when placed at
+    /// random.
+    pub fn with_margin(mut self, margin: usize) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// Always puts the top left corner of the prefab at `(x, y)`, instead of a random position.
+    pub fn with_position(mut self, x: usize, y: usize) -> Self {
+        self.position = Some((x, y));
+        self
+    }
+
+    /// Derives the random position from `seed`.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Stamps the prefab into `gen`, at its position or else at a random one within the margin
+    /// where all of its tiles fit. Returns the position of its top left corner, or `None`,
+    /// without changing anything, if it fits nowhere.
+    ///
+    /// A position fits if every field can still become the tile of the prefab, and propagating
+    /// them leaves every field a tile.
+    pub fn apply(&self, gen: &mut PlotGenerator) -> Option<(usize, usize)> {
+        let mut positions = match self.position {
+            Some(position) => vec![position],
+            None => {
+                let xs = self.margin..(gen.width() + 1).saturating_sub(self.width + self.margin);
+                let ys = self.margin..(gen.height() + 1).saturating_sub(self.height + self.margin);
+                ys.flat_map(|y| xs.clone().map(move |x| (x, y)))
+                    .collect::<Vec<_>>()
+            }
+        };
+        positions.shuffle(&mut ChaCha12Rng::seed_from_u64(self.seed));
+
+        let fields = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .filter_map(|(x, y)| Some((x, y, self.get(x, y)?)))
+            .collect::<Vec<_>>();
+        positions.into_iter().find(|&(px, py)| {
+            let fits = |gen: &PlotGenerator| {
+                fields.iter().all(|&(x, y, tile)| {
+                    px + x < gen.width()
+                        && py + y < gen.height()
+                        && gen.get(px + x, py + y).contains(tile)
+                })
+            };
+            if !fits(gen) {
+                return false;
+            }
+
+            let snapshot = gen.snapshot();
+            let stamped = fields
+                .iter()
+                .all(|&(x, y, tile)| gen.set_tile(px + x, py + y, tile).is_ok())
+                && gen.domains().all(|state| match state {
+                    WaveState::Collapsed(_) => true,
+                    WaveState::Superposition(states) => !states.is_empty(),
+                });
+            if !stamped {
+                gen.restore(&snapshot);
+            }
+            stamped
+        })
+    }
+}
+
+impl Display for PrefabError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            PrefabError::Io(err) => write!(f, "Can't read the prefab: {err}"),
+            #[cfg(feature = "serde")]
+            PrefabError::Parse(err) => write!(f, "Can't parse the prefab: {err}"),
+            #[cfg(feature = "serde")]
+            PrefabError::ParseJson(err) => write!(f, "Can't parse the prefab: {err}"),
+            PrefabError::UnknownTile(name) => {
+                write!(f, "The legend of the prefab lists the unknown tile {name}")
+            }
+            PrefabError::Rows(err) => write!(f, "Can't read the rows of the prefab: {err}"),
+        }
+    }
+}
+
+impl core::error::Error for PrefabError {}
@@ -0,0 +1,80 @@
+//! What the modules take from std, imported with `use crate::prelude::*`. Without the `std`
+//! feature the collections come from `alloc` and `hashbrown`, the float math from `libm`, and
+//! seeds that aren't given are fixed.
+
+pub(crate) use alloc::boxed::Box;
+pub(crate) use alloc::string::{String, ToString};
+pub(crate) use alloc::vec::Vec;
+pub(crate) use alloc::{format, vec};
+#[cfg(not(feature = "std"))]
+pub(crate) use hashbrown::{HashMap, HashSet};
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+#[cfg(feature = "std")]
+pub(crate) use std::collections::{HashMap, HashSet};
+
+/// The seed of builders that weren't given one: random with `std`, 0 without it, as there is no
+/// source of randomness then. See [`PlotGenerator::with_rng`](crate::PlotGenerator::with_rng)
+/// for seeding from one of the device.
+pub(crate) fn random_seed() -> u64 {
+    #[cfg(feature = "std")]
+    return rand::random();
+    #[cfg(not(feature = "std"))]
+    0
+}
+
+/// The random generator of generators that weren't given a seed, seeded like [`random_seed`].
+pub(crate) fn random_rng() -> ChaCha12Rng {
+    #[cfg(feature = "std")]
+    return ChaCha12Rng::from_entropy();
+    #[cfg(not(feature = "std"))]
+    ChaCha12Rng::seed_from_u64(random_seed())
+}
+
+/// The float functions of std, from `libm`.
+#[cfg(not(feature = "std"))]
+pub(crate) trait Float {
+    fn ceil(self) -> Self;
+    fn round(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn ln(self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn hypot(self, other: Self) -> Self;
+}
+
+#[cfg(not(feature = "std"))]
+impl Float for f64 {
+    fn ceil(self) -> Self {
+        libm::ceil(self)
+    }
+
+    fn round(self) -> Self {
+        libm::round(self)
+    }
+
+    fn sqrt(self) -> Self {
+        libm::sqrt(self)
+    }
+
+    fn ln(self) -> Self {
+        libm::log(self)
+    }
+
+    fn sin(self) -> Self {
+        libm::sin(self)
+    }
+
+    fn cos(self) -> Self {
+        libm::cos(self)
+    }
+
+    fn powi(self, n: i32) -> Self {
+        libm::pow(self, n as f64)
+    }
+
+    fn hypot(self, other: Self) -> Self {
+        libm::hypot(self, other)
+    }
+}
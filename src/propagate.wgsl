@@ -0,0 +1,100 @@
+// One sweep of propagation, see `src/gpu.rs`. Every invocation updates one field: it keeps the
+// tiles every active neighbour allows, reading the wave of the last sweep and writing the next.
+
+struct Params {
+    width: u32,
+    height: u32,
+    // 32 bit words per mask, the wave has one more per field for its flags
+    words: u32,
+    directions: u32,
+    wrap: u32,
+}
+
+// The field lies next to a field that changed, or is where propagation starts
+const ACTIVE: u32 = 1u;
+const COLLAPSED: u32 = 2u;
+
+@group(0) @binding(0) var<uniform> params: Params;
+// The step from a field to its neighbour, per direction
+@group(0) @binding(1) var<storage, read> offsets: array<vec2<i32>>;
+// The tiles allowed next to a tile, a mask per direction and tile
+@group(0) @binding(2) var<storage, read> rules: array<u32>;
+@group(0) @binding(3) var<storage, read> wave_in: array<u32>;
+@group(0) @binding(4) var<storage, read_write> wave_out: array<u32>;
+@group(0) @binding(5) var<storage, read_write> changed: atomic<u32>;
+
+// The field that has `(x, y)` as its neighbour in `direction`, -1 if there is none.
+fn source(x: i32, y: i32, direction: u32) -> i32 {
+    let width = i32(params.width);
+    let height = i32(params.height);
+    var sx = x - offsets[direction].x;
+    var sy = y - offsets[direction].y;
+    if params.wrap != 0u {
+        sx = ((sx % width) + width) % width;
+        sy = ((sy % height) + height) % height;
+    } else if sx < 0 || sx >= width || sy < 0 || sy >= height {
+        return -1;
+    }
+    return sy * width + sx;
+}
+
+@compute @workgroup_size(64)
+fn main(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    let field = id.y * groups.x * 64u + id.x;
+    if field >= params.width * params.height {
+        return;
+    }
+
+    let stride = params.words + 1u;
+    let base = field * stride;
+    let flags = wave_in[base + params.words];
+    if (flags & COLLAPSED) != 0u {
+        for (var w = 0u; w < stride; w++) {
+            wave_out[base + w] = wave_in[base + w];
+        }
+        return;
+    }
+
+    let x = i32(field % params.width);
+    let y = i32(field / params.width);
+    var shrank = false;
+    for (var w = 0u; w < params.words; w++) {
+        var word = wave_in[base + w];
+        for (var d = 0u; d < params.directions && word != 0u; d++) {
+            let other = source(x, y, d);
+            if other < 0 {
+                continue;
+            }
+            let other_base = u32(other) * stride;
+            if (wave_in[other_base + params.words] & ACTIVE) == 0u {
+                continue;
+            }
+
+            var allowed = 0u;
+            for (var v = 0u; v < params.words; v++) {
+                var bits = wave_in[other_base + v];
+                while bits != 0u {
+                    let tile = v * 32u + firstTrailingBit(bits);
+                    allowed |= rules[(d * params.words * 32u + tile) * params.words + w];
+                    bits &= bits - 1u;
+                }
+            }
+            word &= allowed;
+        }
+
+        if word != wave_in[base + w] {
+            shrank = true;
+        }
+        wave_out[base + w] = word;
+    }
+
+    if shrank {
+        atomicStore(&changed, 1u);
+        wave_out[base + params.words] = flags | ACTIVE;
+    } else {
+        wave_out[base + params.words] = flags;
+    }
+}
@@ -0,0 +1,109 @@
+//! Python bindings, built with `maturin build --features python,pyo3/extension-module`. The
+//! module needs NumPy to be installed.
+//!
+//! Plots are handed over as NumPy arrays of tile ids, `height` rows of `width` fields, with the
+//! names of the tiles in `Generator.tile_names`. Fields that aren't collapsed yet hold -1:
+//!
+//! ```python
+//! import world_gen
+//!
+//! tiles = world_gen.generate(64, 64, seed=42)
+//! water = tiles == world_gen.Generator(1, 1, 0).tile_names.index("River")
+//! ```
+use crate::{PlotGenerator, Step, TileSet, WaveState, WfcError};
+use numpy::ndarray::Array2;
+use numpy::{IntoPyArray, PyArray2};
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+
+/// Collapses a `width` x `height` plot and returns its tile ids. The tiles are those of the
+/// built-in world, or of the RON file at the path `tileset`.
+#[pyfunction]
+#[pyo3(signature = (width, height, seed, tileset = None))]
+fn generate<'py>(
+    py: Python<'py>,
+    width: usize,
+    height: usize,
+    seed: u64,
+    tileset: Option<&str>,
+) -> PyResult<Bound<'py, PyArray2<i32>>> {
+    let mut gen = Generator::new(width, height, seed, tileset)?;
+    gen.inner.collapse().map_err(wfc_error)?;
+    Ok(gen.tiles(py))
+}
+
+/// A [`PlotGenerator`] that gets collapsed step by step, e.g. to watch the collapse in a
+/// notebook.
+#[pyclass(unsendable)]
+pub struct Generator {
+    inner: PlotGenerator,
+}
+
+#[pymethods]
+impl Generator {
+    /// Creates a generator for a `width` x `height` plot of the built-in world, or of the RON
+    /// file at the path `tileset`.
+    #[new]
+    #[pyo3(signature = (width, height, seed, tileset = None))]
+    pub fn new(width: usize, height: usize, seed: u64, tileset: Option<&str>) -> PyResult<Self> {
+        let tileset = match tileset {
+            Some(path) => {
+                TileSet::load(path).map_err(|err| PyValueError::new_err(err.to_string()))?
+            }
+            None => TileSet::world(),
+        };
+        let inner = PlotGenerator::for_tileset(tileset, width, height).with_seed(seed);
+        Ok(Generator { inner })
+    }
+
+    #[getter]
+    pub fn width(&self) -> usize {
+        self.inner.width()
+    }
+
+    #[getter]
+    pub fn height(&self) -> usize {
+        self.inner.height()
+    }
+
+    /// The names of the tiles, indexed by their ids.
+    #[getter]
+    pub fn tile_names(&self) -> Vec<String> {
+        let tileset = self.inner.tileset();
+        tileset
+            .ids()
+            .map(|tile| tileset.name(tile).to_string())
+            .collect()
+    }
+
+    /// Makes a single [`PlotGenerator::step`], returns `False` once every field is collapsed.
+    pub fn step(&mut self) -> PyResult<bool> {
+        Ok(self.inner.step().map_err(wfc_error)? != Step::Done)
+    }
+
+    /// Collapses every field that is left.
+    pub fn collapse(&mut self) -> PyResult<()> {
+        self.inner.collapse().map_err(wfc_error)
+    }
+
+    /// The tile ids of the plot so far, -1 for fields in superposition.
+    pub fn tiles<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<i32>> {
+        let (width, height) = (self.width(), self.height());
+        let tiles = Array2::from_shape_fn((height, width), |(y, x)| match self.inner.get(x, y) {
+            WaveState::Collapsed(tile) => tile.0 as i32,
+            WaveState::Superposition(_) => -1,
+        });
+        tiles.into_pyarray(py)
+    }
+}
+
+fn wfc_error(err: WfcError) -> PyErr {
+    PyRuntimeError::new_err(err.to_string())
+}
+
+#[pymodule]
+fn world_gen(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(generate, module)?)?;
+    module.add_class::<Generator>()?;
+    Ok(())
+}
@@ -0,0 +1,355 @@
+use crate::prelude::*;
+use crate::{Plot, Theme, TileId};
+use core::fmt::{Display, Write};
+
+/// Writes a plot as text, like the `--format` of the `world-gen` binary. Implemented by
+/// [`Blocks`], [`Legend`], [`Ansi`], [`JsonGrid`] and [`Csv`], and by any format of your own.
+/// [`Annotated`] adds a header and a legend to any of them.
+///
+/// ```
+/// use core::fmt::Write;
+/// use world_gen::{Plot, PlotGenerator, Renderer};
+///
+/// /// The ids of the tiles, separated by spaces.
+/// struct Ids;
+///
+/// impl Renderer for Ids {
+///     fn render(&self, plot: &Plot, out: &mut dyn Write) -> core::fmt::Result {
+///         for y in 0..plot.height() {
+///             let row = (0..plot.width()).map(|x| plot.get(x, y).0.to_string());
+///             writeln!(out, "{}", row.collect::<Vec<_>>().join(" "))?;
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// let plot = PlotGenerator::new(4, 2).with_seed(1).generate()?;
+/// assert_eq!(plot.render(&Ids).lines().count(), 2);
+/// # Ok::<(), world_gen::GenError>(())
+/// ```
+pub trait Renderer {
+    fn render(&self, plot: &Plot, out: &mut dyn Write) -> core::fmt::Result;
+
+    /// How `tile` shows up in the text, for the legend of [`Annotated`]. `None`, the default,
+    /// leaves the format without a legend, e.g. because it names the tiles already.
+    fn glyph(&self, _plot: &Plot, _tile: TileId) -> Option<String> {
+        None
+    }
+}
+
+/// The glyphs of the tile set, one row per line. This is how [`Plot`] gets displayed, and what
+/// [`Plot::from_glyphs`] reads back.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Blocks;
+
+/// One ASCII character per field, followed by an empty line and a legend with the tile of every
+/// character. Characters are picked from the names of the tiles, so `Farmland` becomes `f`, or
+/// `F` if `f` is taken. Tiles that find no character left show `?`. Only the tiles on the plot
+/// are listed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Legend;
+
+/// The glyphs and colors of a [`Theme`], with the colors as 24-bit ANSI escape codes, like
+/// [`Plot::display_themed`].
+#[derive(Debug, Clone, Default)]
+pub struct Ansi {
+    theme: Theme,
+}
+
+/// A JSON object with the size of the plot, the names of the tiles in the order of their ids, and
+/// the rows of the ids of the fields:
+///
+/// ```json
+/// {"width":2,"height":1,"tiles":["River","Wasteland","Farmland"],"grid":[[0,2]]}
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonGrid;
+
+/// The names of the tiles, separated by commas, one row per line. Names get quoted if they
+/// contain commas, quotes or line breaks.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Csv;
+
+/// The text of another renderer, with a header describing the plot and, with
+/// [`Annotated::with_legend`], a legend of its glyphs after it, so a saved map tells what it
+/// shows and how to generate it again. The header lists the size, the
+/// [`TileSet::fingerprint`](crate::TileSet::fingerprint) and the parameters given, and ends
+/// with an empty line:
+///
+/// ```
+/// use world_gen::{Annotated, Blocks, PlotGenerator};
+///
+/// let plot = PlotGenerator::new(4, 2).with_seed(1).generate()?;
+/// let renderer = Annotated::new(Blocks)
+///     .with_seed(1)
+///     .with_parameter("selection", "scanline")
+///     .with_legend();
+/// let text = plot.render(&renderer);
+/// assert!(text.starts_with("size: 4x2\n"));
+/// assert!(text.contains("\nseed: 1\nselection: scanline\n\n"));
+/// # Ok::<(), world_gen::GenError>(())
+/// ```
+///
+/// The header breaks formats meant for other programs, like [`JsonGrid`], and [`Plot`]s can't
+/// be parsed back from annotated text.
+#[derive(Debug, Clone)]
+pub struct Annotated<R> {
+    renderer: R,
+    parameters: Vec<(String, String)>,
+    legend: bool,
+}
+
+impl<R: Renderer> Annotated<R> {
+    pub fn new(renderer: R) -> Self {
+        Annotated {
+            renderer,
+            parameters: vec![],
+            legend: false,
+        }
+    }
+
+    /// Lists the seed the plot got generated with.
+    pub fn with_seed(self, seed: u64) -> Self {
+        self.with_parameter("seed", seed)
+    }
+
+    /// Lists `name: value` in the header, after the parameters before.
+    pub fn with_parameter(mut self, name: impl Into<String>, value: impl Display) -> Self {
+        self.parameters.push((name.into(), value.to_string()));
+        self
+    }
+
+    /// Lists the glyph and name of every tile on the plot after it, if the renderer has glyphs,
+    /// see [`Renderer::glyph`].
+    pub fn with_legend(mut self) -> Self {
+        self.legend = true;
+        self
+    }
+}
+
+impl Ansi {
+    pub fn new(theme: Theme) -> Self {
+        Ansi { theme }
+    }
+}
+
+impl Renderer for Blocks {
+    fn render(&self, plot: &Plot, out: &mut dyn Write) -> core::fmt::Result {
+        for y in 0..plot.height() {
+            for x in 0..plot.width() {
+                write!(out, "{}", plot.tileset().glyph(plot.get(x, y)))?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    fn glyph(&self, plot: &Plot, tile: TileId) -> Option<String> {
+        Some(plot.tileset().glyph(tile).to_string())
+    }
+}
+
+impl Renderer for Legend {
+    fn render(&self, plot: &Plot, out: &mut dyn Write) -> core::fmt::Result {
+        let tileset = plot.tileset();
+        let chars = legend_chars(plot);
+        for y in 0..plot.height() {
+            for x in 0..plot.width() {
+                out.write_char(chars[plot.get(x, y).0 as usize])?;
+            }
+            writeln!(out)?;
+        }
+
+        writeln!(out)?;
+        for tile in tileset.ids().filter(|&tile| plot.count(tile) > 0) {
+            writeln!(out, "{} {}", chars[tile.0 as usize], tileset.name(tile))?;
+        }
+        Ok(())
+    }
+}
+
+impl Renderer for Ansi {
+    fn render(&self, plot: &Plot, out: &mut dyn Write) -> core::fmt::Result {
+        write_themed(plot, &self.theme, out)
+    }
+
+    fn glyph(&self, plot: &Plot, tile: TileId) -> Option<String> {
+        let mut glyph = String::new();
+        write_themed_glyph(plot, &self.theme, tile, &mut glyph).ok()?;
+        Some(glyph)
+    }
+}
+
+impl<R: Renderer + ?Sized> Renderer for &R {
+    fn render(&self, plot: &Plot, out: &mut dyn Write) -> core::fmt::Result {
+        (**self).render(plot, out)
+    }
+
+    fn glyph(&self, plot: &Plot, tile: TileId) -> Option<String> {
+        (**self).glyph(plot, tile)
+    }
+}
+
+impl<R: Renderer> Renderer for Annotated<R> {
+    fn render(&self, plot: &Plot, out: &mut dyn Write) -> core::fmt::Result {
+        writeln!(out, "size: {}x{}", plot.width(), plot.height())?;
+        writeln!(out, "tileset: {:016x}", plot.tileset().fingerprint())?;
+        for (name, value) in &self.parameters {
+            writeln!(out, "{name}: {value}")?;
+        }
+        writeln!(out)?;
+
+        self.renderer.render(plot, out)?;
+
+        if !self.legend {
+            return Ok(());
+        }
+        let tileset = plot.tileset();
+        let glyphs = tileset
+            .ids()
+            .filter(|&tile| plot.count(tile) > 0)
+            .map(|tile| Some((self.renderer.glyph(plot, tile)?, tileset.name(tile))))
+            .collect::<Option<Vec<_>>>();
+        if let Some(glyphs) = glyphs {
+            writeln!(out)?;
+            for (glyph, name) in glyphs {
+                writeln!(out, "{glyph} {name}")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn glyph(&self, plot: &Plot, tile: TileId) -> Option<String> {
+        self.renderer.glyph(plot, tile)
+    }
+}
+
+impl Renderer for JsonGrid {
+    fn render(&self, plot: &Plot, out: &mut dyn Write) -> core::fmt::Result {
+        let tileset = plot.tileset();
+        let tiles = tileset
+            .ids()
+            .map(|tile| format!("\"{}\"", escape_json(tileset.name(tile))))
+            .collect::<Vec<_>>();
+        let rows = (0..plot.height())
+            .map(|y| {
+                let row = (0..plot.width())
+                    .map(|x| plot.get(x, y).0.to_string())
+                    .collect::<Vec<_>>();
+                format!("[{}]", row.join(","))
+            })
+            .collect::<Vec<_>>();
+
+        writeln!(
+            out,
+            "{{\"width\":{},\"height\":{},\"tiles\":[{}],\"grid\":[{}]}}",
+            plot.width(),
+            plot.height(),
+            tiles.join(","),
+            rows.join(",")
+        )
+    }
+}
+
+impl Renderer for Csv {
+    fn render(&self, plot: &Plot, out: &mut dyn Write) -> core::fmt::Result {
+        let tileset = plot.tileset();
+        let names = tileset
+            .ids()
+            .map(|tile| match tileset.name(tile) {
+                name if name.contains([',', '"', '\n', '\r']) => {
+                    format!("\"{}\"", name.replace('"', "\"\""))
+                }
+                name => name.to_string(),
+            })
+            .collect::<Vec<_>>();
+
+        for y in 0..plot.height() {
+            let row = (0..plot.width())
+                .map(|x| names[plot.get(x, y).0 as usize].as_str())
+                .collect::<Vec<_>>();
+            writeln!(out, "{}", row.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes `plot` with the glyphs and colors of `theme`, see [`Ansi`].
+pub(crate) fn write_themed(plot: &Plot, theme: &Theme, out: &mut dyn Write) -> core::fmt::Result {
+    for y in 0..plot.height() {
+        for x in 0..plot.width() {
+            write_themed_glyph(plot, theme, plot.get(x, y), out)?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Writes the glyph of `tile` in the colors of `theme`.
+fn write_themed_glyph(
+    plot: &Plot,
+    theme: &Theme,
+    tile: TileId,
+    out: &mut dyn Write,
+) -> core::fmt::Result {
+    let tileset = plot.tileset();
+    let glyph = theme.glyph(tileset, tile);
+    match theme.colors(tileset, tile) {
+        (None, None) => write!(out, "{glyph}"),
+        (fg, bg) => {
+            if let Some([r, g, b]) = fg {
+                write!(out, "\x1b[38;2;{r};{g};{b}m")?;
+            }
+            if let Some([r, g, b]) = bg {
+                write!(out, "\x1b[48;2;{r};{g};{b}m")?;
+            }
+            // Reset right away, the next tile may not set both colors
+            write!(out, "{glyph}\x1b[0m")
+        }
+    }
+}
+
+/// The character of every tile of the tile set of `plot` for [`Legend`], by id. The tiles on the
+/// plot come first, so they get the best characters.
+fn legend_chars(plot: &Plot) -> Vec<char> {
+    let tileset = plot.tileset();
+    let (used, unused): (Vec<TileId>, Vec<TileId>) =
+        tileset.ids().partition(|&tile| plot.count(tile) > 0);
+
+    let mut chars = vec!['?'; tileset.len()];
+    let mut taken = vec![];
+    for tile in used.into_iter().chain(unused) {
+        let letters = tileset
+            .name(tile)
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .collect::<Vec<_>>();
+        let first = letters.first().into_iter();
+        let mut candidates = first
+            .clone()
+            .map(char::to_ascii_lowercase)
+            .chain(first.map(char::to_ascii_uppercase))
+            .chain(letters.iter().skip(1).map(char::to_ascii_lowercase))
+            .chain('a'..='z')
+            .chain('A'..='Z')
+            .chain('0'..='9');
+        if let Some(c) = candidates.find(|c| !taken.contains(c)) {
+            chars[tile.0 as usize] = c;
+            taken.push(c);
+        }
+    }
+    chars
+}
+
+fn escape_json(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
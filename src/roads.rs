@@ -0,0 +1,160 @@
+use crate::pathfinding::shortest_path;
+use crate::prelude::*;
+use crate::{Plot, TileId};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+/// Connects points of a generated plot with roads, found with A* over the movement costs of the
+/// tiles like [`Plot::find_path`], see [`TileSet::cost`](crate::TileSet::cost).
+///
+/// Every point after the first gets a road to the closest point connected before it, so the
+/// roads form a network. Roads move in the "+" shape and avoid impassable tiles, unless a bridge
+/// tile is set to cross them with. Roads get written over the plot without looking at the
+/// adjacency rules, the paths are kept in [`Plot::roads`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoadNetwork {
+    road: TileId,
+    bridge: Option<(TileId, f64)>,
+    points: Vec<(usize, usize)>,
+    random_points: usize,
+    seed: u64,
+}
+
+impl RoadNetwork {
+    /// Builds roads out of `road` tiles, without any point yet.
+    pub fn new(road: impl Into<TileId>) -> Self {
+        RoadNetwork {
+            road: road.into(),
+            bridge: None,
+            points: vec![],
+            random_points: 0,
+            seed: random_seed(),
+        }
+    }
+
+    /// Connects the field at `(x, y)`.
+    pub fn with_point(mut self, x: usize, y: usize) -> Self {
+        self.points.push((x, y));
+        self
+    }
+
+    /// Also connects `count` random passable fields, after the explicit points.
+    pub fn with_random_points(mut self, count: usize) -> Self {
+        self.random_points = count;
+        self
+    }
+
+    /// Lets roads cross impassable tiles like rivers for `cost` per field, as `bridge` tiles.
+    pub fn with_bridge(mut self, bridge: impl Into<TileId>, cost: f64) -> Self {
+        self.bridge = Some((bridge.into(), cost));
+        self
+    }
+
+    /// Derives the random points from `seed`.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Finds the paths of the roads through `terrain`, without placing them. Points that can't
+    /// be reached get no road.
+    pub fn route(&self, terrain: &Plot) -> Vec<Vec<(usize, usize)>> {
+        let points = self.points(terrain);
+        let mut paths = vec![];
+        for (i, &point) in points.iter().enumerate().skip(1) {
+            paths.extend(self.connect(terrain, point, &points[..i]));
+        }
+        paths
+    }
+
+    /// Places the roads on `plot`, later roads follow the earlier ones where that is cheaper.
+    /// Returns the new paths, which are also added to [`Plot::roads`].
+    pub fn place(&self, plot: &mut Plot) -> Vec<Vec<(usize, usize)>> {
+        let points = self.points(plot);
+        let mut paths = vec![];
+        for (i, &point) in points.iter().enumerate().skip(1) {
+            let Some(path) = self.connect(plot, point, &points[..i]) else {
+                continue;
+            };
+
+            for &(x, y) in &path {
+                let tile = self.tile_at(plot, (x, y));
+                plot.set(x, y, tile);
+            }
+            plot.add_road(path.clone());
+            paths.push(path);
+        }
+        paths
+    }
+
+    /// Places the roads routed through `terrain` on `layer`, e.g. the feature layer of a
+    /// [`LayeredGenerator`](crate::LayeredGenerator). The road and bridge tiles are tiles of
+    /// the layer, the costs those of the terrain.
+    pub fn place_on_layer(&self, terrain: &Plot, layer: &mut Plot) -> Vec<Vec<(usize, usize)>> {
+        assert!(
+            terrain.width() == layer.width() && terrain.height() == layer.height(),
+            "The layer has to cover the terrain"
+        );
+
+        let paths = self.route(terrain);
+        for path in &paths {
+            for &(x, y) in path {
+                layer.set(x, y, self.tile_at(terrain, (x, y)));
+            }
+            layer.add_road(path.clone());
+        }
+        paths
+    }
+
+    /// The explicit points, followed by the random ones.
+    fn points(&self, plot: &Plot) -> Vec<(usize, usize)> {
+        let mut rng = ChaCha12Rng::seed_from_u64(self.seed);
+        let passable = plot
+            .positions()
+            .filter(|&(x, y)| plot.tileset().passable(plot.get(x, y)))
+            .collect::<Vec<_>>();
+
+        let mut points = self.points.clone();
+        if !passable.is_empty() {
+            points.extend(
+                (0..self.random_points).map(|_| passable[rng.gen_range(0..passable.len())]),
+            );
+        }
+        points
+    }
+
+    /// The road or bridge tile a road places on `pos`.
+    fn tile_at(&self, terrain: &Plot, (x, y): (usize, usize)) -> TileId {
+        match self.bridge {
+            Some((bridge, _)) if !terrain.tileset().passable(terrain.get(x, y)) => bridge,
+            _ => self.road,
+        }
+    }
+
+    /// The cost of stepping onto `pos`, `None` if roads can't go there.
+    fn cost(&self, terrain: &Plot, (x, y): (usize, usize)) -> Option<f64> {
+        let tile = terrain.get(x, y);
+        if terrain.tileset().passable(tile) {
+            Some(terrain.tileset().cost(tile))
+        } else {
+            self.bridge.map(|(_, cost)| cost)
+        }
+    }
+
+    /// The cheapest path from `from` to the closest of `targets`, both included.
+    fn connect(
+        &self,
+        terrain: &Plot,
+        from: (usize, usize),
+        targets: &[(usize, usize)],
+    ) -> Option<Vec<(usize, usize)>> {
+        let (width, height) = (terrain.width(), terrain.height());
+        let distance =
+            |(x, y): (usize, usize), (tx, ty): (usize, usize)| x.abs_diff(tx) + y.abs_diff(ty);
+        let &to = targets
+            .iter()
+            .min_by_key(|&&target| distance(from, target))?;
+
+        shortest_path(width, height, from, to, |pos| self.cost(terrain, pos))
+    }
+}
@@ -0,0 +1,275 @@
+use crate::prelude::*;
+use crate::{Direction, DomainMask, TileId, TileSet};
+use core::fmt::Display;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Which tiles may lie next to each other, see
+/// [`PlotGenerator::with_rules`](crate::PlotGenerator::with_rules).
+///
+/// [`TileSet`] implements it with the neighbours listed in its tile definitions, and so does
+/// every `Fn(TileId, Direction) -> DomainMask`. Implement it to compute the rules instead, e.g.
+/// from gameplay logic.
+///
+/// Rules have to go both ways: `b` may lie next to `a` in a direction exactly when `a` may lie
+/// next to `b` in the opposite one.
+pub trait Ruleset {
+    /// The tiles that may lie next to `a` in `dir`, as a mask over every tile of the tile set.
+    fn allowed(&self, a: TileId, dir: Direction) -> DomainMask;
+
+    /// Checks the rules over the tiles of `tileset` for common authoring errors, before they
+    /// show up as contradictions or strange plots. Returns every problem found, with a
+    /// suggested fix, or nothing if the rules look fine.
+    ///
+    /// Directions no tile has any neighbours in are skipped, so rules for the "+" shape don't
+    /// get reported for leaving out the diagonals.
+    fn validate(&self, tileset: &TileSet) -> Vec<Diagnostic> {
+        let rules = RuleTable::compile(self, tileset);
+        let allowed = |tile: TileId, direction: Direction| rules.get(tile, direction);
+        let name = |tile| tileset.name(tile);
+        let mut diagnostics = vec![];
+
+        for a in tileset.ids() {
+            for direction in Direction::ALL {
+                let opposite = direction.opposite();
+                for b in allowed(a, direction).iter() {
+                    if allowed(b, opposite).contains(a) {
+                        continue;
+                    }
+                    diagnostics.push(Diagnostic {
+                        issue: Issue::Asymmetric { a, b, direction },
+                        message: format!(
+                            "{} allows {} to its {direction:?}, but {} doesn't allow {} to its \
+                             {opposite:?}",
+                            name(a),
+                            name(b),
+                            name(b),
+                            name(a)
+                        ),
+                        suggestion: format!(
+                            "Allow {} {opposite:?} of {}, or forbid {} {direction:?} of {}",
+                            name(a),
+                            name(b),
+                            name(b),
+                            name(a)
+                        ),
+                    });
+                }
+            }
+        }
+
+        for direction in Direction::ALL {
+            if tileset
+                .ids()
+                .all(|tile| allowed(tile, direction).is_empty())
+            {
+                continue;
+            }
+            for tile in tileset
+                .ids()
+                .filter(|&tile| allowed(tile, direction).is_empty())
+            {
+                diagnostics.push(Diagnostic {
+                    issue: Issue::NoNeighbours { tile, direction },
+                    message: format!(
+                        "No tile may lie {direction:?} of {}, so it only fits on the edge",
+                        name(tile)
+                    ),
+                    suggestion: format!(
+                        "Allow at least one neighbour {direction:?} of {}",
+                        name(tile)
+                    ),
+                });
+            }
+        }
+
+        for tile in tileset.ids() {
+            let others = Direction::ALL
+                .iter()
+                .any(|&direction| allowed(tile, direction).iter().any(|other| other != tile));
+            if !others && tileset.len() > 1 {
+                diagnostics.push(Diagnostic {
+                    issue: Issue::Unreachable(tile),
+                    message: format!(
+                        "No other tile may lie next to {}, so it only shows up on plots made of \
+                         nothing else",
+                        name(tile)
+                    ),
+                    suggestion: format!("Allow some other tile next to {}", name(tile)),
+                });
+            }
+        }
+
+        if tileset.ids().all(|tile| tileset.weight(tile) <= 0.0) {
+            diagnostics.push(Diagnostic {
+                issue: Issue::NoWeights,
+                message: "No tile has a positive weight, so no field can collapse".to_string(),
+                suggestion: "Give at least one tile a weight above 0".to_string(),
+            });
+            return diagnostics;
+        }
+        for tile in tileset.ids() {
+            for direction in Direction::ALL {
+                let neighbours = allowed(tile, direction);
+                if !neighbours.is_empty()
+                    && neighbours.iter().all(|other| tileset.weight(other) <= 0.0)
+                {
+                    diagnostics.push(Diagnostic {
+                        issue: Issue::ZeroWeight { tile, direction },
+                        message: format!(
+                            "Every tile that may lie {direction:?} of {} has a weight of 0",
+                            name(tile)
+                        ),
+                        suggestion: format!(
+                            "Give a neighbour {direction:?} of {} a weight above 0",
+                            name(tile)
+                        ),
+                    });
+                }
+            }
+        }
+        diagnostics
+    }
+}
+
+/// A [`Ruleset`] compiled into a dense table with a mask per tile and direction, so checking a
+/// rule is a lookup instead of running the rules, and propagating a field a union of masks per
+/// neighbour. [`PlotGenerator::with_rules`](crate::PlotGenerator::with_rules) compiles the rules
+/// it gets once.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RuleTable {
+    /// Indexed as `[tile][direction]`.
+    masks: Vec<[DomainMask; 8]>,
+}
+
+impl RuleTable {
+    /// Asks `rules` about every tile of `tileset` in every direction.
+    pub fn compile(rules: &(impl Ruleset + ?Sized), tileset: &TileSet) -> Self {
+        RuleTable {
+            masks: tileset
+                .ids()
+                .map(|tile| Direction::ALL.map(|direction| rules.allowed(tile, direction)))
+                .collect(),
+        }
+    }
+
+    /// The tiles that may lie next to `a` in `dir`, see [`Ruleset::allowed`].
+    pub fn get(&self, a: TileId, dir: Direction) -> &DomainMask {
+        &self.masks[a.0 as usize][dir as usize]
+    }
+}
+
+/// A [`RuleTable`] for a tile set, compiled once and shared by every generator of a batch, see
+/// [`PlotGenerator::with_compiled_rules`](crate::PlotGenerator::with_compiled_rules). Generators
+/// given rules with [`PlotGenerator::with_rules`](crate::PlotGenerator::with_rules) compile their
+/// own instead.
+///
+/// ```
+/// use std::sync::Arc;
+/// use world_gen::{CompiledRuleset, PlotGenerator, TileSet};
+///
+/// let tileset = Arc::new(TileSet::terrain());
+/// let rules = Arc::new(CompiledRuleset::compile(&*tileset, &tileset));
+/// for seed in 0..8 {
+///     PlotGenerator::for_tileset(Arc::clone(&tileset), 16, 16)
+///         .with_compiled_rules(Arc::clone(&rules))
+///         .with_seed(seed)
+///         .generate()?;
+/// }
+/// # Ok::<(), world_gen::GenError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CompiledRuleset {
+    table: RuleTable,
+    /// The [`TileSet::fingerprint`] of the set the rules got compiled for.
+    fingerprint: u64,
+}
+
+impl CompiledRuleset {
+    /// Asks `rules` about every tile of `tileset` in every direction, see [`RuleTable::compile`].
+    pub fn compile(rules: &(impl Ruleset + ?Sized), tileset: &TileSet) -> Self {
+        CompiledRuleset {
+            table: RuleTable::compile(rules, tileset),
+            fingerprint: tileset.fingerprint(),
+        }
+    }
+
+    /// The tiles that may lie next to `a` in `dir`, see [`Ruleset::allowed`].
+    pub fn get(&self, a: TileId, dir: Direction) -> &DomainMask {
+        self.table.get(a, dir)
+    }
+
+    /// The table the rules got compiled into.
+    pub fn table(&self) -> &RuleTable {
+        &self.table
+    }
+
+    /// Whether the rules got compiled for `tileset`, or a set with the same names and rules.
+    pub fn fits(&self, tileset: &TileSet) -> bool {
+        self.fingerprint == tileset.fingerprint()
+    }
+}
+
+/// A problem with the rules found by [`Ruleset::validate`]. Prints as the problem followed by the
+/// fix.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub issue: Issue,
+    /// What's wrong, naming the tiles.
+    pub message: String,
+    /// How to fix it.
+    pub suggestion: String,
+}
+
+/// The kinds of problems [`Ruleset::validate`] finds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Issue {
+    /// `a` allows `b` to its `direction`, but `b` doesn't allow `a` in the opposite one.
+    /// Generators expect rules to go both ways, and would generate plots breaking them.
+    Asymmetric {
+        a: TileId,
+        b: TileId,
+        direction: Direction,
+    },
+    /// No tile may lie next to `tile` in `direction`, though other tiles have neighbours there.
+    NoNeighbours { tile: TileId, direction: Direction },
+    /// No other tile may lie next to the tile in any direction.
+    Unreachable(TileId),
+    /// Every tile allowed next to `tile` in `direction` has a weight of 0, so the fields there
+    /// are left with nothing to pick.
+    ZeroWeight { tile: TileId, direction: Direction },
+    /// No tile has a positive weight.
+    NoWeights,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}. {}", self.message, self.suggestion)
+    }
+}
+
+impl Ruleset for TileSet {
+    fn allowed(&self, a: TileId, dir: Direction) -> DomainMask {
+        self.neighbour_mask(a, dir).clone()
+    }
+}
+
+impl Ruleset for RuleTable {
+    fn allowed(&self, a: TileId, dir: Direction) -> DomainMask {
+        self.get(a, dir).clone()
+    }
+}
+
+impl Ruleset for CompiledRuleset {
+    fn allowed(&self, a: TileId, dir: Direction) -> DomainMask {
+        self.get(a, dir).clone()
+    }
+}
+
+impl<F: Fn(TileId, Direction) -> DomainMask> Ruleset for F {
+    fn allowed(&self, a: TileId, dir: Direction) -> DomainMask {
+        self(a, dir)
+    }
+}
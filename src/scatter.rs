@@ -0,0 +1,158 @@
+use crate::grid::Grid;
+use crate::prelude::*;
+use crate::{Plot, TileId};
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+
+/// Scatters resources like ore, trees or loot over a generated plot, each kind on its own tiles
+/// and at its own density, keeping the resources of a kind apart like Poisson-disk sampling.
+///
+/// The kinds are up to the caller, e.g. an enum, and come back with the fields they landed on.
+/// Kinds get scattered in the order they were added, and a field holds one resource at most.
+///
+/// ```
+/// use world_gen::{PlotGenerator, Resource, Scatter, TileSet};
+///
+/// #[derive(Debug, Clone, PartialEq)]
+/// enum Loot {
+///     Ore,
+///     Herbs,
+/// }
+///
+/// let tileset = TileSet::world();
+/// let (mountain, forest) = (tileset.id("Mountain").unwrap(), tileset.id("Forest").unwrap());
+/// let plot = PlotGenerator::for_tileset(tileset, 32, 32).with_seed(3).generate()?;
+///
+/// let resources = Scatter::new()
+///     .with_resource(Resource::new(Loot::Ore, 0.1).with_tiles(&[mountain]).with_spacing(3.0))
+///     .with_resource(Resource::new(Loot::Herbs, 0.05).with_tiles(&[forest]))
+///     .with_seed(7)
+///     .find(&plot);
+/// for (x, y, kind) in &resources {
+///     assert!(plot.get(*x, *y) == mountain || plot.get(*x, *y) == forest, "{kind:?}");
+/// }
+/// # Ok::<(), world_gen::GenError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scatter<K> {
+    resources: Vec<Resource<K>>,
+    seed: u64,
+}
+
+/// A kind of resource scattered by [`Scatter`], and where it may lie.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resource<K> {
+    kind: K,
+    density: f64,
+    tiles: Option<Vec<TileId>>,
+    spacing: f64,
+}
+
+impl<K: Clone> Scatter<K> {
+    pub fn new() -> Self {
+        Scatter {
+            resources: vec![],
+            seed: random_seed(),
+        }
+    }
+
+    /// Scatters `resource` after the ones added before.
+    pub fn with_resource(mut self, resource: Resource<K>) -> Self {
+        self.resources.push(resource);
+        self
+    }
+
+    /// Derives every random decision from `seed`, so the same plot always gets the same
+    /// resources.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// The resources on `plot` as `(x, y, kind)`, kind after kind, without changing the plot.
+    pub fn find(&self, plot: &Plot) -> Vec<(usize, usize, K)> {
+        let mut rng = ChaCha12Rng::seed_from_u64(self.seed);
+        let mut taken = Grid::new(plot.width(), plot.height(), false);
+        let mut found = vec![];
+
+        for resource in &self.resources {
+            let mut candidates = plot
+                .positions()
+                .filter(|&(x, y)| !taken[(x, y)] && resource.allows(plot.get(x, y)))
+                .collect::<Vec<_>>();
+            let limit = (resource.density * candidates.len() as f64).round() as usize;
+            candidates.shuffle(&mut rng);
+
+            // Dart throwing: the first candidates in the shuffled order that keep their distance
+            let reach = resource.spacing.ceil() as isize;
+            let mut placed = Grid::new(plot.width(), plot.height(), false);
+            let mut count = 0;
+            for (x, y) in candidates {
+                if count == limit {
+                    break;
+                }
+                let crowded = (-reach..=reach)
+                    .flat_map(|dy| (-reach..=reach).map(move |dx| (dx, dy)))
+                    .filter_map(|offset| placed.offset((x, y), offset))
+                    .any(|(nx, ny)| {
+                        let distance = (nx.abs_diff(x) as f64).hypot(ny.abs_diff(y) as f64);
+                        placed[(nx, ny)] && distance < resource.spacing
+                    });
+                if crowded {
+                    continue;
+                }
+
+                placed[(x, y)] = true;
+                taken[(x, y)] = true;
+                found.push((x, y, resource.kind.clone()));
+                count += 1;
+            }
+        }
+
+        found
+    }
+}
+
+impl<K> Resource<K> {
+    /// Puts `kind` on about `density` of the fields it may lie on, e.g. 0.05 on every 20th. It
+    /// may lie on any tile, without keeping any distance.
+    pub fn new(kind: K, density: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&density),
+            "The density has to be between 0 and 1"
+        );
+        Resource {
+            kind,
+            density,
+            tiles: None,
+            spacing: 0.0,
+        }
+    }
+
+    /// Only puts the resource on `tiles`, e.g. ore on mountains.
+    pub fn with_tiles<T: Copy + Into<TileId>>(mut self, tiles: &[T]) -> Self {
+        self.tiles = Some(tiles.iter().map(|t| (*t).into()).collect());
+        self
+    }
+
+    /// Keeps the resources of this kind at least `spacing` fields apart, as the crow flies.
+    /// Fewer than the density asks for fit if the spacing leaves no room.
+    pub fn with_spacing(mut self, spacing: f64) -> Self {
+        assert!(spacing >= 0.0, "The spacing can't be negative");
+        self.spacing = spacing;
+        self
+    }
+
+    fn allows(&self, tile: TileId) -> bool {
+        self.tiles
+            .as_ref()
+            .is_none_or(|tiles| tiles.contains(&tile))
+    }
+}
+
+impl<K: Clone> Default for Scatter<K> {
+    fn default() -> Self {
+        Scatter::new()
+    }
+}
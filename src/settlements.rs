@@ -0,0 +1,209 @@
+use crate::prelude::*;
+use crate::{Connectivity, Plot, TileId};
+use core::fmt::Display;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Picks the fields of a generated plot where villages and towns would be founded.
+///
+/// Every field of a site tile gets a score from the tiles it lies near, like water or farmland,
+/// see [`Settlements::with_near`]. The best scoring fields become settlements as long as they
+/// keep their distance to the ones picked before, the best of them become towns. Fields with the
+/// same score get picked in a random order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settlements {
+    sites: Option<Vec<TileId>>,
+    near: Vec<Near>,
+    density: f64,
+    spacing: usize,
+    towns: usize,
+    seed: u64,
+}
+
+/// A settlement found by [`Settlements::find`]. Its name only numbers it, see
+/// [`NameGenerator::label_settlements`](crate::NameGenerator::label_settlements) for real ones.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settlement {
+    pub name: String,
+    pub kind: SettlementKind,
+    pub x: usize,
+    pub y: usize,
+    /// How well the field suits a settlement, the sum of its bonuses from
+    /// [`Settlements::with_near`].
+    pub score: f64,
+}
+
+/// The size of a [`Settlement`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SettlementKind {
+    Village,
+    Town,
+}
+
+/// Fields within `within` steps of `tile` score up to `weight`.
+#[derive(Debug, Clone, PartialEq)]
+struct Near {
+    tile: TileId,
+    within: usize,
+    weight: f64,
+}
+
+impl Settlements {
+    /// Places one settlement per 16 x 16 fields on any passable tile, see
+    /// [`TileSet::passable`](crate::TileSet::passable), at least 4 steps apart. The best of
+    /// them becomes a town.
+    pub fn new() -> Self {
+        Settlements {
+            sites: None,
+            near: vec![],
+            density: 1.0,
+            spacing: 4,
+            towns: 1,
+            seed: random_seed(),
+        }
+    }
+
+    /// Only founds settlements on `tiles`, e.g. farmland.
+    pub fn with_sites<T: Copy + Into<TileId>>(mut self, tiles: &[T]) -> Self {
+        self.sites = Some(tiles.iter().map(|t| (*t).into()).collect());
+        self
+    }
+
+    /// Adds up to `weight` to the score of fields within `within` steps of `tile`, in the "#"
+    /// shape, the closer the more, e.g. to settle near rivers.
+    pub fn with_near(mut self, tile: impl Into<TileId>, within: usize, weight: f64) -> Self {
+        self.near.push(Near {
+            tile: tile.into(),
+            within,
+            weight,
+        });
+        self
+    }
+
+    /// Places up to `density` settlements per 16 x 16 fields, rounded up. Fewer fit if the
+    /// plot lacks sites or room.
+    pub fn with_density(mut self, density: f64) -> Self {
+        assert!(density >= 0.0, "The density can't be negative");
+        self.density = density;
+        self
+    }
+
+    /// Keeps settlements at least `spacing` steps apart, in the "#" shape.
+    pub fn with_spacing(mut self, spacing: usize) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Makes the `count` best placed settlements towns, the others stay villages.
+    pub fn with_towns(mut self, count: usize) -> Self {
+        self.towns = count;
+        self
+    }
+
+    /// Derives the order of fields with the same score from `seed`.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Finds the settlements of `plot`, best placed first, without changing it.
+    pub fn find(&self, plot: &Plot) -> Vec<Settlement> {
+        let (width, height) = (plot.width(), plot.height());
+        let limit = (self.density * (width * height) as f64 / 256.0).ceil() as usize;
+        let distances = self
+            .near
+            .iter()
+            .map(|near| plot.distance_field(near.tile, Connectivity::Eight))
+            .collect::<Vec<_>>();
+
+        let mut candidates = plot
+            .positions()
+            .filter(|&(x, y)| {
+                let tile = plot.get(x, y);
+                match &self.sites {
+                    Some(sites) => sites.contains(&tile),
+                    None => plot.tileset().passable(tile),
+                }
+            })
+            .map(|(x, y)| {
+                let score = self
+                    .near
+                    .iter()
+                    .zip(&distances)
+                    .map(|(near, distances)| {
+                        let distance = distances[y][x] as usize;
+                        if distance > near.within {
+                            return 0.0;
+                        }
+                        let closeness = (near.within + 1 - distance) as f64;
+                        near.weight * closeness / (near.within + 1) as f64
+                    })
+                    .sum::<f64>();
+                ((x, y), score)
+            })
+            .collect::<Vec<_>>();
+
+        // Shuffled first, so the stable sort breaks ties randomly
+        candidates.shuffle(&mut ChaCha12Rng::seed_from_u64(self.seed));
+        candidates.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+        let mut settlements: Vec<Settlement> = vec![];
+        for ((x, y), score) in candidates {
+            if settlements.len() == limit {
+                break;
+            }
+            let crowded = settlements.iter().any(|settlement| {
+                settlement.x.abs_diff(x).max(settlement.y.abs_diff(y)) < self.spacing
+            });
+            if crowded {
+                continue;
+            }
+
+            let kind = if settlements.len() < self.towns {
+                SettlementKind::Town
+            } else {
+                SettlementKind::Village
+            };
+            let number = settlements.iter().filter(|s| s.kind == kind).count() + 1;
+            settlements.push(Settlement {
+                name: format!("{kind} {number}"),
+                kind,
+                x,
+                y,
+                score,
+            });
+        }
+
+        settlements
+    }
+
+    /// Finds the settlements of `plot` and marks each with a `marker` tile, written over the
+    /// plot without looking at the adjacency rules.
+    pub fn place(&self, plot: &mut Plot, marker: impl Into<TileId>) -> Vec<Settlement> {
+        let marker = marker.into();
+        let settlements = self.find(plot);
+        for settlement in &settlements {
+            plot.set(settlement.x, settlement.y, marker);
+        }
+        settlements
+    }
+}
+
+impl Default for Settlements {
+    fn default() -> Self {
+        Settlements::new()
+    }
+}
+
+impl Display for SettlementKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SettlementKind::Village => write!(f, "Village"),
+            SettlementKind::Town => write!(f, "Town"),
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use crate::prelude::*;
+use crate::{Connectivity, Plot, TileId};
+use core::fmt::Display;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// How the tiles of a plot are distributed, see [`Plot::stats`]. Prints as a table, one row per
+/// tile.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PlotStats {
+    pub width: usize,
+    pub height: usize,
+    /// Every tile of the tile set, in the order of their ids, including those missing on the
+    /// plot.
+    pub tiles: Vec<TileStats>,
+}
+
+/// The share of one tile in a plot, see [`PlotStats`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TileStats {
+    pub tile: TileId,
+    pub name: String,
+    /// How many fields hold the tile.
+    pub count: usize,
+    /// The sizes of the groups of connected fields holding the tile, connected in the "+" shape,
+    /// largest first.
+    pub regions: Vec<usize>,
+    /// How many fields on the edge of the plot hold the tile.
+    pub border: usize,
+}
+
+impl PlotStats {
+    pub(crate) fn new(plot: &Plot) -> Self {
+        let (width, height) = (plot.width(), plot.height());
+        let tileset = plot.tileset();
+        let on_edge = |x: usize, y: usize| x == 0 || y == 0 || x == width - 1 || y == height - 1;
+
+        let tiles = tileset
+            .ids()
+            .map(|tile| {
+                let mut regions = plot
+                    .regions(tile, Connectivity::Four)
+                    .iter()
+                    .map(|region| region.len())
+                    .collect::<Vec<_>>();
+                regions.sort_unstable_by(|a, b| b.cmp(a));
+                TileStats {
+                    tile,
+                    name: tileset.name(tile).to_string(),
+                    count: plot.count(tile),
+                    regions,
+                    border: plot
+                        .positions()
+                        .filter(|&(x, y)| on_edge(x, y) && plot.get(x, y) == tile)
+                        .count(),
+                }
+            })
+            .collect();
+
+        PlotStats {
+            width,
+            height,
+            tiles,
+        }
+    }
+
+    /// How many fields lie on the edge of the plot.
+    pub fn border_fields(&self) -> usize {
+        match (self.width, self.height) {
+            (0, _) | (_, 0) => 0,
+            (1, height) => height,
+            (width, 1) => width,
+            (width, height) => 2 * (width + height) - 4,
+        }
+    }
+
+    /// The stats of `tile`.
+    pub fn tile(&self, tile: impl Into<TileId>) -> &TileStats {
+        &self.tiles[tile.into().0 as usize]
+    }
+}
+
+impl TileStats {
+    /// The size of the largest region, 0 if the plot lacks the tile.
+    pub fn largest_region(&self) -> usize {
+        self.regions.first().copied().unwrap_or(0)
+    }
+}
+
+impl Display for PlotStats {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let fields = (self.width * self.height).max(1) as f64;
+        let border = self.border_fields().max(1) as f64;
+        let name = self
+            .tiles
+            .iter()
+            .map(|tile| tile.name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(4);
+
+        writeln!(
+            f,
+            "{:name$}  {:>6}  {:>6}  {:>7}  {:<16}  {:>6}",
+            "Tile", "Fields", "Share", "Regions", "Largest", "Border"
+        )?;
+        for tile in &self.tiles {
+            let largest = tile
+                .regions
+                .iter()
+                .take(3)
+                .map(|size| size.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            writeln!(
+                f,
+                "{:name$}  {:>6}  {:>5.1}%  {:>7}  {:<16}  {:>5.1}%",
+                tile.name,
+                tile.count,
+                100.0 * tile.count as f64 / fields,
+                tile.regions.len(),
+                largest,
+                100.0 * tile.border as f64 / border,
+            )?;
+        }
+        Ok(())
+    }
+}
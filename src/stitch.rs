@@ -0,0 +1,197 @@
+use crate::prelude::*;
+use crate::{Connectivity, EditError, EditMode, Plot, TileId};
+
+impl Plot {
+    /// The `width` x `height` part of the plot with its top left corner at `(x, y)`, with the
+    /// heights of its fields. Roads and the legend are dropped.
+    pub fn crop(&self, (x, y): (usize, usize), (width, height): (usize, usize)) -> Plot {
+        assert!(
+            x + width <= self.width() && y + height <= self.height(),
+            "The part has to lie inside the plot"
+        );
+        let fields = || (y..y + height).flat_map(|fy| (x..x + width).map(move |fx| (fx, fy)));
+
+        let tiles = fields().map(|(fx, fy)| self.get(fx, fy)).collect();
+        let plot = Plot::from_tiles(self.shared_tileset(), width, height, tiles);
+        match fields().map(|(fx, fy)| self.elevation(fx, fy)).collect() {
+            Some(elevation) => plot.with_elevation(elevation),
+            None => plot,
+        }
+    }
+
+    /// Copies `other` onto the plot with its top left corner at `(x, y)`, e.g. to put a
+    /// separately generated piece into a world. Both plots need the same tile set, and `other`
+    /// has to fit inside the plot. The heights, roads and legend of the plot stay as they were.
+    ///
+    /// The seam, every pair of `connectivity` neighbours with one field from each plot, gets
+    /// checked against the rules. With [`EditMode::Strict`] a broken rule fails with
+    /// [`EditError::Conflicts`], listing the fields on both sides of it. With
+    /// [`EditMode::Repair`] the fields around them get collapsed again, like [`Plot::edit`] does.
+    /// Returns the fields the repair changed, nothing changes if it fails.
+    ///
+    /// ```
+    /// use world_gen::{Connectivity, EditMode, PlotGenerator};
+    ///
+    /// let mut world = PlotGenerator::new(32, 32).with_seed(1).generate()?;
+    /// let piece = PlotGenerator::new(8, 8).with_seed(2).generate()?;
+    /// let mode = EditMode::Repair { radius: 1 };
+    /// world.paste(&piece, (12, 12), Connectivity::Eight, mode).unwrap();
+    /// let middle = world.crop((14, 14), (4, 4));
+    /// assert_eq!(middle.to_string(), piece.crop((2, 2), (4, 4)).to_string());
+    /// # Ok::<(), world_gen::GenError>(())
+    /// ```
+    pub fn paste(
+        &mut self,
+        other: &Plot,
+        (x, y): (usize, usize),
+        connectivity: Connectivity,
+        mode: EditMode,
+    ) -> Result<Vec<(usize, usize)>, EditError> {
+        assert!(
+            self.tileset() == other.tileset(),
+            "The plots need the same tile set"
+        );
+        assert!(
+            x + other.width() <= self.width() && y + other.height() <= self.height(),
+            "The pasted plot has to fit inside the plot"
+        );
+
+        let mut pasted = self.clone();
+        for (ox, oy) in other.positions() {
+            pasted.set(x + ox, y + oy, other.get(ox, oy));
+        }
+        let inside = |(fx, fy): (usize, usize)| {
+            (x..x + other.width()).contains(&fx) && (y..y + other.height()).contains(&fy)
+        };
+        let changed = pasted.fix_seams(|pos| inside(pos) as usize, connectivity, mode)?;
+
+        *self = pasted;
+        Ok(changed)
+    }
+
+    /// Puts `pieces` together into one plot, row by row with `columns` pieces per row, e.g.
+    /// chunks generated one by one or on separate machines. The pieces of a row need the same
+    /// height, those of a column the same width, and all of them the same tile set.
+    ///
+    /// The seams between the pieces get checked and repaired like [`Plot::paste`] does. Heights
+    /// are kept if every piece has them, roads and legends are dropped.
+    pub fn stitch(
+        pieces: &[&Plot],
+        columns: usize,
+        connectivity: Connectivity,
+        mode: EditMode,
+    ) -> Result<Plot, EditError> {
+        assert!(
+            columns > 0 && !pieces.is_empty() && pieces.len().is_multiple_of(columns),
+            "The pieces have to fill every row"
+        );
+        let rows = pieces.chunks(columns).collect::<Vec<_>>();
+        let widths = rows[0]
+            .iter()
+            .map(|piece| piece.width())
+            .collect::<Vec<_>>();
+        let heights = rows.iter().map(|row| row[0].height()).collect::<Vec<_>>();
+        for (r, row) in rows.iter().enumerate() {
+            for (c, piece) in row.iter().enumerate() {
+                assert!(
+                    piece.width() == widths[c] && piece.height() == heights[r],
+                    "The pieces of a row need the same height, those of a column the same width"
+                );
+                assert!(
+                    piece.tileset() == pieces[0].tileset(),
+                    "The pieces need the same tile set"
+                );
+            }
+        }
+
+        // Where the columns and rows start, and which piece a field comes from
+        let starts = |sizes: &[usize]| {
+            sizes
+                .iter()
+                .scan(0, |start, size| {
+                    *start += size;
+                    Some(*start - size)
+                })
+                .collect::<Vec<_>>()
+        };
+        let (xs, ys) = (starts(&widths), starts(&heights));
+        let (width, height) = (widths.iter().sum(), heights.iter().sum());
+        let piece_at = |(x, y): (usize, usize)| {
+            let column = xs.partition_point(|&start| start <= x) - 1;
+            let row = ys.partition_point(|&start| start <= y) - 1;
+            (row, column)
+        };
+
+        let mut tiles = vec![TileId(0); width * height];
+        let mut elevation = pieces
+            .iter()
+            .all(|piece| piece.elevation(0, 0).is_some())
+            .then(|| vec![0.0; width * height]);
+        for y in 0..height {
+            for x in 0..width {
+                let (row, column) = piece_at((x, y));
+                let piece = rows[row][column];
+                let (px, py) = (x - xs[column], y - ys[row]);
+                tiles[y * width + x] = piece.get(px, py);
+                if let Some(elevation) = &mut elevation {
+                    elevation[y * width + x] = piece.elevation(px, py).expect("Every piece");
+                }
+            }
+        }
+
+        let mut plot = Plot::from_tiles(pieces[0].shared_tileset(), width, height, tiles);
+        if let Some(elevation) = elevation {
+            plot = plot.with_elevation(elevation);
+        }
+        plot.fix_seams(
+            |pos| {
+                let (row, column) = piece_at(pos);
+                row * columns + column
+            },
+            connectivity,
+            mode,
+        )?;
+        Ok(plot)
+    }
+
+    /// Checks the rules between `connectivity` neighbours from different pieces, as told apart
+    /// by `piece`, and repairs them according to `mode`. Returns the fields the repair changed.
+    fn fix_seams(
+        &mut self,
+        piece: impl Fn((usize, usize)) -> usize,
+        connectivity: Connectivity,
+        mode: EditMode,
+    ) -> Result<Vec<(usize, usize)>, EditError> {
+        let broken = self
+            .positions()
+            .filter(|&(x, y)| {
+                self.conflicts(x, y, self.get(x, y), connectivity)
+                    .into_iter()
+                    .any(|neighbour| piece(neighbour) != piece((x, y)))
+            })
+            .collect::<Vec<_>>();
+        if broken.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let EditMode::Repair { radius } = mode else {
+            return Err(EditError::Conflicts(broken));
+        };
+        let mut free = broken
+            .iter()
+            .flat_map(|&(x, y)| {
+                let xs = x.saturating_sub(radius)..(x + radius + 1).min(self.width());
+                let ys = y.saturating_sub(radius)..(y + radius + 1).min(self.height());
+                ys.flat_map(move |fy| xs.clone().map(move |fx| (fx, fy)))
+            })
+            .collect::<Vec<_>>();
+        free.sort_unstable_by_key(|&(x, y)| (y, x));
+        free.dedup();
+
+        let changed = self.resolve(&free, &[], connectivity)?;
+        for &(x, y, tile) in &changed {
+            self.set(x, y, tile);
+        }
+        Ok(changed.into_iter().map(|(x, y, _)| (x, y)).collect())
+    }
+}
@@ -0,0 +1,80 @@
+use crate::prelude::*;
+use crate::TileId;
+
+/// How [`Plot::to_svg`](crate::Plot::to_svg) draws the tiles.
+///
+/// By default every tile is a square in the color of its tile set, without an outline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgStyle {
+    cell_size: f64,
+    stroke: Option<(String, f64)>,
+    fills: HashMap<TileId, String>,
+    paths: HashMap<TileId, String>,
+}
+
+impl SvgStyle {
+    /// Draws every tile as a `cell_size` x `cell_size` square.
+    pub fn new(cell_size: f64) -> Self {
+        SvgStyle {
+            cell_size,
+            stroke: None,
+            fills: HashMap::new(),
+            paths: HashMap::new(),
+        }
+    }
+
+    /// Outlines every tile in `color`, any SVG color like `"black"` or `"#333"`, `width` pixels
+    /// wide.
+    pub fn with_stroke(mut self, color: impl Into<String>, width: f64) -> Self {
+        self.stroke = Some((color.into(), width));
+        self
+    }
+
+    /// Fills `tile` with `color` instead of the color of its tile set.
+    pub fn with_fill(mut self, tile: impl Into<TileId>, color: impl Into<String>) -> Self {
+        self.fills.insert(tile.into(), color.into());
+        self
+    }
+
+    /// Draws `tile` as the SVG path `data` instead of a square, e.g. `"M 0.5 0 L 1 1 L 0 1 Z"`
+    /// for a triangle. The path is drawn in a unit square and scaled to the cell.
+    pub fn with_path(mut self, tile: impl Into<TileId>, data: impl Into<String>) -> Self {
+        self.paths.insert(tile.into(), data.into());
+        self
+    }
+
+    pub fn cell_size(&self) -> f64 {
+        self.cell_size
+    }
+
+    /// The stroke as `(color, width)`, if tiles get outlined.
+    pub fn stroke(&self) -> Option<(&str, f64)> {
+        self.stroke
+            .as_ref()
+            .map(|(color, width)| (color.as_str(), *width))
+    }
+
+    /// The explicit fill of `tile`, if it has one.
+    pub fn fill(&self, tile: TileId) -> Option<&str> {
+        self.fills.get(&tile).map(String::as_str)
+    }
+
+    /// The custom path of `tile`, if it has one.
+    pub fn path(&self, tile: TileId) -> Option<&str> {
+        self.paths.get(&tile).map(String::as_str)
+    }
+}
+
+impl Default for SvgStyle {
+    fn default() -> Self {
+        SvgStyle::new(16.0)
+    }
+}
+
+/// Escapes `text` for use in XML attributes, like the ones of an SVG.
+pub(crate) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,226 @@
+use crate::prelude::*;
+use crate::{TileId, TileSet};
+
+/// How [`Plot::display_themed`](crate::Plot::display_themed) draws the tiles in a terminal: a
+/// glyph, and a foreground and background color for every tile.
+///
+/// Styles are looked up by the name of the tile, so a theme works for every tile set with tiles
+/// of the same names. Tiles without a style use the glyph of their tile set, and its color
+/// wherever [`TileColors`] puts it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    name: String,
+    styles: HashMap<String, Style>,
+    tile_colors: TileColors,
+    colors: bool,
+    /// Builds the glyph of tiles without one, from the name of the tile and the width of the
+    /// glyphs of the tile set.
+    fallback: Fallback,
+}
+
+/// How a single tile is drawn, see [`Theme::with_style`]. Left out parts fall back to the tile
+/// set.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Style {
+    pub glyph: Option<String>,
+    pub fg: Option<[u8; 3]>,
+    pub bg: Option<[u8; 3]>,
+}
+
+/// Where the color of the tile set goes, for tiles without an explicit color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TileColors {
+    /// Behind the glyph, like [`Plot::display_colored`](crate::Plot::display_colored).
+    #[default]
+    Background,
+    /// On the glyph itself.
+    Foreground,
+    /// Nowhere, only explicit colors are used.
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fallback {
+    /// The glyph of the tile set.
+    Glyph,
+    /// The first letter of the name, repeated to the width of the tile set glyphs.
+    Ascii,
+}
+
+impl Theme {
+    /// The names of the built-in themes, see [`Theme::builtin`].
+    pub const BUILTIN: [&'static str; 4] = ["classic", "ascii", "emoji", "high-contrast"];
+
+    /// A theme without any style, so every tile looks like in its tile set.
+    pub fn new(name: impl Into<String>) -> Self {
+        Theme {
+            name: name.into(),
+            styles: HashMap::new(),
+            tile_colors: TileColors::Background,
+            colors: true,
+            fallback: Fallback::Glyph,
+        }
+    }
+
+    /// The built-in theme called `name`, one of [`Theme::BUILTIN`].
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "classic" => Some(Theme::classic()),
+            "ascii" => Some(Theme::ascii()),
+            "emoji" => Some(Theme::emoji()),
+            "high-contrast" => Some(Theme::high_contrast()),
+            _ => None,
+        }
+    }
+
+    /// The glyphs and colors of the tile set, with the colors as background.
+    pub fn classic() -> Self {
+        Theme::new("classic")
+    }
+
+    /// Plain ASCII glyphs in the colors of the tile set, for terminals and fonts without block
+    /// characters. Tiles unknown to the theme show the first letter of their name.
+    pub fn ascii() -> Self {
+        let mut theme = Theme::new("ascii").with_tile_colors(TileColors::Foreground);
+        theme.fallback = Fallback::Ascii;
+
+        [
+            ("River", "~~"),
+            ("Wasteland", ".."),
+            ("Farmland", ",,"),
+            ("Forest", "TT"),
+            ("Mountain", "^^"),
+            ("Lake", "=="),
+            ("Desert", "::"),
+            ("Road", "++"),
+            ("Village", "[]"),
+        ]
+        .into_iter()
+        .fold(theme, |theme, (tile, glyph)| theme.with_glyph(tile, glyph))
+    }
+
+    /// One emoji per tile, which brings its own colors. Emojis are two columns wide, like the
+    /// glyphs of the built-in tile sets.
+    pub fn emoji() -> Self {
+        let theme = Theme::new("emoji").with_tile_colors(TileColors::None);
+
+        [
+            ("River", "🌊"),
+            ("Wasteland", "🟫"),
+            ("Farmland", "🌾"),
+            ("Forest", "🌲"),
+            ("Mountain", "🗻"),
+            ("Lake", "💧"),
+            ("Desert", "🟨"),
+            ("Road", "🟧"),
+            ("Village", "🏠"),
+        ]
+        .into_iter()
+        .fold(theme, |theme, (tile, glyph)| theme.with_glyph(tile, glyph))
+    }
+
+    /// Saturated colors on black, easy to tell apart even with poor eyesight or screens.
+    pub fn high_contrast() -> Self {
+        let theme = Theme::new("high-contrast").with_tile_colors(TileColors::Foreground);
+
+        [
+            ("River", [0, 200, 255]),
+            ("Wasteland", [255, 255, 255]),
+            ("Farmland", [0, 255, 0]),
+            ("Forest", [0, 140, 0]),
+            ("Mountain", [170, 170, 170]),
+            ("Lake", [60, 60, 255]),
+            ("Desert", [255, 255, 0]),
+            ("Road", [255, 128, 0]),
+            ("Village", [255, 0, 255]),
+        ]
+        .into_iter()
+        .fold(theme, |theme, (tile, fg)| {
+            theme.with_style(
+                tile,
+                Style {
+                    glyph: None,
+                    fg: Some(fg),
+                    bg: Some([0, 0, 0]),
+                },
+            )
+        })
+    }
+
+    /// Draws the tile called `tile` with `style`, replacing its previous style.
+    pub fn with_style(mut self, tile: impl Into<String>, style: Style) -> Self {
+        self.styles.insert(tile.into(), style);
+        self
+    }
+
+    /// Draws the tile called `tile` as `glyph`, keeping the rest of its style.
+    pub fn with_glyph(mut self, tile: impl Into<String>, glyph: impl Into<String>) -> Self {
+        self.styles.entry(tile.into()).or_default().glyph = Some(glyph.into());
+        self
+    }
+
+    /// Sets where the colors of the tile set go, see [`TileColors`].
+    pub fn with_tile_colors(mut self, tile_colors: TileColors) -> Self {
+        self.tile_colors = tile_colors;
+        self
+    }
+
+    /// Whether any colors get written, as ANSI escape codes. Without them only the glyphs of the
+    /// theme remain. Defaults to true.
+    pub fn with_colors(mut self, colors: bool) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The style of the tile called `tile`, if it has one.
+    pub fn style(&self, tile: &str) -> Option<&Style> {
+        self.styles.get(tile)
+    }
+
+    /// The glyph `tile` of `tileset` gets drawn as.
+    pub fn glyph(&self, tileset: &TileSet, tile: TileId) -> String {
+        let style = self.style(tileset.name(tile));
+        if let Some(glyph) = style.and_then(|style| style.glyph.as_ref()) {
+            return glyph.clone();
+        }
+
+        match self.fallback {
+            Fallback::Glyph => tileset.glyph(tile).to_string(),
+            Fallback::Ascii => {
+                let letter = tileset
+                    .name(tile)
+                    .chars()
+                    .find(char::is_ascii_alphanumeric)
+                    .unwrap_or('?');
+                let width = tileset.glyph(tile).chars().count().max(1);
+                letter.to_string().repeat(width)
+            }
+        }
+    }
+
+    /// The foreground and background color `tile` of `tileset` gets drawn in.
+    pub fn colors(&self, tileset: &TileSet, tile: TileId) -> (Option<[u8; 3]>, Option<[u8; 3]>) {
+        if !self.colors {
+            return (None, None);
+        }
+
+        let style = self.style(tileset.name(tile));
+        let (fg, bg) = style.map_or((None, None), |style| (style.fg, style.bg));
+        let color = Some(tileset.color(tile));
+        match self.tile_colors {
+            TileColors::Background => (fg, bg.or(color)),
+            TileColors::Foreground => (fg.or(color), bg),
+            TileColors::None => (fg, bg),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::classic()
+    }
+}
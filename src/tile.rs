@@ -0,0 +1,182 @@
+use crate::prelude::*;
+use crate::TileId;
+use core::fmt::Display;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The tiles a [`Plot`](crate::Plot) is made of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[repr(u8)]
+pub enum Tile {
+    River,
+    #[default]
+    Wasteland,
+    Farmland,
+}
+
+/// Relative probabilities of the tiles when a field collapses.
+///
+/// Tiles without an explicit weight fall back to the weight of their [`TileSet`](crate::TileSet).
+///
+/// Directional factors make a tile likelier or rarer on one side of another tile, so structures
+/// grow in some directions more than others, like rivers flowing north to south or mountain
+/// ranges stretching from east to west instead of round blobs:
+///
+/// ```
+/// use world_gen::{Direction, PlotGenerator, Tile, Weights};
+///
+/// let weights = Weights::default()
+///     .with_directional(Tile::River, Direction::North, Tile::River, 8.0)
+///     .with_directional(Tile::River, Direction::South, Tile::River, 8.0)
+///     .with_directional(Tile::River, Direction::East, Tile::River, 0.25)
+///     .with_directional(Tile::River, Direction::West, Tile::River, 0.25);
+/// let plot = PlotGenerator::new(32, 32).with_weights(weights).with_seed(2).generate()?;
+/// # Ok::<(), world_gen::GenError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Weights {
+    weights: HashMap<TileId, f64>,
+    /// `(tile, direction, neighbour, factor)`, see [`Weights::with_directional`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    directional: Vec<(TileId, Direction, TileId, f64)>,
+}
+
+/// The side of a field a neighbour lies on. North is towards the first row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Direction {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl Tile {
+    /// Every tile, in the order of their [`TileId`]s.
+    pub const ALL: [Tile; 3] = [Tile::River, Tile::Wasteland, Tile::Farmland];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Tile::River => "░░",
+            Tile::Wasteland => "▓▓",
+            Tile::Farmland => "██",
+        }
+    }
+
+    /// The RGB color of this tile in image exports.
+    pub fn color(&self) -> [u8; 3] {
+        match self {
+            Tile::River => [66, 135, 245],
+            Tile::Wasteland => [150, 130, 90],
+            Tile::Farmland => [80, 160, 60],
+        }
+    }
+
+    /// The default weight of this tile, see [`Weights`].
+    pub fn weight(&self) -> f64 {
+        1.0
+    }
+
+    pub fn valid_neighbours(&self) -> Vec<Tile> {
+        use Tile::*;
+        match self {
+            River => [River, Wasteland].into(),
+            Wasteland => [River, Wasteland, Farmland].into(),
+            Farmland => [Farmland, Wasteland].into(),
+        }
+    }
+}
+
+impl Weights {
+    /// Sets the weight of `tile`. A weight of 0 means the tile is never picked.
+    pub fn with(mut self, tile: impl Into<TileId>, weight: f64) -> Self {
+        assert!(weight >= 0.0, "Weights can't be negative");
+        self.weights.insert(tile.into(), weight);
+        self
+    }
+
+    /// The explicit weight of `tile`, if it has one.
+    pub fn get(&self, tile: TileId) -> Option<f64> {
+        self.weights.get(&tile).copied()
+    }
+
+    /// Multiplies the weight of `neighbour` by `factor` on the fields `direction` of a collapsed
+    /// `tile` field. Factors for the same tiles and direction multiply, and a field next to
+    /// several collapsed fields gets the factors of all of them. They only change which tile a
+    /// field collapses into, not the order the fields collapse in, and never allow tiles the
+    /// rules forbid. A factor of 0 keeps `neighbour` from being picked there.
+    pub fn with_directional(
+        mut self,
+        tile: impl Into<TileId>,
+        direction: Direction,
+        neighbour: impl Into<TileId>,
+        factor: f64,
+    ) -> Self {
+        assert!(factor >= 0.0, "Weights can't be negative");
+        self.directional
+            .push((tile.into(), direction, neighbour.into(), factor));
+        self
+    }
+
+    /// The factor for `neighbour` on the field `direction` of a `tile` field, 1 without any.
+    pub fn directional(&self, tile: TileId, direction: Direction, neighbour: TileId) -> f64 {
+        self.directional
+            .iter()
+            .filter(|&&(t, d, n, _)| (t, d, n) == (tile, direction, neighbour))
+            .map(|&(_, _, _, factor)| factor)
+            .product()
+    }
+
+    /// Whether any directional factors are set.
+    pub(crate) fn has_directional(&self) -> bool {
+        !self.directional.is_empty()
+    }
+}
+
+impl Direction {
+    /// Every direction, clockwise from north.
+    pub const ALL: [Direction; 8] = [
+        Direction::North,
+        Direction::NorthEast,
+        Direction::East,
+        Direction::SouthEast,
+        Direction::South,
+        Direction::SouthWest,
+        Direction::West,
+        Direction::NorthWest,
+    ];
+
+    /// The `(dx, dy)` step from a field to its neighbour in this direction.
+    pub fn offset(&self) -> (isize, isize) {
+        match self {
+            Direction::North => (0, -1),
+            Direction::NorthEast => (1, -1),
+            Direction::East => (1, 0),
+            Direction::SouthEast => (1, 1),
+            Direction::South => (0, 1),
+            Direction::SouthWest => (-1, 1),
+            Direction::West => (-1, 0),
+            Direction::NorthWest => (-1, -1),
+        }
+    }
+
+    /// The direction pointing back, e.g. south for north.
+    pub fn opposite(&self) -> Direction {
+        Direction::ALL[(*self as usize + 4) % 8]
+    }
+}
+
+impl Display for Tile {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
@@ -0,0 +1,149 @@
+use crate::prelude::*;
+use crate::svg::escape_xml;
+use crate::{Plot, TileId};
+
+/// Writes plots as maps for the [Tiled](https://www.mapeditor.org/) editor, in its `.tmx` or
+/// `.json` format, so they can be loaded by engines that read Tiled maps.
+///
+/// The map has a single tile layer and refers to an external Tiled tile set. Every tile of the
+/// plot becomes the global tile id (GID) `first_gid + TileId` of that tile set, unless it got an
+/// explicit GID with [`TiledExport::with_gid`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TiledExport {
+    tileset_source: String,
+    first_gid: u32,
+    gids: HashMap<TileId, u32>,
+    tile_width: u32,
+    tile_height: u32,
+}
+
+impl TiledExport {
+    /// Exports maps using the Tiled tile set file at `tileset_source`, relative to the map file,
+    /// with 16 x 16 pixel tiles starting at GID 1.
+    pub fn new(tileset_source: impl Into<String>) -> Self {
+        TiledExport {
+            tileset_source: tileset_source.into(),
+            first_gid: 1,
+            gids: HashMap::new(),
+            tile_width: 16,
+            tile_height: 16,
+        }
+    }
+
+    /// Sets the size of a tile in pixels, as used by the tile set.
+    pub fn with_tile_size(mut self, width: u32, height: u32) -> Self {
+        self.tile_width = width;
+        self.tile_height = height;
+        self
+    }
+
+    /// Sets the first GID of the tile set, tiles without an explicit GID follow from it.
+    pub fn with_first_gid(mut self, first_gid: u32) -> Self {
+        assert!(first_gid > 0, "GID 0 means no tile in Tiled");
+        self.first_gid = first_gid;
+        self
+    }
+
+    /// Writes `tile` as `gid`, e.g. to map several tiles onto the same Tiled tile. A GID of 0
+    /// leaves the fields holding `tile` empty.
+    pub fn with_gid(mut self, tile: impl Into<TileId>, gid: u32) -> Self {
+        self.gids.insert(tile.into(), gid);
+        self
+    }
+
+    /// The GID `tile` gets written as.
+    pub fn gid(&self, tile: TileId) -> u32 {
+        self.gids
+            .get(&tile)
+            .copied()
+            .unwrap_or(self.first_gid + tile.0 as u32)
+    }
+
+    /// The plot as a `.tmx` map, with the layer data as CSV.
+    pub fn to_tmx(&self, plot: &Plot) -> String {
+        let (width, height) = (plot.width(), plot.height());
+        let (tile_width, tile_height) = (self.tile_width, self.tile_height);
+
+        let rows = (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| self.gid(plot.get(x, y)).to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .collect::<Vec<_>>()
+            .join(",\n");
+
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" renderorder="right-down" width="{width}" height="{height}" tilewidth="{tile_width}" tileheight="{tile_height}" infinite="0" nextlayerid="2" nextobjectid="1">
+ <tileset firstgid="{}" source="{}"/>
+ <layer id="1" name="Tiles" width="{width}" height="{height}">
+  <data encoding="csv">
+{rows}
+</data>
+ </layer>
+</map>
+"#,
+            self.first_gid,
+            escape_xml(&self.tileset_source),
+        )
+    }
+
+    /// The plot as a `.json` map.
+    pub fn to_json(&self, plot: &Plot) -> String {
+        let (width, height) = (plot.width(), plot.height());
+        let (tile_width, tile_height) = (self.tile_width, self.tile_height);
+
+        let data = plot
+            .positions()
+            .map(|(x, y)| self.gid(plot.get(x, y)).to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        format!(
+            r#"{{
+  "type": "map",
+  "version": "1.10",
+  "orientation": "orthogonal",
+  "renderorder": "right-down",
+  "width": {width},
+  "height": {height},
+  "tilewidth": {tile_width},
+  "tileheight": {tile_height},
+  "infinite": false,
+  "nextlayerid": 2,
+  "nextobjectid": 1,
+  "tilesets": [{{ "firstgid": {}, "source": "{}" }}],
+  "layers": [
+    {{
+      "type": "tilelayer",
+      "id": 1,
+      "name": "Tiles",
+      "x": 0,
+      "y": 0,
+      "width": {width},
+      "height": {height},
+      "opacity": 1,
+      "visible": true,
+      "data": [{data}]
+    }}
+  ]
+}}
+"#,
+            self.first_gid,
+            escape_json(&self.tileset_source),
+        )
+    }
+}
+
+fn escape_json(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '"' => "\\\"".to_string(),
+            '\\' => "\\\\".to_string(),
+            c if c.is_control() => format!("\\u{:04x}", c as u32),
+            c => c.to_string(),
+        })
+        .collect()
+}
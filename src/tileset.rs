@@ -0,0 +1,870 @@
+use crate::prelude::*;
+use crate::{Direction, DomainMask, Tile};
+use core::fmt::Display;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Identifies a tile by its position in a [`TileSet`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub struct TileId(pub u16);
+
+/// The definition of a single tile, as written in a tile set file.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TileDef {
+    pub name: String,
+    /// What the tile looks like when a plot gets printed.
+    pub glyph: String,
+    /// The relative probability of the tile when a field collapses. Defaults to 1.
+    #[cfg_attr(feature = "serde", serde(default = "default_weight"))]
+    pub weight: f64,
+    /// The RGB color of the tile in image exports. Defaults to gray.
+    #[cfg_attr(feature = "serde", serde(default = "default_color"))]
+    pub color: [u8; 3],
+    /// Names of the tiles that may lie next to this one, on every side. Rules always go both
+    /// ways, so a pair only has to be listed at one of its two tiles.
+    pub neighbours: Vec<String>,
+    /// Names of the tiles that may lie next to this one only on the given side. A tile listing
+    /// `North: ["River"]` allows a River to its north, and so the River allows it to its south.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sides: HashMap<Direction, Vec<String>>,
+    /// Turns the tile into one variant per distinct rotation, see [`SymmetryClass`]. Without a
+    /// symmetry the tile is used exactly as defined.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub symmetry: Option<SymmetryClass>,
+    /// The colors of the north, east, south and west edges of the tile, like `"road"` or
+    /// `"grass"`, as an alternative to listing neighbours. Two tiles with edges may lie next to
+    /// each other where their touching edges share a color, and diagonally next to each other in
+    /// any case, as they only touch at a corner. The edges turn along with the variants of a
+    /// [`SymmetryClass`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub edges: Option<[String; 4]>,
+    /// Pairs of tiles this tile is the transition between, like a Riverbank between River and
+    /// Farmland. The two tiles of a pair may no longer lie next to each other, whatever their
+    /// rules say, this tile may lie next to both of them and next to itself, so the solver puts
+    /// a band of it wherever they would otherwise meet.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub between: Vec<(String, String)>,
+    /// Attributes for the game using the plot, like `{"cost": 2.0, "passable": true}`. The
+    /// generator doesn't look at them, see [`Plot::metadata`](crate::Plot::metadata).
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "HashMap::is_empty")
+    )]
+    pub metadata: HashMap<String, MetaValue>,
+}
+
+/// A single attribute in the [`TileDef::metadata`] of a tile.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(untagged))]
+pub enum MetaValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+/// Which rotations and reflections leave a tile unchanged, named after letters with the same
+/// symmetry. Rotations are counted in clockwise quarter turns.
+///
+/// A tile with a symmetry class gets a variant for every distinct rotation: the tile itself, and
+/// e.g. `"Bend 1"`, `"Bend 2"` and `"Bend 3"` for the rotations of a `"Bend"`. The rules of the
+/// tile are rotated and mirrored along with it, so they only have to be written down for the
+/// unrotated tile. Rules can name the variants of other tiles, tiles without a symmetry class
+/// count as looking the same from every side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SymmetryClass {
+    /// Looks the same after any rotation or reflection, like a meadow or a crossing.
+    X,
+    /// Symmetric from west to east, like a T-junction open to the east, south and west.
+    T,
+    /// Symmetric along the diagonal, like a bend running from north to east.
+    L,
+    /// Symmetric from west to east and from north to south, like a straight road.
+    I,
+}
+
+/// The tiles a [`PlotGenerator`](crate::PlotGenerator) is working with: their glyphs, weights
+/// and which tiles may lie next to each other.
+///
+/// [`TileSet::terrain`] is the built-in set of [`Tile`]s, other sets can be defined in code with
+/// [`TileSet::new`] or loaded from a RON file with [`TileSet::load`]:
+///
+/// ```ron
+/// (
+///     tiles: [
+///         (name: "Forest", glyph: "♣♣", weight: 2.0, color: (34, 110, 40), neighbours: ["Forest", "Meadow"]),
+///         (name: "Meadow", glyph: "..", neighbours: ["Meadow"], metadata: {"cost": 1.0, "passable": true}),
+///         (name: "Road", glyph: "==", neighbours: [], sides: {East: ["Road"], West: ["Road"]}),
+///     ],
+/// )
+/// ```
+///
+/// Roads, rivers and coastlines are easier to write down as edge colors, see
+/// [`TileDef::edges`]:
+///
+/// ```ron
+/// (
+///     tiles: [
+///         (name: "Grass", glyph: "..", neighbours: [], edges: Some(("grass", "grass", "grass", "grass"))),
+///         (name: "Road", glyph: "==", neighbours: [], edges: Some(("grass", "road", "grass", "road")), symmetry: Some(I)),
+///         (name: "Bend", glyph: "=╗", neighbours: [], edges: Some(("road", "road", "grass", "grass")), symmetry: Some(L)),
+///     ],
+/// )
+/// ```
+///
+/// Corner and junction pieces don't need a tile for every rotation: a tile with a `symmetry` like
+/// `Some(L)` gets its rotations and their rules derived, see [`SymmetryClass`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "TileSetFile", into = "TileSetFile")
+)]
+pub struct TileSet {
+    defs: Vec<TileDef>,
+    /// The valid neighbours of every tile, indexed by [`Direction`].
+    neighbours: Vec<[Vec<TileId>; 8]>,
+    /// The same as `neighbours`, as masks.
+    masks: Vec<[DomainMask; 8]>,
+}
+
+/// Errors that can happen while building or loading a [`TileSet`].
+#[derive(Debug)]
+pub enum TileSetError {
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    #[cfg(feature = "serde")]
+    Parse(ron::error::SpannedError),
+    /// The set contains no tiles.
+    Empty,
+    /// The set contains more tiles than a [`TileId`] can address.
+    TooManyTiles(usize),
+    /// Two tiles share this name.
+    DuplicateTile(String),
+    /// `tile` lists a `neighbour` that isn't part of the set.
+    UnknownNeighbour { tile: String, neighbour: String },
+    /// This tile has a negative weight.
+    NegativeWeight(String),
+    /// This tile has an empty glyph, so it can't be told apart in a printed plot.
+    EmptyGlyph(String),
+    /// A sample contains this character, but the legend doesn't, see
+    /// [`TileSet::infer_from_text`].
+    UnknownGlyph(char),
+}
+
+/// The layout of a tile set file.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct TileSetFile {
+    tiles: Vec<TileDef>,
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+fn default_color() -> [u8; 3] {
+    [128, 128, 128]
+}
+
+impl TileDef {
+    /// A tile called `name` that looks like `glyph`, with a weight of 1, gray and without any
+    /// neighbour yet.
+    pub fn new(name: impl Into<String>, glyph: impl Into<String>) -> Self {
+        TileDef {
+            name: name.into(),
+            glyph: glyph.into(),
+            weight: default_weight(),
+            color: default_color(),
+            neighbours: vec![],
+            sides: HashMap::new(),
+            symmetry: None,
+            edges: None,
+            between: vec![],
+            metadata: HashMap::new(),
+        }
+    }
+
+    pub fn with_weight(mut self, weight: f64) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    pub fn with_color(mut self, color: [u8; 3]) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Allows the tiles called `names` next to this one on every side, see
+    /// [`TileDef::neighbours`].
+    pub fn with_neighbours(mut self, names: &[impl AsRef<str>]) -> Self {
+        self.neighbours
+            .extend(names.iter().map(|name| name.as_ref().to_string()));
+        self
+    }
+
+    /// Allows the tiles called `names` next to this one on the `direction` side only, see
+    /// [`TileDef::sides`].
+    pub fn with_side(mut self, direction: Direction, names: &[impl AsRef<str>]) -> Self {
+        let side = self.sides.entry(direction).or_default();
+        side.extend(names.iter().map(|name| name.as_ref().to_string()));
+        self
+    }
+
+    pub fn with_symmetry(mut self, symmetry: SymmetryClass) -> Self {
+        self.symmetry = Some(symmetry);
+        self
+    }
+
+    /// Colors the north, east, south and west edges of the tile, see [`TileDef::edges`].
+    pub fn with_edges(mut self, edges: [&str; 4]) -> Self {
+        self.edges = Some(edges.map(str::to_string));
+        self
+    }
+
+    /// Makes the tile the transition between the tiles called `a` and `b`, see
+    /// [`TileDef::between`].
+    ///
+    /// ```
+    /// use world_gen::{Direction, PlotGenerator, Tile, TileDef, TileSet};
+    ///
+    /// let mut tileset = TileSet::terrain();
+    /// let bank = TileDef::new("Riverbank", "::").with_between("River", "Wasteland");
+    /// tileset.register(bank)?;
+    /// assert!(!tileset
+    ///     .valid_neighbours(Tile::River.into(), Direction::North)
+    ///     .contains(&Tile::Wasteland.into()));
+    ///
+    /// assert!(PlotGenerator::for_tileset(tileset, 16, 16).with_seed(2).generate().is_ok());
+    /// # Ok::<(), world_gen::TileSetError>(())
+    /// ```
+    pub fn with_between(mut self, a: impl Into<String>, b: impl Into<String>) -> Self {
+        self.between.push((a.into(), b.into()));
+        self
+    }
+
+    pub fn with_metadata(mut self, key: impl Into<String>, value: MetaValue) -> Self {
+        self.metadata.insert(key.into(), value);
+        self
+    }
+}
+
+impl TileSet {
+    /// Builds a set out of tile definitions, in the order of `defs`. The variants of a tile with
+    /// a [`SymmetryClass`] follow right after it, by their rotation.
+    pub fn new(defs: Vec<TileDef>) -> Result<Self, TileSetError> {
+        let defs = expand_symmetries(defs)?;
+        if defs.is_empty() {
+            return Err(TileSetError::Empty);
+        }
+        if defs.len() > u16::MAX as usize + 1 {
+            return Err(TileSetError::TooManyTiles(defs.len()));
+        }
+
+        let mut ids = HashMap::new();
+        for (i, def) in defs.iter().enumerate() {
+            if def.weight < 0.0 {
+                return Err(TileSetError::NegativeWeight(def.name.clone()));
+            }
+            if def.glyph.is_empty() {
+                return Err(TileSetError::EmptyGlyph(def.name.clone()));
+            }
+            if ids.insert(def.name.as_str(), TileId(i as u16)).is_some() {
+                return Err(TileSetError::DuplicateTile(def.name.clone()));
+            }
+        }
+
+        let mut neighbours: Vec<[Vec<TileId>; 8]> = vec![Default::default(); defs.len()];
+        for (i, def) in defs.iter().enumerate() {
+            let everywhere = def
+                .neighbours
+                .iter()
+                .flat_map(|name| Direction::ALL.map(|direction| (direction, name)));
+            let sides = def
+                .sides
+                .iter()
+                .flat_map(|(direction, names)| names.iter().map(|name| (*direction, name)));
+
+            for (direction, name) in everywhere.chain(sides) {
+                let Some(&neighbour) = ids.get(name.as_str()) else {
+                    return Err(TileSetError::UnknownNeighbour {
+                        tile: def.name.clone(),
+                        neighbour: name.clone(),
+                    });
+                };
+
+                neighbours[i][direction as usize].push(neighbour);
+                neighbours[neighbour.0 as usize][direction.opposite() as usize]
+                    .push(TileId(i as u16));
+            }
+        }
+
+        let edged = defs
+            .iter()
+            .enumerate()
+            .filter_map(|(i, def)| def.edges.as_ref().map(|edges| (i, edges)))
+            .collect::<Vec<_>>();
+        for &(i, edges) in &edged {
+            for &(j, other) in &edged {
+                for direction in Direction::ALL {
+                    let side = direction as usize;
+                    // Only the sides of the "+" shape have edges, the diagonals touch at a corner
+                    if side % 2 == 1 || edges[side / 2] == other[(side / 2 + 2) % 4] {
+                        neighbours[i][side].push(TileId(j as u16));
+                    }
+                }
+            }
+        }
+
+        for (i, def) in defs.iter().enumerate() {
+            let transition = TileId(i as u16);
+            for (a, b) in &def.between {
+                let id = |name: &String| {
+                    ids.get(name.as_str())
+                        .copied()
+                        .ok_or_else(|| TileSetError::UnknownNeighbour {
+                            tile: def.name.clone(),
+                            neighbour: name.clone(),
+                        })
+                };
+                let (a, b) = (id(a)?, id(b)?);
+
+                for direction in Direction::ALL {
+                    let side = direction as usize;
+                    neighbours[a.0 as usize][side].retain(|&tile| tile != b);
+                    neighbours[b.0 as usize][side].retain(|&tile| tile != a);
+                    for tile in [a, b, transition] {
+                        neighbours[i][side].push(tile);
+                        neighbours[tile.0 as usize][side].push(transition);
+                    }
+                }
+            }
+        }
+
+        for list in neighbours.iter_mut().flatten() {
+            list.sort();
+            list.dedup();
+        }
+
+        let masks = neighbours
+            .iter()
+            .map(|lists| {
+                lists
+                    .each_ref()
+                    .map(|list| DomainMask::from_tiles(defs.len(), list.iter().copied()))
+            })
+            .collect();
+        // The names are borrowed from `defs`, and `hashbrown`'s maps hold them until dropped
+        drop(ids);
+
+        Ok(TileSet {
+            defs,
+            neighbours,
+            masks,
+        })
+    }
+
+    /// Adds a tile to the set at runtime, along with its rotations if it has a
+    /// [`SymmetryClass`]. Its rules may name the tiles already in the set, which keep their
+    /// [`TileId`]s. Returns the id of the new tile, its rotations follow right after it.
+    ///
+    /// Fails like [`TileSet::new`], without changing the set. Generators and plots share the set
+    /// they were created with, so they don't see the new tile.
+    pub fn register(&mut self, def: TileDef) -> Result<TileId, TileSetError> {
+        let id = TileId(self.defs.len().min(u16::MAX as usize) as u16);
+        let mut defs = self.defs.clone();
+        defs.push(def);
+
+        *self = TileSet::new(defs)?;
+        Ok(id)
+    }
+
+    /// The built-in set made of every [`Tile`], with [`TileId`]s in the order of the enum.
+    pub fn terrain() -> Self {
+        let defs = Tile::ALL
+            .iter()
+            .map(|tile| TileDef {
+                name: format!("{tile:?}"),
+                glyph: tile.as_str().to_string(),
+                weight: tile.weight(),
+                color: tile.color(),
+                neighbours: tile
+                    .valid_neighbours()
+                    .iter()
+                    .map(|neighbour| format!("{neighbour:?}"))
+                    .collect(),
+                sides: HashMap::new(),
+                symmetry: None,
+                edges: None,
+                between: vec![],
+                metadata: HashMap::new(),
+            })
+            .collect();
+
+        TileSet::new(defs).expect("The built-in terrain is a valid tile set")
+    }
+
+    /// Learns the rules and weights of the [`TileSet::terrain`] tiles from an example map drawn
+    /// as text, one character per field as given by `legend`, instead of writing them down.
+    ///
+    /// Two tiles may lie next to each other on a side if they do so somewhere in the sample, and
+    /// every tile is weighted by how often it occurs, so tiles missing from the sample never get
+    /// picked. The [`TileId`]s stay those of the terrain.
+    pub fn infer_from_text(
+        sample: &str,
+        legend: &HashMap<char, Tile>,
+    ) -> Result<Self, TileSetError> {
+        let rows = sample
+            .lines()
+            .map(|line| {
+                line.chars()
+                    .map(|glyph| {
+                        legend
+                            .get(&glyph)
+                            .copied()
+                            .ok_or(TileSetError::UnknownGlyph(glyph))
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut counts: HashMap<Tile, usize> = HashMap::new();
+        let mut sides: HashMap<(Tile, Direction), Vec<Tile>> = HashMap::new();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &tile) in row.iter().enumerate() {
+                *counts.entry(tile).or_insert(0) += 1;
+                for direction in Direction::ALL {
+                    let (dx, dy) = direction.offset();
+                    let neighbour = y
+                        .checked_add_signed(dy)
+                        .and_then(|ny| rows.get(ny))
+                        .zip(x.checked_add_signed(dx))
+                        .and_then(|(row, nx)| row.get(nx));
+                    if let Some(&neighbour) = neighbour {
+                        let seen = sides.entry((tile, direction)).or_default();
+                        if !seen.contains(&neighbour) {
+                            seen.push(neighbour);
+                        }
+                    }
+                }
+            }
+        }
+
+        let defs = Tile::ALL
+            .iter()
+            .map(|&tile| {
+                let sides = Direction::ALL
+                    .into_iter()
+                    .filter_map(|direction| {
+                        let seen = sides.get(&(tile, direction))?;
+                        Some((direction, seen.iter().map(|t| format!("{t:?}")).collect()))
+                    })
+                    .collect();
+                TileDef {
+                    name: format!("{tile:?}"),
+                    glyph: tile.as_str().to_string(),
+                    weight: counts.get(&tile).copied().unwrap_or(0) as f64,
+                    color: tile.color(),
+                    neighbours: vec![],
+                    sides,
+                    symmetry: None,
+                    edges: None,
+                    between: vec![],
+                    metadata: HashMap::new(),
+                }
+            })
+            .collect();
+
+        TileSet::new(defs)
+    }
+
+    /// The built-in world: the [`TileSet::terrain`] tiles, with the same [`TileId`]s, plus Forest,
+    /// Mountain, Lake, Desert, Road and Village. Its rules live in `tilesets/world.ron`.
+    #[cfg(feature = "serde")]
+    pub fn world() -> Self {
+        TileSet::from_ron(include_str!("../tilesets/world.ron"))
+            .expect("The built-in world is a valid tile set")
+    }
+
+    /// The built-in dungeon: Wall, Floor, Corridor and a Door with a variant for every side of a
+    /// room, leading from its Floor to a Corridor. Its rules live in `tilesets/dungeon.ron`, see
+    /// [`Plot::connect_regions`](crate::Plot::connect_regions) to make every room reachable.
+    #[cfg(feature = "serde")]
+    pub fn dungeon() -> Self {
+        TileSet::from_ron(include_str!("../tilesets/dungeon.ron"))
+            .expect("The built-in dungeon is a valid tile set")
+    }
+
+    /// The built-in island: Ocean, Beach, Grassland, Forest and Mountain, rising from the coast
+    /// inland. Its rules live in `tilesets/island.ron`, see
+    /// [`IslandGenerator`](crate::IslandGenerator) to surround the land with ocean.
+    #[cfg(feature = "serde")]
+    pub fn island() -> Self {
+        TileSet::from_ron(include_str!("../tilesets/island.ron"))
+            .expect("The built-in island is a valid tile set")
+    }
+
+    /// Parses a set from the RON format shown in the [`TileSet`] docs.
+    #[cfg(feature = "serde")]
+    pub fn from_ron(src: &str) -> Result<Self, TileSetError> {
+        ron::from_str(src).map_err(TileSetError::Parse)
+    }
+
+    /// Loads a set from a RON file, see [`TileSet::from_ron`].
+    #[cfg(feature = "serde")]
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, TileSetError> {
+        let src = std::fs::read_to_string(path).map_err(TileSetError::Io)?;
+        TileSet::from_ron(&src)
+    }
+
+    /// How many tiles the set contains.
+    pub fn len(&self) -> usize {
+        self.defs.len()
+    }
+
+    /// A set is never empty, [`TileSet::new`] rejects sets without tiles.
+    pub fn is_empty(&self) -> bool {
+        self.defs.is_empty()
+    }
+
+    /// Every tile of the set, in order.
+    pub fn ids(&self) -> impl Iterator<Item = TileId> {
+        (0..self.defs.len()).map(|i| TileId(i as u16))
+    }
+
+    /// Looks up a tile by its name.
+    pub fn id(&self, name: &str) -> Option<TileId> {
+        self.ids().find(|id| self.name(*id) == name)
+    }
+
+    pub fn def(&self, tile: TileId) -> &TileDef {
+        &self.defs[tile.0 as usize]
+    }
+
+    pub fn name(&self, tile: TileId) -> &str {
+        &self.def(tile).name
+    }
+
+    pub fn glyph(&self, tile: TileId) -> &str {
+        &self.def(tile).glyph
+    }
+
+    pub fn weight(&self, tile: TileId) -> f64 {
+        self.def(tile).weight
+    }
+
+    pub fn color(&self, tile: TileId) -> [u8; 3] {
+        self.def(tile).color
+    }
+
+    /// The attributes of `tile`, see [`TileDef::metadata`].
+    pub fn metadata(&self, tile: TileId) -> &HashMap<String, MetaValue> {
+        &self.def(tile).metadata
+    }
+
+    /// How hard it is to move over `tile`, its `"cost"` [`TileDef::metadata`]. Defaults to 1.
+    pub fn cost(&self, tile: TileId) -> f64 {
+        self.metadata(tile)
+            .get("cost")
+            .and_then(MetaValue::as_f64)
+            .unwrap_or(1.0)
+    }
+
+    /// Whether `tile` can be walked over, its `"passable"` [`TileDef::metadata`]. Defaults to
+    /// true.
+    pub fn passable(&self, tile: TileId) -> bool {
+        self.metadata(tile)
+            .get("passable")
+            .and_then(MetaValue::as_bool)
+            .unwrap_or(true)
+    }
+
+    /// Whether `tile` stands for nothing, like an empty field of a feature layer, its
+    /// `"transparent"` [`TileDef::metadata`]. Defaults to false. [`Plot::to_image`] leaves such
+    /// fields transparent, so the layers below show through.
+    ///
+    /// [`Plot::to_image`]: crate::Plot::to_image
+    pub fn transparent(&self, tile: TileId) -> bool {
+        self.metadata(tile)
+            .get("transparent")
+            .and_then(MetaValue::as_bool)
+            .unwrap_or(false)
+    }
+
+    /// The tiles that may lie next to `tile` in `direction`.
+    pub fn valid_neighbours(&self, tile: TileId, direction: Direction) -> &[TileId] {
+        &self.neighbours[tile.0 as usize][direction as usize]
+    }
+
+    /// [`TileSet::valid_neighbours`] as a mask.
+    pub fn neighbour_mask(&self, tile: TileId, direction: Direction) -> &DomainMask {
+        &self.masks[tile.0 as usize][direction as usize]
+    }
+
+    /// A hash of the names and rules of the tiles, the same on every platform and Rust version,
+    /// e.g. to recognize the set a saved [`World`](crate::World) was generated with. Glyphs,
+    /// weights and the other looks of the tiles don't change it.
+    pub fn fingerprint(&self) -> u64 {
+        // 64 bit FNV-1a
+        let mut hash = 0xCBF2_9CE4_8422_2325_u64;
+        let mut write = |bytes: &[u8]| {
+            for byte in bytes {
+                hash = (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01B3);
+            }
+        };
+
+        for (def, lists) in self.defs.iter().zip(&self.neighbours) {
+            write(def.name.as_bytes());
+            write(&[0xFF]);
+            for list in lists {
+                for neighbour in list {
+                    write(&neighbour.0.to_le_bytes());
+                }
+                write(&[0xFE]);
+            }
+        }
+        hash
+    }
+}
+
+impl SymmetryClass {
+    /// How many distinct rotations a tile of this class has.
+    pub fn rotations(&self) -> usize {
+        match self {
+            SymmetryClass::X => 1,
+            SymmetryClass::I => 2,
+            SymmetryClass::T | SymmetryClass::L => 4,
+        }
+    }
+
+    /// The rotation a tile with `rotation` ends up in when mirrored from west to east.
+    fn mirror(&self, rotation: usize) -> usize {
+        match self {
+            SymmetryClass::X | SymmetryClass::I => rotation,
+            SymmetryClass::T => (4 - rotation) % 4,
+            // A bend from north to east mirrors into one from north to west, 3 turns
+            SymmetryClass::L => (7 - rotation) % 4,
+        }
+    }
+}
+
+/// Replaces every tile with a [`SymmetryClass`] by its variants, with their rules rotated and
+/// mirrored along. The variants have no symmetry class anymore, so expanding them again changes
+/// nothing.
+fn expand_symmetries(defs: Vec<TileDef>) -> Result<Vec<TileDef>, TileSetError> {
+    if defs.iter().all(|def| def.symmetry.is_none()) {
+        return Ok(defs);
+    }
+
+    // Every variant as `(def, its symmetry class and rotation)`
+    let mut variants = vec![];
+    for (i, def) in defs.iter().enumerate() {
+        match def.symmetry {
+            Some(class) => variants.extend((0..class.rotations()).map(|r| (i, Some((class, r))))),
+            None => variants.push((i, None)),
+        }
+    }
+    let name = |(i, symmetry): (usize, Option<(SymmetryClass, usize)>)| match symmetry {
+        Some((_, rotation)) if rotation > 0 => format!("{} {rotation}", defs[i].name),
+        _ => defs[i].name.clone(),
+    };
+
+    let mut ids = HashMap::new();
+    for (v, variant) in variants.iter().enumerate() {
+        if ids.insert(name(*variant), v).is_some() {
+            return Err(TileSetError::DuplicateTile(name(*variant)));
+        }
+    }
+
+    // Rotates by `turns` after mirroring if `mirrored`
+    let transform = |(i, symmetry): (usize, Option<(SymmetryClass, usize)>), turns, mirrored| {
+        let symmetry = symmetry.map(|(class, rotation)| {
+            let rotation = if mirrored {
+                class.mirror(rotation)
+            } else {
+                rotation
+            };
+            (class, (rotation + turns) % class.rotations())
+        });
+        (i, symmetry)
+    };
+    let transform_direction = |direction: Direction, turns: usize, mirrored| {
+        let i = direction as usize;
+        let i = if mirrored { (8 - i) % 8 } else { i };
+        Direction::ALL[(i + 2 * turns) % 8]
+    };
+
+    let mut expanded = variants
+        .iter()
+        .map(|&variant| TileDef {
+            name: name(variant),
+            neighbours: vec![],
+            sides: HashMap::new(),
+            symmetry: None,
+            // The edge on side `k` moves `rotation` sides further clockwise
+            edges: defs[variant.0].edges.clone().map(|edges| {
+                let rotation = variant.1.map_or(0, |(_, rotation)| rotation);
+                core::array::from_fn(|k| edges[(k + 4 - rotation) % 4].clone())
+            }),
+            ..defs[variant.0].clone()
+        })
+        .collect::<Vec<_>>();
+
+    for (i, def) in defs.iter().enumerate() {
+        let transforms = match def.symmetry {
+            Some(_) => (0..4)
+                .flat_map(|turns| [(turns, false), (turns, true)])
+                .collect(),
+            None => vec![(0, false)],
+        };
+        let everywhere = def.neighbours.iter().map(|name| (None, name));
+        let sides = def
+            .sides
+            .iter()
+            .flat_map(|(direction, names)| names.iter().map(|name| (Some(*direction), name)));
+
+        for (direction, neighbour) in everywhere.chain(sides) {
+            let Some(&neighbour) = ids.get(neighbour) else {
+                return Err(TileSetError::UnknownNeighbour {
+                    tile: def.name.clone(),
+                    neighbour: neighbour.clone(),
+                });
+            };
+
+            for &(turns, mirrored) in &transforms {
+                let tile = transform((i, def.symmetry.map(|class| (class, 0))), turns, mirrored);
+                let neighbour = name(transform(variants[neighbour], turns, mirrored));
+                let tile = &mut expanded[ids[&name(tile)]];
+
+                match direction {
+                    Some(direction) => tile
+                        .sides
+                        .entry(transform_direction(direction, turns, mirrored))
+                        .or_default()
+                        .push(neighbour),
+                    None => tile.neighbours.push(neighbour),
+                }
+            }
+        }
+    }
+
+    for def in &mut expanded {
+        for list in core::iter::once(&mut def.neighbours).chain(def.sides.values_mut()) {
+            list.sort();
+            list.dedup();
+        }
+    }
+
+    Ok(expanded)
+}
+
+impl MetaValue {
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            MetaValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            MetaValue::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// The value as a number, integers included.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            MetaValue::Int(value) => Some(*value as f64),
+            MetaValue::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            MetaValue::Text(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl Default for TileSet {
+    fn default() -> Self {
+        TileSet::terrain()
+    }
+}
+
+impl From<Tile> for TileId {
+    fn from(tile: Tile) -> Self {
+        TileId(tile as u16)
+    }
+}
+
+impl PartialEq<Tile> for TileId {
+    fn eq(&self, tile: &Tile) -> bool {
+        *self == TileId::from(*tile)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<TileSetFile> for TileSet {
+    type Error = TileSetError;
+
+    fn try_from(file: TileSetFile) -> Result<Self, Self::Error> {
+        TileSet::new(file.tiles)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<TileSet> for TileSetFile {
+    fn from(tileset: TileSet) -> Self {
+        TileSetFile {
+            tiles: tileset.defs,
+        }
+    }
+}
+
+impl Display for TileSetError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            TileSetError::Io(err) => write!(f, "Can't read the tile set: {err}"),
+            #[cfg(feature = "serde")]
+            TileSetError::Parse(err) => write!(f, "Can't parse the tile set: {err}"),
+            TileSetError::Empty => write!(f, "The tile set contains no tiles"),
+            TileSetError::TooManyTiles(count) => {
+                write!(f, "The tile set contains too many tiles ({count})")
+            }
+            TileSetError::DuplicateTile(name) => write!(f, "The tile {name} is defined twice"),
+            TileSetError::UnknownNeighbour { tile, neighbour } => {
+                write!(f, "The tile {tile} lists the unknown neighbour {neighbour}")
+            }
+            TileSetError::NegativeWeight(name) => {
+                write!(f, "The tile {name} has a negative weight")
+            }
+            TileSetError::EmptyGlyph(name) => write!(f, "The tile {name} has an empty glyph"),
+            TileSetError::UnknownGlyph(glyph) => {
+                write!(
+                    f,
+                    "The sample contains {glyph:?}, which isn't in the legend"
+                )
+            }
+        }
+    }
+}
+
+impl core::error::Error for TileSetError {}
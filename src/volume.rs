@@ -0,0 +1,478 @@
+use crate::generator::{shannon_entropy, Entropy, BACKTRACK_DEPTH, ENTROPY_NOISE};
+use crate::prelude::*;
+use crate::{
+    Direction, DomainMask, Plot, TileId, TileSet, WaveState, WfcError, DEFAULT_BACKTRACK_BUDGET,
+};
+use alloc::collections::{BinaryHeap, VecDeque};
+use alloc::sync::Arc;
+use core::cmp::Reverse;
+use core::fmt::Display;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
+use std::io::Write;
+
+/// Collapses a `width` x `height` x `depth` grid of fields into a [`Volume`], e.g. a dungeon or
+/// voxel terrain.
+///
+/// Every field has 6 neighbours. The tiles next to each other in the same layer follow the
+/// north, east, south and west rules of the [`TileSet`], the diagonal ones are ignored. Which
+/// tiles may lie on top of each other is set with [`VolumeGenerator::with_vertical_rule`],
+/// without any rule every tile may lie on every other one.
+pub struct VolumeGenerator {
+    width: usize,
+    height: usize,
+    depth: usize,
+    tileset: Arc<TileSet>,
+    tiles: Vec<WaveState>,
+    /// The tiles that may lie on top of every tile.
+    above: Vec<DomainMask>,
+    /// The tiles that may lie below every tile, the other way around of `above`.
+    below: Vec<DomainMask>,
+    rng: ChaCha12Rng,
+    history: VecDeque<Snapshot>,
+    /// Uncollapsed fields as `(entropy with noise, possibilities, index)`, lowest entropy first,
+    /// like in a [`PlotGenerator`](crate::PlotGenerator).
+    entropies: BinaryHeap<Reverse<(Entropy, usize, usize)>>,
+    backtrack_budget: usize,
+}
+
+/// The wave right before a field got observed, used for backtracking.
+struct Snapshot {
+    tiles: Vec<WaveState>,
+    index: usize,
+    tile: TileId,
+}
+
+/// A fully collapsed `width` x `height` x `depth` grid of tiles. Layer 0 is the bottom one.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Volume {
+    width: usize,
+    height: usize,
+    depth: usize,
+    tileset: Arc<TileSet>,
+    tiles: Vec<TileId>,
+}
+
+/// Where a neighbour lies, seen from a field.
+#[derive(Debug, Clone, Copy)]
+enum Side {
+    /// In the same layer.
+    Level(Direction),
+    Above,
+    Below,
+}
+
+/// The directions a field has neighbours in, within its layer.
+const HORIZONTAL: [Direction; 4] = [
+    Direction::North,
+    Direction::East,
+    Direction::South,
+    Direction::West,
+];
+
+impl VolumeGenerator {
+    /// Creates a generator for a `width` x `height` x `depth` volume made of the tiles in
+    /// `tileset`, with every field in superposition.
+    pub fn new(
+        tileset: impl Into<Arc<TileSet>>,
+        width: usize,
+        height: usize,
+        depth: usize,
+    ) -> Self {
+        let tileset = tileset.into();
+        let full = DomainMask::full(tileset.len());
+
+        let mut gen = VolumeGenerator {
+            width,
+            height,
+            depth,
+            tiles: vec![WaveState::Superposition(full.clone()); width * height * depth],
+            above: vec![full.clone(); tileset.len()],
+            below: vec![full; tileset.len()],
+            tileset,
+            rng: random_rng(),
+            history: VecDeque::new(),
+            entropies: BinaryHeap::new(),
+            backtrack_budget: DEFAULT_BACKTRACK_BUDGET,
+        };
+        gen.rebuild_entropies();
+
+        gen
+    }
+
+    /// Draws every random decision from a generator seeded with `seed`, see
+    /// [`PlotGenerator::with_seed`](crate::PlotGenerator::with_seed).
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = ChaCha12Rng::seed_from_u64(seed);
+        self.rebuild_entropies();
+        self
+    }
+
+    /// Only allows the `above` tiles on top of `tile`, so `tile` only lies below them. Rules go
+    /// both ways like the rules of a [`TileSet`].
+    ///
+    /// Several rules for the same tile have to hold all at once.
+    pub fn with_vertical_rule<T: Copy + Into<TileId>>(
+        mut self,
+        tile: impl Into<TileId>,
+        above: &[T],
+    ) -> Self {
+        let tile = tile.into();
+        let allowed = DomainMask::from_tiles(self.tileset.len(), above.iter().map(|t| (*t).into()));
+        self.above[tile.0 as usize].intersect_with(&allowed);
+
+        for other in self.tileset.ids() {
+            if !self.above[tile.0 as usize].contains(other) {
+                self.below[other.0 as usize].remove(tile);
+            }
+        }
+        self
+    }
+
+    /// Gives up with a [`WfcError::VolumeContradiction`] after backtracking out of `budget`
+    /// contradictions.
+    pub fn with_backtrack_budget(mut self, budget: usize) -> Self {
+        self.backtrack_budget = budget;
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn tileset(&self) -> &TileSet {
+        &self.tileset
+    }
+
+    /// The state of the field at `(x, y, z)`.
+    pub fn get(&self, x: usize, y: usize, z: usize) -> &WaveState {
+        &self.tiles[self.index((x, y, z))]
+    }
+
+    /// Collapses the field at `(x, y, z)` into `tile` and propagates it right away, e.g. to lay
+    /// a floor before [`VolumeGenerator::collapse`].
+    ///
+    /// Fails with a [`WfcError::VolumeContradiction`] if the field can't become `tile` anymore,
+    /// or if propagating it empties another field.
+    pub fn set_tile(
+        &mut self,
+        x: usize,
+        y: usize,
+        z: usize,
+        tile: impl Into<TileId>,
+    ) -> Result<(), WfcError> {
+        let (tile, index) = (tile.into(), self.index((x, y, z)));
+        if !self.tiles[index].contains(tile) {
+            return Err(WfcError::VolumeContradiction((x, y, z)));
+        }
+
+        self.tiles[index] = WaveState::Collapsed(tile);
+        self.propagate(index)
+    }
+
+    /// Collapses every field, see [`PlotGenerator::collapse`](crate::PlotGenerator::collapse).
+    ///
+    /// Backtracks out of contradictions the same way, and fails once that doesn't resolve them.
+    pub fn collapse(&mut self) -> Result<(), WfcError> {
+        while let Some(index) = self.choose_lowest_entropy() {
+            let tiles = self.tiles.clone();
+            match self.observe(index) {
+                Ok(tile) => {
+                    if self.history.len() == BACKTRACK_DEPTH {
+                        self.history.pop_front();
+                    }
+                    self.history.push_back(Snapshot { tiles, index, tile });
+                }
+                Err(err) => {
+                    if self.backtrack_budget == 0 {
+                        return Err(err);
+                    }
+                    let Some(snapshot) = self.history.pop_back() else {
+                        return Err(err);
+                    };
+
+                    self.backtrack_budget -= 1;
+                    self.tiles = snapshot.tiles;
+                    if let WaveState::Superposition(states) = &mut self.tiles[snapshot.index] {
+                        states.remove(snapshot.tile);
+                    }
+                    self.rebuild_entropies();
+                    // A contradiction here shows up as an empty field, which gets picked next
+                    let _ = self.propagate(snapshot.index);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Panics if a field is still in superposition, e.g. because [`VolumeGenerator::collapse`]
+    /// failed.
+    pub fn into_volume(self) -> Volume {
+        let tiles = self
+            .tiles
+            .iter()
+            .map(|state| match state {
+                WaveState::Collapsed(tile) => *tile,
+                WaveState::Superposition(_) => panic!("Found not collapsed tile"),
+            })
+            .collect();
+
+        Volume {
+            width: self.width,
+            height: self.height,
+            depth: self.depth,
+            tileset: self.tileset,
+            tiles,
+        }
+    }
+
+    /// Collapses the field at `index` into one of its states, picked by their weights, and
+    /// propagates it.
+    fn observe(&mut self, index: usize) -> Result<TileId, WfcError> {
+        let states = self.tiles[index]
+            .states()
+            .map(|t| (t, self.tileset.weight(t)))
+            .collect::<Vec<_>>();
+
+        let (tile, _) = *states
+            .choose_weighted(&mut self.rng, |(_, weight)| *weight)
+            .map_err(|_| WfcError::VolumeContradiction(self.pos(index)))?;
+
+        self.tiles[index] = WaveState::Collapsed(tile);
+        self.propagate(index)?;
+
+        Ok(tile)
+    }
+
+    /// Removes the states that don't fit anymore from the neighbours of the field at `index`,
+    /// and from their neighbours in turn, until no field changes anymore.
+    fn propagate(&mut self, index: usize) -> Result<(), WfcError> {
+        let mut stack = vec![index];
+        let mut allowed = DomainMask::empty(self.tileset.len());
+
+        while let Some(index) = stack.pop() {
+            let (x, y, z) = self.pos(index);
+
+            let mut neighbours = vec![];
+            for direction in HORIZONTAL {
+                let (dx, dy) = direction.offset();
+                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                if (0..self.width as isize).contains(&nx) && (0..self.height as isize).contains(&ny)
+                {
+                    neighbours.push((Side::Level(direction), (nx as usize, ny as usize, z)));
+                }
+            }
+            if z + 1 < self.depth {
+                neighbours.push((Side::Above, (x, y, z + 1)));
+            }
+            if z > 0 {
+                neighbours.push((Side::Below, (x, y, z - 1)));
+            }
+
+            for (side, neighbour) in neighbours {
+                allowed.clear();
+                for t in self.tiles[index].states() {
+                    let mask = match side {
+                        Side::Level(direction) => self.tileset.neighbour_mask(t, direction),
+                        Side::Above => &self.above[t.0 as usize],
+                        Side::Below => &self.below[t.0 as usize],
+                    };
+                    allowed.union_with(mask);
+                }
+
+                let neighbour_index = self.index(neighbour);
+                if let WaveState::Superposition(states) = &mut self.tiles[neighbour_index] {
+                    if states.intersect_with(&allowed) {
+                        if states.is_empty() {
+                            return Err(WfcError::VolumeContradiction(neighbour));
+                        }
+                        stack.push(neighbour_index);
+                        self.push_entropy(neighbour_index);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Pops the entropy heap until an entry still matches its field. `None` once every field is
+    /// collapsed.
+    fn choose_lowest_entropy(&mut self) -> Option<usize> {
+        while let Some(Reverse((_, count, index))) = self.entropies.pop() {
+            if matches!(&self.tiles[index], WaveState::Superposition(states) if states.len() == count)
+            {
+                return Some(index);
+            }
+        }
+
+        None
+    }
+
+    /// Queues the field at `index` with its current entropy, if it's uncollapsed.
+    fn push_entropy(&mut self, index: usize) {
+        if self.entropies.len() > 4 * self.tiles.len() {
+            return self.rebuild_entropies();
+        }
+
+        if let WaveState::Superposition(states) = &self.tiles[index] {
+            let entropy = shannon_entropy(states, |t| self.tileset.weight(t));
+            let noise = self.rng.gen::<f64>() * ENTROPY_NOISE;
+            let entry = (Entropy(entropy + noise), states.len(), index);
+            self.entropies.push(Reverse(entry));
+        }
+    }
+
+    fn rebuild_entropies(&mut self) {
+        self.entropies.clear();
+        for index in 0..self.tiles.len() {
+            self.push_entropy(index);
+        }
+    }
+
+    fn index(&self, (x, y, z): (usize, usize, usize)) -> usize {
+        (z * self.height + y) * self.width + x
+    }
+
+    fn pos(&self, index: usize) -> (usize, usize, usize) {
+        let layer = self.width * self.height;
+        (
+            index % self.width,
+            index % layer / self.width,
+            index / layer,
+        )
+    }
+}
+
+impl Volume {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn tileset(&self) -> &TileSet {
+        &self.tileset
+    }
+
+    /// The tile at `(x, y, z)`.
+    pub fn get(&self, x: usize, y: usize, z: usize) -> TileId {
+        self.tiles[(z * self.height + y) * self.width + x]
+    }
+
+    /// The layer at height `z` as a plot.
+    pub fn layer(&self, z: usize) -> Plot {
+        let size = self.width * self.height;
+        let tiles = self.tiles[z * size..(z + 1) * size].to_vec();
+        Plot::from_tiles(Arc::clone(&self.tileset), self.width, self.height, tiles)
+    }
+
+    /// Writes the volume as a [MagicaVoxel](https://ephtracy.github.io/) `.vox` file, with one
+    /// voxel per field in the color of its tile. Fields holding one of the `empty` tiles, like
+    /// air, are left out.
+    ///
+    /// The format allows at most 256 fields along every axis and 255 tiles.
+    #[cfg(feature = "std")]
+    pub fn write_vox<T: Copy + Into<TileId>>(
+        &self,
+        mut writer: impl Write,
+        empty: &[T],
+    ) -> std::io::Result<()> {
+        assert!(
+            self.width <= 256 && self.height <= 256 && self.depth <= 256,
+            "A .vox volume can't be larger than 256 fields along any axis"
+        );
+        assert!(
+            self.tileset.len() <= 255,
+            "A .vox palette can't hold more than 255 tiles"
+        );
+
+        let empty = empty.iter().map(|t| (*t).into()).collect::<Vec<TileId>>();
+        let mut voxels = vec![];
+        for z in 0..self.depth {
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    let tile = self.get(x, y, z);
+                    if !empty.contains(&tile) {
+                        // Palette indices start at 1, 0 is nothing
+                        voxels.extend([x as u8, y as u8, z as u8, tile.0 as u8 + 1]);
+                    }
+                }
+            }
+        }
+
+        let mut size = vec![];
+        for n in [self.width, self.height, self.depth] {
+            size.extend((n as u32).to_le_bytes());
+        }
+        let mut xyzi = ((voxels.len() / 4) as u32).to_le_bytes().to_vec();
+        xyzi.extend(voxels);
+        let mut rgba = vec![0; 256 * 4];
+        for tile in self.tileset.ids() {
+            let [r, g, b] = self.tileset.color(tile);
+            let i = tile.0 as usize * 4;
+            rgba[i..i + 4].copy_from_slice(&[r, g, b, 255]);
+        }
+
+        let chunks = [(b"SIZE", size), (b"XYZI", xyzi), (b"RGBA", rgba)];
+        let children = chunks
+            .iter()
+            .map(|(_, content)| 12 + content.len())
+            .sum::<usize>();
+
+        writer.write_all(b"VOX ")?;
+        writer.write_all(&150u32.to_le_bytes())?;
+        write_chunk_header(&mut writer, b"MAIN", 0, children)?;
+        for (id, content) in &chunks {
+            write_chunk_header(&mut writer, id, content.len(), 0)?;
+            writer.write_all(content)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the id and sizes that start every chunk of a `.vox` file.
+#[cfg(feature = "std")]
+fn write_chunk_header(
+    writer: &mut impl Write,
+    id: &[u8; 4],
+    content: usize,
+    children: usize,
+) -> std::io::Result<()> {
+    writer.write_all(id)?;
+    writer.write_all(&(content as u32).to_le_bytes())?;
+    writer.write_all(&(children as u32).to_le_bytes())
+}
+
+/// Prints every layer like a [`Plot`], from the bottom up, separated by empty lines.
+impl Display for Volume {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for z in 0..self.depth {
+            if z > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", self.layer(z))?;
+        }
+        Ok(())
+    }
+}
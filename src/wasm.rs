@@ -0,0 +1,73 @@
+//! JavaScript bindings, built with `wasm-pack build --features wasm`.
+//!
+//! Plots are handed over as RGBA pixels, one per field row by row, in the colors of the built-in
+//! [`TileSet::world`]. They fit straight into an `ImageData`:
+//!
+//! ```js
+//! const pixels = generate(64, 64, 42n);
+//! const image = new ImageData(new Uint8ClampedArray(pixels.buffer), 64);
+//! ```
+use crate::{PlotGenerator, Step, TileSet, WaveState};
+use wasm_bindgen::prelude::*;
+
+/// Collapses a `width` x `height` plot of the built-in world and returns its pixels.
+#[wasm_bindgen]
+pub fn generate(width: usize, height: usize, seed: u64) -> Result<Vec<u8>, JsError> {
+    let mut gen = Generator::new(width, height, seed);
+    gen.inner.collapse()?;
+    Ok(gen.pixels())
+}
+
+/// A [`PlotGenerator`] that gets collapsed step by step, e.g. to draw every step of the
+/// collapse in a browser.
+#[wasm_bindgen]
+pub struct Generator {
+    inner: PlotGenerator,
+}
+
+#[wasm_bindgen]
+impl Generator {
+    /// Creates a generator for a `width` x `height` plot of the built-in world.
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: usize, height: usize, seed: u64) -> Generator {
+        let inner = PlotGenerator::for_tileset(TileSet::world(), width, height).with_seed(seed);
+        Generator { inner }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> usize {
+        self.inner.width()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> usize {
+        self.inner.height()
+    }
+
+    /// Makes a single [`PlotGenerator::step`], returns `false` once every field is collapsed.
+    pub fn step(&mut self) -> Result<bool, JsError> {
+        Ok(self.inner.step()? != Step::Done)
+    }
+
+    /// The pixels of the plot so far. Fields in superposition are gray, the brighter the more
+    /// tiles they can still become.
+    pub fn pixels(&self) -> Vec<u8> {
+        let tileset = self.inner.tileset();
+        let mut pixels = Vec::with_capacity(self.width() * self.height() * 4);
+
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let [r, g, b] = match self.inner.get(x, y) {
+                    WaveState::Collapsed(tile) => tileset.color(*tile),
+                    WaveState::Superposition(states) => {
+                        let gray = (states.len() * 255 / tileset.len()) as u8;
+                        [gray; 3]
+                    }
+                };
+                pixels.extend([r, g, b, 255]);
+            }
+        }
+
+        pixels
+    }
+}
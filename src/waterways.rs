@@ -0,0 +1,220 @@
+use crate::grid::Grid;
+use crate::prelude::*;
+use crate::{Connectivity, Plot, TileId};
+use alloc::collections::VecDeque;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+/// Tidies up the water of a generated terrain and the roads crossing it, as a pass after the
+/// terrain and the feature layer on top of it got generated, e.g. by a
+/// [`LayeredGenerator`](crate::LayeredGenerator).
+///
+/// Rivers that don't reach the edge of the plot have nowhere to flow, so their groups become
+/// lakes: the fields surrounded by water on all eight sides become lake tiles, the ones at the
+/// shore stay river, see [`Waterways::with_lake`]. Roads of the feature layer crossing the water
+/// become bridges, and crossings a single river field wide become fords instead, as often as
+/// configured, see [`Waterways::with_fords`]. Crossings get written over the layer without looking
+/// at the adjacency rules, like [`RoadNetwork`](crate::RoadNetwork) does.
+///
+/// ```
+/// use std::collections::HashMap;
+/// use world_gen::{Plot, TileDef, TileSet, Waterways};
+///
+/// let world = TileSet::world();
+/// let (river, lake) = (world.id("River").unwrap(), world.id("Lake").unwrap());
+/// let legend = HashMap::from([('~', river), ('.', world.id("Wasteland").unwrap())]);
+/// let mut terrain = Plot::from_ascii(
+///     world,
+///     ".........\n.~~~~..~.\n.~~~~..~.\n.~~~~..~.\n.......~.",
+///     &legend,
+/// )
+/// .unwrap();
+///
+/// let features = TileSet::new(
+///     ["None", "Road", "Bridge", "Ford"]
+///         .into_iter()
+///         .map(|name| TileDef::new(name, &name[..2]))
+///         .collect(),
+/// )
+/// .unwrap();
+/// let [none, road, bridge, ford] =
+///     ["None", "Road", "Bridge", "Ford"].map(|name| features.id(name).unwrap());
+/// let legend = HashMap::from([('.', none), ('=', road)]);
+/// let mut layer = Plot::from_ascii(
+///     features,
+///     ".........\n.........\n=========\n.........\n.........",
+///     &legend,
+/// )
+/// .unwrap();
+///
+/// Waterways::new(river)
+///     .with_lake(lake)
+///     .with_bridges(road, bridge)
+///     .with_fords(ford, 1.0)
+///     .apply(&mut terrain, &mut layer);
+/// assert_eq!((terrain.get(2, 2), terrain.get(1, 2)), (lake, river));
+/// assert_eq!((layer.get(2, 2), layer.get(7, 2)), (bridge, ford));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Waterways {
+    river: TileId,
+    lake: Option<TileId>,
+    bridges: Option<(TileId, TileId)>,
+    fords: Option<(TileId, f64)>,
+    seed: u64,
+}
+
+impl Waterways {
+    /// Handles the water of terrains with `river` tiles, without lakes, bridges or fords yet.
+    pub fn new(river: impl Into<TileId>) -> Self {
+        Waterways {
+            river: river.into(),
+            lake: None,
+            bridges: None,
+            fords: None,
+            seed: random_seed(),
+        }
+    }
+
+    /// Fills the enclosed rivers with `lake` tiles, which count as water for the crossings as
+    /// well.
+    pub fn with_lake(mut self, lake: impl Into<TileId>) -> Self {
+        self.lake = Some(lake.into());
+        self
+    }
+
+    /// Replaces the `road` tiles of the feature layer on water by `bridge` tiles.
+    pub fn with_bridges(mut self, road: impl Into<TileId>, bridge: impl Into<TileId>) -> Self {
+        self.bridges = Some((road.into(), bridge.into()));
+        self
+    }
+
+    /// Lets a crossing over a single river field become a `ford` tile instead of a bridge, with
+    /// a chance of `frequency`, from 0 to 1. Lakes always get bridges. Only takes effect together
+    /// with [`Waterways::with_bridges`].
+    pub fn with_fords(mut self, ford: impl Into<TileId>, frequency: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&frequency),
+            "The frequency has to be between 0 and 1"
+        );
+        self.fords = Some((ford.into(), frequency));
+        self
+    }
+
+    /// Derives which crossings become fords from `seed`.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Fills the lakes of `terrain`, then places the crossings of `layer` over the water.
+    pub fn apply(&self, terrain: &mut Plot, layer: &mut Plot) {
+        self.fill_lakes(terrain);
+        self.place_crossings(terrain, layer);
+    }
+
+    /// Turns the groups of river fields that don't reach the edge of `terrain` into lakes, if a
+    /// lake tile is set. Returns the fields that became lake, group by group.
+    pub fn fill_lakes(&self, terrain: &mut Plot) -> Vec<Vec<(usize, usize)>> {
+        let Some(lake) = self.lake else {
+            return vec![];
+        };
+        let (width, height) = (terrain.width(), terrain.height());
+        let mut lakes = vec![];
+
+        for region in terrain.regions(self.river, Connectivity::Four) {
+            let ((left, top), (right, bottom)) = region.bounds();
+            if left == 0 || top == 0 || right == width - 1 || bottom == height - 1 {
+                // Drains over the edge
+                continue;
+            }
+
+            let water = |plot: &Plot, (x, y): (usize, usize)| {
+                let tile = plot.get(x, y);
+                tile == self.river || tile == lake
+            };
+            let inner = region
+                .fields()
+                .iter()
+                .copied()
+                .filter(|&pos| {
+                    Connectivity::Eight
+                        .neighbours(pos, width, height)
+                        .all(|neighbour| water(terrain, neighbour))
+                })
+                .collect::<Vec<_>>();
+            for &(x, y) in &inner {
+                terrain.set(x, y, lake);
+            }
+            if !inner.is_empty() {
+                lakes.push(inner);
+            }
+        }
+
+        lakes
+    }
+
+    /// Replaces the road and bridge tiles of `layer` on the water of `terrain` by bridges, or by
+    /// fords where the rules allow them, if bridges are set. Returns the crossings, the connected
+    /// groups of fields on the water.
+    pub fn place_crossings(&self, terrain: &Plot, layer: &mut Plot) -> Vec<Vec<(usize, usize)>> {
+        assert!(
+            terrain.width() == layer.width() && terrain.height() == layer.height(),
+            "The layer has to cover the terrain"
+        );
+        let Some((road, bridge)) = self.bridges else {
+            return vec![];
+        };
+        let (width, height) = (terrain.width(), terrain.height());
+        let mut rng = ChaCha12Rng::seed_from_u64(self.seed);
+
+        let crossing = |(x, y): (usize, usize)| {
+            let (tile, feature) = (terrain.get(x, y), layer.get(x, y));
+            let water = tile == self.river || Some(tile) == self.lake;
+            let on_road = feature == road
+                || feature == bridge
+                || self.fords.is_some_and(|(f, _)| f == feature);
+            water && on_road
+        };
+        let mut visited = Grid::new(width, height, false);
+        let mut crossings = vec![];
+        for pos in terrain.positions() {
+            if visited[pos] || !crossing(pos) {
+                continue;
+            }
+
+            // Flood fill the crossing
+            let mut fields = vec![pos];
+            let mut queue = VecDeque::from([pos]);
+            visited[pos] = true;
+            while let Some(pos) = queue.pop_front() {
+                for neighbour in visited.neighbours(pos, Connectivity::Four) {
+                    if !visited[neighbour] && crossing(neighbour) {
+                        visited[neighbour] = true;
+                        fields.push(neighbour);
+                        queue.push_back(neighbour);
+                    }
+                }
+            }
+            crossings.push(fields);
+        }
+
+        for fields in &crossings {
+            let tile = match self.fords {
+                Some((ford, frequency))
+                    if fields.len() == 1
+                        && terrain.get(fields[0].0, fields[0].1) == self.river
+                        && rng.gen_bool(frequency) =>
+                {
+                    ford
+                }
+                _ => bridge,
+            };
+            for &(x, y) in fields {
+                layer.set(x, y, tile);
+            }
+        }
+
+        crossings
+    }
+}
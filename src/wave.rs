@@ -0,0 +1,367 @@
+use crate::prelude::*;
+use crate::{DomainMask, TileId, WaveState};
+#[cfg(feature = "serde")]
+use alloc::collections::BTreeMap;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The states of the fields of a [`PlotGenerator`](crate::PlotGenerator), row by row, stored so
+/// huge grids fit into memory.
+///
+/// Every field takes a single code, one byte for tile sets of up to 254 tiles and four beyond:
+/// the tile of a collapsed field, or a marker for a field in superposition. Only the frontier,
+/// the fields in superposition that lost a tile already, keeps a [`DomainMask`] of its own,
+/// fields that can still become anything share one. A field of the frontier takes about
+/// `37 + 8 * ceil(tiles / 64)` bytes on top of its code.
+///
+/// Rules that reach far, like long chains of tiles, can put most of the grid into the frontier.
+/// Once it holds half of the fields, every field gets a [`WaveState`] of its own instead, 16
+/// bytes plus the mask, until the frontier shrinks to a quarter again.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "WaveFile", into = "WaveFile")
+)]
+pub(crate) struct Wave {
+    storage: Storage,
+    /// The collapsed state of every tile, shared by the fields with their code.
+    collapsed: Vec<WaveState>,
+    /// The superposition of every tile, shared by the fields that can still become anything.
+    full: WaveState,
+    /// Every tile, lent to [`Wave::remove`] so it doesn't build a mask on every call.
+    scratch: DomainMask,
+}
+
+#[derive(Debug, Clone)]
+enum Storage {
+    Sparse {
+        codes: Codes,
+        frontier: HashMap<usize, WaveState>,
+    },
+    Dense {
+        states: Vec<WaveState>,
+        /// How many of the states are superpositions without every tile.
+        frontier: usize,
+    },
+}
+
+/// The codes of the fields: [`FULL`], [`FRONTIER`] or 2 plus the id of the collapsed tile.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+enum Codes {
+    Narrow(Vec<u8>),
+    Wide(Vec<u32>),
+}
+
+/// The code of a field that can become any tile.
+const FULL: u32 = 0;
+/// The code of a field with a mask in the frontier.
+const FRONTIER: u32 = 1;
+
+/// A [`Wave`] as saved, always sparse and without the shared states. The frontier is ordered,
+/// so the same wave always gets saved the same way.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct WaveFile {
+    tile_count: usize,
+    codes: Codes,
+    frontier: BTreeMap<usize, WaveState>,
+}
+
+/// Why a [`WaveFile`] can't be loaded.
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+enum InvalidWave {
+    /// One byte codes can't tell this many tiles apart.
+    TooManyTiles(usize),
+    /// The code of the field at `index` is neither a marker nor a tile of the tile set.
+    Code { index: usize, code: u32 },
+    /// The field at this index is in the frontier, but has no mask.
+    MissingMask(usize),
+    /// The frontier holds a mask for the field at this index, but the field isn't in it.
+    StrayMask(usize),
+    /// The mask of the field at this index isn't a superposition of some of the tiles.
+    InvalidMask(usize),
+}
+
+impl Codes {
+    /// `fields` fields that can become any of `tile_count` tiles.
+    fn new(fields: usize, tile_count: usize) -> Self {
+        match tile_count + 2 <= u8::MAX as usize + 1 {
+            true => Codes::Narrow(vec![FULL as u8; fields]),
+            false => Codes::Wide(vec![FULL; fields]),
+        }
+    }
+
+    fn get(&self, index: usize) -> u32 {
+        match self {
+            Codes::Narrow(codes) => codes[index] as u32,
+            Codes::Wide(codes) => codes[index],
+        }
+    }
+
+    fn set(&mut self, index: usize, code: u32) {
+        match self {
+            Codes::Narrow(codes) => codes[index] = code as u8,
+            Codes::Wide(codes) => codes[index] = code,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Codes::Narrow(codes) => codes.len(),
+            Codes::Wide(codes) => codes.len(),
+        }
+    }
+}
+
+impl Storage {
+    /// Sparse storage of `states`, for tile sets of `tile_count` tiles.
+    fn sparse(states: impl ExactSizeIterator<Item = WaveState>, tile_count: usize) -> Self {
+        let mut codes = Codes::new(states.len(), tile_count);
+        let mut frontier = HashMap::new();
+        for (index, state) in states.enumerate() {
+            let code = match &state {
+                WaveState::Collapsed(tile) => tile.0 as u32 + 2,
+                WaveState::Superposition(states) if states.len() == tile_count => FULL,
+                WaveState::Superposition(_) => FRONTIER,
+            };
+            if code == FRONTIER {
+                frontier.insert(index, state);
+            }
+            codes.set(index, code);
+        }
+        Storage::Sparse { codes, frontier }
+    }
+}
+
+impl Wave {
+    /// `fields` fields that can become any of `tile_count` tiles.
+    pub(crate) fn new(fields: usize, tile_count: usize) -> Self {
+        Wave {
+            storage: Storage::Sparse {
+                codes: Codes::new(fields, tile_count),
+                frontier: HashMap::new(),
+            },
+            collapsed: (0..tile_count)
+                .map(|t| WaveState::Collapsed(TileId(t as u16)))
+                .collect(),
+            full: WaveState::Superposition(DomainMask::full(tile_count)),
+            scratch: DomainMask::full(tile_count),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match &self.storage {
+            Storage::Sparse { codes, .. } => codes.len(),
+            Storage::Dense { states, .. } => states.len(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The state of the field at row-major `index`.
+    pub(crate) fn get(&self, index: usize) -> &WaveState {
+        match &self.storage {
+            Storage::Sparse { codes, frontier } => match codes.get(index) {
+                FULL => &self.full,
+                FRONTIER => &frontier[&index],
+                code => &self.collapsed[(code - 2) as usize],
+            },
+            Storage::Dense { states, .. } => &states[index],
+        }
+    }
+
+    /// Replaces the state of the field at row-major `index`.
+    pub(crate) fn set(&mut self, index: usize, state: WaveState) {
+        let tile_count = self.collapsed.len();
+        let in_frontier = |state: &WaveState| matches!(state, WaveState::Superposition(states) if states.len() < tile_count);
+        match &mut self.storage {
+            Storage::Sparse { codes, frontier } => {
+                let code = match &state {
+                    WaveState::Collapsed(tile) => tile.0 as u32 + 2,
+                    _ if in_frontier(&state) => FRONTIER,
+                    WaveState::Superposition(_) => FULL,
+                };
+                match code {
+                    FRONTIER => frontier.insert(index, state),
+                    _ => frontier.remove(&index),
+                };
+                codes.set(index, code);
+            }
+            Storage::Dense { states, frontier } => {
+                *frontier -= in_frontier(&states[index]) as usize;
+                *frontier += in_frontier(&state) as usize;
+                states[index] = state;
+            }
+        }
+        self.rebalance();
+    }
+
+    /// Removes every tile missing in `allowed` from the field at row-major `index`, if it is in
+    /// superposition. Returns whether anything got removed.
+    pub(crate) fn intersect(&mut self, index: usize, allowed: &DomainMask) -> bool {
+        let tile_count = self.collapsed.len();
+        let changed = match &mut self.storage {
+            Storage::Sparse { codes, frontier } => match codes.get(index) {
+                FULL => {
+                    let mut state = self.full.clone();
+                    let WaveState::Superposition(states) = &mut state else {
+                        unreachable!("The full state is a superposition")
+                    };
+                    // Fields that keep every tile stay without a mask
+                    let changed = states.intersect_with(allowed);
+                    if changed {
+                        frontier.insert(index, state);
+                        codes.set(index, FRONTIER);
+                    }
+                    changed
+                }
+                FRONTIER => match frontier.get_mut(&index) {
+                    Some(WaveState::Superposition(states)) => states.intersect_with(allowed),
+                    _ => unreachable!("The frontier only holds superpositions"),
+                },
+                _ => false,
+            },
+            Storage::Dense { states, frontier } => match &mut states[index] {
+                WaveState::Superposition(states) => {
+                    let full = states.len() == tile_count;
+                    let changed = states.intersect_with(allowed);
+                    *frontier += (full && changed) as usize;
+                    changed
+                }
+                WaveState::Collapsed(_) => false,
+            },
+        };
+        self.rebalance();
+        changed
+    }
+
+    /// Removes `tile` from the field at row-major `index`, if it is in superposition.
+    pub(crate) fn remove(&mut self, index: usize, tile: TileId) {
+        let mut allowed = core::mem::replace(&mut self.scratch, DomainMask::empty(0));
+        allowed.remove(tile);
+        self.intersect(index, &allowed);
+        allowed.insert(tile);
+        self.scratch = allowed;
+    }
+
+    /// The states of the fields, row by row.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &WaveState> + '_ {
+        (0..self.len()).map(|index| self.get(index))
+    }
+
+    /// Switches between sparse and dense storage, whichever takes less memory for the size of
+    /// the frontier, see [`Wave`].
+    fn rebalance(&mut self) {
+        let len = self.len();
+        match &mut self.storage {
+            Storage::Sparse { frontier, .. } if frontier.len() > len / 2 => {
+                let frontier = frontier.len();
+                let states = self.iter().cloned().collect();
+                self.storage = Storage::Dense { states, frontier };
+            }
+            Storage::Dense { states, frontier } if *frontier < len / 4 => {
+                let states = core::mem::take(states);
+                self.storage = Storage::sparse(states.into_iter(), self.collapsed.len());
+            }
+            // Maps keep their memory when they shrink
+            Storage::Sparse { frontier, .. } if frontier.capacity() > 4 * frontier.len() + 64 => {
+                frontier.shrink_to(2 * frontier.len());
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<WaveFile> for Wave {
+    type Error = InvalidWave;
+
+    fn try_from(file: WaveFile) -> Result<Self, Self::Error> {
+        let tile_count = file.tile_count;
+        if matches!(file.codes, Codes::Narrow(_)) && tile_count + 2 > u8::MAX as usize + 1 {
+            return Err(InvalidWave::TooManyTiles(tile_count));
+        }
+        for index in 0..file.codes.len() {
+            match file.codes.get(index) {
+                FRONTIER if !file.frontier.contains_key(&index) => {
+                    return Err(InvalidWave::MissingMask(index))
+                }
+                code if code >= tile_count as u32 + 2 => {
+                    return Err(InvalidWave::Code { index, code })
+                }
+                _ => {}
+            }
+        }
+        for (&index, state) in &file.frontier {
+            if index >= file.codes.len() || file.codes.get(index) != FRONTIER {
+                return Err(InvalidWave::StrayMask(index));
+            }
+            match state {
+                WaveState::Superposition(states)
+                    if states.fits(tile_count) && states.len() < tile_count => {}
+                _ => return Err(InvalidWave::InvalidMask(index)),
+            }
+        }
+
+        let mut wave = Wave::new(0, tile_count);
+        wave.storage = Storage::Sparse {
+            codes: file.codes,
+            frontier: file.frontier.into_iter().collect(),
+        };
+        wave.rebalance();
+        Ok(wave)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Wave> for WaveFile {
+    fn from(wave: Wave) -> Self {
+        let tile_count = wave.collapsed.len();
+        let storage = match wave.storage {
+            Storage::Dense { states, .. } => Storage::sparse(states.into_iter(), tile_count),
+            sparse => sparse,
+        };
+        let Storage::Sparse { codes, frontier } = storage else {
+            unreachable!("Converted to sparse storage")
+        };
+        WaveFile {
+            tile_count,
+            codes,
+            frontier: frontier.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl core::fmt::Display for InvalidWave {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            InvalidWave::TooManyTiles(count) => {
+                write!(f, "One byte codes can't hold {count} tiles")
+            }
+            InvalidWave::Code { index, code } => {
+                write!(f, "Field {index} has code {code}, which is no tile")
+            }
+            InvalidWave::MissingMask(index) => {
+                write!(f, "Field {index} is in the frontier, but has no mask")
+            }
+            InvalidWave::StrayMask(index) => {
+                write!(
+                    f,
+                    "The frontier holds a mask for field {index}, which isn't in it"
+                )
+            }
+            InvalidWave::InvalidMask(index) => {
+                write!(
+                    f,
+                    "The mask of field {index} isn't a superposition of some tiles"
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,521 @@
+use crate::prelude::*;
+use crate::{Plot, PlotGenerator, TileId, TileSet, WfcError};
+use alloc::sync::Arc;
+use core::fmt::Display;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+/// The first bytes of every world file, see [`World::save`].
+const MAGIC: &[u8; 4] = b"WGEN";
+/// The version of the world file format written by [`World::save`].
+const VERSION: u16 = 1;
+/// The layer holding the heights of a chunk, as `f64`s.
+const ELEVATION_LAYER: u8 = 1;
+
+/// An endless world, generated in `chunk_size` x `chunk_size` [`Plot`]s on demand.
+///
+/// Chunks get generated in 4 phases by the parity of their coordinates: `(even, even)` chunks
+/// first, then `(odd, even)`, `(even, odd)` and `(odd, odd)` ones. A chunk takes the bordering
+/// fields of its neighbours from earlier phases as fixed constraints, generating them first if
+/// needed, so the seams between chunks follow the adjacency rules like every other pair of
+/// fields. Touching chunks are always of different phases.
+///
+/// Every chunk only depends on the seed, its coordinates and those neighbours, so the same seed
+/// gives the same world in whatever order the chunks get generated, e.g. by different players
+/// or after loading a save, see [`World::save`].
+pub struct World {
+    tileset: Arc<TileSet>,
+    chunk_size: usize,
+    seed: u64,
+    chunks: HashMap<(i64, i64), Plot>,
+}
+
+/// Errors that can happen while reading a world file, see [`World::load`].
+#[derive(Debug)]
+pub enum WorldFileError {
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    /// The file doesn't start like a world file.
+    NotAWorld,
+    /// The file has a version of the format this build can't read.
+    UnsupportedVersion(u16),
+    /// The world got generated with other tiles or rules, see [`TileSet::fingerprint`].
+    TileSetMismatch,
+    /// The file ends early or contains impossible values.
+    Corrupt,
+}
+
+/// Reads the numbers of a world file from the front of the bytes.
+struct Reader<'a>(&'a [u8]);
+
+impl World {
+    /// Creates an empty world of the built-in [`TileSet::terrain`].
+    pub fn new(chunk_size: usize) -> Self {
+        World::for_tileset(TileSet::terrain(), chunk_size)
+    }
+
+    /// Creates an empty world made of the tiles in `tileset`.
+    pub fn for_tileset(tileset: impl Into<Arc<TileSet>>, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "Chunks can't be empty");
+        World {
+            tileset: tileset.into(),
+            chunk_size,
+            seed: random_seed(),
+            chunks: HashMap::new(),
+        }
+    }
+
+    /// Derives the seed of every chunk from `seed`.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Generates a `width` x `height` plot of the built-in [`TileSet::terrain`] in chunks that
+    /// overlap, see [`World::generate_overlapping_for_tileset`].
+    pub fn generate_overlapping(
+        width: usize,
+        height: usize,
+        chunk: usize,
+        overlap: usize,
+        seed: u64,
+    ) -> Result<Plot, WfcError> {
+        World::generate_overlapping_for_tileset(
+            TileSet::terrain(),
+            width,
+            height,
+            chunk,
+            overlap,
+            seed,
+        )
+    }
+
+    /// Generates a `width` x `height` plot made of the tiles in `tileset` in `chunk` x `chunk`
+    /// pieces, row by row, each overlapping the pieces before it by `overlap` fields. Only the
+    /// half of an overlap closer to the earlier piece is kept, the other half gets collapsed
+    /// again along with the new piece, constrained by both. That leaves fewer seams than
+    /// stitching pieces at their edges. The pieces at the right and bottom edges get cut off.
+    ///
+    /// Every piece gets collapsed with a ring of one extra field around it, fixed to the fields
+    /// generated before, so the whole plot follows the adjacency rules. Fails with the first
+    /// piece that can't be collapsed, at the position in the plot where it ran into the
+    /// contradiction.
+    ///
+    /// Panics if `overlap` isn't smaller than `chunk`.
+    pub fn generate_overlapping_for_tileset(
+        tileset: impl Into<Arc<TileSet>>,
+        width: usize,
+        height: usize,
+        chunk: usize,
+        overlap: usize,
+        seed: u64,
+    ) -> Result<Plot, WfcError> {
+        assert!(
+            overlap < chunk,
+            "The overlap has to be smaller than the chunks"
+        );
+        let tileset = tileset.into();
+        let stride = chunk - overlap;
+        let keep = overlap / 2;
+        // Every piece starts a stride after the one before, until one reaches the edge
+        let starts = |len: usize| {
+            (0..)
+                .map(move |i| i * stride)
+                .take_while(move |&start| start == 0 || start + overlap < len)
+        };
+
+        let mut tiles: Vec<Option<TileId>> = vec![None; width * height];
+        for (cy, y0) in starts(height).enumerate() {
+            for (cx, x0) in starts(width).enumerate() {
+                let (w, h) = (chunk.min(width - x0), chunk.min(height - y0));
+                let seed = seed
+                    ^ (cx as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+                    ^ (cy as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+                let mut gen =
+                    PlotGenerator::for_tileset(Arc::clone(&tileset), w + 2, h + 2).with_seed(seed);
+                // The field of the plot at a field of the generator, `None` beyond the edges
+                let field = |gx: usize, gy: usize| {
+                    let (x, y) = ((x0 + gx).checked_sub(1)?, (y0 + gy).checked_sub(1)?);
+                    (x < width && y < height).then_some((x, y))
+                };
+                let contradiction = |(gx, gy): (usize, usize)| {
+                    let pos = ((x0 + gx).saturating_sub(1), (y0 + gy).saturating_sub(1));
+                    WfcError::Contradiction(pos)
+                };
+
+                for gy in 0..h + 2 {
+                    for gx in 0..w + 2 {
+                        let Some((x, y)) = field(gx, gy) else {
+                            continue;
+                        };
+                        let Some(tile) = tiles[y * width + x] else {
+                            continue;
+                        };
+                        // Only the sides bordering an earlier piece keep their half
+                        let ring = !(1..=w).contains(&gx) || !(1..=h).contains(&gy);
+                        let left = cx > 0 && gx <= keep;
+                        let top = cy > 0 && gy <= keep;
+                        if ring || left || top {
+                            gen.set_tile(gx, gy, tile)
+                                .map_err(|_| contradiction((gx, gy)))?;
+                        }
+                    }
+                }
+
+                gen.collapse().map_err(|err| match err {
+                    WfcError::Contradiction(pos) => contradiction(pos),
+                    err => err,
+                })?;
+                let piece = gen.into_plot();
+                for gy in 1..=h {
+                    for gx in 1..=w {
+                        let (x, y) = (x0 + gx - 1, y0 + gy - 1);
+                        tiles[y * width + x] = Some(piece.get(gx, gy));
+                    }
+                }
+            }
+        }
+
+        let tiles = tiles
+            .into_iter()
+            .map(|tile| tile.expect("The pieces cover the plot"))
+            .collect();
+        Ok(Plot::from_tiles(tileset, width, height, tiles))
+    }
+
+    /// Writes the world to a file at `path`, see [`World::to_bytes`].
+    #[cfg(feature = "std")]
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_bytes())
+    }
+
+    /// Reads a world of the built-in [`TileSet::terrain`] written by [`World::save`].
+    #[cfg(feature = "std")]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, WorldFileError> {
+        World::load_for_tileset(path, TileSet::terrain())
+    }
+
+    /// Reads a world of `tileset` written by [`World::save`]. Fails if the world got generated
+    /// with other tiles or rules, see [`TileSet::fingerprint`].
+    #[cfg(feature = "std")]
+    pub fn load_for_tileset(
+        path: impl AsRef<Path>,
+        tileset: impl Into<Arc<TileSet>>,
+    ) -> Result<Self, WorldFileError> {
+        let bytes = std::fs::read(path).map_err(WorldFileError::Io)?;
+        World::from_bytes(&bytes, tileset)
+    }
+
+    /// The generated chunks in a compact binary format, along with the seed to generate the rest
+    /// from. All numbers are little endian:
+    ///
+    /// * the magic bytes `WGEN` and the version of the format as a `u16`
+    /// * the chunk size as a `u32`, the seed as a `u64`
+    /// * the [`TileSet::fingerprint`] as a `u64` and the number of tiles in the set as a `u32`
+    /// * the number of chunks as a `u32`, followed by the chunks ordered by their coordinates
+    ///
+    /// Every chunk holds its coordinates as `i64`s, its tiles row by row as `u16`s and the
+    /// number of extra layers as a `u8`. A layer starts with its kind as a `u8` and its length in
+    /// bytes as a `u32`, so readers can skip the layers they don't know. Kind 1 holds the heights
+    /// of the fields as `f64`s, see [`Plot::elevation`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(VERSION.to_le_bytes());
+        bytes.extend((self.chunk_size as u32).to_le_bytes());
+        bytes.extend(self.seed.to_le_bytes());
+        bytes.extend(self.tileset.fingerprint().to_le_bytes());
+        bytes.extend((self.tileset.len() as u32).to_le_bytes());
+
+        let mut coords = self.chunks.keys().copied().collect::<Vec<_>>();
+        coords.sort();
+        bytes.extend((coords.len() as u32).to_le_bytes());
+        for (cx, cy) in coords {
+            let chunk = &self.chunks[&(cx, cy)];
+            bytes.extend(cx.to_le_bytes());
+            bytes.extend(cy.to_le_bytes());
+            for (x, y) in chunk.positions() {
+                bytes.extend(chunk.get(x, y).0.to_le_bytes());
+            }
+
+            match chunk.elevation(0, 0) {
+                Some(_) => {
+                    bytes.push(1);
+                    bytes.push(ELEVATION_LAYER);
+                    bytes.extend((chunk.positions().count() as u32 * 8).to_le_bytes());
+                    for (x, y) in chunk.positions() {
+                        let height = chunk.elevation(x, y).expect("Every field has a height");
+                        bytes.extend(height.to_le_bytes());
+                    }
+                }
+                None => bytes.push(0),
+            }
+        }
+
+        bytes
+    }
+
+    /// Reads a world of `tileset` from the format of [`World::to_bytes`].
+    pub fn from_bytes(
+        bytes: &[u8],
+        tileset: impl Into<Arc<TileSet>>,
+    ) -> Result<Self, WorldFileError> {
+        let tileset = tileset.into();
+        let mut reader = Reader(bytes);
+        if reader.take(MAGIC.len()).ok() != Some(MAGIC.as_slice()) {
+            return Err(WorldFileError::NotAWorld);
+        }
+        let version = reader.u16()?;
+        if version != VERSION {
+            return Err(WorldFileError::UnsupportedVersion(version));
+        }
+
+        let chunk_size = reader.u32()? as usize;
+        let seed = reader.u64()?;
+        let (fingerprint, tiles) = (reader.u64()?, reader.u32()?);
+        if fingerprint != tileset.fingerprint() || tiles as usize != tileset.len() {
+            return Err(WorldFileError::TileSetMismatch);
+        }
+        if chunk_size == 0 {
+            return Err(WorldFileError::Corrupt);
+        }
+
+        let fields = chunk_size
+            .checked_mul(chunk_size)
+            .ok_or(WorldFileError::Corrupt)?;
+        let mut chunks = HashMap::new();
+        for _ in 0..reader.u32()? {
+            let (cx, cy) = (reader.i64()?, reader.i64()?);
+            let tiles = reader
+                .take(fields.checked_mul(2).ok_or(WorldFileError::Corrupt)?)?
+                .chunks_exact(2)
+                .map(|id| TileId(u16::from_le_bytes([id[0], id[1]])))
+                .collect::<Vec<_>>();
+            if tiles.iter().any(|tile| tile.0 as usize >= tileset.len()) {
+                return Err(WorldFileError::Corrupt);
+            }
+
+            let mut chunk = Plot::from_tiles(Arc::clone(&tileset), chunk_size, chunk_size, tiles);
+            for _ in 0..reader.u8()? {
+                let kind = reader.u8()?;
+                let length = reader.u32()? as usize;
+                let layer = reader.take(length)?;
+                if kind == ELEVATION_LAYER {
+                    if layer.len() != fields * 8 {
+                        return Err(WorldFileError::Corrupt);
+                    }
+                    let heights = layer
+                        .chunks_exact(8)
+                        .map(|height| f64::from_le_bytes(height.try_into().expect("8 bytes")))
+                        .collect();
+                    chunk = chunk.with_elevation(heights);
+                }
+            }
+
+            if chunks.insert((cx, cy), chunk).is_some() {
+                return Err(WorldFileError::Corrupt);
+            }
+        }
+
+        Ok(World {
+            tileset,
+            chunk_size,
+            seed,
+            chunks,
+        })
+    }
+
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// The chunk at chunk coordinates `(cx, cy)`, if it already got generated.
+    pub fn generated(&self, cx: i64, cy: i64) -> Option<&Plot> {
+        self.chunks.get(&(cx, cy))
+    }
+
+    /// The chunk at chunk coordinates `(cx, cy)`, generating it first if needed. Its fields cover
+    /// the world positions `cx * chunk_size..(cx + 1) * chunk_size`, and likewise for `cy`.
+    pub fn chunk(&mut self, cx: i64, cy: i64) -> Result<&Plot, WfcError> {
+        self.generate_chunks(&[(cx, cy)])?;
+        Ok(&self.chunks[&(cx, cy)])
+    }
+
+    /// Generates the chunks at the chunk coordinates `chunks` that weren't generated yet, along
+    /// with the neighbours they depend on. With the `parallel` feature the chunks of a phase get
+    /// generated on several threads, with the same result.
+    ///
+    /// Fails with the first chunk that can't be collapsed, with a [`WfcError::WorldContradiction`]
+    /// at the world position it ran into. The chunks generated before stay.
+    pub fn generate_chunks(&mut self, chunks: &[(i64, i64)]) -> Result<(), WfcError> {
+        let mut missing = HashSet::new();
+        let mut stack = chunks.to_vec();
+        while let Some(chunk) = stack.pop() {
+            // A generated chunk has all of its dependencies generated as well
+            if !self.chunks.contains_key(&chunk) && missing.insert(chunk) {
+                stack.extend(Self::dependencies(chunk.0, chunk.1));
+            }
+        }
+
+        for phase in 0..4 {
+            let mut chunks = missing
+                .iter()
+                .copied()
+                .filter(|&(cx, cy)| Self::phase(cx, cy) == phase)
+                .collect::<Vec<_>>();
+            // Fails with the same chunk every time
+            chunks.sort();
+
+            #[cfg(not(feature = "parallel"))]
+            for (cx, cy) in chunks {
+                let chunk = self.generate(cx, cy)?;
+                self.chunks.insert((cx, cy), chunk);
+            }
+
+            #[cfg(feature = "parallel")]
+            {
+                let generated = chunks
+                    .par_iter()
+                    .map(|&(cx, cy)| self.generate(cx, cy).map(|chunk| ((cx, cy), chunk)))
+                    .collect::<Vec<_>>();
+                for result in generated {
+                    let (coords, chunk) = result?;
+                    self.chunks.insert(coords, chunk);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The tile at the world position `(x, y)`, generating its chunk first if needed.
+    pub fn get(&mut self, x: i64, y: i64) -> Result<TileId, WfcError> {
+        let size = self.chunk_size as i64;
+        let chunk = self.chunk(x.div_euclid(size), y.div_euclid(size))?;
+
+        Ok(chunk.get(x.rem_euclid(size) as usize, y.rem_euclid(size) as usize))
+    }
+
+    /// The phase of generation the chunk at `(cx, cy)` belongs to, from 0 to 3.
+    fn phase(cx: i64, cy: i64) -> u8 {
+        (cx.rem_euclid(2) + 2 * cy.rem_euclid(2)) as u8
+    }
+
+    /// The neighbours of the chunk at `(cx, cy)` in the "#" shape that it takes its seams from,
+    /// the ones of earlier phases.
+    fn dependencies(cx: i64, cy: i64) -> impl Iterator<Item = (i64, i64)> {
+        let phase = Self::phase(cx, cy);
+        (-1..=1)
+            .flat_map(move |dy| (-1..=1).map(move |dx| (cx + dx, cy + dy)))
+            .filter(move |&(nx, ny)| Self::phase(nx, ny) < phase)
+    }
+
+    /// Collapses a chunk with a ring of one extra field around it, seeded by its coordinates.
+    /// The ring is fixed to the bordering fields of the neighbours it depends on, which have to
+    /// be generated already, and dropped afterwards.
+    fn generate(&self, cx: i64, cy: i64) -> Result<Plot, WfcError> {
+        let size = self.chunk_size;
+        let seed = self.seed
+            ^ (cx as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            ^ (cy as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+        let mut gen = PlotGenerator::for_tileset(Arc::clone(&self.tileset), size + 2, size + 2)
+            .with_seed(seed);
+        // Moves contradictions from the generator to the world, past the ring
+        let origin = (cx * size as i64 - 1, cy * size as i64 - 1);
+        let in_world = |err| match err {
+            WfcError::Contradiction((x, y)) => {
+                WfcError::WorldContradiction((origin.0 + x as i64, origin.1 + y as i64))
+            }
+            err => err,
+        };
+
+        for y in 0..size + 2 {
+            for x in 0..size + 2 {
+                if (1..=size).contains(&x) && (1..=size).contains(&y) {
+                    continue;
+                }
+
+                // The ring field in world coordinates, relative to the chunk's origin
+                let (wx, wy) = (x as i64 - 1, y as i64 - 1);
+                let size = size as i64;
+                let (nx, ny) = (cx + wx.div_euclid(size), cy + wy.div_euclid(size));
+                if Self::phase(nx, ny) >= Self::phase(cx, cy) {
+                    continue;
+                }
+                let chunk = &self.chunks[&(nx, ny)];
+                let tile = chunk.get(wx.rem_euclid(size) as usize, wy.rem_euclid(size) as usize);
+                gen.set_tile(x, y, tile).map_err(in_world)?;
+            }
+        }
+
+        gen.collapse().map_err(in_world)?;
+        let ring = gen.into_plot();
+
+        let tiles = (1..=size)
+            .flat_map(|y| (1..=size).map(move |x| (x, y)))
+            .map(|(x, y)| ring.get(x, y))
+            .collect();
+
+        Ok(Plot::from_tiles(
+            Arc::clone(&self.tileset),
+            size,
+            size,
+            tiles,
+        ))
+    }
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], WorldFileError> {
+        if count > self.0.len() {
+            return Err(WorldFileError::Corrupt);
+        }
+        let (bytes, rest) = self.0.split_at(count);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], WorldFileError> {
+        Ok(self.take(N)?.try_into().expect("Took N bytes"))
+    }
+
+    fn u8(&mut self) -> Result<u8, WorldFileError> {
+        Ok(u8::from_le_bytes(self.array()?))
+    }
+
+    fn u16(&mut self) -> Result<u16, WorldFileError> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, WorldFileError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, WorldFileError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn i64(&mut self) -> Result<i64, WorldFileError> {
+        Ok(i64::from_le_bytes(self.array()?))
+    }
+}
+
+impl Display for WorldFileError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            WorldFileError::Io(err) => write!(f, "Can't read the world: {err}"),
+            WorldFileError::NotAWorld => write!(f, "The file isn't a world"),
+            WorldFileError::UnsupportedVersion(version) => {
+                write!(f, "The world has the unsupported format version {version}")
+            }
+            WorldFileError::TileSetMismatch => {
+                write!(f, "The world got generated with another tile set")
+            }
+            WorldFileError::Corrupt => write!(f, "The world file is corrupt"),
+        }
+    }
+}
+
+impl core::error::Error for WorldFileError {}
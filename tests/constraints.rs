@@ -0,0 +1,171 @@
+//! Checks the constraints that fix fields of a plot before it collapses.
+
+use world_gen::{
+    Direction, Edge, Plot, PlotGenerator, Tile, TileDef, TileSet, WaveState, WfcError,
+};
+
+/// Every pair of neighbouring fields follows the adjacency rules of the tile set.
+fn follows_the_rules(plot: &Plot) -> bool {
+    plot.positions().all(|(x, y)| {
+        Direction::ALL.into_iter().all(|direction| {
+            let (dx, dy) = direction.offset();
+            let (nx, ny) = (x.wrapping_add_signed(dx), y.wrapping_add_signed(dy));
+            nx >= plot.width()
+                || ny >= plot.height()
+                || plot
+                    .tileset()
+                    .valid_neighbours(plot.get(x, y), direction)
+                    .contains(&plot.get(nx, ny))
+        })
+    })
+}
+
+/// Splits a `width` x `height` grid into a left and a right region, row by row.
+fn halves(width: usize, height: usize) -> Vec<usize> {
+    (0..width * height)
+        .map(|index| (index % width >= width / 2) as usize)
+        .collect()
+}
+
+#[test]
+fn region_borders_get_a_band() {
+    let (width, height) = (10, 6);
+    let mut gen = PlotGenerator::new(width, height).with_seed(3);
+    gen.constrain_region_borders(&halves(width, height), Tile::Wasteland, 1)
+        .unwrap();
+    let plot = gen.generate().unwrap();
+
+    for y in 0..height {
+        for x in width / 2 - 1..=width / 2 {
+            assert_eq!(plot.get(x, y), Tile::Wasteland, "({x}, {y})");
+        }
+    }
+}
+
+/// Splits a `width` x `height` grid into four quarters, row by row.
+fn quarters(width: usize, height: usize) -> Vec<usize> {
+    (0..width * height)
+        .map(|index| {
+            let (x, y) = (index % width, index / width);
+            (x >= width / 2) as usize + 2 * (y >= height / 2) as usize
+        })
+        .collect()
+}
+
+#[test]
+fn wide_region_borders_get_a_band() {
+    let (width, height) = (16, 12);
+    // Whether a field is at most `band` steps from the border of its quarter, on one axis
+    let near = |pos: usize, len: usize, band: usize| {
+        if pos < len / 2 {
+            len / 2 - pos <= band
+        } else {
+            pos + 1 - len / 2 <= band
+        }
+    };
+
+    for band in [2, 3] {
+        let mut gen = PlotGenerator::new(width, height).with_seed(5);
+        gen.constrain_region_borders(&quarters(width, height), Tile::Wasteland, band)
+            .unwrap();
+
+        // The fields further away are left to the collapse
+        for y in 0..height {
+            for x in 0..width {
+                let in_band = near(x, width, band) || near(y, height, band);
+                let forced = matches!(gen.get(x, y), WaveState::Collapsed(_));
+                assert_eq!(forced, in_band, "({x}, {y}) with a band of {band}");
+            }
+        }
+
+        let plot = gen.generate().unwrap();
+        assert!(follows_the_rules(&plot));
+        for y in 0..height {
+            for x in 0..width {
+                if near(x, width, band) || near(y, height, band) {
+                    assert_eq!(
+                        plot.get(x, y),
+                        Tile::Wasteland,
+                        "({x}, {y}) with a band of {band}"
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn region_borders_need_one_region_per_field() {
+    let mut gen = PlotGenerator::new(10, 6);
+    assert_eq!(
+        gen.constrain_region_borders(&halves(6, 10)[1..], Tile::Wasteland, 1),
+        Err(WfcError::GridSize {
+            expected: (10, 6),
+            len: 59
+        })
+    );
+}
+
+#[test]
+fn region_borders_fail_on_a_band_that_contradicts() {
+    let (width, height) = (10, 6);
+    let regions = halves(width, height);
+
+    // The field is already something else
+    let mut gen = PlotGenerator::new(width, height);
+    gen.set_tile(4, 2, Tile::River).unwrap();
+    let before = gen.domains().cloned().collect::<Vec<_>>();
+    assert_eq!(
+        gen.constrain_region_borders(&regions, Tile::Wasteland, 1),
+        Err(WfcError::Contradiction((4, 2)))
+    );
+    assert!(gen.domains().eq(&before));
+
+    // A wall can't lie next to another wall
+    let tileset = TileSet::new(vec![
+        TileDef::new("Floor", "..").with_neighbours(&["Floor", "Wall"]),
+        TileDef::new("Wall", "##"),
+    ])
+    .unwrap();
+    let wall = tileset.id("Wall").unwrap();
+    let mut gen = PlotGenerator::for_tileset(tileset, width, height);
+    assert!(matches!(
+        gen.constrain_region_borders(&regions, wall, 1),
+        Err(WfcError::Contradiction(_))
+    ));
+    assert!(gen.domains().all(|state| state.contains(wall)));
+}
+
+#[test]
+fn edges_match_the_fingerprint() {
+    use Tile::*;
+    let top = [
+        Wasteland, Farmland, Farmland, Farmland, Farmland, Farmland, Wasteland,
+    ];
+    let bottom = [
+        Wasteland, River, River, Wasteland, Wasteland, Wasteland, Wasteland,
+    ];
+    let right = [Wasteland, Farmland, Wasteland, Wasteland, Wasteland];
+    let left = [Wasteland, Wasteland, River, River, Wasteland];
+
+    for seed in 0..8 {
+        let plot = PlotGenerator::new(7, 5)
+            .with_seed(seed)
+            .with_edge_fingerprint(&top, &right, &bottom, &left)
+            .generate()
+            .unwrap();
+
+        for (edge, tiles) in Edge::ALL
+            .into_iter()
+            .zip([&top[..], &right, &bottom, &left])
+        {
+            let fields = edge.fields(7, 5);
+            let found = fields
+                .iter()
+                .map(|&(x, y)| plot.get(x, y))
+                .collect::<Vec<_>>();
+            assert!(found.iter().eq(tiles), "{edge:?} edge with seed {seed}");
+        }
+        assert!(follows_the_rules(&plot), "seed {seed}");
+    }
+}
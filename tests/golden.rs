@@ -0,0 +1,29 @@
+//! Compares plots of fixed seeds with the golden files in `tests/golden`, so changes to the rules
+//! or the solver can't silently change existing seeds. The `seed_<N>` files are what
+//! `world-gen --seed <N>` prints with `--format blocks`.
+//!
+//! Run with `WORLD_GEN_BLESS=1` to write the new output after an intended change.
+
+use world_gen::{Golden, JsonGrid, PlotGenerator, TileSet};
+
+#[test]
+fn golden_files_match() {
+    let mut golden = Golden::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden"));
+    for seed in [1, 42, 1234] {
+        golden = golden.with_case(format!("seed_{seed}"), move || {
+            PlotGenerator::for_tileset(TileSet::world(), 16, 16).with_seed(seed)
+        });
+    }
+    let golden = golden
+        .with_case("terrain_7", || PlotGenerator::new(24, 12).with_seed(7))
+        .with_rendered_case(
+            "dungeon_3",
+            || PlotGenerator::for_tileset(TileSet::dungeon(), 20, 10).with_seed(3),
+            JsonGrid,
+        );
+
+    if let Err(failures) = golden.check() {
+        let failures = failures.iter().map(ToString::to_string).collect::<Vec<_>>();
+        panic!("{}", failures.join("\n\n"));
+    }
+}
@@ -0,0 +1,84 @@
+//! Checks the image exports and masks read from images.
+#![cfg(feature = "image")]
+
+use image::{Rgba, RgbaImage};
+use world_gen::{Mask, MetaValue, PlotGenerator, Tile, TileDef, TileSet, WaveState};
+
+/// A feature layer of trees on otherwise empty fields.
+fn features() -> TileSet {
+    TileSet::new(vec![
+        TileDef::new("Empty", "  ")
+            .with_neighbours(&["Empty", "Tree"])
+            .with_metadata("transparent", MetaValue::Bool(true)),
+        TileDef::new("Tree", "♣♣")
+            .with_color([34, 110, 40])
+            .with_neighbours(&["Tree"]),
+    ])
+    .unwrap()
+}
+
+#[test]
+fn transparent_tiles_have_no_alpha() {
+    let plot = PlotGenerator::for_tileset(features(), 12, 8)
+        .with_seed(5)
+        .generate()
+        .unwrap();
+    let tileset = plot.tileset();
+    let empty = tileset.id("Empty").unwrap();
+    assert!(tileset.transparent(empty));
+    assert!(!tileset.transparent(tileset.id("Tree").unwrap()));
+
+    let scale = 3;
+    let image = plot.to_image(scale);
+    assert!(plot.count(empty) > 0 && plot.count(empty) < 12 * 8);
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let tile = plot.get((x / scale) as usize, (y / scale) as usize);
+        let alpha = match tile == empty {
+            true => 0,
+            false => 255,
+        };
+        assert_eq!(pixel.0[3], alpha, "({x}, {y})");
+        assert_eq!(pixel.0[..3], tileset.color(tile));
+    }
+}
+
+/// A `width` x `height` image, opaque in the middle and on the top left pixel, half transparent
+/// right of that, and transparent around, scaled up by `scale`.
+fn stencil(width: u32, height: u32, scale: u32) -> RgbaImage {
+    RgbaImage::from_fn(width * scale, height * scale, |x, y| {
+        let (x, y) = (x / scale, y / scale);
+        let alpha = match (x, y) {
+            (0, 0) => 255,
+            (1, 0) => 100,
+            _ if (1..width - 1).contains(&x) && (1..height - 1).contains(&y) => 200,
+            _ => 0,
+        };
+        Rgba([10, 20, 30, alpha])
+    })
+}
+
+#[test]
+fn masks_follow_the_alpha() {
+    let image = stencil(6, 4, 1);
+    let mask = Mask::from_alpha(&image, 128);
+    assert_eq!((mask.width(), mask.height()), (6, 4));
+    for (x, y, pixel) in image.enumerate_pixels() {
+        assert_eq!(mask.contains(x as usize, y as usize), pixel.0[3] >= 128);
+    }
+    assert!(!mask.contains(1, 0));
+    assert!(Mask::from_alpha(&image, 100).contains(1, 0));
+    assert!((0..6).all(|x| (0..4).all(|y| Mask::from_alpha(&image, 0).contains(x, y))));
+
+    // Bigger images get scaled down to the grid
+    for image in [image, stencil(6, 4, 3)] {
+        let gen = PlotGenerator::new(6, 4).with_mask_image(&image, 128, Tile::Wasteland);
+        let fields = gen.domains().collect::<Vec<_>>();
+        for (x, y) in (0..4).flat_map(|y| (0..6).map(move |x| (x, y))) {
+            let state = fields[y * 6 + x];
+            match mask.contains(x, y) {
+                true => assert!(matches!(state, WaveState::Superposition(_)), "({x}, {y})"),
+                false => assert_eq!(*state, WaveState::Collapsed(Tile::Wasteland.into())),
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! Checks [`PlotGenerator::estimated_bytes`] against the bytes a generator really allocates, by
+//! counting them. Lives in a test of its own, as the counting allocator sees every thread.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use world_gen::{PlotGenerator, TileDef, TileSet};
+
+/// Counts the bytes currently allocated, and the most there were since the last reset.
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(allocated, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// `count` tiles in a chain, like in `benches/memory.rs`.
+fn chain(count: usize) -> TileSet {
+    let defs = (0..count)
+        .map(|i| {
+            let neighbours = [Some(i), i.checked_sub(1)]
+                .into_iter()
+                .flatten()
+                .map(|n| format!("T{n}"))
+                .collect::<Vec<_>>();
+            TileDef::new(format!("T{i}"), format!("{i:02}")).with_neighbours(&neighbours)
+        })
+        .collect();
+
+    TileSet::new(defs).unwrap()
+}
+
+/// The most bytes `run` had allocated at once, beyond what there was before.
+fn peak(run: impl FnOnce()) -> usize {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    PEAK.store(before, Ordering::Relaxed);
+    run();
+    PEAK.load(Ordering::Relaxed) - before
+}
+
+#[test]
+fn generating_stays_within_the_estimate() {
+    for tileset in [TileSet::world(), chain(64), chain(260)] {
+        let tileset = Arc::new(tileset);
+        let tile_count = tileset.len();
+        for size in [8, 32] {
+            let estimate = PlotGenerator::estimated_bytes(size, size, tile_count);
+            let used = peak(|| {
+                let mut gen =
+                    PlotGenerator::for_tileset(Arc::clone(&tileset), size, size).with_seed(1);
+                let _ = gen.collapse();
+            });
+            assert!(
+                used <= estimate,
+                "{tile_count} tiles at {size}²: {used} bytes, estimated {estimate}"
+            );
+        }
+    }
+}
+
+#[test]
+fn huge_estimates_saturate() {
+    assert_eq!(PlotGenerator::estimated_bytes(usize::MAX, 2, 4), usize::MAX);
+    assert_eq!(
+        PlotGenerator::estimated_bytes(1 << 20, 1 << 20, usize::MAX),
+        usize::MAX
+    );
+    assert!(
+        PlotGenerator::estimated_bytes(200, 100, 9)
+            >= 4 * PlotGenerator::estimated_bytes(100, 50, 9)
+    );
+}
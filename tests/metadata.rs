@@ -0,0 +1,51 @@
+//! Generates plots from tile sets with metadata and reads the attributes back from the placed
+//! tiles.
+#![cfg(feature = "serde")]
+
+use proptest::prelude::*;
+use world_gen::{MetaValue, PlotGenerator, TileSet};
+
+const TILESET: &str = r#"(
+    tiles: [
+        (name: "Meadow", glyph: "..", neighbours: ["Meadow", "Forest", "Ore"], metadata: {"spawn": 0.5, "passable": true}),
+        (name: "Forest", glyph: "♣♣", neighbours: ["Forest"], metadata: {"spawn": 0.1, "material": "wood"}),
+        (name: "Ore", glyph: "**", neighbours: [], metadata: {"material": "iron", "cost": 3}),
+    ],
+)"#;
+
+proptest! {
+    #[test]
+    fn placed_tiles_carry_their_metadata(seed: u64, width in 1usize..12, height in 1usize..12) {
+        let tileset = TileSet::from_ron(TILESET).unwrap();
+        let plot = PlotGenerator::for_tileset(tileset, width, height)
+            .with_seed(seed)
+            .generate()
+            .unwrap();
+        for y in 0..height {
+            for x in 0..width {
+                let tile = plot.tile(x, y);
+                prop_assert_eq!(tile.id(), plot.get(x, y));
+                prop_assert_eq!(tile.metadata(), plot.metadata(x, y));
+                let (spawn, material) = match tile.name() {
+                    "Meadow" => (Some(0.5), None),
+                    "Forest" => (Some(0.1), Some("wood")),
+                    "Ore" => (None, Some("iron")),
+                    name => panic!("unexpected tile {name}"),
+                };
+                prop_assert_eq!(tile.meta("spawn").and_then(MetaValue::as_f64), spawn);
+                prop_assert_eq!(tile.meta("material").and_then(MetaValue::as_str), material);
+                if tile.name() == "Ore" {
+                    prop_assert_eq!(tile.meta("cost"), Some(&MetaValue::Int(3)));
+                    prop_assert_eq!(plot.tileset().cost(tile.id()), 3.0);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn tiles_without_metadata_have_none() {
+    let plot = PlotGenerator::new(6, 6).with_seed(3).generate().unwrap();
+    assert!(plot.tile(2, 4).metadata().is_empty());
+    assert_eq!(plot.tile(2, 4).meta("spawn"), None);
+}
@@ -0,0 +1,72 @@
+//! Checks the metrics of finished plots on hand-drawn ones.
+
+use std::collections::HashMap;
+use world_gen::{Connectivity, Plot, Tile, TileId, TileSet};
+
+/// `~` for rivers, `.` for wasteland and `#` for farmland.
+fn fixture(text: &str) -> Plot {
+    let legend = HashMap::from([
+        ('~', Tile::River),
+        ('.', Tile::Wasteland),
+        ('#', Tile::Farmland),
+    ]);
+    Plot::from_ascii(TileSet::terrain(), text, &legend).unwrap()
+}
+
+fn pairs(counts: &[(Tile, Tile, usize)]) -> HashMap<(TileId, TileId), usize> {
+    counts
+        .iter()
+        .map(|&(a, b, count)| ((a.into(), b.into()), count))
+        .collect()
+}
+
+#[test]
+fn histogram_counts_every_adjacency_from_both_sides() {
+    use Tile::*;
+    let plot = fixture(
+        "\
+~.
+..",
+    );
+    assert_eq!(
+        plot.adjacency_histogram(Connectivity::Four),
+        pairs(&[
+            (River, Wasteland, 2),
+            (Wasteland, River, 2),
+            (Wasteland, Wasteland, 4)
+        ])
+    );
+    assert_eq!(
+        plot.adjacency_histogram(Connectivity::Eight),
+        pairs(&[
+            (River, Wasteland, 3),
+            (Wasteland, River, 3),
+            (Wasteland, Wasteland, 6)
+        ])
+    );
+
+    let row = fixture("~.#");
+    assert_eq!(
+        row.adjacency_histogram(Connectivity::Eight),
+        pairs(&[
+            (River, Wasteland, 1),
+            (Wasteland, River, 1),
+            (Wasteland, Farmland, 1),
+            (Farmland, Wasteland, 1),
+        ])
+    );
+}
+
+#[test]
+fn entropy_grows_with_variety() {
+    assert_eq!(fixture("...\n...").global_entropy(), 0.0);
+
+    let halves = fixture("~~~\n###").global_entropy();
+    assert!((halves - 2f64.ln()).abs() < 1e-12);
+    let thirds = fixture("~.#\n#.~").global_entropy();
+    assert!((thirds - 3f64.ln()).abs() < 1e-12);
+
+    // Uneven mixes lie between a single tile and an even mix
+    let uneven = fixture("~..\n...").global_entropy();
+    assert!(0.0 < uneven && uneven < halves);
+}
@@ -0,0 +1,120 @@
+//! Checks the neighbours of fields for every topology, neighbourhood and boundary, and that the
+//! generator steps to the same ones.
+
+use proptest::prelude::*;
+use world_gen::{BoundaryMode, Connectivity, Direction, Neighbourhood, PlotGenerator, Topology};
+
+#[test]
+fn neighbourhoods_have_their_directions() {
+    use Direction::*;
+    let square = Topology::Square;
+    assert_eq!(Neighbourhood::Moore.directions(square), &Direction::ALL);
+    assert_eq!(
+        Neighbourhood::VonNeumann.directions(square),
+        &[North, East, South, West]
+    );
+    let rows = Neighbourhood::Custom(vec![East, West]);
+    assert_eq!(rows.directions(square), &[East, West]);
+
+    // Hex grids ignore the neighbourhood
+    let hex = [East, NorthEast, NorthWest, West, SouthWest, SouthEast];
+    for neighbourhood in [Neighbourhood::Moore, Neighbourhood::VonNeumann, rows] {
+        assert_eq!(neighbourhood.directions(Topology::Hex), &hex);
+    }
+    assert_eq!(Topology::Hex.directions(), &hex);
+}
+
+#[test]
+fn hex_rows_are_shifted() {
+    assert_eq!(Topology::Hex.offset(Direction::NorthWest), (0, -1));
+    assert_eq!(Topology::Hex.offset(Direction::SouthEast), (0, 1));
+    assert_eq!(Topology::Hex.offset(Direction::NorthEast), (1, -1));
+    assert_eq!(Topology::Square.offset(Direction::NorthWest), (-1, -1));
+}
+
+#[test]
+fn opposite_directions_step_back() {
+    for topology in [Topology::Square, Topology::Hex] {
+        for &direction in topology.directions() {
+            let (dx, dy) = topology.offset(direction);
+            assert_eq!(
+                topology.offset(direction.opposite()),
+                (-dx, -dy),
+                "{direction:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn boundaries_clamp_or_wrap() {
+    let (width, height) = (4, 3);
+    let corner = (0, 0);
+    assert_eq!(
+        BoundaryMode::Clamp.neighbour(corner, (-1, 0), width, height),
+        None
+    );
+    assert_eq!(
+        BoundaryMode::Clamp.neighbour(corner, (1, 1), width, height),
+        Some((1, 1))
+    );
+    assert_eq!(
+        BoundaryMode::Wrap.neighbour(corner, (-1, -1), width, height),
+        Some((3, 2))
+    );
+    assert_eq!(
+        BoundaryMode::Wrap.neighbour((3, 2), (1, 1), width, height),
+        Some((0, 0))
+    );
+}
+
+#[test]
+fn connectivity_stays_inside_the_grid() {
+    let count = |connectivity: Connectivity, pos| connectivity.neighbours(pos, 4, 3).count();
+    assert_eq!(count(Connectivity::Four, (0, 0)), 2);
+    assert_eq!(count(Connectivity::Eight, (0, 0)), 3);
+    assert_eq!(count(Connectivity::Four, (1, 0)), 3);
+    assert_eq!(count(Connectivity::Eight, (1, 0)), 5);
+    assert_eq!(count(Connectivity::Four, (1, 1)), 4);
+    assert_eq!(count(Connectivity::Eight, (1, 1)), 8);
+    assert_eq!(Connectivity::Four.neighbours((0, 0), 1, 1).count(), 0);
+}
+
+#[test]
+fn repeated_directions_count_once() {
+    use Direction::*;
+    let generate = |directions: Vec<Direction>| {
+        PlotGenerator::new(12, 12)
+            .with_seed(7)
+            .with_neighbourhood(Neighbourhood::Custom(directions))
+            .generate()
+            .unwrap()
+            .to_string()
+    };
+    assert_eq!(
+        generate(vec![North, East, East, South, West, North]),
+        generate(vec![North, East, South, West])
+    );
+}
+
+proptest! {
+    /// Wrapped steps land on the same field as clamped ones wherever those stay inside.
+    #[test]
+    fn wrapping_agrees_inside(
+        x in 0usize..8,
+        y in 0usize..8,
+        direction in 0usize..8,
+        hex in any::<bool>(),
+    ) {
+        let topology = match hex {
+            true => Topology::Hex,
+            false => Topology::Square,
+        };
+        let offset = topology.offset(Direction::ALL[direction]);
+        let wrapped = BoundaryMode::Wrap.neighbour((x, y), offset, 8, 8).unwrap();
+        prop_assert!(wrapped.0 < 8 && wrapped.1 < 8);
+        if let Some(clamped) = BoundaryMode::Clamp.neighbour((x, y), offset, 8, 8) {
+            prop_assert_eq!(clamped, wrapped);
+        }
+    }
+}
@@ -0,0 +1,47 @@
+//! Checks that printed plots parse back, and that broken text and tile sets get rejected.
+
+use world_gen::{ParsePlotError, Plot, PlotGenerator, TileDef, TileSet, TileSetError};
+
+#[test]
+fn printed_plots_parse_back() {
+    let plot = PlotGenerator::new(9, 5).with_seed(4).generate().unwrap();
+    let parsed: Plot = plot.to_string().parse().unwrap();
+    assert_eq!(parsed.to_string(), plot.to_string());
+}
+
+#[test]
+fn glyph_errors_say_where() {
+    let tileset = TileSet::new(vec![
+        TileDef::new("Grass", "..").with_neighbours(&["Grass", "Rock"]),
+        TileDef::new("Rock", "##"),
+    ])
+    .unwrap();
+    assert_eq!(
+        Plot::from_glyphs(tileset.clone(), "..##\n..#?\n").err(),
+        Some(ParsePlotError::UnknownGlyph { line: 2, column: 3 })
+    );
+    assert_eq!(
+        Plot::from_glyphs(tileset.clone(), "..##\n..\n").err(),
+        Some(ParsePlotError::RaggedRow(2))
+    );
+    assert_eq!(
+        Plot::from_glyphs(tileset.clone(), "\n\n").err(),
+        Some(ParsePlotError::Empty)
+    );
+    assert_eq!(
+        Plot::from_glyphs(tileset, "##..\n").unwrap().to_string(),
+        "##..\n"
+    );
+}
+
+#[test]
+fn empty_glyphs_are_rejected() {
+    let defs = vec![
+        TileDef::new("Grass", "..").with_neighbours(&["Grass", "Void"]),
+        TileDef::new("Void", ""),
+    ];
+    assert!(matches!(
+        TileSet::new(defs),
+        Err(TileSetError::EmptyGlyph(name)) if name == "Void"
+    ));
+}
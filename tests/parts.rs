@@ -0,0 +1,34 @@
+//! Checks that the parts a generator hands out reproduce its plot.
+
+use world_gen::{BoundaryMode, PlotGenerator, SelectionStrategy, Tile, TileSet, Weights};
+
+#[test]
+fn spec_reproduces_the_plot() {
+    let mut gen = PlotGenerator::for_tileset(TileSet::terrain(), 24, 16)
+        .with_weights(Weights::default().with(Tile::River, 3.0))
+        .with_boundary(BoundaryMode::Wrap)
+        .with_selection(SelectionStrategy::Scanline)
+        .with_seed(11);
+    gen.generate().unwrap();
+    let (plot, stats, spec) = gen.into_parts();
+
+    assert_eq!(
+        spec.generator().generate().unwrap().to_string(),
+        plot.to_string()
+    );
+    assert_eq!((spec.width, spec.height), (24, 16));
+    assert_eq!(spec.boundary, BoundaryMode::Wrap);
+    assert!(stats.observations > 0);
+    assert!(stats.observations - stats.backtracks <= 24 * 16);
+}
+
+#[test]
+fn stats_count_the_steps() {
+    let mut gen = PlotGenerator::new(8, 8).with_seed(3);
+    let mut steps = 0;
+    while gen.step().unwrap() != world_gen::Step::Done {
+        steps += 1;
+    }
+    let (_, stats, _) = gen.into_parts();
+    assert_eq!(stats.observations + stats.backtracks, steps);
+}
@@ -0,0 +1,165 @@
+//! Checks that [`Plot::find_path`] finds the cheapest paths, on hand-drawn plots and against a
+//! breadth-first search on generated ones, and the distances of [`Plot::distance_field`].
+
+use proptest::prelude::*;
+use std::collections::{HashMap, VecDeque};
+use world_gen::{Connectivity, Plot, PlotGenerator, Tile, TileId, TileSet};
+
+/// `~` for rivers, `.` for wasteland and `#` for farmland.
+fn fixture(text: &str) -> Plot {
+    let legend = HashMap::from([
+        ('~', Tile::River),
+        ('.', Tile::Wasteland),
+        ('#', Tile::Farmland),
+    ]);
+    Plot::from_ascii(TileSet::terrain(), text, &legend).unwrap()
+}
+
+/// Rivers can't be crossed, farmland costs 10, wasteland 1.
+fn cost(tile: TileId) -> Option<f64> {
+    match tile {
+        tile if tile == TileId::from(Tile::River) => None,
+        tile if tile == TileId::from(Tile::Farmland) => Some(10.0),
+        _ => Some(1.0),
+    }
+}
+
+fn total(plot: &Plot, path: &[(usize, usize)]) -> f64 {
+    path[1..]
+        .iter()
+        .map(|&(x, y)| cost(plot.get(x, y)).unwrap())
+        .sum()
+}
+
+/// The fewest steps from `start` to `goal` over passable fields, if it can be reached.
+fn steps(plot: &Plot, start: (usize, usize), goal: (usize, usize)) -> Option<usize> {
+    let mut distances = HashMap::from([(start, 0)]);
+    let mut queue = VecDeque::from([start]);
+    while let Some((x, y)) = queue.pop_front() {
+        let distance = distances[&(x, y)];
+        if (x, y) == goal {
+            return Some(distance);
+        }
+        let neighbours = [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ];
+        for (nx, ny) in neighbours {
+            if nx < plot.width()
+                && ny < plot.height()
+                && plot.tileset().passable(plot.get(nx, ny))
+                && !distances.contains_key(&(nx, ny))
+            {
+                distances.insert((nx, ny), distance + 1);
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+    None
+}
+
+#[test]
+fn goes_around_rivers() {
+    let plot = fixture(
+        "\
+.....
+.~~~.
+.~...
+.~~~.
+.....",
+    );
+    let path = plot.find_path((2, 2), (0, 2), cost).unwrap();
+    assert_eq!(path.first(), Some(&(2, 2)));
+    assert_eq!(path.last(), Some(&(0, 2)));
+    assert_eq!(total(&plot, &path), 10.0);
+    assert!(path
+        .iter()
+        .all(|&(x, y)| plot.get(x, y) != TileId::from(Tile::River)));
+}
+
+#[test]
+fn goes_around_expensive_fields() {
+    let plot = fixture(
+        "\
+.#.
+.#.
+...",
+    );
+    let path = plot.find_path((0, 0), (2, 0), cost).unwrap();
+    assert_eq!(total(&plot, &path), 6.0);
+    assert_eq!(path.len(), 7);
+}
+
+#[test]
+fn steps_onto_every_field_once() {
+    let plot = fixture(".....");
+    let path = plot.find_path((0, 0), (4, 0), cost).unwrap();
+    assert_eq!(path, vec![(0, 0), (1, 0), (2, 0), (3, 0), (4, 0)]);
+    assert_eq!(plot.find_path((3, 0), (3, 0), cost), Some(vec![(3, 0)]));
+}
+
+#[test]
+fn unreachable_goals_have_no_path() {
+    let plot = fixture(
+        "\
+..~..
+..~..",
+    );
+    assert_eq!(plot.find_path((0, 0), (4, 1), cost), None);
+    assert_eq!(plot.find_path((0, 0), (2, 0), cost), None);
+}
+
+#[test]
+fn distances_grow_from_the_water() {
+    let plot = fixture(
+        "\
+.~...
+.....
+.....",
+    );
+    assert_eq!(
+        plot.distance_field(Tile::River, Connectivity::Four),
+        vec![
+            vec![1, 0, 1, 2, 3],
+            vec![2, 1, 2, 3, 4],
+            vec![3, 2, 3, 4, 5],
+        ]
+    );
+    assert_eq!(
+        plot.distance_field(Tile::River, Connectivity::Eight),
+        vec![
+            vec![1, 0, 1, 2, 3],
+            vec![1, 1, 1, 2, 3],
+            vec![2, 2, 2, 2, 3],
+        ]
+    );
+    assert!(plot
+        .distance_field(Tile::Farmland, Connectivity::Four)
+        .iter()
+        .flatten()
+        .all(|&distance| distance == u32::MAX));
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    /// With every passable field costing the same, the path is as short as a breadth-first
+    /// search says, and steps from field to neighbouring field.
+    #[test]
+    fn unit_costs_give_shortest_paths(seed in any::<u64>(), start in 0usize..144, goal in 0usize..144) {
+        let Ok(plot) = PlotGenerator::for_tileset(TileSet::world(), 12, 12).with_seed(seed).generate() else {
+            return Ok(());
+        };
+        let tileset = plot.tileset();
+        let (start, goal) = ((start % 12, start / 12), (goal % 12, goal / 12));
+        prop_assume!(tileset.passable(plot.get(start.0, start.1)));
+
+        let path = plot.find_path(start, goal, |tile| tileset.passable(tile).then_some(1.0));
+        prop_assert_eq!(path.as_ref().map(|path| path.len() - 1), steps(&plot, start, goal));
+        for pair in path.iter().flat_map(|path| path.windows(2)) {
+            prop_assert_eq!(pair[0].0.abs_diff(pair[1].0) + pair[0].1.abs_diff(pair[1].1), 1);
+        }
+    }
+}
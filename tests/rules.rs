@@ -0,0 +1,272 @@
+//! Generates many seeded plots and checks the invariants every plot has to hold, whatever the
+//! seed and size.
+
+use proptest::prelude::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::sync::Arc;
+use world_gen::{
+    CompiledRuleset, Direction, DomainMask, Neighbourhood, Plot, PlotGenerator, Step, Symmetry,
+    Tile, TileDef, TileId, TileSet, WaveState, WfcError,
+};
+
+/// Every pair of neighbouring fields follows the adjacency rules of the tile set.
+fn assert_rules_hold(plot: &Plot) {
+    let tileset = plot.tileset();
+    for (x, y) in plot.positions() {
+        let tile = plot.get(x, y);
+        for direction in Direction::ALL {
+            let (dx, dy) = direction.offset();
+            let (nx, ny) = (x as isize + dx, y as isize + dy);
+            if !(0..plot.width() as isize).contains(&nx)
+                || !(0..plot.height() as isize).contains(&ny)
+            {
+                continue;
+            }
+
+            let neighbour = plot.get(nx as usize, ny as usize);
+            assert!(
+                tileset
+                    .valid_neighbours(tile, direction)
+                    .contains(&neighbour),
+                "{} at ({x}, {y}) next to {} in the {direction:?}",
+                tileset.name(tile),
+                tileset.name(neighbour),
+            );
+        }
+    }
+}
+
+/// Five tiles with few neighbours, so generating runs into contradictions and backtracks a lot.
+fn sparse() -> TileSet {
+    TileSet::new(vec![
+        TileDef::new("T0", "00").with_neighbours(&["T1", "T4"]),
+        TileDef::new("T1", "11").with_neighbours(&["T2", "T3", "T4"]),
+        TileDef::new("T2", "22").with_neighbours(&["T2", "T3"]),
+        TileDef::new("T3", "33").with_neighbours(&["T4"]),
+        TileDef::new("T4", "44"),
+    ])
+    .unwrap()
+}
+
+fn tiles(plot: &Plot) -> Vec<u16> {
+    plot.positions().map(|(x, y)| plot.get(x, y).0).collect()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn terrain_follows_the_rules(seed: u64, width in 1..32usize, height in 1..32usize) {
+        let plot = PlotGenerator::new(width, height).with_seed(seed).generate().unwrap();
+        assert_rules_hold(&plot);
+    }
+
+    #[test]
+    fn world_follows_the_rules(seed: u64, width in 1..24usize, height in 1..24usize) {
+        // The world can contradict, but whatever it generates has to follow its rules
+        let mut gen = PlotGenerator::for_tileset(TileSet::world(), width, height).with_seed(seed);
+        if let Ok(plot) = gen.generate() {
+            assert_rules_hold(&plot);
+        }
+    }
+
+    #[test]
+    fn collapse_leaves_no_superposition(seed: u64, width in 1..32usize, height in 1..32usize) {
+        let mut gen = PlotGenerator::new(width, height).with_seed(seed);
+        gen.collapse().unwrap();
+
+        for y in 0..height {
+            for x in 0..width {
+                prop_assert!(matches!(gen.get(x, y), WaveState::Collapsed(_)), "({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_same_plot(seed: u64, width in 1..24usize, height in 1..24usize) {
+        let tileset = Arc::new(TileSet::world());
+        let generate = || {
+            PlotGenerator::for_tileset(Arc::clone(&tileset), width, height)
+                .with_seed(seed)
+                .generate()
+                .map(|plot| tiles(&plot))
+        };
+
+        prop_assert_eq!(generate(), generate());
+    }
+
+    #[test]
+    fn same_rng_same_plot(seed: u64, width in 1..24usize, height in 1..24usize) {
+        let generate = || {
+            PlotGenerator::new(width, height)
+                .with_rng(&mut ChaCha8Rng::seed_from_u64(seed))
+                .generate()
+                .map(|plot| tiles(&plot))
+        };
+
+        prop_assert_eq!(generate(), generate());
+    }
+
+    #[test]
+    fn backtracking_undoes_observations(seed: u64, width in 1..12usize, height in 1..12usize) {
+        let mut gen = PlotGenerator::for_tileset(sparse(), width, height).with_seed(seed);
+        let domains = |gen: &PlotGenerator| gen.domains().cloned().collect::<Vec<_>>();
+
+        // The observations that can still be undone, with the wave before them
+        let mut observations = vec![];
+        loop {
+            let before = domains(&gen);
+            match gen.step() {
+                Ok(Step::Collapsed { pos, tile, .. }) => observations.push((pos, tile, before)),
+                Ok(Step::Backtracked { undone, .. }) => {
+                    let (pos, tile, before) = observations.pop().unwrap();
+                    prop_assert_eq!(pos, undone);
+                    // The wave went back to before the observation, minus the tile picked there
+                    let after = domains(&gen);
+                    prop_assert!(!after[pos.1 * width + pos.0].contains(tile));
+                    for (after, before) in after.iter().zip(&before) {
+                        prop_assert!(after.states().all(|t| before.contains(t)));
+                    }
+                }
+                Ok(Step::Done) => {
+                    assert_rules_hold(&gen.into_plot());
+                    break;
+                }
+                Err(_) => break,
+            }
+        }
+    }
+
+    #[test]
+    fn anchors_appear_once(seed: u64, width in 1..24usize, height in 1..24usize) {
+        let mut gen = PlotGenerator::new(width, height).with_seed(seed);
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let (x, y) = gen.place_anchor(Tile::River, &mut rng).unwrap();
+
+        let plot = gen.generate().unwrap();
+        prop_assert_eq!(plot.get(x, y), Tile::River);
+        prop_assert_eq!(plot.count(Tile::River), 1);
+        assert_rules_hold(&plot);
+    }
+
+    #[test]
+    fn mirrored_rows_read_the_same_both_ways(
+        seed: u64,
+        width in 1..16usize,
+        height in 1..16usize,
+        deprecated: bool,
+    ) {
+        #[allow(deprecated)]
+        let symmetry = match deprecated {
+            true => Symmetry::PalindromeX,
+            false => Symmetry::MirrorX,
+        };
+        let plot = PlotGenerator::new(width, height)
+            .with_seed(seed)
+            .with_symmetry(symmetry)
+            .generate()
+            .unwrap();
+
+        for y in 0..height {
+            let row = (0..width).map(|x| plot.get(x, y)).collect::<Vec<_>>();
+            prop_assert!(row.iter().eq(row.iter().rev()), "row {}", y);
+        }
+        assert_rules_hold(&plot);
+    }
+}
+
+#[test]
+fn anchors_need_a_field() {
+    let mut gen = PlotGenerator::new(3, 3);
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    for (x, y) in (0..3).flat_map(|x| (0..3).map(move |y| (x, y))) {
+        gen.constrain(x, y, &[Tile::Wasteland, Tile::Farmland])
+            .unwrap();
+    }
+    assert_eq!(
+        gen.place_anchor(Tile::River, &mut rng),
+        Err(WfcError::NoField(Tile::River.into()))
+    );
+}
+
+/// Three tiles that may only lie next to the other two, like colouring a map.
+fn colours() -> TileSet {
+    TileSet::new(vec![
+        TileDef::new("Red", "rr").with_neighbours(&["Green", "Blue"]),
+        TileDef::new("Green", "gg").with_neighbours(&["Red", "Blue"]),
+        TileDef::new("Blue", "bb").with_neighbours(&["Red", "Green"]),
+    ])
+    .unwrap()
+}
+
+#[test]
+fn easy_rules_succeed_in_every_order() {
+    // Wasteland lies next to everything, so no field runs out of tiles
+    assert!(PlotGenerator::new(3, 2).all_orders_succeed());
+    // Without the diagonals, every 2 x 2 square has two pairs of fields that don't touch
+    let gen =
+        PlotGenerator::for_tileset(colours(), 2, 2).with_neighbourhood(Neighbourhood::VonNeumann);
+    assert!(gen.all_orders_succeed());
+    // Already collapsed fields don't count towards the limit
+    let mut gen = PlotGenerator::new(5, 4);
+    for x in 0..5 {
+        for y in 0..3 {
+            gen.set_tile(x, y, Tile::Wasteland).unwrap();
+        }
+    }
+    assert!(gen.all_orders_succeed());
+}
+
+#[test]
+fn hard_rules_fail_in_some_order() {
+    // A 2 x 2 square of neighbours needs four colours
+    assert!(!PlotGenerator::for_tileset(colours(), 2, 2).all_orders_succeed());
+    // Three neighbours of the middle field can take every colour, even though the grid can be
+    // coloured like a checkerboard
+    let mut gen = PlotGenerator::for_tileset(colours(), 3, 3)
+        .with_neighbourhood(Neighbourhood::VonNeumann)
+        .with_seed(1);
+    assert!(!gen.all_orders_succeed());
+    assert!(gen.generate().is_ok());
+}
+
+#[test]
+#[should_panic(expected = "takes too long")]
+fn big_grids_are_not_checked_for_every_order() {
+    PlotGenerator::new(13, 1).all_orders_succeed();
+}
+
+/// Rules for the three terrain tiles that only let a tile lie next to itself.
+fn alone(tile: TileId, _: Direction) -> DomainMask {
+    DomainMask::from_tiles(3, [tile])
+}
+
+#[test]
+fn compiled_rules_are_shared() {
+    let tileset = Arc::new(TileSet::terrain());
+    let rules = Arc::new(CompiledRuleset::compile(&alone, &tileset));
+    for seed in 0..4 {
+        let shared = PlotGenerator::for_tileset(Arc::clone(&tileset), 12, 8)
+            .with_compiled_rules(Arc::clone(&rules))
+            .with_seed(seed)
+            .generate()
+            .unwrap();
+        let own = PlotGenerator::for_tileset(Arc::clone(&tileset), 12, 8)
+            .with_rules(alone)
+            .with_seed(seed)
+            .generate()
+            .unwrap();
+        assert_eq!(shared.to_string(), own.to_string());
+        assert!(shared
+            .positions()
+            .all(|(x, y)| shared.get(x, y) == shared.get(0, 0)));
+    }
+}
+
+#[test]
+#[should_panic(expected = "compiled for another tile set")]
+fn compiled_rules_need_their_tile_set() {
+    let rules = Arc::new(CompiledRuleset::compile(&alone, &TileSet::terrain()));
+    PlotGenerator::for_tileset(colours(), 4, 4).with_compiled_rules(rules);
+}
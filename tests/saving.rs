@@ -0,0 +1,79 @@
+//! Checks that saved generators resume where they stopped, and that broken saves get rejected.
+#![cfg(feature = "serde")]
+
+use serde_json::{json, Value};
+use world_gen::{Direction, DomainMask, PlotGenerator, Tile, TileId};
+
+/// A generator with a collapsed field and a frontier around it.
+fn generator() -> PlotGenerator {
+    let mut gen = PlotGenerator::new(8, 6).with_seed(4);
+    gen.set_tile(2, 2, Tile::River).unwrap();
+    gen
+}
+
+/// The generator, saved as JSON.
+fn saved() -> Value {
+    serde_json::to_value(generator()).unwrap()
+}
+
+fn load(value: Value) -> Result<PlotGenerator, serde_json::Error> {
+    serde_json::from_value(value)
+}
+
+#[test]
+fn saved_generator_resumes() {
+    let mut resumed = load(saved()).unwrap();
+    assert_eq!(
+        resumed.generate().unwrap().to_string(),
+        generator().generate().unwrap().to_string()
+    );
+}
+
+#[test]
+fn saved_rules_stay() {
+    // Every tile may only lie next to itself, unlike with the rules of the terrain
+    let alone = |tile: TileId, _: Direction| DomainMask::from_tiles(3, [tile]);
+    let gen = PlotGenerator::new(8, 6).with_seed(2).with_rules(alone);
+    let mut resumed = load(serde_json::to_value(&gen).unwrap()).unwrap();
+    let plot = resumed.generate().unwrap();
+    assert!(plot
+        .positions()
+        .all(|(x, y)| plot.get(x, y) == plot.get(0, 0)));
+}
+
+#[test]
+fn broken_waves_get_rejected() {
+    let frontier = saved()["tiles"]["frontier"].as_object().unwrap().clone();
+    let (first, mask) = frontier.iter().next().unwrap();
+    let first: usize = first.parse().unwrap();
+    assert_eq!(saved()["tiles"]["codes"]["Narrow"][first], json!(1));
+
+    // A code beyond the three tiles
+    let mut value = saved();
+    value["tiles"]["codes"]["Narrow"][0] = json!(5);
+    let err = load(value).err().unwrap().to_string();
+    assert!(err.contains("Field 0 has code 5"), "{err}");
+
+    // A frontier field without its mask
+    let mut value = saved();
+    value["tiles"]["frontier"]
+        .as_object_mut()
+        .unwrap()
+        .remove(&first.to_string());
+    let err = load(value).err().unwrap().to_string();
+    assert!(err.contains("has no mask"), "{err}");
+
+    // A mask for a field that isn't in the frontier, or beyond the last field
+    for index in [2 * 8 + 2, 8 * 6] {
+        let mut value = saved();
+        value["tiles"]["frontier"][index.to_string()] = mask.clone();
+        let err = load(value).err().unwrap().to_string();
+        assert!(err.contains(&format!("mask for field {index}")), "{err}");
+    }
+
+    // A mask of another tile set
+    let mut value = saved();
+    value["tiles"]["frontier"][first.to_string()] = json!({ "Superposition": { "words": [0, 1] } });
+    let err = load(value).err().unwrap().to_string();
+    assert!(err.contains(&format!("mask of field {first}")), "{err}");
+}
@@ -0,0 +1,117 @@
+//! Checks what a generator reports and how it stops while collapsing step by step.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use world_gen::{
+    Neighbourhood, PlotGenerator, RiskSummary, Tile, TileDef, TileId, TileSet, WaveState, WfcError,
+    CANCEL_CHECK_INTERVAL,
+};
+
+#[test]
+fn eliminated_tiles_are_not_possible() {
+    let mut gen = PlotGenerator::new(6, 4).with_seed(2);
+    let all = [Tile::River, Tile::Wasteland, Tile::Farmland].map(TileId::from);
+    assert_eq!(gen.possible_tiles(), all.into());
+
+    for y in 0..4 {
+        for x in 0..6 {
+            gen.constrain(x, y, &[Tile::Wasteland, Tile::Farmland])
+                .unwrap();
+        }
+    }
+    assert_eq!(gen.possible_tiles(), all[1..].iter().copied().collect());
+
+    // Collapsed fields count as well
+    gen.set_tile(0, 0, Tile::Farmland).unwrap();
+    gen.constrain(5, 3, &[Tile::Farmland]).unwrap();
+    gen.collapse().unwrap();
+    assert!(gen.possible_tiles().contains(&Tile::Farmland.into()));
+    assert!(!gen.possible_tiles().contains(&Tile::River.into()));
+}
+
+#[test]
+fn collapsing_stops_at_the_count() {
+    let mut gen = PlotGenerator::new(16, 16).with_seed(5);
+    gen.collapse_until_count(Tile::River, 3).unwrap();
+    let river = WaveState::Collapsed(Tile::River.into());
+    assert_eq!(gen.domains().filter(|&state| *state == river).count(), 3);
+    assert!(gen
+        .domains()
+        .any(|state| matches!(state, WaveState::Superposition(_))));
+
+    // Reached counts return right away, and the rest can still be collapsed
+    let before = gen.domains().cloned().collect::<Vec<_>>();
+    gen.collapse_until_count(Tile::River, 2).unwrap();
+    assert!(gen.domains().eq(&before));
+    gen.collapse().unwrap();
+    assert!(gen.into_plot().count(Tile::River) >= 3);
+}
+
+#[test]
+fn cancelling_stops_the_collapse() {
+    let collapsed = |gen: &PlotGenerator| {
+        gen.domains()
+            .filter(|state| matches!(state, WaveState::Collapsed(_)))
+            .count()
+    };
+
+    // A flag set before doesn't let a single field collapse
+    let mut gen = PlotGenerator::new(64, 64).with_seed(1);
+    let cancel = AtomicBool::new(true);
+    assert_eq!(
+        gen.collapse_cancellable(&cancel).err(),
+        Some(WfcError::Cancelled)
+    );
+    assert_eq!(collapsed(&gen), 0);
+
+    // The flag gets checked within CANCEL_CHECK_INTERVAL observations of being set
+    let cancel = AtomicBool::new(false);
+    let mut set_after = None;
+    let result = gen.collapse_with_progress(&cancel, |share| {
+        if share > 0.0 && set_after.is_none() {
+            set_after = Some((share * 64.0 * 64.0).round() as usize);
+            cancel.store(true, Ordering::Relaxed);
+        }
+    });
+    assert_eq!(result.err(), Some(WfcError::Cancelled));
+    let set_after = set_after.unwrap();
+    assert!(collapsed(&gen) <= set_after + CANCEL_CHECK_INTERVAL);
+
+    // The partial state can still be finished
+    cancel.store(false, Ordering::Relaxed);
+    assert!(gen.collapse_cancellable(&cancel).is_ok());
+}
+
+#[test]
+fn risk_rises_towards_a_contradiction() {
+    // The two tiles only lie next to each other, so they can only form a checkerboard
+    let checkers = TileSet::new(vec![
+        TileDef::new("White", "  ").with_neighbours(&["Black"]),
+        TileDef::new("Black", "##").with_neighbours(&["White"]),
+    ])
+    .unwrap();
+    let white = checkers.id("White").unwrap();
+
+    let mut gen = PlotGenerator::for_tileset(checkers.clone(), 4, 3)
+        .with_neighbourhood(Neighbourhood::VonNeumann);
+    let calm = RiskSummary {
+        forced: 0,
+        contradicted: 0,
+        min_entropy: 2,
+    };
+    assert_eq!(gen.risk_summary(), calm);
+
+    // One field decides every other one
+    gen.set_tile(0, 0, white).unwrap();
+    let risk = gen.risk_summary();
+    assert_eq!(
+        (risk.forced, risk.contradicted, risk.min_entropy),
+        (11, 0, 1)
+    );
+
+    // Diagonal neighbours can't hold the same tile, but a checkerboard needs them to
+    let mut gen = PlotGenerator::for_tileset(checkers, 4, 3);
+    gen.set_tile(0, 0, white).unwrap();
+    let risk = gen.risk_summary();
+    assert!(risk.contradicted > 0);
+    assert_eq!(risk.min_entropy, 0);
+}
@@ -0,0 +1,111 @@
+//! Checks how weights steer which tiles the fields collapse into.
+
+use std::collections::HashMap;
+use world_gen::{Plot, PlotGenerator, Tile, TileId, WfcError};
+
+/// A plot that is mostly River, with wasteland along the top row.
+fn river_heavy(width: usize, height: usize) -> Plot {
+    let mut plot = Plot::new(width, height);
+    for (x, y) in plot.positions().filter(|&(_, y)| y > 0).collect::<Vec<_>>() {
+        plot.set(x, y, Tile::River);
+    }
+    plot
+}
+
+fn weight(gen: &PlotGenerator, tile: Tile) -> f64 {
+    let tile = TileId::from(tile);
+    gen.weights()
+        .get(tile)
+        .unwrap_or_else(|| gen.tileset().weight(tile))
+}
+
+#[test]
+fn reinforcing_moves_weights_towards_the_liked_plot() {
+    let liked = river_heavy(8, 8);
+    let mut gen = PlotGenerator::new(8, 8);
+    let total = |gen: &PlotGenerator| Tile::ALL.iter().map(|&t| weight(gen, t)).sum::<f64>();
+    let before = total(&gen);
+
+    let mut river = weight(&gen, Tile::River);
+    for _ in 0..5 {
+        gen.reinforce(&liked, 0.3);
+        assert!(weight(&gen, Tile::River) > river);
+        river = weight(&gen, Tile::River);
+    }
+    assert!(weight(&gen, Tile::River) > weight(&gen, Tile::Wasteland));
+    assert!((total(&gen) - before).abs() < 1e-9);
+    // Farmland is missing from the plot
+    assert!(weight(&gen, Tile::Farmland) < Tile::Farmland.weight());
+
+    // The plots follow
+    let rivers = |gen: PlotGenerator| gen.with_seed(4).generate().unwrap().count(Tile::River);
+    let reinforced = rivers(gen);
+    assert!(reinforced > rivers(PlotGenerator::new(8, 8)));
+}
+
+#[test]
+fn reinforcing_without_a_rate_keeps_the_weights() {
+    let mut gen = PlotGenerator::new(8, 8);
+    gen.reinforce(&river_heavy(8, 8), 0.0);
+    for tile in Tile::ALL {
+        assert_eq!(weight(&gen, tile), tile.weight());
+    }
+}
+
+#[test]
+fn weight_fields_split_the_plot() {
+    let (width, height) = (16, 10);
+    // Wasteland has to be possible everywhere, it separates the rivers from the farmland
+    let field = (0..height)
+        .map(|_| {
+            (0..width)
+                .map(|x| {
+                    let tile = match x < width / 2 {
+                        true => Tile::River,
+                        false => Tile::Farmland,
+                    };
+                    HashMap::from([(tile.into(), 1.0), (Tile::Wasteland.into(), 0.01)])
+                })
+                .collect()
+        })
+        .collect::<Vec<Vec<_>>>();
+
+    let mut gen = PlotGenerator::new(width, height).with_seed(9);
+    gen.set_weight_field(field.clone()).unwrap();
+    let plot = gen.generate().unwrap();
+
+    let (mut rivers, mut farmland) = (0, 0);
+    for (x, y) in plot.positions() {
+        let tile = plot.get(x, y);
+        match x < width / 2 {
+            true => {
+                assert_ne!(tile, Tile::Farmland, "({x}, {y})");
+                rivers += (tile == Tile::River) as usize;
+            }
+            false => {
+                assert_ne!(tile, Tile::River, "({x}, {y})");
+                farmland += (tile == Tile::Farmland) as usize;
+            }
+        }
+    }
+    let half = width / 2 * height;
+    assert!(rivers > half * 3 / 4 && farmland > half * 3 / 4);
+
+    // Every row needs a distribution for every field
+    let mut ragged = field.clone();
+    ragged[3].pop();
+    assert_eq!(
+        gen.set_weight_field(ragged),
+        Err(WfcError::GridSize {
+            expected: (width, height),
+            len: width * height - 1
+        })
+    );
+    assert_eq!(
+        gen.set_weight_field(field[1..].to_vec()),
+        Err(WfcError::GridSize {
+            expected: (width, height),
+            len: width * (height - 1)
+        })
+    );
+}
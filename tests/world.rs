@@ -0,0 +1,127 @@
+//! Checks that plots generated in overlapping pieces follow the rules across the overlaps, and
+//! that only the half of an overlap next to an earlier piece is kept. Also checks where the
+//! chunks of a world report their contradictions.
+
+use std::ops::Range;
+use world_gen::{Direction, Plot, PlotGenerator, TileDef, TileSet, WfcError, World};
+
+/// Every pair of neighbouring fields follows the adjacency rules of the tile set.
+fn assert_rules_hold(plot: &Plot) {
+    let tileset = plot.tileset();
+    for (x, y) in plot.positions() {
+        for direction in Direction::ALL {
+            let (dx, dy) = direction.offset();
+            let (nx, ny) = (x.wrapping_add_signed(dx), y.wrapping_add_signed(dy));
+            if nx >= plot.width() || ny >= plot.height() {
+                continue;
+            }
+            let (tile, neighbour) = (plot.get(x, y), plot.get(nx, ny));
+            assert!(
+                tileset
+                    .valid_neighbours(tile, direction)
+                    .contains(&neighbour),
+                "{} at ({x}, {y}) next to {} in the {direction:?}",
+                tileset.name(tile),
+                tileset.name(neighbour),
+            );
+        }
+    }
+}
+
+#[test]
+fn overlapping_pieces_follow_the_rules() {
+    // Cut off pieces at the edges, overlaps of one field and of more than half a piece
+    for (width, height, chunk, overlap) in [(40, 28, 12, 4), (37, 23, 10, 1), (30, 30, 8, 5)] {
+        for seed in 0..3 {
+            let plot = World::generate_overlapping(width, height, chunk, overlap, seed).unwrap();
+            assert_eq!((plot.width(), plot.height()), (width, height));
+            assert_rules_hold(&plot);
+        }
+    }
+
+    // A single piece and no overlap
+    assert_rules_hold(&World::generate_overlapping(6, 5, 8, 3, 1).unwrap());
+    assert_rules_hold(&World::generate_overlapping(20, 20, 5, 0, 1).unwrap());
+}
+
+#[test]
+fn tight_rules_follow_the_rules_across_overlaps() {
+    // Roads may only lie next to grass, and sand never next to roads
+    let tileset = TileSet::new(vec![
+        TileDef::new("Grass", "..").with_neighbours(&["Grass", "Sand", "Road"]),
+        TileDef::new("Sand", "::").with_neighbours(&["Sand", "Grass"]),
+        TileDef::new("Road", "==").with_neighbours(&["Grass"]),
+    ])
+    .unwrap();
+    let plot = World::generate_overlapping_for_tileset(tileset, 32, 24, 10, 4, 7).unwrap();
+    assert_rules_hold(&plot);
+}
+
+#[test]
+fn same_seed_same_plot() {
+    let generate = |seed| {
+        World::generate_overlapping(24, 18, 8, 3, seed)
+            .unwrap()
+            .to_string()
+    };
+    assert_eq!(generate(4), generate(4));
+    assert_ne!(generate(4), generate(5));
+}
+
+#[test]
+fn far_half_of_the_overlaps_gets_regenerated() {
+    // Two by two pieces of 10 fields, overlapping by 6, of which 3 are kept
+    let (chunk, overlap, keep) = (10, 6, 3);
+    let (stride, size) = (chunk - overlap, 2 * chunk - overlap);
+    let (mut top_corner, mut left_corner) = (false, false);
+    for seed in 0..8 {
+        let plot = World::generate_overlapping(size, size, chunk, overlap, seed).unwrap();
+        // The first piece, with the ring around it
+        let first = PlotGenerator::new(chunk + 2, chunk + 2)
+            .with_seed(seed)
+            .generate()
+            .unwrap();
+        let same = |x: usize, y: usize| plot.get(x, y) == first.get(x + 1, y + 1);
+
+        // Nothing after the first piece regenerates the near halves
+        for y in 0..stride + keep {
+            for x in 0..stride + keep {
+                assert!(same(x, y), "({x}, {y}) got regenerated");
+            }
+        }
+
+        // The far halves have no piece above or left of them, so they don't stay as they were
+        let differs = |xs: Range<usize>, ys: Range<usize>| {
+            ys.flat_map(|y| xs.clone().map(move |x| (x, y)))
+                .any(|(x, y)| !same(x, y))
+        };
+        top_corner |= differs(stride + keep..chunk, 0..keep);
+        left_corner |= differs(0..keep, stride + keep..chunk);
+    }
+    assert!(
+        top_corner,
+        "The overlap of the first row stays as the first piece left it"
+    );
+    assert!(
+        left_corner,
+        "The overlap of the first column stays as the first piece left it"
+    );
+}
+
+#[test]
+fn chunk_contradictions_are_in_world_coordinates() {
+    // A rock may lie next to nothing, not even another rock
+    let tileset = TileSet::new(vec![TileDef::new("Rock", "##")]).unwrap();
+    let mut world = World::for_tileset(tileset, 4).with_seed(2);
+    // An (even, even) chunk, generated without neighbours first
+    match world.chunk(-2, 2).err() {
+        Some(WfcError::WorldContradiction((x, y))) => {
+            // The chunk covers -8..-4 and 8..12, its ring one more field
+            assert!(
+                (-9..=-4).contains(&x) && (7..=12).contains(&y),
+                "({x}, {y})"
+            );
+        }
+        other => panic!("Expected a contradiction in the world, got {other:?}"),
+    }
+}